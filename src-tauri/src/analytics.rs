@@ -1,6 +1,6 @@
 use crate::transfer_events::{TransferEvent, TransferProgressEvent, TransferCompletedEvent, TransferFailedEvent};
 use serde::{Deserialize, Serialize};
-use std::collections::VecDeque;
+use std::collections::{BTreeMap, VecDeque};
 use std::sync::Arc;
 use std::time::{SystemTime, UNIX_EPOCH};
 use tokio::sync::Mutex;
@@ -86,6 +86,145 @@ pub struct SuspiciousActivityAlert {
     pub details: std::collections::HashMap<String, String>,
 }
 
+/// Whether a transfer sent or received data
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TransferDirection {
+    Upload,
+    Download,
+}
+
+/// Outcome of a single upload or download
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransferRecord {
+    pub transfer_id: String,
+    pub direction: TransferDirection,
+    pub bytes: u64,
+    pub duration_ms: u64,
+    /// Peer ID or source (e.g. "local-storage", an HTTP URL) the data went to/came from
+    pub source: String,
+    pub success: bool,
+    pub timestamp: u64,
+}
+
+/// Totals for one UTC day
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DailyTransferTotals {
+    /// Unix timestamp of the day's midnight (UTC)
+    pub day_start: u64,
+    pub uploaded_bytes: u64,
+    pub downloaded_bytes: u64,
+    pub successful_transfers: u64,
+    pub failed_transfers: u64,
+}
+
+/// Summary over every recorded transfer
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AnalyticsSummary {
+    pub total_transfers: u64,
+    pub successful_transfers: u64,
+    pub failed_transfers: u64,
+    pub total_uploaded_bytes: u64,
+    pub total_downloaded_bytes: u64,
+    /// Bytes per second over successful transfers
+    pub average_throughput_bps: f64,
+    /// Fraction of transfers that succeeded (0.0-1.0)
+    pub success_rate: f64,
+    /// Oldest day first
+    pub daily_totals: Vec<DailyTransferTotals>,
+    /// Newest first
+    pub recent_transfers: Vec<TransferRecord>,
+}
+
+const MAX_TRANSFER_RECORDS: usize = 500;
+const MAX_DAILY_TOTALS: usize = 30;
+const SECONDS_PER_DAY: u64 = 86_400;
+
+/// Ring buffer of recent transfers plus rolling per-day and all-time totals
+#[derive(Debug, Default)]
+pub struct AnalyticsRecorder {
+    records: VecDeque<TransferRecord>,
+    daily_totals: BTreeMap<u64, DailyTransferTotals>,
+    successful_transfers: u64,
+    failed_transfers: u64,
+    uploaded_bytes: u64,
+    downloaded_bytes: u64,
+    successful_bytes: u64,
+    successful_duration_ms: u64,
+}
+
+impl AnalyticsRecorder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record(&mut self, record: TransferRecord) {
+        let day_start = record.timestamp - record.timestamp % SECONDS_PER_DAY;
+        let day = self
+            .daily_totals
+            .entry(day_start)
+            .or_insert_with(|| DailyTransferTotals {
+                day_start,
+                ..Default::default()
+            });
+
+        if record.success {
+            match record.direction {
+                TransferDirection::Upload => {
+                    day.uploaded_bytes += record.bytes;
+                    self.uploaded_bytes += record.bytes;
+                }
+                TransferDirection::Download => {
+                    day.downloaded_bytes += record.bytes;
+                    self.downloaded_bytes += record.bytes;
+                }
+            }
+            day.successful_transfers += 1;
+            self.successful_transfers += 1;
+            self.successful_bytes += record.bytes;
+            self.successful_duration_ms += record.duration_ms;
+        } else {
+            day.failed_transfers += 1;
+            self.failed_transfers += 1;
+        }
+
+        while self.daily_totals.len() > MAX_DAILY_TOTALS {
+            self.daily_totals.pop_first();
+        }
+
+        self.records.push_back(record);
+        while self.records.len() > MAX_TRANSFER_RECORDS {
+            self.records.pop_front();
+        }
+    }
+
+    pub fn summary(&self) -> AnalyticsSummary {
+        let total_transfers = self.successful_transfers + self.failed_transfers;
+        AnalyticsSummary {
+            total_transfers,
+            successful_transfers: self.successful_transfers,
+            failed_transfers: self.failed_transfers,
+            total_uploaded_bytes: self.uploaded_bytes,
+            total_downloaded_bytes: self.downloaded_bytes,
+            average_throughput_bps: if self.successful_duration_ms > 0 {
+                self.successful_bytes as f64 * 1000.0 / self.successful_duration_ms as f64
+            } else {
+                0.0
+            },
+            success_rate: if total_transfers > 0 {
+                self.successful_transfers as f64 / total_transfers as f64
+            } else {
+                0.0
+            },
+            daily_totals: self.daily_totals.values().cloned().collect(),
+            recent_transfers: self.records.iter().rev().cloned().collect(),
+        }
+    }
+}
+
 const MAX_HISTORY_SIZE: usize = 1000;
 const HISTORY_INTERVAL_SECONDS: u64 = 60; // Record every minute
 const MAX_ALERTS: usize = 100;
@@ -101,6 +240,7 @@ pub struct AnalyticsService {
     last_history_update: Arc<Mutex<u64>>,
     unique_peers: Arc<Mutex<std::collections::HashSet<String>>>,
     suspicious_alerts: Arc<Mutex<VecDeque<SuspiciousActivityAlert>>>,
+    recorder: Arc<Mutex<AnalyticsRecorder>>,
 }
 
 impl AnalyticsService {
@@ -147,6 +287,7 @@ impl AnalyticsService {
             last_history_update: Arc::new(Mutex::new(now)),
             unique_peers: Arc::new(Mutex::new(std::collections::HashSet::new())),
             suspicious_alerts: Arc::new(Mutex::new(VecDeque::new())),
+            recorder: Arc::new(Mutex::new(AnalyticsRecorder::new())),
         }
     }
 
@@ -221,6 +362,16 @@ impl AnalyticsService {
         }
     }
 
+    /// Log a finished transfer in the per-transfer ring buffer and daily totals
+    pub async fn record_transfer_outcome(&self, record: TransferRecord) {
+        self.recorder.lock().await.record(record);
+    }
+
+    /// Totals, average throughput and success rate over recorded transfers
+    pub async fn get_analytics_summary(&self) -> AnalyticsSummary {
+        self.recorder.lock().await.summary()
+    }
+
     /// Update latency metric
     pub async fn record_latency(&self, latency_ms: f64) {
         let mut perf = self.performance.lock().await;
@@ -445,6 +596,7 @@ impl AnalyticsService {
 
         self.bandwidth_history.lock().await.clear();
         self.contribution_history.lock().await.clear();
+        *self.recorder.lock().await = AnalyticsRecorder::new();
     }
 
     // =========================================================================
@@ -572,6 +724,24 @@ impl AnalyticsService {
         activity.active_downloads = activity.active_downloads.saturating_sub(1);
         activity.completed_downloads += 1;
 
+        drop(activity);
+
+        self.record_transfer_outcome(TransferRecord {
+            transfer_id: completed.transfer_id.clone(),
+            direction: TransferDirection::Download,
+            bytes: completed.file_size,
+            duration_ms: completed.duration_seconds * 1000,
+            source: completed
+                .sources_used
+                .iter()
+                .map(|source| source.source_id.as_str())
+                .collect::<Vec<_>>()
+                .join(","),
+            success: true,
+            timestamp: completed.completed_at / 1000,
+        })
+        .await;
+
         debug!(
             "Transfer completed: {} ({} bytes in {} seconds)",
             completed.file_name, completed.file_size, completed.duration_seconds
@@ -588,6 +758,18 @@ impl AnalyticsService {
         // Update network activity
         let mut activity = self.network_activity.lock().await;
         activity.active_downloads = activity.active_downloads.saturating_sub(1);
+        drop(activity);
+
+        self.record_transfer_outcome(TransferRecord {
+            transfer_id: failed.transfer_id.clone(),
+            direction: TransferDirection::Download,
+            bytes: failed.downloaded_bytes,
+            duration_ms: 0,
+            source: String::new(),
+            success: false,
+            timestamp: failed.failed_at / 1000,
+        })
+        .await;
 
         debug!(
             "Transfer failed: {} - {} ({}/{} bytes)",
@@ -715,6 +897,7 @@ impl Clone for AnalyticsService {
             last_history_update: Arc::clone(&self.last_history_update),
             unique_peers: Arc::clone(&self.unique_peers),
             suspicious_alerts: Arc::clone(&self.suspicious_alerts),
+            recorder: Arc::clone(&self.recorder),
         }
     }
 }
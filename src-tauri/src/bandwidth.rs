@@ -198,6 +198,37 @@ impl BandwidthController {
         self.acquire(bytes, Direction::Download, Some(transfer_id.to_string())).await;
     }
 
+    /// Acquire upload bandwidth for a payload of any size (e.g. a whole file)
+    pub async fn acquire_upload_chunked(&self, bytes: usize) {
+        self.acquire_chunked(bytes, Direction::Upload).await;
+    }
+
+    /// Acquire download bandwidth for a payload of any size (e.g. a whole file)
+    pub async fn acquire_download_chunked(&self, bytes: usize) {
+        self.acquire_chunked(bytes, Direction::Download).await;
+    }
+
+    /// Acquire in pieces of at most one second's worth of the limit, so every
+    /// request fits in the bucket's burst capacity.
+    async fn acquire_chunked(&self, mut bytes: usize, direction: Direction) {
+        while bytes > 0 {
+            let limit_kbps = {
+                let inner = self.inner.lock().await;
+                match direction {
+                    Direction::Upload => inner.upload.limit_kbps(),
+                    Direction::Download => inner.download.limit_kbps(),
+                }
+            };
+            let piece = if limit_kbps == 0 {
+                bytes
+            } else {
+                bytes.min((limit_kbps * 1024) as usize)
+            };
+            self.acquire(piece, direction, None).await;
+            bytes -= piece;
+        }
+    }
+
     async fn acquire(&self, bytes: usize, direction: Direction, transfer_id: Option<String>) {
         if bytes == 0 {
             return;
@@ -307,18 +338,24 @@ impl BandwidthController {
             0.0
         };
         
-        if let Some(bus) = &self.event_bus {
-            let event = BandwidthEvent::UsageStats(BandwidthUsageEvent {
-                upload_bytes_used: upload_bytes,
-                download_bytes_used: download_bytes,
-                upload_limit_kbps: upload_limit,
-                download_limit_kbps: download_limit,
-                upload_utilization_percent: upload_util,
-                download_utilization_percent: download_util,
-                period_seconds: period,
-                timestamp: current_timestamp_ms(),
-            });
-            emit_bandwidth_event(self, event).await;
+        let event = BandwidthEvent::UsageStats(BandwidthUsageEvent {
+            upload_bytes_used: upload_bytes,
+            download_bytes_used: download_bytes,
+            upload_limit_kbps: upload_limit,
+            download_limit_kbps: download_limit,
+            upload_utilization_percent: upload_util,
+            download_utilization_percent: download_util,
+            period_seconds: period,
+            timestamp: current_timestamp_ms(),
+        });
+        emit_bandwidth_event(self, event).await;
+    }
+
+    /// Emit `bandwidth:usage_stats` every `interval` so the UI can show throughput
+    pub async fn report_usage_periodically(self: Arc<Self>, interval: Duration) {
+        loop {
+            sleep(interval).await;
+            self.emit_usage_stats().await;
         }
     }
     
@@ -332,6 +369,7 @@ impl BandwidthController {
     }
 }
 
+#[derive(Clone, Copy)]
 enum Direction {
     Upload,
     Download,
@@ -606,4 +644,25 @@ mod tests {
         assert!(json.contains("test-123"));
         assert!(json.contains("download"));
     }
-}
\ No newline at end of file
+
+    #[tokio::test]
+    async fn test_low_cap_enforces_minimum_transfer_time() {
+        let controller = BandwidthController::new();
+        controller.set_limits(0, 4).await; // 4 KB/s download, 8 KB burst
+
+        // 16 KB: the first 8 KB come from the burst, the rest at 4 KB/s
+        let start = Instant::now();
+        controller.acquire_download_chunked(16 * 1024).await;
+        let elapsed = start.elapsed();
+
+        assert!(
+            elapsed >= Duration::from_millis(1900),
+            "16 KB at 4 KB/s finished in {:?}",
+            elapsed
+        );
+
+        let (upload, download, _) = controller.get_and_reset_usage().await;
+        assert_eq!(upload, 0);
+        assert_eq!(download, 16 * 1024);
+    }
+}
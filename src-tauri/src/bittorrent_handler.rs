@@ -1516,12 +1516,12 @@ impl BitTorrentHandler {
         let chunk_storage_path = self.download_directory.join("chunks");
         let manager = ChunkManager::new(chunk_storage_path);
         
-        // Use chunk_and_encrypt_file_canonical to generate FileManifest
+        // Chunk with the canonical key and save the FileManifest, so gc keeps the chunks
         // This will calculate chunk hashes even without encryption
         let file_manifest_result = tokio::task::spawn_blocking({
             let file_path_clone = file_path.clone();
             move || {
-                manager.chunk_and_encrypt_file_canonical(Path::new(&file_path_clone))
+                manager.store_file_with_manifest(Path::new(&file_path_clone))
             }
         }).await
         .map_err(|e| format!("Failed to spawn blocking task: {}", e))?;
@@ -1,4 +1,5 @@
-use crate::{AppState, ProxyAuthToken};
+use crate::AppState;
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
 use rand::Rng;
 use std::collections::HashMap;
 use std::sync::Mutex;
@@ -6,7 +7,113 @@ use tauri::State;
 use tracing::info;
 
 /// In-memory token storage (in production, use a proper database)
-type TokenStore = Mutex<HashMap<String, crate::ProxyAuthToken>>;
+type TokenStore = Mutex<HashMap<String, ProxyAuthToken>>;
+
+/// How close to expiry (either side) a proxy auth token may be refreshed
+pub(crate) const TOKEN_REFRESH_GRACE_SECS: u64 = 300;
+
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub(crate) enum AuthError {
+    #[error("token expired")]
+    Expired,
+    #[error("token signature invalid")]
+    BadSignature,
+    #[error("token is not within the refresh window")]
+    NotRefreshable,
+    #[error("invalid token: {0}")]
+    Invalid(String),
+}
+
+/// Bearer token authorizing `proxy_address` until `expires_at`, signed with
+/// the node's ed25519 key so a token altered after issue is rejected.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct ProxyAuthToken {
+    pub(crate) token: String,
+    pub(crate) proxy_address: String,
+    pub(crate) expires_at: u64,
+    pub(crate) created_at: u64,
+    /// hex-encoded ed25519 signature over the other fields
+    pub(crate) signature: String,
+}
+
+impl ProxyAuthToken {
+    /// Issue a token for `proxy_address` valid for `ttl_secs` from now
+    pub(crate) fn issue(proxy_address: &str, ttl_secs: u64, signing_key: &SigningKey) -> Self {
+        Self::issue_at(proxy_address, ttl_secs, signing_key, unix_now())
+    }
+
+    fn issue_at(proxy_address: &str, ttl_secs: u64, signing_key: &SigningKey, now: u64) -> Self {
+        let mut token = Self {
+            token: generate_secure_token(),
+            proxy_address: proxy_address.to_string(),
+            expires_at: now.saturating_add(ttl_secs),
+            created_at: now,
+            signature: String::new(),
+        };
+        let signature = signing_key.sign(&token.signable_bytes());
+        token.signature = hex::encode(signature.to_bytes());
+        token
+    }
+
+    fn signable_bytes(&self) -> Vec<u8> {
+        serde_json::json!({
+            "token": self.token,
+            "proxy_address": self.proxy_address,
+            "created_at": self.created_at,
+            "expires_at": self.expires_at,
+        })
+        .to_string()
+        .into_bytes()
+    }
+
+    /// Reject the token if it has expired or wasn't signed by `verifying_key`
+    pub(crate) fn validate(&self, verifying_key: &VerifyingKey) -> Result<(), AuthError> {
+        self.validate_at(verifying_key, unix_now())
+    }
+
+    fn validate_at(&self, verifying_key: &VerifyingKey, now: u64) -> Result<(), AuthError> {
+        self.verify_signature(verifying_key)?;
+        if now > self.expires_at {
+            return Err(AuthError::Expired);
+        }
+        Ok(())
+    }
+
+    fn verify_signature(&self, verifying_key: &VerifyingKey) -> Result<(), AuthError> {
+        let signature_bytes: [u8; 64] = hex::decode(&self.signature)
+            .map_err(|e| AuthError::Invalid(e.to_string()))?
+            .try_into()
+            .map_err(|_| AuthError::Invalid("invalid signature length".to_string()))?;
+        let signature = Signature::from_bytes(&signature_bytes);
+        verifying_key
+            .verify(&self.signable_bytes(), &signature)
+            .map_err(|_| AuthError::BadSignature)
+    }
+
+    /// Issue a replacement with the same lifetime if this token is within
+    /// `TOKEN_REFRESH_GRACE_SECS` of its expiry. A token that expired less than
+    /// the grace window ago can still be refreshed.
+    pub(crate) fn refresh(&self, signing_key: &SigningKey) -> Result<Self, AuthError> {
+        self.refresh_at(signing_key, unix_now())
+    }
+
+    fn refresh_at(&self, signing_key: &SigningKey, now: u64) -> Result<Self, AuthError> {
+        self.verify_signature(&signing_key.verifying_key())?;
+        if now > self.expires_at.saturating_add(TOKEN_REFRESH_GRACE_SECS) {
+            return Err(AuthError::Expired);
+        }
+        if now < self.expires_at.saturating_sub(TOKEN_REFRESH_GRACE_SECS) {
+            return Err(AuthError::NotRefreshable);
+        }
+        let ttl_secs = self.expires_at.saturating_sub(self.created_at);
+        Ok(Self::issue_at(
+            &self.proxy_address,
+            ttl_secs,
+            signing_key,
+            now,
+        ))
+    }
+}
 
 #[tauri::command]
 pub(crate) async fn generate_proxy_auth_token(
@@ -17,23 +124,14 @@ pub(crate) async fn generate_proxy_auth_token(
 ) -> Result<serde_json::Value, String> {
     let mut store = state.proxy_auth_tokens.lock().await;
 
-    // Generate a cryptographically secure token
-    let token = generate_secure_token();
-
-    // Calculate expiry time
-    let now = std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)
-        .unwrap_or(std::time::Duration::from_secs(0))
-        .as_secs();
-    let expires_at = now + (expiry_hours as u64 * 3600);
-
-    // Store the token
-    let token_data = ProxyAuthToken {
-        token: token.clone(),
-        proxy_address: proxy_address.clone(),
-        expires_at,
-        created_at: now,
-    };
+    // Issue a signed token with a cryptographically secure value
+    let token_data = ProxyAuthToken::issue(
+        &proxy_address,
+        expiry_hours as u64 * 3600,
+        &state.proxy_auth_signing_key,
+    );
+    let token = token_data.token.clone();
+    let expires_at = token_data.expires_at;
 
     store.insert(token.clone(), token_data);
 
@@ -65,7 +163,11 @@ pub(crate) async fn validate_proxy_auth_token(
 
     // Check if token exists and matches the proxy address
     if let Some(token_data) = store.get(&token) {
-        if token_data.proxy_address == proxy_address && !is_token_expired(token_data.expires_at) {
+        if token_data.proxy_address == proxy_address
+            && token_data
+                .validate(&state.proxy_auth_signing_key.verifying_key())
+                .is_ok()
+        {
             return Ok(true);
         }
     }
@@ -73,6 +175,35 @@ pub(crate) async fn validate_proxy_auth_token(
     Ok(false)
 }
 
+/// Replace a token that is within `TOKEN_REFRESH_GRACE_SECS` of its expiry
+/// with a new one of the same lifetime, revoking the old token.
+#[tauri::command]
+pub(crate) async fn refresh_proxy_auth_token(
+    _app: tauri::AppHandle,
+    state: State<'_, AppState>,
+    token: String,
+) -> Result<serde_json::Value, String> {
+    let mut store = state.proxy_auth_tokens.lock().await;
+
+    let token_data = store
+        .get(&token)
+        .ok_or_else(|| "Unknown proxy auth token".to_string())?;
+    let refreshed = token_data
+        .refresh(&state.proxy_auth_signing_key)
+        .map_err(|e| format!("Cannot refresh proxy auth token: {}", e))?;
+    let new_token = refreshed.token.clone();
+    let expires_at = refreshed.expires_at;
+
+    info!("Refreshed proxy auth token for {}", refreshed.proxy_address);
+    store.remove(&token);
+    store.insert(new_token.clone(), refreshed);
+
+    Ok(serde_json::json!({
+        "token": new_token,
+        "expires_at": expires_at
+    }))
+}
+
 #[tauri::command]
 pub(crate) async fn revoke_proxy_auth_token(
     _app: tauri::AppHandle,
@@ -107,13 +238,16 @@ fn generate_secure_token() -> String {
     hex::encode(bytes)
 }
 
-/// Check if a token is expired
-fn is_token_expired(expires_at: u64) -> bool {
-    let now = std::time::SystemTime::now()
+fn unix_now() -> u64 {
+    std::time::SystemTime::now()
         .duration_since(std::time::UNIX_EPOCH)
         .unwrap_or(std::time::Duration::from_secs(0))
-        .as_secs();
-    now > expires_at
+        .as_secs()
+}
+
+/// Check if a token is expired
+fn is_token_expired(expires_at: u64) -> bool {
+    unix_now() > expires_at
 }
 
 /// Clean up expired tokens from the store
@@ -125,3 +259,95 @@ fn cleanup_expired_tokens(store: &mut HashMap<String, ProxyAuthToken>) {
 
     store.retain(|_, token_data| !is_token_expired(token_data.expires_at));
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn token_signing_key() -> SigningKey {
+        SigningKey::generate(&mut rand::rngs::OsRng)
+    }
+
+    #[test]
+    fn test_proxy_token_valid() {
+        let signing_key = token_signing_key();
+        let token = ProxyAuthToken::issue("127.0.0.1:8080", 3600, &signing_key);
+
+        assert_eq!(token.validate(&signing_key.verifying_key()), Ok(()));
+    }
+
+    #[test]
+    fn test_proxy_token_expired() {
+        let signing_key = token_signing_key();
+        let token = ProxyAuthToken::issue_at("127.0.0.1:8080", 60, &signing_key, 1_000);
+
+        assert_eq!(
+            token.validate_at(&signing_key.verifying_key(), 1_060),
+            Ok(())
+        );
+        assert_eq!(
+            token.validate_at(&signing_key.verifying_key(), 1_061),
+            Err(AuthError::Expired)
+        );
+    }
+
+    #[test]
+    fn test_proxy_token_tampered_signature() {
+        let signing_key = token_signing_key();
+        let mut token = ProxyAuthToken::issue("127.0.0.1:8080", 3600, &signing_key);
+
+        // Extending the expiry or moving the token to another proxy invalidates the signature
+        let mut extended = token.clone();
+        extended.expires_at += 3600;
+        assert_eq!(
+            extended.validate(&signing_key.verifying_key()),
+            Err(AuthError::BadSignature)
+        );
+        let mut moved = token.clone();
+        moved.proxy_address = "10.0.0.1:8080".to_string();
+        assert_eq!(
+            moved.validate(&signing_key.verifying_key()),
+            Err(AuthError::BadSignature)
+        );
+
+        // So does a token signed by someone else
+        let other_key = token_signing_key();
+        assert_eq!(
+            token.validate(&other_key.verifying_key()),
+            Err(AuthError::BadSignature)
+        );
+
+        let mut signature = hex::decode(&token.signature).unwrap();
+        signature[0] ^= 0xff;
+        token.signature = hex::encode(signature);
+        assert_eq!(
+            token.validate(&signing_key.verifying_key()),
+            Err(AuthError::BadSignature)
+        );
+    }
+
+    #[test]
+    fn test_proxy_token_refresh_within_grace_window() {
+        let signing_key = token_signing_key();
+        let token = ProxyAuthToken::issue_at("127.0.0.1:8080", 3600, &signing_key, 1_000);
+
+        assert_eq!(
+            token.refresh_at(&signing_key, 1_000),
+            Err(AuthError::NotRefreshable)
+        );
+
+        let refreshed = token.refresh_at(&signing_key, 4_500).unwrap();
+        assert_eq!(refreshed.expires_at, 8_100);
+        assert_eq!(refreshed.proxy_address, token.proxy_address);
+        assert_ne!(refreshed.token, token.token);
+        assert_eq!(
+            refreshed.validate_at(&signing_key.verifying_key(), 8_000),
+            Ok(())
+        );
+
+        assert_eq!(
+            token.refresh_at(&signing_key, 4_600 + TOKEN_REFRESH_GRACE_SECS + 1),
+            Err(AuthError::Expired)
+        );
+    }
+}
@@ -224,7 +224,7 @@ use relay::client::Event as RelayClientEvent;
 use rs_merkle::{Hasher, MerkleTree};
 use serde::Serialize;
 use sha2::{Digest, Sha256};
-use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
@@ -233,13 +233,15 @@ use std::{
     path::PathBuf,
     str::FromStr,
 };
-use tokio::sync::{mpsc, oneshot, Mutex};
+use tokio::sync::{broadcast, mpsc, oneshot, Mutex, OwnedSemaphorePermit, Semaphore};
 use tokio_util::compat::TokioAsyncReadCompatExt;
 use tracing::{debug, error, info, trace, warn};
 
+use crate::event_bus::{EventBus, EventBusError, SequencedEvent, DEFAULT_EVENT_HISTORY};
 use crate::manager::Sha256Hasher;
+use crate::peer_cache::{PeerCache, PeerCacheEntry};
 use crate::peer_selection::{PeerMetrics, PeerSelectionService, SelectionStrategy};
-use crate::reputation::{TransactionVerdict, VerdictOutcome};
+use crate::reputation::{TransactionVerdict, VerdictDraft, VerdictOutcome};
 use crate::webrtc_service::{get_webrtc_service, FileChunk};
 use std::io::{self};
 use tokio_socks::tcp::Socks5Stream;
@@ -260,8 +262,10 @@ use anyhow::Result;
 // Rate limiting for connection error logs (log at most once every 30 seconds)
 static LAST_CONNECTION_ERROR_LOG: AtomicU64 = AtomicU64::new(0);
 
+use ipnet::IpNet;
 use libp2p::{
     autonat::v2,
+    connection_limits::{self, ConnectionLimits},
     core::{
         muxing::StreamMuxerBox,
         // FIXED E0432: ListenerEvent is removed, only import what is available.
@@ -279,11 +283,17 @@ use libp2p::{
     noise,
     ping::{self, Behaviour as Ping, Event as PingEvent},
     relay, request_response as rr,
-    swarm::{behaviour::toggle, NetworkBehaviour, SwarmEvent},
+    swarm::{
+        behaviour::toggle,
+        dial_opts::{DialOpts as PeerDialOpts, PeerCondition},
+        DialError, ListenError, NetworkBehaviour, SwarmEvent,
+    },
     tcp, upnp, yamux, Multiaddr, PeerId, StreamProtocol, Swarm, SwarmBuilder,
 };
 use rand::rngs::OsRng;
 const EXPECTED_PROTOCOL_VERSION: &str = "/chiral/1.0.0";
+/// Marks the region a node reports in its identify agent version
+const AGENT_REGION_PREFIX: &str = "region/";
 const MAX_MULTIHASH_LENGHT: usize = 64;
 /// Prefix for DHT records that map a torrent info_hash to a Chiral Merkle root.
 const INFO_HASH_PREFIX: &str = "info_hash_idx::";
@@ -292,6 +302,70 @@ pub const RAW_CODEC: u64 = 0x55;
 const FILE_HEARTBEAT_INTERVAL: Duration = Duration::from_secs(15); // More frequent updates
 /// File seeder TTL – if no heartbeat lands within this window, drop the entry.
 const FILE_HEARTBEAT_TTL: Duration = Duration::from_secs(90); // Longer TTL with grace period
+/// Prefix for DHT records that point a retired PeerId at its replacement.
+const IDENTITY_ROTATION_PREFIX: &str = "identity_rotation::";
+/// Default cap on outbound Kademlia lookups issued through the public API at once.
+const DEFAULT_MAX_CONCURRENT_QUERIES: usize = 16;
+/// Default interval between republishing every record this node has published.
+const DEFAULT_REPUBLISH_INTERVAL: Duration = Duration::from_secs(12 * 60 * 60);
+/// How long a rotated identity keeps listing the old PeerId next to the new one.
+pub const DEFAULT_IDENTITY_ROTATION_GRACE: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Why `DhtService::rotate_identity` failed, and whether a node is still up
+#[derive(Debug, Clone, PartialEq)]
+pub enum IdentityRotationError {
+    /// The current node could not be shut down and keeps running as before
+    StillRunning(String),
+    /// The current node was shut down and no node with the new identity is running
+    Stopped(String),
+}
+
+impl std::fmt::Display for IdentityRotationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            IdentityRotationError::StillRunning(e) => {
+                write!(f, "Identity rotation failed, DHT node still running: {}", e)
+            }
+            IdentityRotationError::Stopped(e) => write!(
+                f,
+                "Identity rotation failed and the DHT node was stopped: {}",
+                e
+            ),
+        }
+    }
+}
+
+impl std::error::Error for IdentityRotationError {}
+/// Default time a connection with no active streams is kept open.
+const DEFAULT_IDLE_TIMEOUT: Duration = Duration::from_secs(300);
+/// Default interval between keepalive pings to each connected peer.
+const DEFAULT_PING_INTERVAL: Duration = Duration::from_secs(15);
+/// Default number of consecutive failed pings before a peer counts as unresponsive.
+const DEFAULT_PING_FAILURE_THRESHOLD: u32 = 3;
+/// Default time without any connection event before the watchdog resets the swarm.
+const DEFAULT_WATCHDOG_WINDOW: Duration = Duration::from_secs(10 * 60);
+/// First retry delay after a configured bootstrap peer disconnects.
+const BOOTSTRAP_RECONNECT_INITIAL: Duration = Duration::from_secs(1);
+/// Upper bound on the delay between reconnect attempts to one bootstrap peer.
+const BOOTSTRAP_RECONNECT_MAX: Duration = Duration::from_secs(5 * 60);
+/// Relays a node reserves a slot on when AutoNAT finds it unreachable.
+const MAX_RELAY_RESERVATIONS: usize = 2;
+/// Connection caps a bootstrap node gets unless configured otherwise.
+const DEFAULT_BOOTSTRAP_MAX_ESTABLISHED: u32 = 1024;
+const DEFAULT_BOOTSTRAP_MAX_PER_PEER: u32 = 4;
+const DEFAULT_BOOTSTRAP_MAX_PENDING: u32 = 128;
+/// The watchdog only resets the swarm while fewer peers than this are connected.
+const WATCHDOG_LOW_PEER_COUNT: usize = 2;
+/// Default number of peers a file record put must reach before it counts as stored.
+const DEFAULT_PUT_QUORUM_TARGET: usize = 3;
+/// Kademlia protocol spoken by Chiral nodes; nodes on different protocols don't see each other.
+const DEFAULT_KAD_PROTOCOL: &str = "/chiral/kad/1.0.0";
+/// Number of peers a Kademlia record is replicated to (as per spec table).
+const DEFAULT_REPLICATION_FACTOR: usize = 3;
+/// How long a single Kademlia query may run before it is abandoned.
+const DEFAULT_KAD_QUERY_TIMEOUT: Duration = Duration::from_secs(30);
+/// Largest Kademlia message accepted, matching libp2p's default.
+const DEFAULT_KAD_MAX_PACKET_SIZE: usize = 16 * 1024;
 
 /// thread-safe, mutable block store
 
@@ -301,7 +375,7 @@ struct DhtBehaviour {
     identify: identify::Behaviour,
     mdns: toggle::Toggle<Mdns>,
     bitswap: beetswap::Behaviour<MAX_MULTIHASH_LENGHT, RedbBlockstore>,
-    ping: ping::Behaviour,
+    ping: toggle::Toggle<ping::Behaviour>,
     proxy_rr: rr::Behaviour<ProxyCodec>,
     webrtc_signaling_rr: rr::Behaviour<WebRTCSignalingCodec>,
     key_request: rr::Behaviour<KeyRequestCodec>,
@@ -311,12 +385,15 @@ struct DhtBehaviour {
     relay_server: toggle::Toggle<relay::Behaviour>,
     dcutr: toggle::Toggle<dcutr::Behaviour>,
     upnp: toggle::Toggle<upnp::tokio::Behaviour>,
+    connection_limits: connection_limits::Behaviour,
 }
 #[derive(Debug)]
 pub enum DhtCommand {
     PublishFile {
         metadata: FileMetadata,
         response_tx: oneshot::Sender<FileMetadata>,
+        /// Told how many peers stored the record once its put finishes
+        stored_tx: Option<oneshot::Sender<usize>>,
     },
     SearchByInfohash {
         info_hash: String,
@@ -337,6 +414,8 @@ pub enum DhtCommand {
     ConnectPeer(String),
     ConnectToPeerById(PeerId),
     DisconnectPeer(PeerId),
+    /// Reserve a slot on a relay (`/.../p2p/{relay}`) and listen through it
+    ReserveRelay(Multiaddr),
     SetPrivacyProxies {
         addresses: Vec<String>,
     },
@@ -391,6 +470,11 @@ pub enum DhtCommand {
         key: String,
         sender: oneshot::Sender<Result<Option<Vec<u8>>, String>>,
     },
+    /// Publish a pointer from a retired PeerId to this node's PeerId
+    AnnounceIdentityRotation {
+        old_peer_id: String,
+        sender: oneshot::Sender<Result<(), String>>,
+    },
     /// Re-bootstrap the DHT to discover new peers
     ReBootstrap {
         sender: oneshot::Sender<Result<usize, String>>,
@@ -462,6 +546,22 @@ pub enum DhtEvent {
         last_error: Option<String>,
         summary: Option<String>,
     },
+    /// A peer or address was rejected by the configured allowlist/denylist
+    PeerBlocked {
+        peer_id: Option<String>,
+        address: Option<String>,
+        reason: String,
+    },
+    /// A Kademlia bootstrap finished; `ok` is false if it timed out
+    BootstrapCompleted {
+        num_remaining: Option<u32>,
+        ok: bool,
+    },
+    /// Reachability flipped between public, private and unknown
+    NatStatusChanged {
+        previous: NatReachabilityState,
+        current: NatReachabilityState,
+    },
     BitswapDataReceived {
         query_id: String,
         data: Vec<u8>,
@@ -486,6 +586,39 @@ pub enum DhtEvent {
         from_peer: String,
         payload: serde_json::Value,
     },
+    IdentityRotated {
+        old: String,
+        new: String,
+    },
+    /// Peers announcing a provider record for a file
+    ProvidersFound {
+        file_hash: String,
+        providers: Vec<String>,
+    },
+    /// A peer failed `failures` keepalive pings in a row and was evicted
+    PeerUnresponsive {
+        peer_id: String,
+        failures: u32,
+    },
+    /// The watchdog saw no connections for `idle_secs` with only `peer_count`
+    /// peers, and re-issued listen and bootstrap
+    SwarmReset {
+        idle_secs: u64,
+        peer_count: usize,
+    },
+    /// A metadata lookup ended without a record for a reason other than the
+    /// key not existing (`FileNotFound`): the query timed out or too few
+    /// peers returned the record
+    QueryTimedOut {
+        file_hash: String,
+        reason: String,
+    },
+    /// A relay reservation was accepted (`ok`) or failed/was lost; `relay`
+    /// is the relay's peer id
+    RelayReservation {
+        relay: String,
+        ok: bool,
+    },
 }
 
 struct RelayState {
@@ -804,6 +937,38 @@ impl PendingSearchQuery {
         self.found_record.is_some() // Only complete when we have the actual metadata
     }
 
+    /// Add provider lookup results, skipping peers already known
+    fn add_providers(&mut self, providers: Vec<String>) {
+        let found = self.found_providers.get_or_insert_with(Vec::new);
+        for provider in providers {
+            if !found.contains(&provider) {
+                found.push(provider);
+            }
+        }
+    }
+
+    /// Answer the search with its metadata record, listing every provider as a seeder
+    async fn respond(self, event_tx: &mpsc::Sender<DhtEvent>) {
+        let Some(mut metadata) = self.found_record else {
+            return;
+        };
+        for provider in self.found_providers.unwrap_or_default() {
+            if !metadata.seeders.contains(&provider) {
+                metadata.seeders.push(provider);
+            }
+        }
+        info!(
+            "✅ Found searched file: {} ({}) with {} seeders (merged from metadata + providers)",
+            metadata.file_name,
+            metadata.merkle_root,
+            metadata.seeders.len()
+        );
+        let _ = event_tx
+            .send(DhtEvent::FileDiscovered(metadata.clone()))
+            .await;
+        let _ = self.sender.send(Ok(Some(metadata)));
+    }
+
     fn finalize(self) -> Result<Option<FileMetadata>, String> {
         if let Some(metadata) = self.found_record {
             Ok(Some(metadata))
@@ -1115,6 +1280,7 @@ impl DhtMetricsSnapshot {
 
         let DhtMetrics {
             last_bootstrap,
+            last_successful_bootstrap,
             last_success,
             last_error_at,
             last_error,
@@ -1145,6 +1311,8 @@ impl DhtMetricsSnapshot {
             dcutr_hole_punch_failures,
             last_dcutr_success,
             last_dcutr_failure,
+            last_connection_event,
+            connections_refused,
             ..
         } = metrics;
 
@@ -1173,6 +1341,7 @@ impl DhtMetricsSnapshot {
         DhtMetricsSnapshot {
             peer_count,
             last_bootstrap: last_bootstrap.and_then(to_secs),
+            last_successful_bootstrap: last_successful_bootstrap.and_then(to_secs),
             last_peer_event: last_success.and_then(to_secs),
             last_error,
             last_error_at: last_error_at.and_then(to_secs),
@@ -1204,10 +1373,26 @@ impl DhtMetricsSnapshot {
             dcutr_hole_punch_failures,
             last_dcutr_success: last_dcutr_success.and_then(to_secs),
             last_dcutr_failure: last_dcutr_failure.and_then(to_secs),
+            last_connection_event: last_connection_event.and_then(to_secs),
+            connections_refused,
         }
     }
 }
 
+/// A swarm that has made no connections for a whole `window` while short on
+/// peers has most likely stopped accepting or dialing, rather than just being quiet.
+fn swarm_looks_wedged(
+    last_activity: SystemTime,
+    now: SystemTime,
+    window: Duration,
+    peer_count: usize,
+) -> bool {
+    peer_count < WATCHDOG_LOW_PEER_COUNT
+        && now
+            .duration_since(last_activity)
+            .map_or(false, |idle| idle >= window)
+}
+
 impl DhtMetrics {
     fn record_listen_addr(&mut self, addr: &Multiaddr) {
         let addr_str = addr.to_string();
@@ -1304,6 +1489,95 @@ impl DhtMetrics {
     }
 }
 
+/// Attach provider lookup results to the file search that started the lookup,
+/// answering it if its metadata record already arrived.
+async fn record_search_providers(
+    pending_search_queries: &Arc<Mutex<HashMap<kad::QueryId, PendingSearchQuery>>>,
+    providers_query_id: kad::QueryId,
+    file_hash: &str,
+    providers: Vec<String>,
+    event_tx: &mpsc::Sender<DhtEvent>,
+) {
+    let mut search_queries = pending_search_queries.lock().await;
+    // Provider lookups started outside SearchFile still count for a search of the same file
+    let record_query_id = search_queries
+        .iter()
+        .find(|(_, q)| q.providers_query_id == Some(providers_query_id))
+        .or_else(|| {
+            search_queries
+                .iter()
+                .find(|(_, q)| q.file_hash == file_hash)
+        })
+        .map(|(id, _)| *id);
+    let Some(record_query_id) = record_query_id else {
+        return;
+    };
+
+    let Some(pending_search) = search_queries.get_mut(&record_query_id) else {
+        return;
+    };
+    pending_search.add_providers(providers);
+    info!(
+        "✅ Updated pending search for {} with {} providers",
+        file_hash,
+        pending_search.found_providers.as_ref().map_or(0, Vec::len)
+    );
+
+    if pending_search.found_record.is_some() {
+        if let Some(pending_search) = search_queries.remove(&record_query_id) {
+            drop(search_queries);
+            pending_search.respond(event_tx).await;
+        }
+    }
+}
+
+/// Handle the end of a search's provider lookup that reported no providers.
+/// Returns false if `providers_query_id` doesn't belong to a file search.
+async fn complete_search_without_providers(
+    pending_search_queries: &Arc<Mutex<HashMap<kad::QueryId, PendingSearchQuery>>>,
+    providers_query_id: kad::QueryId,
+    event_tx: &mpsc::Sender<DhtEvent>,
+) -> bool {
+    let mut search_queries = pending_search_queries.lock().await;
+    let Some(record_query_id) = search_queries
+        .iter()
+        .find(|(_, q)| q.providers_query_id == Some(providers_query_id))
+        .map(|(id, _)| *id)
+    else {
+        return false;
+    };
+
+    let waiting_for_providers = search_queries
+        .get(&record_query_id)
+        .is_some_and(|q| q.found_record.is_some());
+    if waiting_for_providers {
+        if let Some(pending_search) = search_queries.remove(&record_query_id) {
+            drop(search_queries);
+            pending_search.respond(event_tx).await;
+        }
+    } else if let Some(pending_search) = search_queries.get_mut(&record_query_id) {
+        // Don't hold the record back waiting for providers when it arrives
+        pending_search.add_providers(Vec::new());
+    }
+    true
+}
+
+/// Reason a `GetRecord` query failed for anything but a missing key, which
+/// `FileNotFound` covers
+fn get_record_failure_reason(err: &kad::GetRecordError) -> Option<String> {
+    match err {
+        kad::GetRecordError::NotFound { .. } => None,
+        kad::GetRecordError::Timeout { .. } => Some("timeout".to_string()),
+        kad::GetRecordError::QuorumFailed {
+            records, quorum, ..
+        } => Some(format!(
+            "quorum failed: {} of {} records",
+            records.len(),
+            quorum
+        )),
+    }
+}
+
 async fn notify_pending_searches(
     pending: &Arc<Mutex<HashMap<String, Vec<PendingSearch>>>>,
     key: &str,
@@ -1369,6 +1643,15 @@ async fn run_dht_node(
     bootstrap_peer_ids: HashSet<PeerId>,
     pure_client_mode: bool,
     force_server_mode: bool,
+    put_quorum_target: usize,
+    peer_cache_path: Option<PathBuf>,
+    peer_filter: PeerFilter,
+    republish_interval: Duration,
+    owned_records: Arc<Mutex<HashMap<String, FileMetadata>>>,
+    ping_failure_threshold: u32,
+    mut tcp_listeners: Vec<(Multiaddr, Option<ListenerId>)>,
+    watchdog_window: Option<Duration>,
+    mut bootstrap_reconnects: BootstrapReconnects,
 ) {
     // Track peers that support relay (discovered via identify protocol)
     let relay_capable_peers: Arc<Mutex<HashMap<PeerId, Vec<Multiaddr>>>> =
@@ -1385,6 +1668,23 @@ async fn run_dht_node(
         tokio::time::interval(Duration::from_secs(24 * 60 * 60)) // 24 hours if disabled
     };
     relay_discovery_interval.tick().await;
+    // Re-announce our own records before they expire on other nodes
+    let mut republish_ticker = tokio::time::interval(republish_interval);
+    republish_ticker.tick().await;
+    // Record puts whose caller wants to know how many peers stored the record,
+    // with the quorum each was issued with
+    let pending_put_queries: Arc<Mutex<HashMap<kad::QueryId, (usize, oneshot::Sender<usize>)>>> =
+        Arc::new(Mutex::new(HashMap::new()));
+    // Watchdog for a swarm that silently stops making connections
+    let mut watchdog_ticker = tokio::time::interval(
+        watchdog_window
+            .map(|window| (window / 4).max(Duration::from_millis(50)))
+            .unwrap_or(Duration::from_secs(24 * 60 * 60)),
+    );
+    watchdog_ticker.tick().await;
+    let mut last_swarm_reset = SystemTime::now();
+    // Redials bootstrap peers that dropped, on their own backoff schedule
+    let mut bootstrap_reconnect_ticker = tokio::time::interval(Duration::from_millis(500));
     // Periodic bootstrap interval
 
     /// Creates a proper circuit relay address for connecting through a relay peer
@@ -1452,10 +1752,12 @@ async fn run_dht_node(
     }
 
     let mut shutdown_ack: Option<oneshot::Sender<()>> = None;
-    let mut ping_failures: HashMap<PeerId, u8> = HashMap::new();
+    let mut ping_failures: HashMap<PeerId, u32> = HashMap::new();
     let mut relay_blacklist: HashSet<PeerId> = HashSet::new();
     let mut relay_cooldown: HashMap<PeerId, Instant> = HashMap::new();
     let mut last_tried_relay: Option<PeerId> = None;
+    // Circuit listeners we opened to hold a relay reservation
+    let mut relay_listeners: HashMap<ListenerId, PeerId> = HashMap::new();
 
     let queries: HashMap<beetswap::QueryId, u32> = HashMap::new();
     let downloaded_chunks: HashMap<usize, Vec<u8>> = HashMap::new();
@@ -1607,6 +1909,104 @@ async fn run_dht_node(
                                 info!("🔍 Periodic relay discovery started (QueryId: {:?})", query_id);
                             }
 
+                            _ = watchdog_ticker.tick(), if watchdog_window.is_some() => {
+                                // A listener that failed to re-bind last time is retried every tick
+                                for (listen_addr, tcp_listener) in tcp_listeners.iter_mut() {
+                                    if tcp_listener.is_none() {
+                                        match swarm.listen_on(listen_addr.clone()) {
+                                            Ok(id) => *tcp_listener = Some(id),
+                                            Err(e) => warn!("Watchdog could not listen on {}: {}", listen_addr, e),
+                                        }
+                                    }
+                                }
+
+                                let window = watchdog_window.unwrap_or(DEFAULT_WATCHDOG_WINDOW);
+                                let last_connection = metrics.lock().await.last_connection_event;
+                                let last_activity = last_connection
+                                    .map_or(last_swarm_reset, |t| t.max(last_swarm_reset));
+                                let peer_count = connected_peers.lock().await.len();
+                                let now = SystemTime::now();
+                                if swarm_looks_wedged(last_activity, now, window, peer_count) {
+                                    let idle_secs = now
+                                        .duration_since(last_activity)
+                                        .map(|d| d.as_secs())
+                                        .unwrap_or_default();
+                                    warn!(
+                                        "🐕 No connection events for {}s with {} peer(s); resetting listener and bootstrapping",
+                                        idle_secs, peer_count
+                                    );
+                                    // The old socket stays bound until the swarm is polled, so
+                                    // each listener is re-bound once its ListenerClosed arrives
+                                    for (_, tcp_listener) in &tcp_listeners {
+                                        if let Some(id) = tcp_listener {
+                                            swarm.remove_listener(*id);
+                                        }
+                                    }
+                                    if let Err(e) = swarm.behaviour_mut().kademlia.bootstrap() {
+                                        warn!("Watchdog bootstrap failed: {:?}", e);
+                                    }
+                                    last_swarm_reset = now;
+                                    let _ = event_tx
+                                        .send(DhtEvent::SwarmReset { idle_secs, peer_count })
+                                        .await;
+                                }
+                            }
+
+                            _ = bootstrap_reconnect_ticker.tick(), if !bootstrap_reconnects.is_idle() => {
+                                for (peer, addrs) in bootstrap_reconnects.due(Instant::now()) {
+                                    info!("🔁 Reconnecting to bootstrap peer {}", peer);
+                                    let opts = PeerDialOpts::peer_id(peer)
+                                        .addresses(addrs)
+                                        .condition(PeerCondition::Disconnected)
+                                        .build();
+                                    if let Err(e) = swarm.dial(opts) {
+                                        debug!("Bootstrap reconnect to {} not dialed: {}", peer, e);
+                                    }
+                                }
+                            }
+
+                            _ = republish_ticker.tick() => {
+                                let records: Vec<FileMetadata> =
+                                    owned_records.lock().await.values().cloned().collect();
+                                if !records.is_empty() {
+                                    let connected_peers_count = connected_peers.lock().await.len();
+                                    let (quorum, _) = select_put_quorum(put_quorum_target, connected_peers_count);
+                                    let can_provide = swarm_has_dialable_addr(&swarm);
+                                    info!("🔁 Republishing {} DHT record(s)", records.len());
+                                    for metadata in &records {
+                                        let record_key = kad::RecordKey::new(&metadata.merkle_root.as_bytes());
+                                        // Prefer the heartbeat-tracked record so live seeder entries aren't reset
+                                        let dht_metadata = seeder_heartbeats_cache
+                                            .lock()
+                                            .await
+                                            .get(&metadata.merkle_root)
+                                            .map(|entry| entry.metadata.clone())
+                                            .unwrap_or_else(|| file_record_json(metadata));
+                                        let value = match serde_json::to_vec(&dht_metadata) {
+                                            Ok(value) => value,
+                                            Err(e) => {
+                                                warn!("Failed to serialize {} for republish: {}", metadata.merkle_root, e);
+                                                continue;
+                                            }
+                                        };
+                                        let record = Record {
+                                            key: record_key.clone(),
+                                            value,
+                                            publisher: Some(peer_id),
+                                            expires: None,
+                                        };
+                                        if let Err(e) = swarm.behaviour_mut().kademlia.put_record(record, quorum) {
+                                            warn!("Failed to republish record {}: {}", metadata.merkle_root, e);
+                                        }
+                                        if can_provide {
+                                            if let Err(e) = swarm.behaviour_mut().kademlia.start_providing(record_key) {
+                                                warn!("Failed to republish provider record {}: {}", metadata.merkle_root, e);
+                                            }
+                                        }
+                                    }
+                                }
+                            }
+
                             cmd = cmd_rx.recv() => {
                                 match cmd {
                                     Some(DhtCommand::Shutdown(ack)) => {
@@ -1614,7 +2014,7 @@ async fn run_dht_node(
                                         shutdown_ack = Some(ack);
                                         break 'outer;
                                     }
-                                    Some(DhtCommand::PublishFile { mut metadata, response_tx }) => {
+                                    Some(DhtCommand::PublishFile { mut metadata, response_tx, stored_tx }) => {
             let now = unix_timestamp();
             let peer_id_str = peer_id.to_string();
 
@@ -1640,29 +2040,7 @@ async fn run_dht_node(
             }
 
             // 4. Create the JSON for DHT storage
-            let dht_metadata = serde_json::json!({
-                "file_hash": merged_metadata.merkle_root, // Changed from file_hash
-                "merkle_root": merged_metadata.merkle_root,
-                "file_name": merged_metadata.file_name,
-                "file_size": merged_metadata.file_size,
-                "created_at": merged_metadata.created_at,
-                "mime_type": merged_metadata.mime_type,
-                "is_encrypted": merged_metadata.is_encrypted,
-                "encryption_method": merged_metadata.encryption_method,
-                "key_fingerprint": merged_metadata.key_fingerprint,
-                "parent_hash": merged_metadata.parent_hash,
-                "cids": merged_metadata.cids,
-                "encrypted_key_bundle": merged_metadata.encrypted_key_bundle,
-                "info_hash": merged_metadata.info_hash,
-                "trackers": merged_metadata.trackers,
-                "seeders": merged_metadata.seeders,
-                "seederHeartbeats": [],
-                "price": merged_metadata.price,
-                "uploader_address": merged_metadata.uploader_address,
-                "httpSources": merged_metadata.http_sources,
-                "ed2kSources": merged_metadata.ed2k_sources,
-                "ftpSources": merged_metadata.ftp_sources,
-            });
+            let dht_metadata = file_record_json(&merged_metadata);
 
             let record_key = kad::RecordKey::new(&merged_metadata.merkle_root.as_bytes());
 
@@ -1682,26 +2060,24 @@ async fn run_dht_node(
             };
 
             let connected_peers_count = connected_peers.lock().await.len();
-            let replication_factor = 3;
-
-            let quorum = if connected_peers_count >= 3 {
-                let half_up = (connected_peers_count + 1) / 2;
-                let target = std::cmp::min(replication_factor, std::cmp::max(1, half_up));
-                if let Some(n) = std::num::NonZeroUsize::new(target) {
-                    kad::Quorum::N(n)
-                } else {
-                    kad::Quorum::One
-                }
-            } else {
-                kad::Quorum::One
-            };
+            let (quorum, quorum_size) = select_put_quorum(put_quorum_target, connected_peers_count);
+            info!(
+                "Publishing {} with quorum {} ({} peers connected)",
+                merged_metadata.merkle_root, quorum_size, connected_peers_count
+            );
 
             match swarm.behaviour_mut().kademlia.put_record(record, quorum) {
-                Ok(_) => {
+                Ok(query_id) => {
                     // FIX: Use indexing for JSON value access instead of dot notation
                     info!("put file: {}", dht_metadata["file_hash"]);
+                    if let Some(stored_tx) = stored_tx {
+                        pending_put_queries.lock().await.insert(query_id, (quorum_size, stored_tx));
+                    }
                 }
                 Err(e) => {
+                    if let Some(stored_tx) = stored_tx {
+                        let _ = stored_tx.send(0);
+                    }
                     error!("failed to put file {}: {}", merged_metadata.merkle_root, e);
                     let _ = event_tx.send(DhtEvent::Error(format!("failed to start providing: {}", e))).await;
                 }
@@ -1820,6 +2196,11 @@ async fn run_dht_node(
                                         if let Err(e) = swarm.behaviour_mut().kademlia.put_record(record, kad::Quorum::One) {
                                             error!("Failed to put record for encrypted file {}: {}", metadata.merkle_root, e);
                                         }
+                                        {
+                                            let mut owned = metadata.clone();
+                                            owned.file_data.clear();
+                                            owned_records.lock().await.insert(owned.merkle_root.clone(), owned);
+                                        }
 
                                         // 4. Announce self as provider (only if we have dialable addrs)
                                         if !swarm_has_dialable_addr(&swarm) {
@@ -2070,26 +2451,12 @@ async fn run_dht_node(
 
                                             // Determine appropriate quorum based on number of connected peers
                                         let connected_peers_count = connected_peers.lock().await.len();
-                                        let replication_factor = 3; // Must match kad_cfg.set_replication_factor
-
-                                        let quorum = if connected_peers_count >= replication_factor {
-                                            // Use N(3) for better reliability in heartbeat updates
-                                            if let Some(n) = std::num::NonZeroUsize::new(replication_factor) {
-                                                debug!(
-                                                    "Using Quorum::N({}) for heartbeat update of {} ({} peers available)",
-                                                    replication_factor, file_hash, connected_peers_count
-                                                );
-                                                kad::Quorum::N(n)
-                                            } else {
-                                                kad::Quorum::One
-                                            }
-                                        } else {
-                                            debug!(
-                                                "Using Quorum::One for heartbeat update of {} (only {} peers available)",
-                                                file_hash, connected_peers_count
-                                            );
-                                            kad::Quorum::One
-                                        };
+                                        let (quorum, quorum_size) =
+                                            select_put_quorum(put_quorum_target, connected_peers_count);
+                                        debug!(
+                                            "Using quorum {} for heartbeat update of {} ({} peers available)",
+                                            quorum_size, file_hash, connected_peers_count
+                                        );
 
                                         match swarm
                                             .behaviour_mut()
@@ -2160,6 +2527,11 @@ async fn run_dht_node(
                                         info!("🔍 Searching for file metadata: {} (record query: {:?})", file_hash, record_query_id);
                                         pending_query.record_query_id = Some(record_query_id);
 
+                                        // Every seeder registers as a provider, so this finds seeders
+                                        // the (single) metadata record doesn't list
+                                        let providers_query_id = swarm.behaviour_mut().kademlia.get_providers(key);
+                                        pending_query.providers_query_id = Some(providers_query_id);
+
                                         // Track both queries under the record query ID (primary)
                                         pending_search_queries.lock().await.insert(record_query_id, pending_query);
                                     }
@@ -2274,6 +2646,27 @@ async fn run_dht_node(
                                                 }
                                             });
 
+                                            if let Some(reason) = peer_filter.rejection(maybe_peer_id.as_ref(), Some(&multiaddr)) {
+                                                warn!("🚫 Refusing to dial {}: {}", multiaddr, reason);
+                                                let _ = event_tx.send(DhtEvent::PeerBlocked {
+                                                    peer_id: maybe_peer_id.map(|p| p.to_string()),
+                                                    address: Some(multiaddr.to_string()),
+                                                    reason,
+                                                }).await;
+                                                continue;
+                                            }
+
+                                            // Already a relayed address: the relay is named in it, so dial as given
+                                            if multiaddr.iter().any(|p| matches!(p, Protocol::P2pCircuit)) {
+                                                if let Err(e) = swarm.dial(multiaddr.clone()) {
+                                                    error!("Failed to dial relayed address {}: {}", multiaddr, e);
+                                                    let _ = event_tx
+                                                        .send(DhtEvent::Error(format!("Failed to connect: {}", e)))
+                                                        .await;
+                                                }
+                                                continue;
+                                            }
+
                                             if let Some(peer_id) = maybe_peer_id.clone() {
                                                 // Check if the address contains a private IP
                                                 let has_private_ip = multiaddr.iter().any(|p| {
@@ -2519,6 +2912,9 @@ async fn run_dht_node(
                                         let _ = swarm.disconnect_peer_id(peer_id.clone());
                                         proxy_mgr.lock().await.remove_all(&peer_id);
                                     }
+                                    Some(DhtCommand::ReserveRelay(relay_addr)) => {
+                                        reserve_relay_slot(&mut swarm, &relay_addr, &mut relay_listeners, &event_tx).await;
+                                    }
                                     Some(DhtCommand::GetPeerCount(tx)) => {
                                         let count = connected_peers.lock().await.len();
                                         let _ = tx.send(count);
@@ -2611,6 +3007,35 @@ async fn run_dht_node(
                                             }
                                         }
                                     }
+                                    Some(DhtCommand::AnnounceIdentityRotation { old_peer_id, sender }) => {
+                                        let new_peer_id = peer_id.to_string();
+                                        let announcement = serde_json::json!({
+                                            "oldPeerId": old_peer_id,
+                                            "newPeerId": new_peer_id,
+                                            "rotatedAt": unix_timestamp(),
+                                        });
+                                        let record = kad::Record {
+                                            key: kad::RecordKey::new(&identity_rotation_key(&old_peer_id)),
+                                            value: announcement.to_string().into_bytes(),
+                                            publisher: Some(peer_id),
+                                            expires: None,
+                                        };
+
+                                        match swarm.behaviour_mut().kademlia.put_record(record, kad::Quorum::One) {
+                                            Ok(_) => {
+                                                info!("🔁 Announced identity rotation {} -> {}", old_peer_id, new_peer_id);
+                                                let _ = event_tx.send(DhtEvent::IdentityRotated {
+                                                    old: old_peer_id,
+                                                    new: new_peer_id,
+                                                }).await;
+                                                let _ = sender.send(Ok(()));
+                                            }
+                                            Err(e) => {
+                                                error!("❌ Failed to announce identity rotation for {}: {}", old_peer_id, e);
+                                                let _ = sender.send(Err(format!("Failed to announce identity rotation: {}", e)));
+                                            }
+                                        }
+                                    }
                                     Some(DhtCommand::GetDhtValue { key, sender }) => {
                                         info!("🔍 Fetching DHT value with key: {}", key);
                                         let record_key = kad::RecordKey::new(&key);
@@ -2755,6 +3180,8 @@ async fn run_dht_node(
                                             &pending_dht_queries,
                                             &pending_search_queries,
                                             &pending_relay_discoveries,
+                                            &pending_put_queries,
+                                            &metrics,
                                         )
                                         .await;
                                     }
@@ -2770,6 +3197,7 @@ async fn run_dht_node(
                                             &peer_selection,
                                             relay_capable_peers.clone(),
                                             &peer_id,
+                                            &peer_filter,
                                         )
                                         .await;
                                     }
@@ -2780,8 +3208,16 @@ async fn run_dht_node(
                                     }
                                     SwarmEvent::Behaviour(DhtBehaviourEvent::RelayClient(relay_event)) if !is_bootstrap => {
                                         match relay_event {
-                                            RelayClientEvent::ReservationReqAccepted { relay_peer_id, .. } => {
+                                            RelayClientEvent::ReservationReqAccepted { relay_peer_id, renewal, .. } => {
                                                 info!("✅ Relay reservation accepted from {}", relay_peer_id);
+                                                if !renewal {
+                                                    let _ = event_tx
+                                                        .send(DhtEvent::RelayReservation {
+                                                            relay: relay_peer_id.to_string(),
+                                                            ok: true,
+                                                        })
+                                                        .await;
+                                                }
                                                 let mut mgr = proxy_mgr.lock().await;
                                                 let newly_ready = mgr.mark_relay_ready(relay_peer_id);
                                                 drop(mgr);
@@ -3361,36 +3797,42 @@ async fn run_dht_node(
                                                     // Ignore
                                                 }
                                             }
-                                            libp2p::ping::Event { peer, result: Err(libp2p::ping::Failure::Timeout), .. } => {
-                                                let _ = event_tx
-                                                    .send(DhtEvent::Error(format!("Ping timeout {}", peer)))
-                                                    .await;
-                                                let count = ping_failures.entry(peer).or_insert(0);
-                                                *count += 1;
-                                                if *count >= 3 {
-                                                    swarm.behaviour_mut().kademlia.remove_peer(&peer);
-                                                    ping_failures.remove(&peer);
-                                                    let _ = event_tx.send(DhtEvent::Error(format!(
-                                                        "Peer {} removed after 3 failed pings", peer
-                                                    ))).await;
-                                                }
-                                            }
                                             libp2p::ping::Event { peer, result: Err(e), .. } => {
-                                                warn!("ping error with {}: {}", peer, e);
+                                                if matches!(e, libp2p::ping::Failure::Timeout) {
+                                                    let _ = event_tx
+                                                        .send(DhtEvent::Error(format!("Ping timeout {}", peer)))
+                                                        .await;
+                                                } else {
+                                                    warn!("ping error with {}: {}", peer, e);
+                                                }
                                                 let count = ping_failures.entry(peer).or_insert(0);
                                                 *count += 1;
-                                                if *count >= 3 {
+                                                if *count >= ping_failure_threshold {
+                                                    let failures = *count;
                                                     swarm.behaviour_mut().kademlia.remove_peer(&peer);
+                                                    let _ = swarm.disconnect_peer_id(peer);
                                                     ping_failures.remove(&peer);
-                                                    let _ = event_tx.send(DhtEvent::Error(format!(
-                                                        "Peer {} removed after 3 failed pings", peer
-                                                    ))).await;
+                                                    warn!("Peer {} unresponsive after {} failed pings; evicted", peer, failures);
+                                                    let _ = event_tx.send(DhtEvent::PeerUnresponsive {
+                                                        peer_id: peer.to_string(),
+                                                        failures,
+                                                    }).await;
                                                 }
                                             }
                                         }
                                     }
                                     SwarmEvent::Behaviour(DhtBehaviourEvent::AutonatClient(ev)) if !is_bootstrap => {
+                                        let was_private = metrics.lock().await.reachability_state == NatReachabilityState::Private;
                                         handle_autonat_client_event(&mut swarm, ev, &metrics, &event_tx).await;
+                                        let is_private = metrics.lock().await.reachability_state == NatReachabilityState::Private;
+
+                                        // Undialable directly: hold a reservation so peers can reach us through a relay
+                                        if is_private && !was_private && relay_listeners.is_empty() {
+                                            let relays = filter_relay_candidates(&relay_candidates, &relay_blacklist, &relay_cooldown);
+                                            for (_, relay_addr) in relays.into_iter().take(MAX_RELAY_RESERVATIONS) {
+                                                reserve_relay_slot(&mut swarm, &relay_addr, &mut relay_listeners, &event_tx).await;
+                                            }
+                                        }
                                     }
                                     SwarmEvent::Behaviour(DhtBehaviourEvent::AutonatServer(ev)) if !is_bootstrap => {
                                         debug!(?ev, "AutoNAT server event");
@@ -3411,6 +3853,17 @@ async fn run_dht_node(
                                     }
                                     SwarmEvent::ConnectionEstablished { peer_id, endpoint, num_established, .. } => {
                                         let remote_addr = endpoint.get_remote_address().clone();
+                                        if let Some(reason) = peer_filter.rejection(Some(&peer_id), Some(&remote_addr)) {
+                                            warn!("🚫 Disconnecting blocked peer {} ({}): {}", peer_id, remote_addr, reason);
+                                            let _ = swarm.disconnect_peer_id(peer_id);
+                                            let _ = event_tx.send(DhtEvent::PeerBlocked {
+                                                peer_id: Some(peer_id.to_string()),
+                                                address: Some(remote_addr.to_string()),
+                                                reason,
+                                            }).await;
+                                            continue;
+                                        }
+                                        bootstrap_reconnects.connected(&peer_id);
                                         let is_relay = remote_addr.iter().any(|p| matches!(p, Protocol::P2pCircuit));
 
                                         // Initialize peer metrics for smart selection
@@ -3434,8 +3887,11 @@ async fn run_dht_node(
                                             peers.insert(peer_id);
                                             peers.len()
                                         };
-                                        if let Ok(mut m) = metrics.try_lock() {
-                                            m.last_success = Some(SystemTime::now());
+                                        {
+                                            let mut m = metrics.lock().await;
+                                            let now = SystemTime::now();
+                                            m.last_success = Some(now);
+                                            m.last_connection_event = Some(now);
                                         }
 
                                         // Log connection type for diagnostics
@@ -3454,9 +3910,12 @@ async fn run_dht_node(
                                             })
                                             .await;
                                     }
-                                    SwarmEvent::ConnectionClosed { peer_id, cause, .. } => {
+                                    SwarmEvent::ConnectionClosed { peer_id, cause, num_established, .. } => {
                                         warn!("❌ DISCONNECTED from peer: {}", peer_id);
                                         warn!("   Cause: {:?}", cause);
+                                        if num_established == 0 && bootstrap_reconnects.disconnected(peer_id, Instant::now()) {
+                                            info!("Bootstrap peer {} dropped; scheduling reconnect", peer_id);
+                                        }
                                         swarm.behaviour_mut().kademlia.remove_peer(&peer_id);
 
                                         let peers_count = {
@@ -3580,14 +4039,24 @@ async fn run_dht_node(
                                         info!("   Remaining connected peers: {}", peers_count);
                                 }
                                     SwarmEvent::NewListenAddr { address, .. } => {
-                                        // Attempt to find an IPv4 protocol within the Multiaddr
-                                        if let Some(Protocol::Ip4(v4)) = address.iter().find(|p| matches!(p, Protocol::Ip4(_))) {
+                                        // Attempt to find an IPv4 or IPv6 protocol within the Multiaddr
+                                        let ip = address.iter().find_map(|p| match p {
+                                            Protocol::Ip4(v4) => Some(IpAddr::V4(v4)),
+                                            Protocol::Ip6(v6) => Some(IpAddr::V6(v6)),
+                                            _ => None,
+                                        });
+                                        if let Some(ip) = ip {
 
-                                            // Determine reachability: allow all in tests, otherwise reject loopback/private
-                                            let is_reachable = if cfg!(test) {
+                                            // Determine reachability: allow all in tests and relayed addresses
+                                            // (reachable wherever the relay is), otherwise reject loopback/private
+                                            let is_relayed = address.iter().any(|p| matches!(p, Protocol::P2pCircuit));
+                                            let is_reachable = if cfg!(test) || is_relayed {
                                                 true
                                             } else {
-                                                !v4.is_loopback() && !v4.is_private()
+                                                match ip {
+                                                    IpAddr::V4(v4) => !v4.is_loopback() && !v4.is_private(),
+                                                    IpAddr::V6(v6) => is_global_unicast_v6(v6),
+                                                }
                                             };
 
                                             if is_reachable {
@@ -3608,6 +4077,22 @@ async fn run_dht_node(
                                             }
                                         }
                                     }
+                                    SwarmEvent::IncomingConnectionError { send_back_addr, error: ListenError::Denied { cause }, .. }
+                                        if cause.downcast_ref::<connection_limits::Exceeded>().is_some() =>
+                                    {
+                                        warn!("Refused incoming connection from {}: {}", send_back_addr, cause);
+                                        if let Ok(mut m) = metrics.try_lock() {
+                                            m.connections_refused = m.connections_refused.saturating_add(1);
+                                        }
+                                    }
+                                    SwarmEvent::OutgoingConnectionError { peer_id, error: DialError::Denied { cause }, .. }
+                                        if cause.downcast_ref::<connection_limits::Exceeded>().is_some() =>
+                                    {
+                                        warn!("Refused outgoing connection to {:?}: {}", peer_id, cause);
+                                        if let Ok(mut m) = metrics.try_lock() {
+                                            m.connections_refused = m.connections_refused.saturating_add(1);
+                                        }
+                                    }
                                     SwarmEvent::OutgoingConnectionError { peer_id, error, .. } => {
                                         if let Ok(mut m) = metrics.try_lock() {
                                             m.last_error = Some(error.to_string());
@@ -3974,6 +4459,47 @@ async fn run_dht_node(
                                             RREvent::ResponseSent { .. } => {}
                                         }
                                     }
+                                    SwarmEvent::ListenerClosed { listener_id, reason, .. }
+                                        if tcp_listeners.iter().any(|(_, id)| *id == Some(listener_id)) =>
+                                    {
+                                        // Re-bind right away; if that fails the watchdog retries on its next tick
+                                        for (listen_addr, tcp_listener) in tcp_listeners.iter_mut() {
+                                            if *tcp_listener == Some(listener_id) {
+                                                warn!("TCP listener on {} closed: {:?}", listen_addr, reason);
+                                                *tcp_listener = match swarm.listen_on(listen_addr.clone()) {
+                                                    Ok(id) => Some(id),
+                                                    Err(e) => {
+                                                        warn!("Could not listen on {} again: {}", listen_addr, e);
+                                                        None
+                                                    }
+                                                };
+                                            }
+                                        }
+                                    }
+                                    SwarmEvent::ListenerClosed { listener_id, reason, .. }
+                                        if relay_listeners.contains_key(&listener_id) =>
+                                    {
+                                        if let Some(relay) = relay_listeners.remove(&listener_id) {
+                                            if let Err(e) = &reason {
+                                                let s = format!("{:?}", e);
+                                                match classify_err_str(&s) {
+                                                    RelayErrClass::Permanent => {
+                                                        relay_blacklist.insert(relay);
+                                                    }
+                                                    RelayErrClass::Transient => {
+                                                        relay_cooldown.insert(relay, Instant::now() + Duration::from_secs(600));
+                                                    }
+                                                }
+                                            }
+                                            warn!("Relay reservation with {} ended: {:?}", relay, reason);
+                                            let _ = event_tx
+                                                .send(DhtEvent::RelayReservation {
+                                                    relay: relay.to_string(),
+                                                    ok: false,
+                                                })
+                                                .await;
+                                        }
+                                    }
                                     SwarmEvent::ListenerClosed { reason, .. } if !is_bootstrap => {
                                         if !is_bootstrap{
                                         if reason.is_ok() {
@@ -4162,6 +4688,9 @@ async fn run_dht_node(
         }
     }
 
+    if let Some(path) = &peer_cache_path {
+        persist_routing_table(&mut swarm, path).await;
+    }
     connected_peers.lock().await.clear();
     info!("DHT node task exiting");
     if let Some(ack) = shutdown_ack {
@@ -4185,6 +4714,43 @@ fn addr_to_socket_addr(addr: &libp2p::Multiaddr) -> Option<SocketAddr> {
     }
 }
 
+/// Listen through the relay at `relay_addr`, which makes the relay client
+/// request a reservation there. Outcome arrives as `DhtEvent::RelayReservation`.
+async fn reserve_relay_slot(
+    swarm: &mut Swarm<DhtBehaviour>,
+    relay_addr: &Multiaddr,
+    relay_listeners: &mut HashMap<ListenerId, PeerId>,
+    event_tx: &mpsc::Sender<DhtEvent>,
+) {
+    let relay = relay_addr.iter().find_map(|p| match p {
+        Protocol::P2p(peer_id) => Some(peer_id),
+        _ => None,
+    });
+    let (Some(relay), Some(circuit_addr)) = (relay, build_relay_listen_addr(relay_addr)) else {
+        warn!("Relay address {} has no /p2p peer id", relay_addr);
+        return;
+    };
+    if relay_listeners.values().any(|p| *p == relay) {
+        return;
+    }
+
+    match swarm.listen_on(circuit_addr.clone()) {
+        Ok(listener_id) => {
+            info!("📡 Requesting relay reservation via {}", circuit_addr);
+            relay_listeners.insert(listener_id, relay);
+        }
+        Err(e) => {
+            warn!("Failed to listen on relay address {}: {}", circuit_addr, e);
+            let _ = event_tx
+                .send(DhtEvent::RelayReservation {
+                    relay: relay.to_string(),
+                    ok: false,
+                })
+                .await;
+        }
+    }
+}
+
 pub fn build_relay_listen_addr(base: &Multiaddr) -> Option<Multiaddr> {
     let mut out = base.clone();
     let has_p2p = out.iter().any(|p| matches!(p, Protocol::P2p(_)));
@@ -4328,17 +4894,190 @@ fn unix_timestamp() -> u64 {
         .as_secs()
 }
 
-fn merge_heartbeats(
-    mut a: Vec<SeederHeartbeat>,
-    mut b: Vec<SeederHeartbeat>,
-) -> Vec<SeederHeartbeat> {
-    let mut merged = Vec::new();
-    let mut seen_peers = std::collections::HashSet::new();
-    let now = unix_timestamp();
+/// JSON value stored in the DHT record for a published file
+fn file_record_json(metadata: &FileMetadata) -> serde_json::Value {
+    serde_json::json!({
+        "file_hash": metadata.merkle_root,
+        "merkle_root": metadata.merkle_root,
+        "file_name": metadata.file_name,
+        "file_size": metadata.file_size,
+        "created_at": metadata.created_at,
+        "mime_type": metadata.mime_type,
+        "is_encrypted": metadata.is_encrypted,
+        "encryption_method": metadata.encryption_method,
+        "key_fingerprint": metadata.key_fingerprint,
+        "parent_hash": metadata.parent_hash,
+        "cids": metadata.cids,
+        "encrypted_key_bundle": metadata.encrypted_key_bundle,
+        "info_hash": metadata.info_hash,
+        "trackers": metadata.trackers,
+        "seeders": metadata.seeders,
+        "seederHeartbeats": [],
+        "price": metadata.price,
+        "uploader_address": metadata.uploader_address,
+        "httpSources": metadata.http_sources,
+        "ed2kSources": metadata.ed2k_sources,
+        "ftpSources": metadata.ftp_sources,
+    })
+}
 
-    // Create sets to track which peers appear in both vectors
-    let a_peers: HashSet<String> = a.iter().map(|hb| hb.peer_id.clone()).collect();
-    let b_peers: HashSet<String> = b.iter().map(|hb| hb.peer_id.clone()).collect();
+/// Pick the quorum for a record put: as many peers as are connected, capped at
+/// `target`, and never less than one so isolated nodes still store locally.
+/// Returns the quorum together with the number of peers it requires.
+fn select_put_quorum(target: usize, connected_peers: usize) -> (kad::Quorum, usize) {
+    let size = target.min(connected_peers).max(1);
+    match std::num::NonZeroUsize::new(size) {
+        Some(n) if size > 1 => (kad::Quorum::N(n), size),
+        _ => (kad::Quorum::One, 1),
+    }
+}
+
+/// Add the peers saved by a previous run to the routing table so the node can
+/// rejoin without relying on the bootstrap nodes alone. Returns how many peers
+/// were added.
+async fn restore_routing_table(
+    swarm: &mut Swarm<DhtBehaviour>,
+    local_peer_id: &PeerId,
+    path: &std::path::Path,
+) -> usize {
+    let mut cache = match PeerCache::load_from_file(path).await {
+        Ok(cache) => cache,
+        Err(e) => {
+            warn!("Ignoring unreadable peer cache: {}", e);
+            let _ = PeerCache::delete_file(path).await;
+            return 0;
+        }
+    };
+    cache.filter_stale_peers();
+
+    let mut restored = 0;
+    for entry in &cache.peers {
+        let Ok(peer_id) = entry.peer_id.parse::<PeerId>() else {
+            continue;
+        };
+        if peer_id == *local_peer_id {
+            continue;
+        }
+        let mut added = false;
+        for addr in entry
+            .addresses
+            .iter()
+            .filter_map(|a| a.parse::<Multiaddr>().ok())
+        {
+            swarm.behaviour_mut().kademlia.add_address(&peer_id, addr);
+            added = true;
+        }
+        if added {
+            restored += 1;
+        }
+    }
+    info!("Restored {} peers from cache at {:?}", restored, path);
+    restored
+}
+
+/// SHA-256 (hex) and size of the file at `path`, streamed in 64 KiB reads
+async fn hash_local_file(path: &std::path::Path) -> Result<(String, u64), String> {
+    use tokio::io::AsyncReadExt;
+
+    let mut file = tokio::fs::File::open(path)
+        .await
+        .map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0u8; 64 * 1024];
+    let mut size = 0u64;
+    loop {
+        let n = file
+            .read(&mut buffer)
+            .await
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buffer[..n]);
+        size += n as u64;
+    }
+    Ok((hex::encode(hasher.finalize()), size))
+}
+
+/// Save the routing table's peers and addresses for the next start.
+async fn persist_routing_table(swarm: &mut Swarm<DhtBehaviour>, path: &std::path::Path) {
+    let now = unix_timestamp();
+    let peers: Vec<PeerCacheEntry> = swarm
+        .behaviour_mut()
+        .kademlia
+        .kbuckets()
+        .flat_map(|bucket| {
+            bucket
+                .iter()
+                .filter_map(|entry| {
+                    let addresses: Vec<String> =
+                        entry.node.value.iter().map(|a| a.to_string()).collect();
+                    if addresses.is_empty() {
+                        return None;
+                    }
+                    Some(PeerCacheEntry {
+                        peer_id: entry.node.key.preimage().to_string(),
+                        addresses,
+                        last_seen: now,
+                        connection_count: 0,
+                        successful_transfers: 0,
+                        failed_transfers: 0,
+                        total_bytes_transferred: 0,
+                        average_latency_ms: 0,
+                        is_bootstrap: false,
+                        supports_relay: false,
+                        reliability_score: 0.0,
+                    })
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect();
+
+    let mut cache = PeerCache::from_peers(peers);
+    cache.sort_and_limit();
+    if let Err(e) = cache.save_to_file(path).await {
+        warn!("Failed to persist routing table: {}", e);
+    }
+}
+
+/// Number of peers that stored a record whose put did not reach its quorum.
+fn put_record_successes(err: &kad::PutRecordError) -> usize {
+    match err {
+        kad::PutRecordError::QuorumFailed { success, .. }
+        | kad::PutRecordError::Timeout { success, .. } => success.len(),
+    }
+}
+
+fn identity_rotation_key(old_peer_id: &str) -> String {
+    format!("{}{}", IDENTITY_ROTATION_PREFIX, old_peer_id)
+}
+
+/// Swap `old` for `new` in a seeder list, optionally keeping `old` listed while
+/// a rotation grace period is still running.
+fn migrate_seeders(seeders: &[String], old: &str, new: &str, keep_old: bool) -> Vec<String> {
+    let mut migrated: Vec<String> = seeders
+        .iter()
+        .filter(|s| s.as_str() != old && s.as_str() != new)
+        .cloned()
+        .collect();
+    migrated.insert(0, new.to_string());
+    if keep_old {
+        migrated.push(old.to_string());
+    }
+    migrated
+}
+
+fn merge_heartbeats(
+    mut a: Vec<SeederHeartbeat>,
+    mut b: Vec<SeederHeartbeat>,
+) -> Vec<SeederHeartbeat> {
+    let mut merged = Vec::new();
+    let mut seen_peers = std::collections::HashSet::new();
+    let now = unix_timestamp();
+
+    // Create sets to track which peers appear in both vectors
+    let a_peers: HashSet<String> = a.iter().map(|hb| hb.peer_id.clone()).collect();
+    let b_peers: HashSet<String> = b.iter().map(|hb| hb.peer_id.clone()).collect();
     let common_peers: HashSet<_> = a_peers.intersection(&b_peers).cloned().collect();
 
     // Filter and collect entries in one pass instead of using retain
@@ -4478,6 +5217,74 @@ fn heartbeats_to_peer_list(entries: &[SeederHeartbeat]) -> Vec<String> {
     entries.iter().map(|hb| hb.peer_id.clone()).collect()
 }
 
+/// Reconnect schedule for the configured bootstrap peers. A peer that drops
+/// is redialed after `initial`, then after a delay that doubles per attempt up
+/// to `max`; a successful connection clears its backoff.
+struct BootstrapReconnects {
+    addrs: HashMap<PeerId, Vec<Multiaddr>>,
+    /// Next attempt and current delay for each disconnected bootstrap peer
+    pending: HashMap<PeerId, (Instant, Duration)>,
+    initial: Duration,
+    max: Duration,
+}
+
+impl BootstrapReconnects {
+    fn new(bootstrap_nodes: &[String], initial: Duration, max: Duration) -> Self {
+        let mut addrs: HashMap<PeerId, Vec<Multiaddr>> = HashMap::new();
+        let parsed = bootstrap_nodes
+            .iter()
+            .filter_map(|s| s.parse::<Multiaddr>().ok());
+        for ma in parsed {
+            let peer_id = ma.iter().find_map(|p| match p {
+                Protocol::P2p(peer_id) => Some(peer_id),
+                _ => None,
+            });
+            if let Some(peer_id) = peer_id {
+                addrs.entry(peer_id).or_default().push(ma);
+            }
+        }
+        Self {
+            addrs,
+            pending: HashMap::new(),
+            initial,
+            max,
+        }
+    }
+
+    /// Schedule a reconnect if `peer` is a bootstrap peer; returns whether it is one
+    fn disconnected(&mut self, peer: PeerId, now: Instant) -> bool {
+        if !self.addrs.contains_key(&peer) {
+            return false;
+        }
+        self.pending
+            .entry(peer)
+            .or_insert((now + self.initial, self.initial));
+        true
+    }
+
+    fn connected(&mut self, peer: &PeerId) {
+        self.pending.remove(peer);
+    }
+
+    /// Peers whose attempt is due, with their addresses. Each one's next
+    /// attempt is pushed back by double the previous delay.
+    fn due(&mut self, now: Instant) -> Vec<(PeerId, Vec<Multiaddr>)> {
+        let mut due = Vec::new();
+        for (peer, (next_attempt, delay)) in self.pending.iter_mut() {
+            if *next_attempt <= now {
+                *delay = (*delay * 2).min(self.max);
+                *next_attempt = now + *delay;
+                due.push((*peer, self.addrs[peer].clone()));
+            }
+        }
+        due
+    }
+
+    fn is_idle(&self) -> bool {
+        self.pending.is_empty()
+    }
+}
+
 fn extract_bootstrap_peer_ids(bootstrap_nodes: &[String]) -> HashSet<PeerId> {
     use libp2p::multiaddr::Protocol;
     use libp2p::{Multiaddr, PeerId};
@@ -4517,6 +5324,8 @@ async fn handle_kademlia_event(
     pending_relay_discoveries: &Arc<
         Mutex<HashMap<kad::QueryId, oneshot::Sender<Result<Vec<String>, String>>>>,
     >,
+    pending_put_queries: &Arc<Mutex<HashMap<kad::QueryId, (usize, oneshot::Sender<usize>)>>>,
+    metrics: &Arc<Mutex<DhtMetrics>>,
 ) {
     match event {
         KademliaEvent::RoutingUpdated { peer, .. } => {
@@ -4528,6 +5337,43 @@ async fn handle_kademlia_event(
         KademliaEvent::RoutablePeer { peer, address, .. } => {
             debug!("Peer {} became routable", peer);
         }
+        KademliaEvent::OutboundQueryProgressed {
+            result: QueryResult::Bootstrap(result),
+            step,
+            ..
+        } => {
+            // Bootstrap reports once per refreshed bucket; only the last step is the outcome
+            if !step.last() {
+                return;
+            }
+            let (num_remaining, ok) = match result {
+                Ok(kad::BootstrapOk { num_remaining, .. }) => {
+                    info!(
+                        "✅ Bootstrap complete ({} queries remaining)",
+                        num_remaining
+                    );
+                    (Some(num_remaining), true)
+                }
+                Err(kad::BootstrapError::Timeout { num_remaining, .. }) => {
+                    warn!(
+                        "⏰ Bootstrap timed out ({:?} queries remaining)",
+                        num_remaining
+                    );
+                    (num_remaining, false)
+                }
+            };
+            {
+                let mut m = metrics.lock().await;
+                if ok {
+                    m.last_successful_bootstrap = Some(SystemTime::now());
+                } else {
+                    m.bootstrap_failures = m.bootstrap_failures.saturating_add(1);
+                }
+            }
+            let _ = event_tx
+                .send(DhtEvent::BootstrapCompleted { num_remaining, ok })
+                .await;
+        }
         KademliaEvent::OutboundQueryProgressed { id, result, .. } => {
             match result {
                 QueryResult::GetRecord(Ok(ok)) => match ok {
@@ -4543,7 +5389,7 @@ async fn handle_kademlia_event(
                         }
 
                         // Check if this is a response to a file search query
-                        if let Some(pending_search) =
+                        if let Some(mut pending_search) =
                             pending_search_queries.lock().await.remove(&id)
                         {
                             let search_file_hash = pending_search.file_hash.clone();
@@ -4647,6 +5493,19 @@ async fn handle_kademlia_event(
                                                 }
                                             }
 
+                                            // Provider lookup still running: answer once it reports
+                                            // so seeders missing from the record are included
+                                            if pending_search.providers_query_id.is_some()
+                                                && pending_search.found_providers.is_none()
+                                            {
+                                                pending_search.found_record = Some(metadata);
+                                                pending_search_queries
+                                                    .lock()
+                                                    .await
+                                                    .insert(id, pending_search);
+                                                return;
+                                            }
+
                                             // Send event to frontend for search results
                                             info!("📡 Sending DhtEvent::FileDiscovered for file: {} (CIDs: {:?}, FTP: {})",
                                             metadata.file_name,
@@ -5039,16 +5898,35 @@ async fn handle_kademlia_event(
                         return;
                     }
 
-                    // If the error includes the key, emit FileNotFound
-                    if let kad::GetRecordError::NotFound { key, .. } = err {
-                        let file_hash = String::from_utf8_lossy(key.as_ref()).to_string();
+                    // A file search can't complete without its metadata record
+                    if let Some(search) = pending_search_queries.lock().await.remove(&id) {
+                        let _ = search.sender.send(Ok(None));
+                    }
 
-                        // Also check if this was a failed info_hash lookup
-                        if let Some(search) = pending_infohash_searches.lock().await.remove(&id) {
-                            warn!("Infohash or subsequent merkle_root lookup failed for query {:?}: Not Found", id);
-                            let _ = search.sender.send(None);
-                        }
+                    // Also check if this was a failed info_hash lookup
+                    if let Some(search) = pending_infohash_searches.lock().await.remove(&id) {
+                        warn!(
+                            "Infohash or subsequent merkle_root lookup failed for query {:?}: {:?}",
+                            id, err
+                        );
+                        let _ = search.sender.send(None);
+                    }
 
+                    let file_hash = String::from_utf8_lossy(err.key().as_ref()).to_string();
+                    if let Some(reason) = get_record_failure_reason(&err) {
+                        let _ = event_tx
+                            .send(DhtEvent::QueryTimedOut {
+                                file_hash: file_hash.clone(),
+                                reason,
+                            })
+                            .await;
+                        notify_pending_searches(
+                            pending_searches,
+                            &file_hash,
+                            SearchResponse::NotFound,
+                        )
+                        .await;
+                    } else {
                         // Don't immediately emit FileNotFound - wait to see if providers query succeeds
                         // The providers query was already initiated in SearchFile command
                         info!("Metadata record not found for {}, checking if providers query will succeed", file_hash);
@@ -5094,6 +5972,12 @@ async fn handle_kademlia_event(
                 QueryResult::PutRecord(Ok(PutRecordOk { key })) => {
                     let key_str = String::from_utf8_lossy(key.as_ref());
                     info!("✅ PutRecord completed successfully for key: {}", key_str);
+                    // The put only succeeds once its quorum of peers stored the record
+                    if let Some((quorum_size, stored_tx)) =
+                        pending_put_queries.lock().await.remove(&id)
+                    {
+                        let _ = stored_tx.send(quorum_size);
+                    }
 
                     // Check if this is an info_hash index
                     if key_str.starts_with(INFO_HASH_PREFIX) {
@@ -5102,6 +5986,9 @@ async fn handle_kademlia_event(
                 }
                 QueryResult::PutRecord(Err(err)) => {
                     error!("❌ PutRecord failed: {:?}", err);
+                    if let Some((_, stored_tx)) = pending_put_queries.lock().await.remove(&id) {
+                        let _ = stored_tx.send(put_record_successes(&err));
+                    }
                     let _ = event_tx
                         .send(DhtEvent::Error(format!("PutRecord failed: {:?}", err)))
                         .await;
@@ -5270,24 +6157,25 @@ async fn handle_kademlia_event(
                             let provider_strings: Vec<String> =
                                 providers.iter().map(|p| p.to_string()).collect();
 
+                            if key_bytes != b"chiral:service:relay" {
+                                let _ = event_tx
+                                    .send(DhtEvent::ProvidersFound {
+                                        file_hash: file_hash.clone(),
+                                        providers: provider_strings.clone(),
+                                    })
+                                    .await;
+                            }
+
                             // Update pending search queries with found providers (for SearchFile queries)
                             // This is needed because SearchFile runs both GetRecord and GetProviders in parallel
-                            {
-                                let mut search_queries = pending_search_queries.lock().await;
-                                // Find the pending search query by file_hash and update its found_providers
-                                for (_query_id, pending_search) in search_queries.iter_mut() {
-                                    if pending_search.file_hash == file_hash {
-                                        pending_search.found_providers =
-                                            Some(provider_strings.clone());
-                                        info!(
-                                            "✅ Updated pending search for {} with {} providers",
-                                            file_hash,
-                                            provider_strings.len()
-                                        );
-                                        break;
-                                    }
-                                }
-                            }
+                            record_search_providers(
+                                pending_search_queries,
+                                id,
+                                &file_hash,
+                                provider_strings.clone(),
+                                event_tx,
+                            )
+                            .await;
 
                             // Provider results - check for direct queries first
                             // Check for direct provider queries (not from SearchFile)
@@ -5297,6 +6185,17 @@ async fn handle_kademlia_event(
                             }
                         }
                         Ok(kad::GetProvidersOk::FinishedWithNoAdditionalRecord { .. }) => {
+                            // A search's provider lookup is done; answer it with what was found
+                            if complete_search_without_providers(
+                                pending_search_queries,
+                                id,
+                                event_tx,
+                            )
+                            .await
+                            {
+                                return;
+                            }
+
                             // Check if this is a relay discovery query
                             let mut pending_relays = pending_relay_discoveries.lock().await;
                             if let Some(sender) = pending_relays.remove(&id) {
@@ -5333,6 +6232,17 @@ async fn handle_kademlia_event(
                         Err(err) => {
                             warn!("GetProviders query failed: {:?}", err);
 
+                            // Not finding providers doesn't fail a search that has the record
+                            if complete_search_without_providers(
+                                pending_search_queries,
+                                id,
+                                event_tx,
+                            )
+                            .await
+                            {
+                                return;
+                            }
+
                             // Extract file hash from error for proper cleanup
                             let kad::GetProvidersError::Timeout { key, .. } = &err;
                             let file_hash = String::from_utf8_lossy(key.as_ref()).to_string();
@@ -5355,23 +6265,6 @@ async fn handle_kademlia_event(
                         }
                     }
                 }
-                // QueryResult::Bootstrap(Ok(BootstrapOk {
-                //     peer,
-                //     num_remaining,
-                // })) => {
-                //     println!(
-                //         "✅ Bootstrap query succeeded. Peer: {peer}, remaining: {num_remaining}"
-                //     );
-                //     if num_remaining == 0 {
-                //         println!("🎯 Bootstrap fully complete!");
-                //     }
-                // }
-                // QueryResult::Bootstrap(Err(BootstrapError::Timeout { peer, .. })) => {
-                //     eprintln!("⏰ Bootstrap timed out; contacted peers: {:?}", peer);
-                // }
-                // QueryResult::Bootstrap(Err(e)) => {
-                //     eprintln!("❌ Bootstrap failed: {:?}", e);
-                // }
                 _ => {}
             }
         }
@@ -5389,10 +6282,24 @@ async fn handle_identify_event(
     peer_selection: &Arc<Mutex<PeerSelectionService>>,
     relay_capable_peers: Arc<Mutex<HashMap<PeerId, Vec<Multiaddr>>>>,
     local_peer_id: &PeerId,
+    peer_filter: &PeerFilter,
 ) {
     match event {
-        IdentifyEvent::Received { peer_id, info, .. } => {
+        IdentifyEvent::Received {
+            peer_id, mut info, ..
+        } => {
             info!("Identified peer {}: {:?}", peer_id, info.protocol_version);
+            if !peer_filter.allows_peer(&peer_id) {
+                swarm.behaviour_mut().kademlia.remove_peer(&peer_id);
+                return;
+            }
+            info.listen_addrs.retain(|addr| {
+                let blocked = peer_filter.blocks_addr(addr);
+                if blocked {
+                    debug!("Skipping blocked address from peer {}: {}", peer_id, addr);
+                }
+                !blocked
+            });
             // Add identified peer to Kademlia routing table
             if info.protocol_version != EXPECTED_PROTOCOL_VERSION {
                 warn!(
@@ -5444,6 +6351,13 @@ async fn handle_identify_event(
                 return;
             }
 
+            // Peers report their region in the agent version
+            let region = region_from_agent_version(&info.agent_version);
+            peer_selection
+                .lock()
+                .await
+                .set_peer_region(&peer_id.to_string(), region);
+
             let hop_proto = "/libp2p/circuit/relay/0.2.0/hop";
             let supports_relay = info
                 .protocols
@@ -5666,17 +6580,18 @@ async fn handle_autonat_client_event(
     if !metrics_guard.autonat_enabled {
         return;
     }
-    swarm.add_external_address(tested_addr.clone());
-    info!(
-        "Added {} to external address from autonat observed address.",
-        tested_addr
-    );
 
     let addr_str = tested_addr.to_string();
     let server_str = server.to_string();
     let (state, summary) = match result {
         Ok(()) => {
             metrics_guard.record_observed_addr(&tested_addr);
+            // Only advertise addresses a remote server actually dialed back on
+            swarm.add_external_address(tested_addr.clone());
+            info!(
+                "Added {} to external address from autonat observed address.",
+                tested_addr
+            );
             info!(
                 server = %server_str,
                 address = %addr_str,
@@ -5710,6 +6625,8 @@ async fn handle_autonat_client_event(
                 bytes = bytes_sent,
                 "AutoNAT probe failed"
             );
+            // Stop advertising an address peers cannot reach us on
+            swarm.remove_external_address(&tested_addr);
             (
                 NatReachabilityState::Private,
                 Some(format!(
@@ -5719,11 +6636,12 @@ async fn handle_autonat_client_event(
         }
     };
 
+    let previous_state = metrics_guard.reachability_state;
     metrics_guard.update_reachability(state, summary.clone());
     let nat_state = metrics_guard.reachability_state;
     let confidence = metrics_guard.reachability_confidence;
     let last_error = metrics_guard.last_reachability_error.clone();
-    let was_public = metrics_guard.reachability_state == NatReachabilityState::Public;
+    let was_public = previous_state == NatReachabilityState::Public;
     drop(metrics_guard);
 
     // If we just became public and have relay server enabled, advertise it in DHT
@@ -5752,6 +6670,15 @@ async fn handle_autonat_client_event(
         }
     }
 
+    if previous_state != nat_state {
+        let _ = event_tx
+            .send(DhtEvent::NatStatusChanged {
+                previous: previous_state,
+                current: nat_state,
+            })
+            .await;
+    }
+
     let _ = event_tx
         .send(DhtEvent::NatStatus {
             state: nat_state,
@@ -6204,7 +7131,7 @@ impl DhtService {
 // Public API for the DHT
 pub struct DhtService {
     cmd_tx: mpsc::Sender<DhtCommand>,
-    event_rx: Arc<Mutex<mpsc::Receiver<DhtEvent>>>,
+    events: Arc<EventBus<DhtEvent>>,
     peer_id: String,
     ed25519_secret_key: Arc<[u8; 32]>, // Store ed25519 secret for signing verdicts
     connected_peers: Arc<Mutex<HashSet<PeerId>>>,
@@ -6238,6 +7165,16 @@ pub struct DhtService {
     file_heartbeat_state: Arc<Mutex<HashMap<String, FileHeartbeatState>>>,
     seeder_heartbeats_cache: Arc<Mutex<HashMap<String, FileHeartbeatCacheEntry>>>,
     pending_heartbeat_updates: Arc<Mutex<HashSet<String>>>,
+    /// Records this node has published itself, keyed by merkle root
+    owned_records: Arc<Mutex<HashMap<String, FileMetadata>>>,
+    /// Unpublished negative verdicts from failed transfers, keyed by peer ID
+    verdict_drafts: Arc<Mutex<HashMap<String, VerdictDraft>>>,
+    query_limiter: Arc<Semaphore>,
+    max_concurrent_queries: usize,
+    /// Kademlia query timeout, also used to bound fire-and-forget lookups
+    query_timeout: Duration,
+    idle_timeout: Duration,
+    ping_interval: Option<Duration>,
 }
 use memmap2::MmapMut;
 use std::fs::OpenOptions;
@@ -6454,6 +7391,10 @@ struct FileHeartbeatState {
 
 pub struct DhtConfig<'a> {
     pub port: u16,
+    /// TCP addresses to listen on, e.g. a specific interface or `/ip6/::`.
+    /// Addresses without a `/tcp` part use `port`; empty means
+    /// `/ip4/0.0.0.0/tcp/{port}`.
+    pub listen_addrs: Vec<Multiaddr>,
     pub bootstrap_nodes: Vec<String>,
     pub secret: Option<String>,
     pub is_bootstrap: bool,
@@ -6472,12 +7413,59 @@ pub struct DhtConfig<'a> {
     pub force_server_mode: bool,
     pub last_autorelay_enabled_at: Option<SystemTime>,
     pub last_autorelay_disabled_at: Option<SystemTime>,
+    /// Maximum number of lookups (get_record/get_providers) in flight at once;
+    /// further lookups wait for a free slot.
+    pub max_concurrent_queries: usize,
+    /// Upper bound on the quorum used when putting file records; the quorum
+    /// actually used is capped by the number of connected peers.
+    pub put_quorum_target: usize,
+    /// Number of closest peers each Kademlia record is replicated to.
+    pub replication_factor: usize,
+    /// Timeout for a single Kademlia query.
+    pub query_timeout: Duration,
+    /// Maximum size in bytes of a Kademlia message.
+    pub max_packet_size: usize,
+    /// Kademlia protocol name, e.g. `/chiral/kad/1.0.0`.
+    pub protocol_name: String,
+    /// File the routing table is saved to on shutdown and restored from on
+    /// start; `None` disables persistence.
+    pub peer_cache_path: Option<PathBuf>,
+    /// When set, only these peers may connect or be dialed (private networks).
+    pub allowed_peers: Option<HashSet<PeerId>>,
+    /// IP ranges that are never dialed, added to the routing table or kept connected.
+    pub blocked_addrs: Vec<IpNet>,
+    /// How often records and provider entries published by this node are
+    /// re-announced so they don't age out of other nodes' stores.
+    pub republish_interval: Duration,
+    /// How long a connection with no active streams stays open. Lower values
+    /// save battery on laptops; bootstrap nodes can afford longer.
+    pub idle_timeout: Duration,
+    /// Interval between keepalive pings; `None` disables the ping behaviour.
+    pub ping_interval: Option<Duration>,
+    /// Consecutive failed pings after which a peer is evicted and
+    /// `DhtEvent::PeerUnresponsive` is emitted.
+    pub ping_failure_threshold: u32,
+    /// How long the swarm may go without any connection event, while short on
+    /// peers, before it re-listens and re-bootstraps. `None` disables the watchdog.
+    pub watchdog_window: Option<Duration>,
+    /// Maximum number of established connections across all peers; further
+    /// connections are refused and counted in `connections_refused`.
+    pub max_established_total: Option<u32>,
+    /// Maximum number of established connections to a single peer.
+    pub max_established_per_peer: Option<u32>,
+    /// Maximum number of connections still being negotiated, applied to
+    /// incoming and outgoing connections separately.
+    pub max_pending: Option<u32>,
+    /// Region this node reports to peers through identify, e.g. "EU", so they
+    /// can prefer it with `SelectionStrategy::RegionPreferred`.
+    pub region: Option<String>,
 }
 
 impl<'a> Default for DhtConfig<'a> {
     fn default() -> Self {
         Self {
             port: 0,
+            listen_addrs: Vec::new(),
             bootstrap_nodes: Vec::new(),
             secret: None,
             is_bootstrap: false,
@@ -6496,6 +7484,24 @@ impl<'a> Default for DhtConfig<'a> {
             force_server_mode: true,
             last_autorelay_enabled_at: None,
             last_autorelay_disabled_at: None,
+            max_concurrent_queries: DEFAULT_MAX_CONCURRENT_QUERIES,
+            put_quorum_target: DEFAULT_PUT_QUORUM_TARGET,
+            replication_factor: DEFAULT_REPLICATION_FACTOR,
+            query_timeout: DEFAULT_KAD_QUERY_TIMEOUT,
+            max_packet_size: DEFAULT_KAD_MAX_PACKET_SIZE,
+            protocol_name: DEFAULT_KAD_PROTOCOL.to_string(),
+            peer_cache_path: None,
+            allowed_peers: None,
+            blocked_addrs: Vec::new(),
+            republish_interval: DEFAULT_REPUBLISH_INTERVAL,
+            idle_timeout: DEFAULT_IDLE_TIMEOUT,
+            ping_interval: Some(DEFAULT_PING_INTERVAL),
+            ping_failure_threshold: DEFAULT_PING_FAILURE_THRESHOLD,
+            watchdog_window: Some(DEFAULT_WATCHDOG_WINDOW),
+            max_established_total: None,
+            max_established_per_peer: None,
+            max_pending: None,
+            region: None,
         }
     }
 }
@@ -6521,11 +7527,24 @@ impl<'a> DhtConfig<'a> {
         config.force_server_mode = true;
         // Optionally clear bootstrap nodes so it doesn't dial itself
         config.bootstrap_nodes = Vec::new();
-        config
+        config.with_bootstrap_connection_limits()
+    }
+
+    /// Apply the bootstrap connection caps to any limit not already set.
+    /// Every new node dials the bootstrap nodes, so cap what one can hold open.
+    pub fn with_bootstrap_connection_limits(mut self) -> Self {
+        self.max_established_total
+            .get_or_insert(DEFAULT_BOOTSTRAP_MAX_ESTABLISHED);
+        self.max_established_per_peer
+            .get_or_insert(DEFAULT_BOOTSTRAP_MAX_PER_PEER);
+        self.max_pending
+            .get_or_insert(DEFAULT_BOOTSTRAP_MAX_PENDING);
+        self
     }
 }
 impl DhtService {
-    // should maybe be migrated to use DhtConfig at some point...
+    /// Positional constructor kept for existing callers; settings not listed here
+    /// take their `DhtConfig::default()` values.
     pub async fn new(
         port: u16,
         bootstrap_nodes: Vec<String>,
@@ -6550,6 +7569,82 @@ impl DhtService {
         pure_client_mode: bool,
         force_server_mode: bool,
     ) -> Result<Self, Box<dyn Error>> {
+        let config = DhtConfig {
+            port,
+            bootstrap_nodes,
+            secret,
+            is_bootstrap,
+            enable_autonat,
+            autonat_probe_interval,
+            autonat_servers,
+            proxy_address,
+            chunk_size_kb,
+            cache_size_mb,
+            enable_autorelay,
+            preferred_relays,
+            enable_relay_server,
+            enable_upnp,
+            blockstore_db_path,
+            pure_client_mode,
+            force_server_mode,
+            last_autorelay_enabled_at,
+            last_autorelay_disabled_at,
+            ..DhtConfig::default()
+        };
+        Self::new_with_config(config, file_transfer_service, webrtc_service, chunk_manager).await
+    }
+
+    pub async fn new_with_config(
+        config: DhtConfig<'_>,
+        file_transfer_service: Option<Arc<FileTransferService>>,
+        webrtc_service: Option<Arc<crate::webrtc_service::WebRTCService>>,
+        chunk_manager: Option<Arc<ChunkManager>>,
+    ) -> Result<Self, Box<dyn Error>> {
+        let DhtConfig {
+            port,
+            listen_addrs,
+            bootstrap_nodes,
+            secret,
+            is_bootstrap,
+            enable_autonat,
+            autonat_probe_interval,
+            autonat_servers,
+            proxy_address,
+            chunk_size_kb,
+            cache_size_mb,
+            enable_autorelay,
+            preferred_relays,
+            enable_relay_server,
+            enable_upnp,
+            blockstore_db_path,
+            pure_client_mode,
+            force_server_mode,
+            last_autorelay_enabled_at,
+            last_autorelay_disabled_at,
+            max_concurrent_queries,
+            put_quorum_target,
+            replication_factor,
+            query_timeout,
+            max_packet_size,
+            protocol_name,
+            peer_cache_path,
+            allowed_peers,
+            blocked_addrs,
+            republish_interval,
+            idle_timeout,
+            ping_interval,
+            ping_failure_threshold,
+            watchdog_window,
+            max_established_total,
+            max_established_per_peer,
+            max_pending,
+            region,
+        } = config;
+        let max_concurrent_queries = max_concurrent_queries.max(1);
+        let ping_failure_threshold = ping_failure_threshold.max(1);
+        let replication_factor = replication_factor.max(1);
+        let listen_addrs = resolve_listen_addrs(&listen_addrs, port)?;
+
         // Respect user-configured AutoRelay preference (allow env to force-disable)
         let mut final_enable_autorelay = enable_autorelay;
         info!("AutoRelay requested: {}", enable_autorelay);
@@ -6559,7 +7654,9 @@ impl DhtService {
         }
         info!("AutoRelay enabled (final): {}", final_enable_autorelay);
         // Convert chunk size from KB to bytes
-        let chunk_size = chunk_size_kb.unwrap_or(256) * 1024; // Default 256 KB
+        let chunk_size = crate::manager::validate_chunk_size(
+            chunk_size_kb.map_or(crate::manager::DEFAULT_CHUNK_SIZE, |kb| kb * 1024),
+        )?;
         let cache_size = cache_size_mb.unwrap_or(1024); // Default 1024 MB
         let blockstore = if let Some(path) = blockstore_db_path {
             if let Some(path_str) = path.to_str() {
@@ -6609,7 +7706,9 @@ impl DhtService {
 
         // Create a Kademlia behaviour with tuned configuration
         let store = MemoryStore::new(local_peer_id);
-        let mut kad_cfg = KademliaConfig::new(StreamProtocol::new("/chiral/kad/1.0.0"));
+        let kad_protocol = StreamProtocol::try_from_owned(protocol_name)
+            .map_err(|e| format!("Invalid Kademlia protocol name: {}", e))?;
+        let mut kad_cfg = KademliaConfig::new(kad_protocol);
         let bootstrap_interval = Duration::from_secs(1);
         if is_bootstrap {
             // These settings result in node to not provide files, only acts as a router
@@ -6634,13 +7733,15 @@ impl DhtService {
             }
         }
 
-        // Align with docs: shorter queries, higher replication
-        kad_cfg.set_query_timeout(Duration::from_secs(30));
-
-        // Replication factor of 3 (as per spec table)
-        if let Some(nz) = std::num::NonZeroUsize::new(3) {
+        kad_cfg.set_query_timeout(query_timeout);
+        kad_cfg.set_max_packet_size(max_packet_size);
+        if let Some(nz) = std::num::NonZeroUsize::new(replication_factor) {
             kad_cfg.set_replication_factor(nz);
         }
+        info!(
+            "Kademlia config: replication factor {}, query timeout {:?}, max packet size {} bytes",
+            replication_factor, query_timeout, max_packet_size
+        );
 
         let mut kademlia = Kademlia::with_config(local_peer_id, store, kad_cfg);
 
@@ -6660,7 +7761,7 @@ impl DhtService {
         // Create identify behaviour with proactive push updates
         let identify_config =
             identify::Config::new(EXPECTED_PROTOCOL_VERSION.to_string(), local_key.public())
-                .with_agent_version(format!("chiral-network/{}", env!("CARGO_PKG_VERSION")))
+                .with_agent_version(agent_version(region.as_deref()))
                 .with_push_listen_addr_updates(true);
         let identify = identify::Behaviour::new(identify_config);
 
@@ -6820,17 +7921,21 @@ impl DhtService {
             // .with_quic() seems to destablize peer connect/download, disabled for now until solution
             .with_relay_client(noise::Config::new, yamux::Config::default)?
             .with_behaviour(move |_, relay_client_behaviour: relay::client::Behaviour| {
-                // Configure ping with more aggressive keep-alive to prevent connection drops
-                let ping_config = ping::Config::new()
-                    .with_interval(Duration::from_secs(15)) // Ping every 15 seconds (default is 15s)
-                    .with_timeout(Duration::from_secs(20)); // Timeout after 20 seconds (default is 20s)
+                // Keepalive pings detect dead peers; timeout stays at libp2p's 20s default
+                let ping_toggle = toggle::Toggle::from(ping_interval.map(|interval| {
+                    Ping::new(
+                        ping::Config::new()
+                            .with_interval(interval)
+                            .with_timeout(Duration::from_secs(20)),
+                    )
+                }));
 
                 DhtBehaviour {
                     kademlia,
                     identify,
                     mdns: mdns_toggle,
                     bitswap,
-                    ping: Ping::new(ping_config),
+                    ping: ping_toggle,
                     proxy_rr,
                     webrtc_signaling_rr,
                     key_request,
@@ -6840,16 +7945,23 @@ impl DhtService {
                     relay_server: relay_server_toggle,
                     dcutr: dcutr_toggle,
                     upnp: upnp_toggle,
+                    connection_limits: connection_limits::Behaviour::new(
+                        ConnectionLimits::default()
+                            .with_max_established(max_established_total)
+                            .with_max_established_per_peer(max_established_per_peer)
+                            .with_max_pending_incoming(max_pending)
+                            .with_max_pending_outgoing(max_pending),
+                    ),
                 }
             })?
-            .with_swarm_config(
-                |c| c.with_idle_connection_timeout(Duration::from_secs(300)), // 5 minutes
-            )
+            .with_swarm_config(|c| c.with_idle_connection_timeout(idle_timeout))
             .build();
 
-        // Always listen on the specified port
-        let tcp_addr: Multiaddr = format!("/ip4/0.0.0.0/tcp/{}", port).parse()?;
-        swarm.listen_on(tcp_addr)?;
+        let mut tcp_listeners = Vec::with_capacity(listen_addrs.len());
+        for addr in listen_addrs {
+            let listener = swarm.listen_on(addr.clone())?;
+            tcp_listeners.push((addr, Some(listener)));
+        }
 
         // QUIC also bound to the same port (udp), seems to destablize peer connect/download, disabled for now until solution
         // let quic_addr: Multiaddr = format!("/ip4/0.0.0.0/udp/{}/quic-v1", port).parse()?;
@@ -6961,14 +8073,20 @@ impl DhtService {
             }
         }
 
+        // Peers known from the previous run also seed the routing table
+        let restored_peers = match &peer_cache_path {
+            Some(path) => restore_routing_table(&mut swarm, &local_peer_id, path).await,
+            None => 0,
+        };
+
         // Trigger initial bootstrap only if we successfully connected to at least one bootstrap node
         // Kademlia bootstrap requires at least one peer in the routing table to work
-        if !bootstrap_nodes.is_empty() {
-            if successful_connections > 0 {
+        if !bootstrap_nodes.is_empty() || restored_peers > 0 {
+            if successful_connections > 0 || restored_peers > 0 {
                 let _ = swarm.behaviour_mut().kademlia.bootstrap();
                 info!(
-                    "✓ Starting Kademlia bootstrap with {} bootstrap connection(s)",
-                    successful_connections
+                    "✓ Starting Kademlia bootstrap with {} bootstrap connection(s) and {} cached peer(s)",
+                    successful_connections, restored_peers
                 );
             } else {
                 warn!("⚠ No bootstrap connections succeeded - cannot bootstrap DHT");
@@ -6981,6 +8099,8 @@ impl DhtService {
 
         let (cmd_tx, cmd_rx) = mpsc::channel(100);
         let (event_tx, event_rx) = mpsc::channel(100);
+        let events = Arc::new(EventBus::new(DEFAULT_EVENT_HISTORY));
+        events.forward_from(event_rx);
         let connected_peers = Arc::new(Mutex::new(HashSet::new()));
         let metrics = Arc::new(Mutex::new(DhtMetrics::default()));
         let pending_echo = Arc::new(Mutex::new(HashMap::new()));
@@ -7005,6 +8125,8 @@ impl DhtService {
             Arc::new(Mutex::new(HashMap::new()));
         let pending_heartbeat_updates: Arc<Mutex<HashSet<String>>> =
             Arc::new(Mutex::new(HashSet::new()));
+        let owned_records: Arc<Mutex<HashMap<String, FileMetadata>>> =
+            Arc::new(Mutex::new(HashMap::new()));
         let pending_infohash_searches: Arc<Mutex<HashMap<kad::QueryId, PendingInfohashSearch>>> =
             Arc::new(Mutex::new(HashMap::new()));
         let pending_dht_queries: Arc<
@@ -7036,6 +8158,11 @@ impl DhtService {
         // Spawn the Dht node task
         let received_chunks_clone = Arc::new(Mutex::new(HashMap::new()));
         let bootstrap_peer_ids = extract_bootstrap_peer_ids(&bootstrap_nodes);
+        let bootstrap_reconnects = BootstrapReconnects::new(
+            &bootstrap_nodes,
+            BOOTSTRAP_RECONNECT_INITIAL,
+            BOOTSTRAP_RECONNECT_MAX,
+        );
         let file_metadata_cache_local: Arc<Mutex<HashMap<String, FileMetadata>>> =
             Arc::new(Mutex::new(HashMap::new()));
         let pending_provider_registrations: Arc<Mutex<HashSet<String>>> =
@@ -7077,11 +8204,20 @@ impl DhtService {
             bootstrap_peer_ids,
             pure_client_mode,
             force_server_mode,
+            put_quorum_target,
+            peer_cache_path,
+            PeerFilter::new(allowed_peers, blocked_addrs),
+            republish_interval,
+            owned_records.clone(),
+            ping_failure_threshold,
+            tcp_listeners,
+            watchdog_window,
+            bootstrap_reconnects,
         ));
 
         Ok(DhtService {
             cmd_tx,
-            event_rx: Arc::new(Mutex::new(event_rx)),
+            events,
             peer_id: peer_id_str,
             ed25519_secret_key: Arc::new(ed25519_secret_key),
             connected_peers,
@@ -7107,47 +8243,65 @@ impl DhtService {
             file_heartbeat_state,
             seeder_heartbeats_cache,
             pending_heartbeat_updates,
+            owned_records,
+            verdict_drafts: Arc::new(Mutex::new(HashMap::new())),
+            query_limiter: Arc::new(Semaphore::new(max_concurrent_queries)),
+            max_concurrent_queries,
+            query_timeout,
+            idle_timeout,
+            ping_interval,
         })
     }
 
-    pub async fn new_with_config(
-        config: DhtConfig<'_>,
-        file_transfer_service: Option<Arc<FileTransferService>>,
-        webrtc_service: Option<Arc<crate::webrtc_service::WebRTCService>>,
-        chunk_manager: Option<Arc<ChunkManager>>,
-    ) -> Result<Self, Box<dyn Error>> {
-        // Call the existing function by destructuring the config
-        Self::new(
-            config.port,
-            config.bootstrap_nodes,
-            config.secret,
-            config.is_bootstrap,
-            config.enable_autonat,
-            config.autonat_probe_interval,
-            config.autonat_servers,
-            config.proxy_address,
-            file_transfer_service,
-            webrtc_service,
-            chunk_manager,
-            config.chunk_size_kb,
-            config.cache_size_mb,
-            config.enable_autorelay,
-            config.preferred_relays,
-            config.enable_relay_server,
-            config.enable_upnp,
-            config.blockstore_db_path,
-            config.last_autorelay_enabled_at,
-            config.last_autorelay_disabled_at,
-            config.pure_client_mode,
-            config.force_server_mode,
-        )
-        .await
-    }
     pub fn chunk_size(&self) -> usize {
         // Note: This might need to be adjusted if chunk_manager is the source of truth
         self.chunk_size
     }
 
+    /// Wait for a free lookup slot so a burst of lookups is queued here instead
+    /// of all being sent to our routing peers at once.
+    async fn acquire_query_permit(&self) -> Result<OwnedSemaphorePermit, String> {
+        self.query_limiter
+            .clone()
+            .acquire_owned()
+            .await
+            .map_err(|e| format!("query limiter closed: {}", e))
+    }
+
+    /// Number of lookups currently holding a slot
+    pub fn inflight_queries(&self) -> usize {
+        self.max_concurrent_queries - self.query_limiter.available_permits()
+    }
+
+    /// How long idle connections are kept open
+    pub fn idle_timeout(&self) -> Duration {
+        self.idle_timeout
+    }
+
+    /// Keepalive ping interval, or `None` if pings are disabled
+    pub fn ping_interval(&self) -> Option<Duration> {
+        self.ping_interval
+    }
+
+    /// Send a fire-and-forget SearchFile, keeping its lookup slot until the
+    /// result arrives or the Kademlia query timeout has passed.
+    async fn send_search_file(&self, file_hash: String) -> Result<(), String> {
+        let permit = self.acquire_query_permit().await?;
+        let (sender, receiver) = oneshot::channel();
+
+        self.cmd_tx
+            .send(DhtCommand::SearchFile { file_hash, sender })
+            .await
+            .map_err(|e| e.to_string())?;
+
+        let query_timeout = self.query_timeout;
+        tokio::spawn(async move {
+            let _ = tokio::time::timeout(query_timeout, receiver).await;
+            drop(permit);
+        });
+        Ok(())
+    }
+
     pub async fn start_file_heartbeat(&self, file_hash: &str) -> Result<(), String> {
         let file_hash_owned = file_hash.to_string();
 
@@ -7229,9 +8383,32 @@ impl DhtService {
     }
 
     pub async fn publish_file(
+        &self,
+        metadata: FileMetadata,
+        ftp_sources: Option<Vec<FtpSourceInfo>>,
+    ) -> Result<(), String> {
+        self.send_publish(metadata, ftp_sources, None).await
+    }
+
+    /// Publish a file and wait for its DHT record put to finish, returning how
+    /// many peers stored the record (the put quorum when it was met, fewer when
+    /// the put failed or timed out).
+    pub async fn publish_file_with_quorum(
+        &self,
+        metadata: FileMetadata,
+        ftp_sources: Option<Vec<FtpSourceInfo>>,
+    ) -> Result<usize, String> {
+        let (stored_tx, stored_rx) = oneshot::channel();
+        self.send_publish(metadata, ftp_sources, Some(stored_tx))
+            .await?;
+        stored_rx.await.map_err(|e| e.to_string())
+    }
+
+    async fn send_publish(
         &self,
         mut metadata: FileMetadata,
         ftp_sources: Option<Vec<FtpSourceInfo>>,
+        stored_tx: Option<oneshot::Sender<usize>>,
     ) -> Result<(), String> {
         // Add FTP sources to metadata before publishing
         if let Some(sources) = ftp_sources {
@@ -7254,6 +8431,7 @@ impl DhtService {
             .send(DhtCommand::PublishFile {
                 metadata,
                 response_tx,
+                stored_tx,
             })
             .await
             .map_err(|e| e.to_string())?;
@@ -7261,11 +8439,23 @@ impl DhtService {
         let cid_populated_metadata = response_rx.await.map_err(|e| e.to_string())?;
         // self.start_file_heartbeat(&cid_populated_metadata.merkle_root)
         //     .await?;
+        let mut owned = cid_populated_metadata;
+        owned.file_data.clear();
+        self.owned_records
+            .lock()
+            .await
+            .insert(owned.merkle_root.clone(), owned);
         Ok(())
     }
 
+    /// Snapshot of the records published by this node
+    pub async fn owned_records(&self) -> Vec<FileMetadata> {
+        self.owned_records.lock().await.values().cloned().collect()
+    }
+
     pub async fn stop_publishing_file(&self, file_hash: String) -> Result<(), String> {
         let file_hash_clone = file_hash.clone();
+        self.owned_records.lock().await.remove(&file_hash);
 
         self.cmd_tx
             .send(DhtCommand::StopPublish(file_hash))
@@ -7282,6 +8472,41 @@ impl DhtService {
             .insert(metadata.merkle_root.clone(), metadata.clone());
     }
 
+    /// Publish metadata for a file this node holds at `path`. The SHA-256 and size
+    /// are recomputed from the file rather than trusted: blank values in `metadata`
+    /// are filled in, and values that don't match the content are rejected.
+    pub async fn publish_local_file(
+        &self,
+        mut metadata: FileMetadata,
+        path: &std::path::Path,
+        ftp_sources: Option<Vec<FtpSourceInfo>>,
+    ) -> Result<(), String> {
+        let (file_hash, file_size) = hash_local_file(path).await?;
+
+        if metadata.merkle_root.is_empty() {
+            metadata.merkle_root = file_hash.clone();
+        } else if !metadata.merkle_root.eq_ignore_ascii_case(&file_hash) {
+            return Err(format!(
+                "File hash mismatch for {}: metadata claims {}, content hashes to {}",
+                path.display(),
+                metadata.merkle_root,
+                file_hash
+            ));
+        }
+        if metadata.file_size == 0 {
+            metadata.file_size = file_size;
+        } else if metadata.file_size != file_size {
+            return Err(format!(
+                "File size mismatch for {}: metadata claims {} bytes, file has {}",
+                path.display(),
+                metadata.file_size,
+                file_size
+            ));
+        }
+
+        self.publish_file(metadata, ftp_sources).await
+    }
+
     /// Promote a freshly downloaded file to a seeder by publishing its metadata back to the DHT
     /// and registering as a provider. This reuses the standard publish flow so heartbeats and
     /// provider records stay consistent with uploads.
@@ -7411,13 +8636,36 @@ impl DhtService {
 
     // Fix the search_file method around line 6464:
     pub async fn search_file(&self, file_hash: String) -> Result<(), String> {
-        // Create a dummy channel since this is fire-and-forget
-        let (sender, _receiver) = oneshot::channel();
+        self.send_search_file(file_hash).await
+    }
 
-        self.cmd_tx
-            .send(DhtCommand::SearchFile { file_hash, sender })
+    /// Start a lookup for `file_hash` and return a receiver for this query's
+    /// result alone, so several searches can be in flight at once. Resolves to
+    /// `None` if the file isn't found, the lookup fails or the Kademlia query
+    /// timeout passes. `FileDiscovered` is still emitted as with `search_file`.
+    pub async fn search_file_tracked(
+        &self,
+        file_hash: String,
+    ) -> Result<oneshot::Receiver<Option<FileMetadata>>, String> {
+        let permit = self.acquire_query_permit().await?;
+        let (sender, receiver) = oneshot::channel();
+
+        self.cmd_tx
+            .send(DhtCommand::SearchFile { file_hash, sender })
             .await
-            .map_err(|e| e.to_string())
+            .map_err(|e| e.to_string())?;
+
+        let (result_tx, result_rx) = oneshot::channel();
+        let query_timeout = self.query_timeout;
+        tokio::spawn(async move {
+            let result = match tokio::time::timeout(query_timeout, receiver).await {
+                Ok(Ok(Ok(metadata))) => metadata,
+                _ => None,
+            };
+            drop(permit);
+            let _ = result_tx.send(result);
+        });
+        Ok(result_rx)
     }
 
     pub async fn get_file(&self, file_hash: String) -> Result<(), String> {
@@ -7426,13 +8674,7 @@ impl DhtService {
 
     // Fix the search_metadata method around line 6474:
     pub async fn search_metadata(&self, file_hash: String, timeout_ms: u64) -> Result<(), String> {
-        // Create a dummy channel since this is fire-and-forget
-        let (sender, _receiver) = oneshot::channel();
-
-        self.cmd_tx
-            .send(DhtCommand::SearchFile { file_hash, sender })
-            .await
-            .map_err(|e| e.to_string())
+        self.send_search_file(file_hash).await
     }
     pub async fn synchronous_search_metadata(
         &self,
@@ -7449,15 +8691,12 @@ impl DhtService {
         info!("Querying DHT for fresh metadata for file {}...", file_hash);
 
         if timeout_ms == 0 {
-            let (sender, _receiver) = oneshot::channel();
-            self.cmd_tx
-                .send(DhtCommand::SearchFile { file_hash, sender })
-                .await
-                .map_err(|e| e.to_string())?;
+            self.send_search_file(file_hash).await?;
             return Ok(None);
         }
 
         let timeout_duration = Duration::from_millis(timeout_ms);
+        let _permit = self.acquire_query_permit().await?;
         let (tx, rx) = oneshot::channel();
 
         // Send the validated search command
@@ -7547,6 +8786,19 @@ impl DhtService {
             .map_err(|e| e.to_string())
     }
 
+    /// Reserve a slot on the relay at `relay_addr` (must end in `/p2p/{relay}`)
+    /// so peers can reach this node at `{relay_addr}/p2p-circuit/p2p/{self}`.
+    /// The result is reported as `DhtEvent::RelayReservation`.
+    pub async fn reserve_relay(&self, relay_addr: String) -> Result<(), String> {
+        let relay_addr: Multiaddr = relay_addr
+            .parse()
+            .map_err(|e| format!("Invalid relay address: {}", e))?;
+        self.cmd_tx
+            .send(DhtCommand::ReserveRelay(relay_addr))
+            .await
+            .map_err(|e| e.to_string())
+    }
+
     pub async fn disconnect_peer(&self, peer_id: PeerId) -> Result<(), String> {
         self.cmd_tx
             .send(DhtCommand::DisconnectPeer(peer_id))
@@ -7884,6 +9136,7 @@ impl DhtService {
 
         // Query DHT for providers of this service
         // This finds peers that have registered as providers for proxy services
+        let _permit = self.acquire_query_permit().await?;
         let (tx, rx) = oneshot::channel();
 
         // Send command to query providers
@@ -7948,19 +9201,31 @@ impl DhtService {
             .map_err(|e| e.to_string())
     }
 
-    // Drain up to `max` pending events without blocking
+    /// Receive every DHT event as it happens, without polling. A subscriber that
+    /// gets `Lagged` can recover the missed events with `events_since`.
+    pub fn subscribe_events(&self) -> broadcast::Receiver<SequencedEvent<DhtEvent>> {
+        self.events.subscribe()
+    }
+
+    /// `subscribe_events` plus the sequence number of the last event published
+    /// before subscribing, for consumers that replay with `events_since`
+    pub fn subscribe_events_with_seq(
+        &self,
+    ) -> (u64, broadcast::Receiver<SequencedEvent<DhtEvent>>) {
+        self.events.subscribe_with_seq()
+    }
+
+    /// Up to `max` retained events with sequence number `>= from_seq`
+    pub fn events_since(
+        &self,
+        from_seq: u64,
+        max: usize,
+    ) -> Result<Vec<SequencedEvent<DhtEvent>>, EventBusError> {
+        self.events.events_since(from_seq, max)
+    }
+
     pub async fn drain_events(&self, max: usize) -> Vec<DhtEvent> {
-        use tokio::sync::mpsc::error::TryRecvError;
-        let mut rx = self.event_rx.lock().await;
-        let mut events = Vec::new();
-        while events.len() < max {
-            match rx.try_recv() {
-                Ok(ev) => events.push(ev),
-                Err(TryRecvError::Empty) => break,
-                Err(TryRecvError::Disconnected) => break,
-            }
-        }
-        events
+        self.events.drain(max)
     }
 
     /// Get recommended peers for file download using smart selection
@@ -8098,12 +9363,78 @@ impl DhtService {
         }
     }
 
+    /// Record a failed transfer in the peer metrics and keep a local `Bad`
+    /// verdict draft for it, without publishing anything. The draft is only
+    /// signed and published by `confirm_verdict_draft`.
+    pub async fn record_transfer_failure_draft(&self, peer_id: &str, error: &str) {
+        self.peer_selection
+            .lock()
+            .await
+            .record_transfer_failure(peer_id, error);
+
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        let mut drafts = self.verdict_drafts.lock().await;
+        let draft = drafts
+            .entry(peer_id.to_string())
+            .or_insert_with(|| VerdictDraft {
+                target_id: peer_id.to_string(),
+                outcome: VerdictOutcome::Bad,
+                details: String::new(),
+                failures: 0,
+                last_failure_at: now,
+            });
+        draft.failures += 1;
+        draft.details = error.to_string();
+        draft.last_failure_at = now;
+    }
+
+    /// Verdict drafts waiting for confirmation
+    pub async fn verdict_drafts(&self) -> Vec<VerdictDraft> {
+        self.verdict_drafts.lock().await.values().cloned().collect()
+    }
+
+    /// Sign and publish the draft verdict for `peer_id`
+    pub async fn confirm_verdict_draft(&self, peer_id: &str) -> Result<(), String> {
+        let draft = self
+            .verdict_drafts
+            .lock()
+            .await
+            .remove(peer_id)
+            .ok_or_else(|| format!("No verdict draft for {}", peer_id))?;
+        if let Err(e) = self
+            .publish_transfer_verdict(peer_id, draft.outcome.clone(), 0)
+            .await
+        {
+            // Keep the draft so the user can retry
+            self.verdict_drafts
+                .lock()
+                .await
+                .insert(peer_id.to_string(), draft);
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    /// Drop the draft verdict for `peer_id`; returns whether there was one
+    pub async fn discard_verdict_draft(&self, peer_id: &str) -> bool {
+        self.verdict_drafts.lock().await.remove(peer_id).is_some()
+    }
+
     /// Update peer encryption support
     pub async fn set_peer_encryption_support(&self, peer_id: &str, supported: bool) {
         let mut peer_selection = self.peer_selection.lock().await;
         peer_selection.set_peer_encryption_support(peer_id, supported);
     }
 
+    /// Record the region a peer reported for itself
+    pub async fn set_peer_region(&self, peer_id: &str, region: Option<String>) {
+        let mut peer_selection = self.peer_selection.lock().await;
+        peer_selection.set_peer_region(peer_id, region);
+    }
+
     /// Report malicious behavior from a peer
     pub async fn report_malicious_peer(&self, peer_id: &str, severity: &str) {
         let mut peer_selection = self.peer_selection.lock().await;
@@ -8264,6 +9595,13 @@ impl DhtService {
     pub async fn get_seeders_for_file(&self, file_hash: &str) -> Vec<String> {
         // Send command to DHT task to query provider records for this file
         info!("getting seeders");
+        let _permit = match self.acquire_query_permit().await {
+            Ok(permit) => permit,
+            Err(e) => {
+                warn!("Failed to acquire lookup slot: {}", e);
+                return Vec::new();
+            }
+        };
         let (tx, rx) = oneshot::channel();
 
         if let Err(e) = self
@@ -8325,6 +9663,139 @@ impl DhtService {
             .map_err(|e| format!("Failed to receive shutdown acknowledgment: {}", e))
     }
 
+    /// Replace this node's identity with one derived from `new_secret`.
+    ///
+    /// The current node is shut down (freeing its listen port) and a new node is
+    /// started from `config` with the new secret. Every record this node owned is
+    /// republished with the new PeerId as seeder, and a pointer from the old PeerId
+    /// to the new one is stored in the DHT. Until `grace_period` elapses the old
+    /// PeerId stays listed next to the new one and the rotation announcement is
+    /// refreshed, so peers holding stale records can still migrate.
+    ///
+    /// Once this node has been shut down, any failure leaves no node running;
+    /// that is reported as `IdentityRotationError::Stopped`.
+    pub async fn rotate_identity(
+        &self,
+        new_secret: String,
+        config: DhtConfig<'_>,
+        chunk_manager: Option<Arc<ChunkManager>>,
+        grace_period: Duration,
+    ) -> Result<DhtService, IdentityRotationError> {
+        let old_peer_id = self.peer_id.clone();
+        let records = self.owned_records().await;
+
+        self.shutdown()
+            .await
+            .map_err(IdentityRotationError::StillRunning)?;
+
+        let config = DhtConfig {
+            secret: Some(new_secret),
+            ..config
+        };
+        let new_service = DhtService::new_with_config(
+            config,
+            self.file_transfer_service.clone(),
+            self.webrtc_service.clone(),
+            chunk_manager,
+        )
+        .await
+        .map_err(|e| {
+            IdentityRotationError::Stopped(format!(
+                "Failed to start DHT with rotated identity: {}",
+                e
+            ))
+        })?;
+        let new_peer_id = new_service.peer_id.clone();
+
+        let keep_old = !grace_period.is_zero();
+        let migrated = async {
+            if new_peer_id == old_peer_id {
+                return Err("New secret produced the same PeerId".to_string());
+            }
+            for mut record in records.iter().cloned() {
+                record.seeders =
+                    migrate_seeders(&record.seeders, &old_peer_id, &new_peer_id, keep_old);
+                new_service.publish_file(record, None).await?;
+            }
+
+            let (sender, receiver) = oneshot::channel();
+            new_service
+                .cmd_tx
+                .send(DhtCommand::AnnounceIdentityRotation {
+                    old_peer_id: old_peer_id.clone(),
+                    sender,
+                })
+                .await
+                .map_err(|e| e.to_string())?;
+            receiver.await.map_err(|e| e.to_string())?
+        }
+        .await;
+        if let Err(e) = migrated {
+            let _ = new_service.shutdown().await;
+            return Err(IdentityRotationError::Stopped(e));
+        }
+
+        if keep_old {
+            let cmd_tx = new_service.cmd_tx.clone();
+            let owned_records = new_service.owned_records.clone();
+            let file_metadata_cache = new_service.file_metadata_cache.clone();
+            let merkle_roots: Vec<String> = records.into_iter().map(|r| r.merkle_root).collect();
+            tokio::spawn(async move {
+                let deadline = tokio::time::Instant::now() + grace_period;
+                let mut interval = tokio::time::interval(FILE_HEARTBEAT_INTERVAL.min(grace_period));
+                // The first tick fires immediately and the records were just published
+                interval.tick().await;
+                loop {
+                    tokio::select! {
+                        _ = interval.tick() => {}
+                        _ = tokio::time::sleep_until(deadline) => break,
+                    }
+                    let (sender, receiver) = oneshot::channel();
+                    if cmd_tx
+                        .send(DhtCommand::AnnounceIdentityRotation {
+                            old_peer_id: old_peer_id.clone(),
+                            sender,
+                        })
+                        .await
+                        .is_err()
+                    {
+                        return;
+                    }
+                    let _ = receiver.await;
+                }
+
+                // Grace period over: drop the old PeerId from every record still owned
+                for merkle_root in merkle_roots {
+                    let record = owned_records.lock().await.get(&merkle_root).cloned();
+                    let Some(mut record) = record else { continue };
+                    record.seeders =
+                        migrate_seeders(&record.seeders, &old_peer_id, &new_peer_id, false);
+                    // Publishing merges seeders with the cached copy, which still lists the old PeerId
+                    file_metadata_cache.lock().await.remove(&merkle_root);
+                    let (response_tx, response_rx) = oneshot::channel();
+                    if cmd_tx
+                        .send(DhtCommand::PublishFile {
+                            metadata: record,
+                            response_tx,
+                            stored_tx: None,
+                        })
+                        .await
+                        .is_err()
+                    {
+                        return;
+                    }
+                    if let Ok(mut published) = response_rx.await {
+                        published.file_data.clear();
+                        owned_records.lock().await.insert(merkle_root, published);
+                    }
+                }
+                info!("Identity rotation grace period ended for {}", old_peer_id);
+            });
+        }
+
+        Ok(new_service)
+    }
+
     /// Enable privacy routing through proxy nodes
     pub async fn enable_privacy_routing(&self, mode: PrivacyMode) -> Result<(), String> {
         let mut proxy_mgr = self.proxy_mgr.lock().await;
@@ -8590,6 +10061,7 @@ impl DhtService {
         info_hash: String,
     ) -> Result<Option<FileMetadata>, String> {
         info!("🔍 DHT search_by_infohash called for: {}", info_hash);
+        let _permit = self.acquire_query_permit().await?;
         let (sender, receiver) = oneshot::channel();
         self.cmd_tx
             .send(DhtCommand::SearchByInfohash {
@@ -8620,6 +10092,7 @@ impl DhtService {
 
     /// Retrieve a value from the DHT by key
     pub async fn get_dht_value(&self, key: String) -> Result<Option<Vec<u8>>, String> {
+        let _permit = self.acquire_query_permit().await?;
         let (sender, receiver) = oneshot::channel();
         self.cmd_tx
             .send(DhtCommand::GetDhtValue { key, sender })
@@ -8632,6 +10105,7 @@ impl DhtService {
 impl DhtService {
     /// Finds Chiral peers in the DHT that are seeding a torrent with the given info_hash.
     pub async fn search_peers_by_infohash(&self, info_hash: String) -> Result<Vec<String>, String> {
+        let _permit = self.acquire_query_permit().await?;
         let (sender, receiver) = oneshot::channel();
         self.cmd_tx
             .send(DhtCommand::SearchPeersByInfohash { info_hash, sender })
@@ -8783,6 +10257,88 @@ fn ipv4_in_same_subnet(target: Ipv4Addr, iface_ip: Ipv4Addr, iface_mask: Ipv4Add
     (t & m) == (i & m)
 }
 
+/// Allowlist/denylist applied to dials, identified addresses and inbound connections
+#[derive(Debug, Clone, Default)]
+struct PeerFilter {
+    allowed_peers: Option<HashSet<PeerId>>,
+    blocked_addrs: Vec<IpNet>,
+}
+
+impl PeerFilter {
+    fn new(allowed_peers: Option<HashSet<PeerId>>, blocked_addrs: Vec<IpNet>) -> Self {
+        Self {
+            allowed_peers,
+            blocked_addrs,
+        }
+    }
+
+    fn allows_peer(&self, peer_id: &PeerId) -> bool {
+        match &self.allowed_peers {
+            Some(allowed) => allowed.contains(peer_id),
+            None => true,
+        }
+    }
+
+    /// True if any IP component of `addr` falls in a blocked range
+    fn blocks_addr(&self, addr: &Multiaddr) -> bool {
+        if self.blocked_addrs.is_empty() {
+            return false;
+        }
+        addr.iter().any(|p| {
+            let ip = match p {
+                Protocol::Ip4(v4) => IpAddr::V4(v4),
+                Protocol::Ip6(v6) => IpAddr::V6(v6),
+                _ => return false,
+            };
+            self.blocked_addrs.iter().any(|net| net.contains(&ip))
+        })
+    }
+
+    /// Why a peer/address pair is refused, or `None` if it is allowed.
+    /// A peer id that is unknown (e.g. a bare address) only fails the allowlist
+    /// when one is configured.
+    fn rejection(&self, peer_id: Option<&PeerId>, addr: Option<&Multiaddr>) -> Option<String> {
+        if self.allowed_peers.is_some() {
+            match peer_id {
+                Some(peer_id) if !self.allows_peer(peer_id) => {
+                    return Some(format!("peer {} is not in the allowlist", peer_id));
+                }
+                None => {
+                    return Some(
+                        "address has no peer id to check against the allowlist".to_string(),
+                    )
+                }
+                _ => {}
+            }
+        }
+        match addr {
+            Some(addr) if self.blocks_addr(addr) => Some(format!("address {} is blocked", addr)),
+            _ => None,
+        }
+    }
+}
+
+/// Identify agent version, with the node's region appended when it has one
+fn agent_version(region: Option<&str>) -> String {
+    let base = format!("chiral-network/{}", env!("CARGO_PKG_VERSION"));
+    let region = region
+        .map(str::trim)
+        .filter(|r| !r.is_empty() && !r.contains(' '));
+    match region {
+        Some(region) => format!("{} {}{}", base, AGENT_REGION_PREFIX, region),
+        None => base,
+    }
+}
+
+/// The region a peer appended to its agent version, if any
+fn region_from_agent_version(agent_version: &str) -> Option<String> {
+    agent_version
+        .split_whitespace()
+        .find_map(|part| part.strip_prefix(AGENT_REGION_PREFIX))
+        .filter(|region| !region.is_empty())
+        .map(str::to_string)
+}
+
 /// If multiaddr can be plausibly reached from this machine
 /// - Relay paths (p2p-circuit) are allowed
 /// - IPv4 loopback (127.0.0.1) is REJECTED (not reachable from remote peers)
@@ -8792,16 +10348,74 @@ fn ma_plausibly_reachable(ma: &Multiaddr) -> bool {
     if ma.iter().any(|p| matches!(p, Protocol::P2pCircuit)) {
         return true;
     }
-    // Only consider IPv4 (IPv6 can be added if needed)
-    if let Some(Protocol::Ip4(v4)) = ma.iter().find(|p| matches!(p, Protocol::Ip4(_))) {
-        // Reject loopback addresses - they're not reachable from remote peers
-        if v4.is_loopback() {
-            return false;
+    match ma
+        .iter()
+        .find(|p| matches!(p, Protocol::Ip4(_) | Protocol::Ip6(_)))
+    {
+        Some(Protocol::Ip4(v4)) => {
+            // Reject loopback addresses - they're not reachable from remote peers
+            if v4.is_loopback() {
+                return false;
+            }
+            // Allow public addresses, reject private
+            !v4.is_private()
         }
-        // Allow public addresses, reject private
-        return !v4.is_private();
+        Some(Protocol::Ip6(v6)) => is_global_unicast_v6(v6),
+        _ => false,
     }
-    false
+}
+
+/// Not loopback, unspecified, link-local (fe80::/10) or unique-local (fc00::/7).
+fn is_global_unicast_v6(ip: Ipv6Addr) -> bool {
+    let first = ip.segments()[0];
+    !ip.is_loopback()
+        && !ip.is_unspecified()
+        && (first & 0xffc0) != 0xfe80
+        && (first & 0xfe00) != 0xfc00
+}
+
+/// The addresses the swarm listens on: `listen_addrs`, or the IPv4
+/// any-address when none are given. Each must be `/ip4/<addr>` or
+/// `/ip6/<addr>`, optionally followed by `/tcp/<port>`; `port` is used when
+/// the TCP part is missing.
+fn resolve_listen_addrs(listen_addrs: &[Multiaddr], port: u16) -> Result<Vec<Multiaddr>, String> {
+    if listen_addrs.is_empty() {
+        let any = Multiaddr::empty()
+            .with(Protocol::Ip4(Ipv4Addr::UNSPECIFIED))
+            .with(Protocol::Tcp(port));
+        return Ok(vec![any]);
+    }
+    listen_addrs
+        .iter()
+        .map(|addr| {
+            let mut protocols = addr.iter();
+            if !matches!(protocols.next(), Some(Protocol::Ip4(_) | Protocol::Ip6(_))) {
+                return Err(format!(
+                    "Listen address {} must start with /ip4 or /ip6",
+                    addr
+                ));
+            }
+            match (protocols.next(), protocols.next()) {
+                (None, _) => Ok(addr.clone().with(Protocol::Tcp(port))),
+                (Some(Protocol::Tcp(_)), None) => Ok(addr.clone()),
+                _ => Err(format!(
+                    "Listen address {} must be an IP address with an optional /tcp port",
+                    addr
+                )),
+            }
+        })
+        .collect()
+}
+
+/// Parse listen addresses given as strings (CLI flags, config files).
+pub fn parse_listen_addrs(addrs: &[String]) -> Result<Vec<Multiaddr>, String> {
+    addrs
+        .iter()
+        .map(|addr| {
+            addr.parse::<Multiaddr>()
+                .map_err(|e| format!("Invalid listen address {}: {}", addr, e))
+        })
+        .collect()
 }
 
 /// A softer check that accepts any non-loopback IPv4 address (used as a fallback
@@ -9016,12 +10630,161 @@ mod tests {
     }
 
     async fn spawn_test_node(bootstrap_nodes: Vec<String>) -> DhtService {
-        let config = DhtConfig::client();
+        let config = DhtConfig {
+            bootstrap_nodes,
+            ..DhtConfig::client()
+        };
 
         DhtService::new_with_config(config, None, None, None)
             .await
             .expect("Failed to create DhtService")
     }
+    #[tokio::test]
+    async fn test_publish_local_file_rejects_wrong_hash() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("stored.bin");
+        std::fs::write(&path, b"locally stored content").unwrap();
+        let real_hash = hex::encode(Sha256::digest(b"locally stored content"));
+
+        let node = spawn_test_node(vec![]).await;
+
+        let forged = FileMetadata {
+            merkle_root: "f".repeat(64),
+            file_name: "stored.bin".to_string(),
+            ..Default::default()
+        };
+        let err = node
+            .publish_local_file(forged, &path, None)
+            .await
+            .unwrap_err();
+        assert!(err.contains("hash mismatch"), "unexpected error: {}", err);
+
+        let wrong_size = FileMetadata {
+            merkle_root: real_hash.clone(),
+            file_size: 1,
+            ..Default::default()
+        };
+        let err = node
+            .publish_local_file(wrong_size, &path, None)
+            .await
+            .unwrap_err();
+        assert!(err.contains("size mismatch"), "unexpected error: {}", err);
+        assert!(node.owned_records().await.is_empty());
+
+        // Blank hash and size are derived from the content
+        let derived = FileMetadata {
+            file_name: "stored.bin".to_string(),
+            ..Default::default()
+        };
+        node.publish_local_file(derived, &path, None).await.unwrap();
+        let records = node.owned_records().await;
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].merkle_root, real_hash);
+        assert_eq!(records[0].file_size, 22);
+
+        node.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_subscribers_receive_events_without_polling() {
+        let node = spawn_test_node(vec![]).await;
+        let mut events = node.subscribe_events();
+
+        let metadata = FileMetadata {
+            merkle_root: "push-event-test-file".to_string(),
+            file_name: "push.txt".to_string(),
+            file_size: 4,
+            ..Default::default()
+        };
+        node.publish_file(metadata, None).await.unwrap();
+
+        let published = timeout(Duration::from_secs(5), async {
+            loop {
+                match events.recv().await.unwrap().event {
+                    DhtEvent::PublishedFile(meta) => break meta,
+                    _ => continue,
+                }
+            }
+        })
+        .await
+        .expect("PublishedFile event was not pushed to the subscriber");
+        assert_eq!(published.merkle_root, "push-event-test-file");
+
+        // The drain API still sees the same event
+        assert!(node.drain_events(100).await.iter().any(
+            |e| matches!(e, DhtEvent::PublishedFile(m) if m.merkle_root == "push-event-test-file")
+        ));
+
+        node.shutdown().await.unwrap();
+    }
+
+    #[test]
+    fn test_swarm_looks_wedged_only_when_stale_and_short_on_peers() {
+        let now = SystemTime::now();
+        let window = Duration::from_secs(600);
+        let stale = now - Duration::from_secs(601);
+        let recent = now - Duration::from_secs(30);
+
+        assert!(swarm_looks_wedged(stale, now, window, 0));
+        assert!(!swarm_looks_wedged(recent, now, window, 0));
+        let enough_peers = WATCHDOG_LOW_PEER_COUNT;
+        assert!(!swarm_looks_wedged(stale, now, window, enough_peers));
+    }
+
+    #[tokio::test]
+    async fn test_watchdog_resets_idle_swarm() {
+        // A fixed port, as in production, so the re-bind can collide with the old socket
+        let port = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let node = DhtService::new_with_config(
+            DhtConfig {
+                listen_addrs: vec![format!("/ip4/127.0.0.1/tcp/{}", port).parse().unwrap()],
+                watchdog_window: Some(Duration::from_secs(2)),
+                ..DhtConfig::client()
+            },
+            None,
+            None,
+            None,
+        )
+        .await
+        .expect("Failed to create DhtService");
+        let mut events = node.subscribe_events();
+
+        // With no peers the startup timestamp goes stale and the watchdog fires
+        let peer_count = timeout(Duration::from_secs(5), async {
+            loop {
+                match events.recv().await.unwrap().event {
+                    DhtEvent::SwarmReset { peer_count, .. } => break peer_count,
+                    _ => continue,
+                }
+            }
+        })
+        .await
+        .expect("watchdog did not reset the idle swarm");
+        assert_eq!(peer_count, 0);
+
+        // The same port accepts connections again well before the watchdog's
+        // next tick (window / 4), so the re-bind didn't wait for a retry
+        let mut listening = false;
+        for _ in 0..15 {
+            if tokio::net::TcpStream::connect(("127.0.0.1", port))
+                .await
+                .is_ok()
+            {
+                listening = true;
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        assert!(listening);
+        assert_eq!(node.metrics_snapshot().await.last_connection_event, None);
+
+        node.shutdown().await.unwrap();
+    }
+
     #[tokio::test]
     async fn test_node_spawn_and_shutdown() {
         // 1. Spawn a single node
@@ -9052,28 +10815,721 @@ mod tests {
         );
     }
     #[tokio::test]
-    async fn test_multi_node_bootstrap_discovery() {
-        // 1. Create the Bootstrap Node
-        // We force server mode so it answers Kademlia queries immediately.
-        let config = DhtConfig::default_bootstrap_config();
-
-        let bootstrap_node = DhtService::new_with_config(config, None, None, None)
+    async fn test_rotate_identity_republishes_owned_records() {
+        let node = DhtService::new_with_config(
+            DhtConfig {
+                secret: Some("rotation-old-secret".to_string()),
+                ..DhtConfig::client()
+            },
+            None,
+            None,
+            None,
+        )
+        .await
+        .expect("Failed to create DhtService");
+        let old_peer_id = node.get_peer_id().await;
+
+        let metadata = FileMetadata {
+            merkle_root: "rotation-test-file".to_string(),
+            file_name: "rotation.txt".to_string(),
+            file_size: 42,
+            seeders: vec![old_peer_id.clone()],
+            ..Default::default()
+        };
+        node.publish_file(metadata, None).await.unwrap();
+        assert_eq!(node.owned_records().await.len(), 1);
+
+        let grace_period = Duration::from_millis(300);
+        let rotated = node
+            .rotate_identity(
+                "rotation-new-secret".to_string(),
+                DhtConfig::client(),
+                None,
+                grace_period,
+            )
             .await
-            .unwrap();
-
-        let bootstrap_addrs = wait_for_address(&bootstrap_node, 10).await;
-        let bootstrap_peer_id = bootstrap_node.get_peer_id().await;
+            .expect("rotation failed");
+        let new_peer_id = rotated.get_peer_id().await;
+        assert_ne!(old_peer_id, new_peer_id);
+
+        // During the grace period both identities are listed
+        let records = rotated.owned_records().await;
+        assert_eq!(records.len(), 1);
+        assert!(records[0].seeders.contains(&new_peer_id));
+        assert!(records[0].seeders.contains(&old_peer_id));
+
+        let events = rotated.drain_events(100).await;
+        assert!(events.iter().any(|e| matches!(
+            e,
+            DhtEvent::IdentityRotated { old, new } if *old == old_peer_id && *new == new_peer_id
+        )));
+
+        // Once it ends, only the new identity remains
+        sleep(grace_period + Duration::from_millis(500)).await;
+        let records = rotated.owned_records().await;
+        assert_eq!(records[0].merkle_root, "rotation-test-file");
+        assert_eq!(records[0].seeders, vec![new_peer_id]);
+
+        rotated.shutdown().await.unwrap();
+
+        // A secret giving the same PeerId is only noticed after the old node is down
+        let node = DhtService::new_with_config(
+            DhtConfig {
+                secret: Some("rotation-same-secret".to_string()),
+                ..DhtConfig::client()
+            },
+            None,
+            None,
+            None,
+        )
+        .await
+        .expect("Failed to create DhtService");
+        let result = node
+            .rotate_identity(
+                "rotation-same-secret".to_string(),
+                DhtConfig::client(),
+                None,
+                grace_period,
+            )
+            .await;
+        assert!(matches!(result, Err(IdentityRotationError::Stopped(_))));
+    }
 
-        // We need the specific address that includes the peer ID for dialing
-        let bootstrap_addr = bootstrap_addrs[0].clone();
-        println!(
-            "BootstrapNode spawned with addresses: {:?}",
-            bootstrap_addrs
-        );
+    #[test]
+    fn test_put_quorum_scales_with_connected_peers() {
+        let target = 5;
+        assert_eq!(select_put_quorum(target, 0).1, 1);
+        assert!(matches!(select_put_quorum(target, 0).0, kad::Quorum::One));
+        assert_eq!(select_put_quorum(target, 1).1, 1);
+
+        let mut previous = 0;
+        for peers in 0..20 {
+            let (quorum, size) = select_put_quorum(target, peers);
+            assert!(size >= previous, "quorum shrank as peers grew");
+            assert!(size <= target);
+            assert_eq!(size, peers.clamp(1, target));
+            if size > 1 {
+                assert!(matches!(quorum, kad::Quorum::N(n) if n.get() == size));
+            }
+            previous = size;
+        }
 
-        // 2. Create two client nodes pointing to the bootstrap node
-        let node_a = spawn_test_node(vec![bootstrap_addr.clone()]).await;
-        let node_b = spawn_test_node(vec![bootstrap_addr.clone()]).await;
+        // A zero target still stores on at least one peer
+        assert_eq!(select_put_quorum(0, 10).1, 1);
+    }
+
+    #[test]
+    fn test_failed_put_reports_peers_reached() {
+        let key = kad::RecordKey::new(&"file-hash");
+        let success = vec![PeerId::random(), PeerId::random()];
+        let quorum = std::num::NonZeroUsize::new(3).unwrap();
+
+        let failed = kad::PutRecordError::QuorumFailed {
+            key: key.clone(),
+            success: success.clone(),
+            quorum,
+        };
+        assert_eq!(put_record_successes(&failed), 2);
+
+        let timed_out = kad::PutRecordError::Timeout {
+            key,
+            success: Vec::new(),
+            quorum,
+        };
+        assert_eq!(put_record_successes(&timed_out), 0);
+    }
+
+    #[tokio::test]
+    async fn test_owned_records_are_republished() {
+        let node = DhtService::new_with_config(
+            DhtConfig {
+                republish_interval: Duration::from_millis(200),
+                ..DhtConfig::client()
+            },
+            None,
+            None,
+            None,
+        )
+        .await
+        .expect("Failed to create DhtService");
+
+        // Owned but never put by this node task, so only the republish can store it
+        let file_hash = "republish-test-file".to_string();
+        node.owned_records.lock().await.insert(
+            file_hash.clone(),
+            FileMetadata {
+                merkle_root: file_hash.clone(),
+                file_name: "republish.txt".to_string(),
+                file_size: 9,
+                seeders: vec![node.get_peer_id().await],
+                ..Default::default()
+            },
+        );
+
+        let mut found = None;
+        for _ in 0..10 {
+            tokio::time::sleep(Duration::from_millis(200)).await;
+            found = node
+                .synchronous_search_metadata(file_hash.clone(), 500)
+                .await
+                .unwrap();
+            if found.is_some() {
+                break;
+            }
+        }
+        let found = found.expect("owned record was never republished");
+        assert_eq!(found.file_name, "republish.txt");
+
+        node.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_lookups_respect_query_limit() {
+        let limit = 2;
+        let node = Arc::new(
+            DhtService::new_with_config(
+                DhtConfig {
+                    max_concurrent_queries: limit,
+                    ..DhtConfig::client()
+                },
+                None,
+                None,
+                None,
+            )
+            .await
+            .expect("Failed to create DhtService"),
+        );
+
+        let mut handles = Vec::new();
+        for i in 0..20 {
+            let node = node.clone();
+            handles.push(tokio::spawn(async move {
+                node.get_dht_value(format!("missing-key-{}", i)).await
+            }));
+        }
+
+        let mut peak = 0;
+        while handles.iter().any(|h| !h.is_finished()) {
+            let inflight = node.inflight_queries();
+            assert!(
+                inflight <= limit,
+                "{} lookups in flight, limit {}",
+                inflight,
+                limit
+            );
+            peak = peak.max(inflight);
+            tokio::task::yield_now().await;
+        }
+        for handle in handles {
+            assert!(handle.await.unwrap().is_ok());
+        }
+        assert!(peak <= limit);
+        assert_eq!(node.inflight_queries(), 0);
+
+        node.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_search_file_slot_is_released_after_query_timeout() {
+        let node = DhtService::new_with_config(
+            DhtConfig {
+                max_concurrent_queries: 1,
+                query_timeout: Duration::from_secs(1),
+                ..DhtConfig::client()
+            },
+            None,
+            None,
+            None,
+        )
+        .await
+        .expect("Failed to create DhtService");
+
+        node.send_search_file("missing-file".to_string()).await.unwrap();
+        // The slot follows the configured query timeout, not a fixed 30s
+        tokio::time::timeout(Duration::from_secs(3), async {
+            while node.inflight_queries() > 0 {
+                tokio::time::sleep(Duration::from_millis(50)).await;
+            }
+        })
+        .await
+        .expect("lookup slot was still held after the query timeout");
+
+        node.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_custom_idle_timeout_and_disabled_ping() {
+        use tokio::sync::broadcast::error::RecvError;
+
+        // Without pings, a 1s idle timeout closes a connection nobody uses
+        let quiet = DhtService::new_with_config(
+            DhtConfig {
+                idle_timeout: Duration::from_secs(1),
+                ping_interval: None,
+                ..DhtConfig::default_bootstrap_config()
+            },
+            None,
+            None,
+            None,
+        )
+        .await
+        .expect("Failed to create DhtService");
+        assert_eq!(quiet.idle_timeout(), Duration::from_secs(1));
+        assert_eq!(quiet.ping_interval(), None);
+        let mut events = quiet.subscribe_events();
+        let quiet_addr = wait_for_address(&quiet, 10).await[0].clone();
+
+        let client = DhtService::new_with_config(
+            DhtConfig {
+                bootstrap_nodes: vec![quiet_addr],
+                idle_timeout: Duration::from_secs(1),
+                ping_interval: None,
+                ..DhtConfig::client()
+            },
+            None,
+            None,
+            None,
+        )
+        .await
+        .expect("Failed to create DhtService");
+        let client_peer_id = client.get_peer_id().await;
+
+        let closed = timeout(Duration::from_secs(20), async {
+            loop {
+                match events.recv().await {
+                    Ok(sequenced) => {
+                        if let DhtEvent::PeerDisconnected { peer_id } = sequenced.event {
+                            if peer_id == client_peer_id {
+                                break;
+                            }
+                        }
+                    }
+                    Err(RecvError::Lagged(_)) => continue,
+                    Err(RecvError::Closed) => panic!("event stream closed"),
+                }
+            }
+        })
+        .await;
+        assert!(
+            closed.is_ok(),
+            "idle connection outlived the 1s idle timeout"
+        );
+        client.shutdown().await.unwrap();
+        quiet.shutdown().await.unwrap();
+
+        // With pings enabled the node measures its peers' RTT
+        let pinging = DhtService::new_with_config(
+            DhtConfig {
+                ping_interval: Some(Duration::from_millis(200)),
+                ..DhtConfig::default_bootstrap_config()
+            },
+            None,
+            None,
+            None,
+        )
+        .await
+        .expect("Failed to create DhtService");
+        let pinging_addr = wait_for_address(&pinging, 10).await[0].clone();
+        let client = spawn_test_node(vec![pinging_addr]).await;
+        let client_peer_id = client.get_peer_id().await;
+        let mut latency = None;
+        for _ in 0..20 {
+            latency = pinging
+                .get_peer_metrics()
+                .await
+                .into_iter()
+                .find(|metrics| metrics.peer_id == client_peer_id)
+                .and_then(|metrics| metrics.latency_ms);
+            if latency.is_some() {
+                break;
+            }
+            sleep(Duration::from_millis(500)).await;
+        }
+        assert!(latency.is_some(), "no ping RTT recorded for the client");
+        client.shutdown().await.unwrap();
+        pinging.shutdown().await.unwrap();
+
+        let defaults = DhtConfig::default();
+        assert_eq!(defaults.idle_timeout, DEFAULT_IDLE_TIMEOUT);
+        assert_eq!(defaults.ping_interval, Some(DEFAULT_PING_INTERVAL));
+        assert_eq!(defaults.ping_failure_threshold, 3);
+    }
+
+    #[tokio::test]
+    async fn test_replication_factor_one_record_still_retrievable() {
+        let node = DhtService::new_with_config(
+            DhtConfig {
+                replication_factor: 1,
+                ..DhtConfig::client()
+            },
+            None,
+            None,
+            None,
+        )
+        .await
+        .expect("Failed to create DhtService");
+
+        let file_hash = "replication-one-test-file".to_string();
+        let metadata = FileMetadata {
+            merkle_root: file_hash.clone(),
+            file_name: "replication.txt".to_string(),
+            file_size: 7,
+            seeders: vec![node.get_peer_id().await],
+            ..Default::default()
+        };
+        node.publish_file(metadata, None).await.unwrap();
+
+        let found = node
+            .synchronous_search_metadata(file_hash.clone(), 2000)
+            .await
+            .unwrap()
+            .expect("record published with replication factor 1 was not found");
+        assert_eq!(found.merkle_root, file_hash);
+        assert_eq!(found.file_name, "replication.txt");
+
+        node.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_tracked_searches_resolve_to_their_own_file() {
+        let bootstrap_node =
+            DhtService::new_with_config(DhtConfig::default_bootstrap_config(), None, None, None)
+                .await
+                .unwrap();
+        let b_addr = wait_for_address(&bootstrap_node, 10).await[0].clone();
+
+        let seeder = spawn_test_node(vec![b_addr.clone()]).await;
+        let searcher = spawn_test_node(vec![b_addr]).await;
+        for _ in 0..20 {
+            if seeder.get_peer_count().await >= 1 && searcher.get_peer_count().await >= 1 {
+                break;
+            }
+            sleep(Duration::from_millis(500)).await;
+        }
+
+        let hashes = ["tracked-search-a", "tracked-search-b"];
+        for hash in hashes {
+            let metadata = FileMetadata {
+                merkle_root: hash.to_string(),
+                file_name: format!("{hash}.bin"),
+                file_size: 32,
+                ..Default::default()
+            };
+            seeder.publish_file(metadata, None).await.unwrap();
+        }
+        sleep(Duration::from_millis(1000)).await;
+
+        // Both lookups are in flight at once; each receiver gets its own file
+        let rx_a = searcher
+            .search_file_tracked(hashes[0].to_string())
+            .await
+            .unwrap();
+        let rx_b = searcher
+            .search_file_tracked(hashes[1].to_string())
+            .await
+            .unwrap();
+        let (found_a, found_b) = tokio::join!(rx_a, rx_b);
+
+        assert_eq!(
+            found_a.unwrap().map(|m| m.merkle_root),
+            Some(hashes[0].to_string())
+        );
+        assert_eq!(
+            found_b.unwrap().map(|m| m.merkle_root),
+            Some(hashes[1].to_string())
+        );
+
+        searcher.shutdown().await.unwrap();
+        seeder.shutdown().await.unwrap();
+        bootstrap_node.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_failed_lookup_on_isolated_node_resolves_search() {
+        let node = spawn_test_node(vec![]).await;
+        let mut events = node.subscribe_events();
+
+        // With no peers the record lookup fails straight away; the tracked
+        // search must resolve well before the query timeout
+        let rx = node
+            .search_file_tracked("no-such-file".to_string())
+            .await
+            .unwrap();
+        let found = timeout(Duration::from_secs(3), rx)
+            .await
+            .expect("tracked search was left hanging")
+            .unwrap();
+        assert!(found.is_none());
+
+        let failed_hash = timeout(Duration::from_secs(10), async {
+            loop {
+                match events.recv().await.unwrap().event {
+                    DhtEvent::FileNotFound(hash) => break hash,
+                    DhtEvent::QueryTimedOut { file_hash, .. } => break file_hash,
+                    _ => continue,
+                }
+            }
+        })
+        .await
+        .expect("no not-found or timeout event for the failed lookup");
+        assert_eq!(failed_hash, "no-such-file");
+
+        node.shutdown().await.unwrap();
+    }
+
+    #[test]
+    fn test_get_record_failure_reasons() {
+        let key = kad::RecordKey::new(&"file");
+        assert_eq!(
+            get_record_failure_reason(&kad::GetRecordError::Timeout { key: key.clone() }),
+            Some("timeout".to_string())
+        );
+        assert_eq!(
+            get_record_failure_reason(&kad::GetRecordError::QuorumFailed {
+                key: key.clone(),
+                records: Vec::new(),
+                quorum: std::num::NonZeroUsize::new(2).unwrap(),
+            }),
+            Some("quorum failed: 0 of 2 records".to_string())
+        );
+        assert_eq!(
+            get_record_failure_reason(&kad::GetRecordError::NotFound {
+                key,
+                closest_peers: Vec::new(),
+            }),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn test_search_lists_every_provider_as_seeder() {
+        let bootstrap_node =
+            DhtService::new_with_config(DhtConfig::default_bootstrap_config(), None, None, None)
+                .await
+                .unwrap();
+        let b_addr = wait_for_address(&bootstrap_node, 10).await[0].clone();
+
+        let mut nodes = Vec::new();
+        for _ in 0..3 {
+            let node = DhtService::new_with_config(
+                DhtConfig {
+                    bootstrap_nodes: vec![b_addr.clone()],
+                    ..DhtConfig::client()
+                },
+                None,
+                None,
+                None,
+            )
+            .await
+            .expect("Failed to create DhtService");
+            nodes.push(node);
+        }
+        for _ in 0..20 {
+            let mut all_connected = true;
+            for node in &nodes {
+                all_connected &= node.get_peer_count().await >= 1;
+            }
+            if all_connected {
+                break;
+            }
+            sleep(Duration::from_millis(500)).await;
+        }
+
+        // Two seeders publish the same file; the second record overwrites the first
+        let file_hash = "provider-merge-test-file".to_string();
+        let mut expected = Vec::new();
+        for node in &nodes[..2] {
+            let peer_id = node.get_peer_id().await;
+            let metadata = FileMetadata {
+                merkle_root: file_hash.clone(),
+                file_name: "shared.bin".to_string(),
+                file_size: 64,
+                seeders: vec![peer_id.clone()],
+                ..Default::default()
+            };
+            node.publish_file(metadata, None).await.unwrap();
+            expected.push(peer_id);
+        }
+
+        let mut seeders = Vec::new();
+        for _ in 0..10 {
+            if let Ok(Some(found)) = nodes[2]
+                .synchronous_search_metadata(file_hash.clone(), 2000)
+                .await
+            {
+                seeders = found.seeders;
+                if expected.iter().all(|p| seeders.contains(p)) {
+                    break;
+                }
+            }
+            sleep(Duration::from_millis(1000)).await;
+        }
+        for peer_id in &expected {
+            assert!(
+                seeders.contains(peer_id),
+                "seeder {} missing from {:?}",
+                peer_id,
+                seeders
+            );
+        }
+
+        for node in nodes {
+            node.shutdown().await.unwrap();
+        }
+        bootstrap_node.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_routing_table_restored_from_peer_cache() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache_path = dir.path().join("peer_cache.json");
+
+        // A peer from "the previous run" that is still online
+        let known_peer = spawn_test_node(vec![]).await;
+        let known_peer_id = known_peer.get_peer_id().await;
+        let known_addrs = wait_for_address(&known_peer, 10).await;
+        let mut cache = PeerCache::new();
+        cache.peers.push(PeerCacheEntry::from_metrics(
+            known_peer_id.clone(),
+            known_addrs[0].clone(),
+            1,
+            0,
+            0,
+            0,
+            None,
+            0.5,
+            unix_timestamp(),
+            false,
+            false,
+        ));
+        cache.save_to_file(&cache_path).await.unwrap();
+
+        // No bootstrap nodes: the cached peer is the only way in
+        let node = DhtService::new_with_config(
+            DhtConfig {
+                peer_cache_path: Some(cache_path.clone()),
+                ..DhtConfig::client()
+            },
+            None,
+            None,
+            None,
+        )
+        .await
+        .expect("Failed to create DhtService");
+
+        let addresses = node
+            .get_peer_addresses(vec![known_peer_id.clone()])
+            .await
+            .unwrap();
+        let restored = addresses
+            .get(&known_peer_id)
+            .expect("cached peer missing from routing table");
+        assert!(!restored.is_empty());
+
+        // Shutting down writes the routing table back out
+        node.shutdown().await.unwrap();
+        let saved = PeerCache::load_from_file(&cache_path).await.unwrap();
+        assert!(saved.peers.iter().any(|p| p.peer_id == known_peer_id));
+
+        known_peer.shutdown().await.unwrap();
+    }
+
+    #[test]
+    fn test_peer_filter_blocks_ranges_and_unlisted_peers() {
+        let allowed = PeerId::random();
+        let stranger = PeerId::random();
+        let filter = PeerFilter::new(
+            Some(HashSet::from([allowed])),
+            vec!["176.183.245.0/24".parse().unwrap()],
+        );
+
+        let blocked: Multiaddr = format!("/ip4/176.183.245.3/tcp/4001/p2p/{allowed}")
+            .parse()
+            .unwrap();
+        let open: Multiaddr = format!("/ip4/203.0.113.7/tcp/4001/p2p/{allowed}")
+            .parse()
+            .unwrap();
+
+        assert!(filter.blocks_addr(&blocked));
+        assert!(filter.rejection(Some(&allowed), Some(&blocked)).is_some());
+        assert!(filter.rejection(Some(&allowed), Some(&open)).is_none());
+        assert!(filter.rejection(Some(&stranger), Some(&open)).is_some());
+        assert!(filter.rejection(None, Some(&open)).is_some());
+
+        // Without an allowlist only the address ranges apply
+        let open_filter = PeerFilter::new(None, vec!["10.0.0.0/8".parse().unwrap()]);
+        assert!(open_filter
+            .rejection(Some(&stranger), Some(&open))
+            .is_none());
+        assert!(open_filter
+            .rejection(None, Some(&"/ip4/10.1.2.3/tcp/1".parse().unwrap()))
+            .is_some());
+    }
+
+    #[tokio::test]
+    async fn test_bootstrap_completed_event_drained() {
+        let bootstrap_node =
+            DhtService::new_with_config(DhtConfig::default_bootstrap_config(), None, None, None)
+                .await
+                .unwrap();
+        let bootstrap_addrs = wait_for_address(&bootstrap_node, 10).await;
+
+        let node = spawn_test_node(vec![bootstrap_addrs[0].clone()]).await;
+
+        let mut completed = None;
+        for _ in 0..40 {
+            completed = node
+                .drain_events(100)
+                .await
+                .into_iter()
+                .find_map(|event| match event {
+                    DhtEvent::BootstrapCompleted { ok, .. } => Some(ok),
+                    _ => None,
+                });
+            if completed.is_some() {
+                break;
+            }
+            sleep(Duration::from_millis(250)).await;
+        }
+
+        assert_eq!(
+            completed,
+            Some(true),
+            "no successful BootstrapCompleted event"
+        );
+        assert!(node
+            .metrics_snapshot()
+            .await
+            .last_successful_bootstrap
+            .is_some());
+
+        node.shutdown().await.unwrap();
+        bootstrap_node.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_multi_node_bootstrap_discovery() {
+        // 1. Create the Bootstrap Node
+        // We force server mode so it answers Kademlia queries immediately.
+        let config = DhtConfig::default_bootstrap_config();
+
+        let bootstrap_node = DhtService::new_with_config(config, None, None, None)
+            .await
+            .unwrap();
+
+        let bootstrap_addrs = wait_for_address(&bootstrap_node, 10).await;
+        let bootstrap_peer_id = bootstrap_node.get_peer_id().await;
+
+        // We need the specific address that includes the peer ID for dialing
+        let bootstrap_addr = bootstrap_addrs[0].clone();
+        println!(
+            "BootstrapNode spawned with addresses: {:?}",
+            bootstrap_addrs
+        );
+
+        // 2. Create two client nodes pointing to the bootstrap node
+        let node_a = spawn_test_node(vec![bootstrap_addr.clone()]).await;
+        let node_b = spawn_test_node(vec![bootstrap_addr.clone()]).await;
 
         // 3. Assertion Loop
         // Discovery via Kademlia takes time. We poll until the condition is met.
@@ -9103,6 +11559,49 @@ mod tests {
         bootstrap_node.shutdown().await.unwrap();
     }
 
+    #[test]
+    fn test_region_round_trips_through_agent_version() {
+        assert_eq!(
+            region_from_agent_version(&agent_version(Some("EU"))),
+            Some("EU".to_string())
+        );
+        assert_eq!(region_from_agent_version(&agent_version(None)), None);
+        assert_eq!(region_from_agent_version(&agent_version(Some("  "))), None);
+        assert_eq!(region_from_agent_version("rust-libp2p/0.53.0"), None);
+    }
+
+    #[tokio::test]
+    async fn test_peer_region_learned_from_identify() {
+        let config = DhtConfig {
+            region: Some("EU".to_string()),
+            ..DhtConfig::default_bootstrap_config()
+        };
+        let bootstrap_node = DhtService::new_with_config(config, None, None, None)
+            .await
+            .unwrap();
+        let bootstrap_addr = wait_for_address(&bootstrap_node, 10).await[0].clone();
+        let bootstrap_peer_id = bootstrap_node.get_peer_id().await;
+
+        let node = spawn_test_node(vec![bootstrap_addr]).await;
+        let mut region = None;
+        for _ in 0..20 {
+            region = node
+                .get_peer_metrics()
+                .await
+                .into_iter()
+                .find(|metrics| metrics.peer_id == bootstrap_peer_id)
+                .and_then(|metrics| metrics.region);
+            if region.is_some() {
+                break;
+            }
+            sleep(Duration::from_millis(500)).await;
+        }
+        assert_eq!(region.as_deref(), Some("EU"));
+
+        node.shutdown().await.unwrap();
+        bootstrap_node.shutdown().await.unwrap();
+    }
+
     #[tokio::test]
     async fn test_file_upload_discovery() {
         init();
@@ -9463,6 +11962,218 @@ mod tests {
         assert_eq!(snapshot.reachability, NatReachabilityState::Unknown);
     }
 
+    #[test]
+    fn test_resolve_listen_addrs() {
+        assert_eq!(
+            resolve_listen_addrs(&[], 4001).unwrap(),
+            vec!["/ip4/0.0.0.0/tcp/4001".parse::<Multiaddr>().unwrap()]
+        );
+
+        let addrs = parse_listen_addrs(&[
+            "/ip6/::".to_string(),
+            "/ip4/192.168.1.20/tcp/4100".to_string(),
+        ])
+        .unwrap();
+        assert_eq!(
+            resolve_listen_addrs(&addrs, 4001).unwrap(),
+            vec![
+                "/ip6/::/tcp/4001".parse::<Multiaddr>().unwrap(),
+                "/ip4/192.168.1.20/tcp/4100".parse::<Multiaddr>().unwrap(),
+            ]
+        );
+
+        assert!(parse_listen_addrs(&["not-an-addr".to_string()]).is_err());
+        for invalid in [
+            "/dns4/example.com/tcp/4001",
+            "/ip4/0.0.0.0/udp/4001/quic-v1",
+        ] {
+            let addr: Multiaddr = invalid.parse().unwrap();
+            assert!(resolve_listen_addrs(&[addr], 4001).is_err(), "{}", invalid);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_ipv6_listen_addr_is_recorded_in_metrics() {
+        if std::net::TcpListener::bind("[::1]:0").is_err() {
+            eprintln!("IPv6 loopback unavailable; skipping");
+            return;
+        }
+        let node = DhtService::new_with_config(
+            DhtConfig {
+                listen_addrs: vec![
+                    "/ip4/127.0.0.1/tcp/0".parse().unwrap(),
+                    "/ip6/::1/tcp/0".parse().unwrap(),
+                ],
+                ..DhtConfig::client()
+            },
+            None,
+            None,
+            None,
+        )
+        .await
+        .expect("Failed to create DhtService");
+
+        let mut listen_addrs = Vec::new();
+        for _ in 0..50 {
+            listen_addrs = node.metrics_snapshot().await.listen_addrs;
+            if listen_addrs.len() >= 2 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+        assert!(
+            listen_addrs.iter().any(|a| a.starts_with("/ip6/::1/tcp/")),
+            "{:?}",
+            listen_addrs
+        );
+        assert!(listen_addrs
+            .iter()
+            .any(|a| a.starts_with("/ip4/127.0.0.1/tcp/")));
+
+        node.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_relayed_connection_reaches_reserved_peer() {
+        let relay = DhtService::new_with_config(
+            DhtConfig {
+                listen_addrs: vec!["/ip4/127.0.0.1/tcp/0".parse().unwrap()],
+                enable_relay_server: true,
+                ..DhtConfig::client()
+            },
+            None,
+            None,
+            None,
+        )
+        .await
+        .expect("Failed to create relay");
+        let relay_addr = wait_for_address(&relay, 10).await[0].clone();
+        let relay_id = relay.get_peer_id().await;
+
+        let listener = spawn_test_node(vec![]).await;
+        let mut listener_events = listener.subscribe_events();
+        listener.reserve_relay(relay_addr.clone()).await.unwrap();
+        let reservation = timeout(Duration::from_secs(10), async {
+            loop {
+                if let DhtEvent::RelayReservation { relay, ok } =
+                    listener_events.recv().await.unwrap().event
+                {
+                    break (relay, ok);
+                }
+            }
+        })
+        .await
+        .expect("no RelayReservation event");
+        assert_eq!(reservation, (relay_id.clone(), true));
+
+        // The dialer is only told the relayed address
+        let dialer = spawn_test_node(vec![]).await;
+        let mut dialer_events = dialer.subscribe_events();
+        let listener_id = listener.get_peer_id().await;
+        dialer
+            .connect_peer(format!("{}/p2p-circuit/p2p/{}", relay_addr, listener_id))
+            .await
+            .unwrap();
+        let circuit_relay = timeout(Duration::from_secs(10), async {
+            loop {
+                if let DhtEvent::ProxyStatus { id, status, .. } =
+                    dialer_events.recv().await.unwrap().event
+                {
+                    if status == "relay_circuit" {
+                        break id;
+                    }
+                }
+            }
+        })
+        .await
+        .expect("no relayed circuit was established");
+        assert_eq!(circuit_relay, relay_id);
+        assert!(dialer.get_connected_peers().await.contains(&listener_id));
+
+        dialer.shutdown().await.unwrap();
+        listener.shutdown().await.unwrap();
+        relay.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_connections_beyond_limit_are_refused() {
+        let limited = DhtService::new_with_config(
+            DhtConfig {
+                listen_addrs: vec!["/ip4/127.0.0.1/tcp/0".parse().unwrap()],
+                max_established_total: Some(1),
+                ..DhtConfig::client()
+            },
+            None,
+            None,
+            None,
+        )
+        .await
+        .expect("Failed to create DhtService");
+        let limited_addr = wait_for_address(&limited, 10).await[0].clone();
+
+        let first = spawn_test_node(vec![limited_addr.clone()]).await;
+        let second = spawn_test_node(vec![limited_addr.clone()]).await;
+        first.connect_peer(limited_addr.clone()).await.unwrap();
+        second.connect_peer(limited_addr).await.unwrap();
+
+        let mut refused = 0;
+        for _ in 0..50 {
+            refused = limited.metrics_snapshot().await.connections_refused;
+            if refused >= 1 {
+                break;
+            }
+            sleep(Duration::from_millis(100)).await;
+        }
+        assert!(refused >= 1, "no connection was refused at the limit");
+        assert!(limited.get_connected_peers().await.len() <= 1);
+
+        first.shutdown().await.unwrap();
+        second.shutdown().await.unwrap();
+        limited.shutdown().await.unwrap();
+    }
+
+    #[test]
+    fn test_dropped_bootstrap_peer_is_redialed_with_backoff() {
+        let peer = PeerId::random();
+        let addr = format!("/ip4/10.0.0.1/tcp/4001/p2p/{}", peer);
+        let mut reconnects = BootstrapReconnects::new(
+            &[addr.clone()],
+            Duration::from_secs(1),
+            Duration::from_secs(4),
+        );
+        let start = Instant::now();
+        let at = |secs| start + Duration::from_secs(secs);
+
+        assert!(!reconnects.disconnected(PeerId::random(), start));
+        assert!(reconnects.disconnected(peer, start));
+        assert!(reconnects.due(start).is_empty());
+        assert_eq!(
+            reconnects.due(at(1)),
+            vec![(peer, vec![addr.parse::<Multiaddr>().unwrap()])]
+        );
+
+        // Delay doubles to 2s, then 4s, then stays at the 4s cap
+        assert!(reconnects.due(at(2)).is_empty());
+        assert_eq!(reconnects.due(at(3)).len(), 1);
+        assert!(reconnects.due(at(6)).is_empty());
+        assert_eq!(reconnects.due(at(7)).len(), 1);
+        assert_eq!(reconnects.due(at(11)).len(), 1);
+
+        // Reconnecting resets the backoff
+        reconnects.connected(&peer);
+        assert!(reconnects.is_idle());
+        reconnects.disconnected(peer, at(20));
+        assert_eq!(reconnects.due(at(21)).len(), 1);
+    }
+
+    #[test]
+    fn test_global_unicast_v6() {
+        assert!(is_global_unicast_v6("2001:db8::1".parse().unwrap()));
+        for local in ["::1", "::", "fe80::1", "fd00::1"] {
+            assert!(!is_global_unicast_v6(local.parse().unwrap()), "{}", local);
+        }
+    }
+
     #[test]
     fn metrics_snapshot_carries_listen_addrs() {
         let mut metrics = DhtMetrics::default();
@@ -368,6 +368,7 @@ pub struct ReachabilityRecord {
 #[derive(Debug, Clone, Default)]
 pub struct DhtMetrics {
     pub last_bootstrap: Option<SystemTime>,
+    pub last_successful_bootstrap: Option<SystemTime>,
     pub last_success: Option<SystemTime>,
     pub last_error_at: Option<SystemTime>,
     pub last_error: Option<String>,
@@ -400,6 +401,10 @@ pub struct DhtMetrics {
     pub dcutr_hole_punch_failures: u64,
     pub last_dcutr_success: Option<SystemTime>,
     pub last_dcutr_failure: Option<SystemTime>,
+    /// Last inbound or outbound connection, used by the swarm watchdog
+    pub last_connection_event: Option<SystemTime>,
+    /// Connections refused because a configured connection limit was reached
+    pub connections_refused: u64,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -407,6 +412,7 @@ pub struct DhtMetrics {
 pub struct DhtMetricsSnapshot {
     pub peer_count: usize,
     pub last_bootstrap: Option<u64>,
+    pub last_successful_bootstrap: Option<u64>,
     pub last_peer_event: Option<u64>,
     pub last_error: Option<String>,
     pub last_error_at: Option<u64>,
@@ -438,4 +444,6 @@ pub struct DhtMetricsSnapshot {
     pub dcutr_hole_punch_failures: u64,
     pub last_dcutr_success: Option<u64>,
     pub last_dcutr_failure: Option<u64>,
+    pub last_connection_event: Option<u64>,
+    pub connections_refused: u64,
 }
@@ -21,7 +21,7 @@ use crate::http_server;
 use crate::manager::ChunkManager;
 use crate::transaction_services;
 use crate::file_transfer::FileTransferService;
-use crate::webrtc_service::{set_webrtc_service, WebRTCService};
+use crate::webrtc_service::{set_webrtc_service, WebRTCService, WebRtcConfig};
 
 #[derive(Clone)]
 pub struct E2eApiState {
@@ -162,7 +162,7 @@ async fn ensure_p2p_services_started(app: &tauri::AppHandle) -> Result<(), Strin
         if let Some(ft) = existing {
             ft
         } else {
-            let ft = FileTransferService::new_with_app_handle(app.clone())
+            let ft = FileTransferService::new_with_app_handle(app.clone(), state.analytics.clone())
                 .await
                 .map_err(|e| format!("Failed to start file transfer service: {}", e))?;
             let ft_arc = Arc::new(ft);
@@ -186,6 +186,7 @@ async fn ensure_p2p_services_started(app: &tauri::AppHandle) -> Result<(), Strin
                 ft_arc.clone(),
                 state.keystore.clone(),
                 state.bandwidth.clone(),
+                WebRtcConfig::from_env(),
             )
             .await
             .map_err(|e| format!("Failed to start WebRTC service: {}", e))?;
@@ -535,6 +536,7 @@ async fn api_upload_generate(
                 name: file_name.clone(),
                 size: file_size,
                 encrypted: false,
+                price: crate::reputation::price_to_amount(price),
             })
             .await;
 
@@ -595,7 +597,7 @@ async fn api_upload_generate(
         let expected_merkle_root = if protocol_upper == "WEBRTC" {
             // Use the same ChunkManager logic as upload_file_to_network (but without secrets) to get the merkle root.
             let chunk_storage_path = match state.app.path().app_data_dir() {
-                Ok(p) => p.join("chunks"),
+                Ok(p) => p.join("chunk_storage"),
                 Err(e) => {
                     return (StatusCode::INTERNAL_SERVER_ERROR, Json(crate::http_server::ErrorResponse {
                         error: format!("Failed to get app data dir for chunk storage: {}", e),
@@ -606,7 +608,7 @@ async fn api_upload_generate(
             let manager = ChunkManager::new(chunk_storage_path);
             let tmp_path_clone = tmp_path.clone();
             let result = tokio::task::spawn_blocking(move || {
-                manager.chunk_and_encrypt_file_canonical(std::path::Path::new(&tmp_path_clone))
+                manager.store_file_with_manifest(std::path::Path::new(&tmp_path_clone))
             })
             .await
             .map_err(|e| format!("Failed to spawn blocking chunking task: {}", e));
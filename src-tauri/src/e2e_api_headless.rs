@@ -287,6 +287,7 @@ async fn api_upload_generate(
                 name: file_name.clone(),
                 size: file_size,
                 encrypted: false,
+                price: crate::reputation::price_to_amount(price),
             })
             .await;
 
@@ -397,8 +398,8 @@ async fn api_upload_generate(
         // Surface the seeder URL in the upload response for debugging.
         seeder_url = ftp_url.clone();
 
-        // Build manifest (SHA-256 per 256KB chunk) so multi-source validators can verify chunks if used.
-        let chunk_size: usize = 256 * 1024;
+        // Build manifest (SHA-256 per chunk) so multi-source validators can verify chunks if used.
+        let chunk_size = crate::manager::DEFAULT_CHUNK_SIZE;
         let mut manifest_chunks: Vec<crate::manager::ChunkInfo> = Vec::new();
         {
             use sha2::{Digest as _, Sha256};
@@ -417,6 +418,9 @@ async fn api_upload_generate(
                     size,
                     encrypted_hash: hash,
                     encrypted_size: size,
+                    compression_type: crate::manager::COMPRESSION_NONE,
+                    compressed_size: size,
+                    cipher_suite: crate::manager::CipherSuite::Aes256Gcm,
                 });
                 offset = end;
                 index += 1;
@@ -426,6 +430,12 @@ async fn api_upload_generate(
             merkle_root: file_hash.clone(),
             chunks: manifest_chunks,
             encrypted_key_bundle: None,
+            hash_algorithm: crate::manager::HashAlgorithm::Sha256,
+            encryption_info: None,
+            mime_type: None,
+            chunk_size,
+            signature: None,
+            file_hash: Some(file_hash.clone()),
         };
         let manifest_json = match serde_json::to_string(&file_manifest) {
             Ok(s) => s,
@@ -22,6 +22,24 @@ pub struct EncryptionInfo {
     pub key_fingerprint: String,
     pub nonce: Vec<u8>,
     pub salt: Vec<u8>,
+    /// The file key wrapped for each recipient, keyed by the recipient's
+    /// hex-encoded X25519 public key
+    #[serde(default)]
+    pub encrypted_key_bundles: Vec<(String, EncryptedAesKeyBundle)>,
+}
+
+impl EncryptionInfo {
+    /// The bundle wrapped for `recipient_public_key`, if it is a recipient
+    pub fn key_bundle_for(
+        &self,
+        recipient_public_key: &PublicKey,
+    ) -> Option<&EncryptedAesKeyBundle> {
+        let recipient_id = hex::encode(recipient_public_key.as_bytes());
+        self.encrypted_key_bundles
+            .iter()
+            .find(|(id, _)| *id == recipient_id)
+            .map(|(_, bundle)| bundle)
+    }
 }
 
 /// Result of file encryption operation
@@ -145,6 +163,7 @@ impl FileEncryption {
             key_fingerprint: Self::generate_key_fingerprint(&key_array),
             nonce: nonce.to_vec(),
             salt: salt.to_vec(),
+            encrypted_key_bundles: Vec::new(),
         };
 
         Ok(EncryptionResult {
@@ -1,3 +1,5 @@
+/// Crate-wide error type. Tauri commands still return `Result<_, String>`;
+/// `?` converts through the `From<ChiralError> for String` impl below.
 #[derive(Debug, thiserror::Error)]
 pub enum ChiralError {
     #[error("Protocol error: {0}")]
@@ -8,4 +10,45 @@ pub enum ChiralError {
     Io(#[from] std::io::Error),
     #[error("Serialization error: {0}")]
     Serialization(String),
-}
\ No newline at end of file
+    #[error("Crypto error: {0}")]
+    Crypto(String),
+    #[error("DHT error: {0}")]
+    Dht(String),
+    #[error("Storage error: {0}")]
+    Storage(String),
+    #[error("Not found: {0}")]
+    NotFound(String),
+    #[error("Storage full: need {needed} bytes, {available} available")]
+    CapacityFull { needed: u64, available: u64 },
+    #[error("Operation cancelled")]
+    Cancelled,
+}
+
+impl From<serde_json::Error> for ChiralError {
+    fn from(e: serde_json::Error) -> Self {
+        ChiralError::Serialization(e.to_string())
+    }
+}
+
+impl From<ChiralError> for String {
+    fn from(e: ChiralError) -> Self {
+        e.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_converts_to_string_at_the_command_boundary() {
+        fn command() -> Result<(), String> {
+            Err(ChiralError::NotFound("chunk abc".to_string()))?;
+            Ok(())
+        }
+        assert_eq!(command().unwrap_err(), "Not found: chunk abc");
+
+        let io = std::io::Error::new(std::io::ErrorKind::PermissionDenied, "permission denied");
+        assert!(matches!(ChiralError::from(io), ChiralError::Io(_)));
+    }
+}
@@ -1,6 +1,7 @@
 use crate::config::{CHAIN_ID, NETWORK_ID};
 use chrono;
 use ethers::prelude::*;
+use ethers::types::transaction::eip2718::TypedTransaction;
 use once_cell::sync::Lazy;
 use rand::rngs::OsRng;
 use secp256k1::{PublicKey, Secp256k1, SecretKey};
@@ -29,6 +30,7 @@ pub const BLOCK_REWARD: f64 = 2.0;
 #[derive(Debug, Clone)]
 pub struct NetworkConfig {
     pub rpc_endpoint: String,
+    pub ws_endpoint: String,
     pub chain_id: u64,
     pub network_id: u64,
 }
@@ -37,6 +39,7 @@ impl Default for NetworkConfig {
     fn default() -> Self {
         Self {
             rpc_endpoint: "http://127.0.0.1:8545".to_string(),
+            ws_endpoint: "ws://127.0.0.1:8546".to_string(),
             chain_id: *CHAIN_ID,
             network_id: *NETWORK_ID,
         }
@@ -48,6 +51,8 @@ pub static NETWORK_CONFIG: Lazy<NetworkConfig> = Lazy::new(|| {
     NetworkConfig {
         rpc_endpoint: std::env::var("CHIRAL_RPC_ENDPOINT")
             .unwrap_or_else(|_| "http://127.0.0.1:8545".to_string()),
+        ws_endpoint: std::env::var("CHIRAL_WS_ENDPOINT")
+            .unwrap_or_else(|_| "ws://127.0.0.1:8546".to_string()),
         chain_id: *CHAIN_ID,
         network_id: *NETWORK_ID,
     }
@@ -399,6 +404,16 @@ impl GethProcess {
             .arg("eth,net,web3,personal,debug,miner,admin,txpool")
             .arg("--http.corsdomain")
             .arg("*")
+            // WebSocket endpoint for newHeads subscriptions
+            .arg("--ws")
+            .arg("--ws.addr")
+            .arg("127.0.0.1")
+            .arg("--ws.port")
+            .arg("8546")
+            .arg("--ws.api")
+            .arg("eth,net,web3")
+            .arg("--ws.origins")
+            .arg(GETH_WS_ORIGINS)
             .arg("--syncmode")
             .arg("snap") // Always use snap mode (light mode doesn't work for private networks)
             // Sync performance optimizations
@@ -1361,6 +1376,109 @@ pub async fn get_block_number() -> Result<u64, String> {
     Ok(block_number)
 }
 
+/// Payload of the `new-block` event
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct NewBlockEvent {
+    pub number: u64,
+    pub timestamp: u64,
+}
+
+/// Browser origins allowed on geth's WebSocket endpoint: the app's own
+/// webview. Native clients send no Origin header and aren't affected.
+const GETH_WS_ORIGINS: &str = "tauri://localhost,http://tauri.localhost,https://tauri.localhost";
+
+/// How long to poll over HTTP before retrying the WebSocket subscription
+const WS_RETRY_INTERVAL: Duration = Duration::from_secs(30);
+/// Poll interval used while the WebSocket endpoint is unavailable
+const BLOCK_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Emit a `new-block` event for every head geth mines or receives. Uses an
+/// `eth_subscribe` `newHeads` subscription on the WebSocket endpoint, and
+/// falls back to polling `eth_blockNumber` while it is unavailable.
+pub async fn watch_new_blocks(app: tauri::AppHandle) {
+    let ws_url = NETWORK_CONFIG.ws_endpoint.clone();
+    // Latest block emitted, kept across subscription and polling cycles so
+    // a block that arrives while switching between them is still emitted
+    let last_seen = std::sync::Arc::new(std::sync::Mutex::new(None::<u64>));
+    loop {
+        let emitter = app.clone();
+        let subscription_last_seen = last_seen.clone();
+        match subscribe_new_heads(&ws_url, move |block| {
+            *subscription_last_seen.lock().unwrap() = Some(block.number);
+            let _ = emitter.emit("new-block", block);
+        })
+        .await
+        {
+            Ok(()) => tracing::info!("newHeads subscription closed"),
+            Err(e) => tracing::debug!("Falling back to block polling: {}", e),
+        }
+        poll_new_blocks(&app, WS_RETRY_INTERVAL, &last_seen).await;
+    }
+}
+
+/// Subscribe to `newHeads` on `ws_url` and call `on_block` for each head.
+/// Returns once the subscription stream ends.
+pub async fn subscribe_new_heads<F>(ws_url: &str, on_block: F) -> Result<(), String>
+where
+    F: Fn(NewBlockEvent) + Send,
+{
+    use futures::StreamExt;
+
+    let provider = Provider::<Ws>::connect(ws_url)
+        .await
+        .map_err(|e| format!("Failed to connect to {}: {}", ws_url, e))?;
+    let mut heads = provider
+        .subscribe::<_, serde_json::Value>(["newHeads"])
+        .await
+        .map_err(|e| format!("Failed to subscribe to newHeads: {}", e))?;
+
+    while let Some(head) = heads.next().await {
+        match new_block_event(&head) {
+            Ok(block) => on_block(block),
+            Err(e) => tracing::warn!("Ignoring malformed newHeads payload: {}", e),
+        }
+    }
+    Ok(())
+}
+
+fn new_block_event(head: &serde_json::Value) -> Result<NewBlockEvent, String> {
+    let number = parse_quantity(&head["number"])?;
+    let timestamp = parse_quantity(&head["timestamp"])?;
+    Ok(NewBlockEvent {
+        number: number.low_u64(),
+        timestamp: timestamp.low_u64(),
+    })
+}
+
+/// Poll for new blocks over HTTP for `duration`, emitting `new-block` when
+/// the head moves past `last_seen`
+async fn poll_new_blocks(
+    app: &tauri::AppHandle,
+    duration: Duration,
+    last_seen: &std::sync::Mutex<Option<u64>>,
+) {
+    let deadline = tokio::time::Instant::now() + duration;
+    while tokio::time::Instant::now() < deadline {
+        if let Ok(number) = get_block_number().await {
+            let previous = *last_seen.lock().unwrap();
+            if previous.map_or(false, |last| number > last) {
+                let block = rpc_request(
+                    &NETWORK_CONFIG.rpc_endpoint,
+                    "eth_getBlockByNumber",
+                    json!([format!("0x{:x}", number), false]),
+                )
+                .await
+                .and_then(|block| new_block_event(&block));
+                if let Ok(block) = block {
+                    let _ = app.emit("new-block", block);
+                }
+            }
+            *last_seen.lock().unwrap() = Some(number);
+        }
+        tokio::time::sleep(BLOCK_POLL_INTERVAL).await;
+    }
+}
+
 pub async fn get_network_difficulty() -> Result<String, String> {
     // Get the latest block to extract difficulty
     let payload = json!({
@@ -2529,26 +2647,15 @@ pub async fn get_network_hashrate() -> Result<String, String> {
 }
 
 
+/// Send `amount_chiral` from `from_address` to `to_address`, signed and
+/// submitted with `send_raw_transaction`. Checks the balance covers the amount
+/// plus gas first, then logs the txpool and monitors the transaction until mined.
 pub async fn send_transaction(
     from_address: &str,
     to_address: &str,
     amount_chiral: f64,
     private_key: &str,
 ) -> Result<String, String> {
-    let private_key_clean = private_key.strip_prefix("0x").unwrap_or(private_key);
-
-    let wallet: LocalWallet = private_key_clean
-        .parse()
-        .map_err(|e| format!("Invalid private key: {}", e))?;
-
-    let wallet_address = format!("{:?}", wallet.address());
-    if wallet_address.to_lowercase() != from_address.to_lowercase() {
-        return Err(format!(
-            "Private key doesn't match account. Expected: {}, Got: {}",
-            from_address, wallet_address
-        ));
-    }
-
     // Debug network connectivity before sending
     tracing::info!("=== NETWORK DEBUG BEFORE SENDING ===");
     match get_peer_count().await {
@@ -2561,62 +2668,18 @@ pub async fn send_transaction(
         Err(e) => tracing::error!("   Failed to get peer count: {}", e),
     }
 
-    let provider = Provider::<Http>::try_from(NETWORK_CONFIG.rpc_endpoint.as_str())
-        .map_err(|e| format!("Failed to connect to RPC ({}): {}", NETWORK_CONFIG.rpc_endpoint, e))?;
-
-    let wallet = wallet.with_chain_id(NETWORK_CONFIG.chain_id);
-
-    let client = SignerMiddleware::new(provider.clone(), wallet);
-
-    let to: Address = to_address
-        .parse()
-        .map_err(|e| format!("Invalid to address: {}", e))?;
-
     let amount_wei = U256::from((amount_chiral * 1_000_000_000_000_000_000.0) as u128);
 
-    // Get nonce for pending block (includes pending transactions)
-    let from_addr: Address = from_address
-        .parse()
-        .map_err(|e| format!("Invalid from address: {}", e))?;
-
-    // Get both confirmed and pending nonces for debugging
-    let confirmed_nonce = provider
-        .get_transaction_count(from_addr, Some(BlockNumber::Latest.into()))
-        .await
-        .map_err(|e| format!("Failed to get confirmed nonce: {}", e))?;
-    
-    let nonce = provider
-        .get_transaction_count(from_addr, Some(BlockNumber::Pending.into()))
-        .await
-        .map_err(|e| format!("Failed to get nonce: {}", e))?;
-    
-    tracing::info!("   Confirmed nonce: {}, Pending nonce: {}", confirmed_nonce, nonce);
-    if nonce > confirmed_nonce {
-        tracing::warn!("   ⚠️ There are {} pending transactions for this address!", nonce - confirmed_nonce);
-    }
-
-    // Get the actual gas price to be used in the transaction
-
-    let base_fee = match provider.get_block(BlockNumber::Latest).await {
-        Ok(Some(block)) => block.base_fee_per_gas.unwrap_or(U256::from(1)),
-        _ => U256::from(1), // Fallback to minimal fee
-    };
-
-    // Set max fee to 2x base fee to handle fee fluctuations, priority fee to 1 wei
-    let max_fee = base_fee * 2;
-    let priority_fee = U256::from(1u64);
-    let gas_limit = U256::from(21000u64);
-    
-    let gas_price = provider
-        .get_gas_price()
-        .await
-        .map_err(|e| format!("Failed to get gas price: {}", e))?;
-    let gas_cost = gas_price * gas_limit;
+    // Check sender's balance against the amount plus a plain transfer's gas
+    let rpc_url = NETWORK_CONFIG.rpc_endpoint.as_str();
+    let gas_price = parse_quantity(&rpc_request(rpc_url, "eth_gasPrice", json!([])).await?)?;
+    let gas_cost = gas_price * U256::from(21000u64);
     let total_cost = amount_wei + gas_cost;
-
-    // Check sender's balance
-
-    let sender_balance = provider.get_balance(from_addr, None).await.map_err(|e| format!("Failed to get sender balance: {}", e))?;
+    let sender_balance = parse_quantity(
+        &rpc_request(rpc_url, "eth_getBalance", json!([from_address, "latest"]))
+            .await
+            .map_err(|e| format!("Failed to get sender balance: {}", e))?,
+    )?;
     tracing::info!("   Sender balance: {} wei", sender_balance);
     tracing::info!("   Amount to send: {} wei, Gas cost: {} wei, Total needed: {} wei", amount_wei, gas_cost, total_cost);
 
@@ -2626,26 +2689,19 @@ pub async fn send_transaction(
             sender_balance, total_cost, amount_wei, gas_cost
         ));
     }
-    tracing::info!("   Base fee: {} wei, Max fee: {} wei, Priority fee: {} wei, Gas price: {} wei", base_fee, max_fee, priority_fee, gas_price);
 
-    let tx = TransactionRequest::new()
-        .to(to)
-        .value(amount_wei)
-        .gas(21000)
-        .gas_price(gas_price)
-        .nonce(nonce);
-
-    let pending_tx = client
-        .send_transaction(tx, None)
-        .await
-        .map_err(|e| format!("Failed to send transaction: {}", e))?;
-
-    let tx_hash = format!("{:?}", pending_tx.tx_hash());
+    let tx_hash = send_raw_transaction(
+        from_address,
+        to_address,
+        amount_wei,
+        Vec::new(),
+        private_key,
+    )
+    .await
+    .map_err(|e| format!("Failed to send transaction: {}", e))?;
 
     tracing::info!("✅ Transaction sent: {} from {} to {} amount {} CHIRAL", 
         tx_hash, from_address, to_address, amount_chiral);
-    tracing::info!("   Nonce: {}, Gas Price: {} wei, Gas Limit: 21000, Chain ID: {}", 
-        nonce, gas_price, NETWORK_CONFIG.chain_id);
     
     // Verify the transaction was added to the local txpool
     tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
@@ -2796,6 +2852,264 @@ pub async fn send_transaction(
     Ok(tx_hash)
 }
 
+/// Build, sign (EIP-155) and submit a transaction carrying `data` (e.g. a
+/// contract call) via `eth_sendRawTransaction`, returning the transaction hash.
+/// The nonce comes from the pending block and the gas limit from `eth_estimateGas`.
+pub async fn send_raw_transaction(
+    from_address: &str,
+    to_address: &str,
+    value_wei: U256,
+    data: Vec<u8>,
+    private_key: &str,
+) -> Result<String, String> {
+    send_raw_transaction_via(
+        &NETWORK_CONFIG.rpc_endpoint,
+        NETWORK_CONFIG.chain_id,
+        from_address,
+        to_address,
+        value_wei,
+        data,
+        private_key,
+    )
+    .await
+}
+
+async fn send_raw_transaction_via(
+    rpc_url: &str,
+    chain_id: u64,
+    from_address: &str,
+    to_address: &str,
+    value_wei: U256,
+    data: Vec<u8>,
+    private_key: &str,
+) -> Result<String, String> {
+    let wallet: LocalWallet = private_key
+        .strip_prefix("0x")
+        .unwrap_or(private_key)
+        .parse()
+        .map_err(|e| format!("Invalid private key: {}", e))?;
+    let from: Address = from_address
+        .parse()
+        .map_err(|e| format!("Invalid from address: {}", e))?;
+    if wallet.address() != from {
+        return Err(format!(
+            "Private key doesn't match account. Expected: {}, Got: {:?}",
+            from_address,
+            wallet.address()
+        ));
+    }
+    let to: Address = to_address
+        .parse()
+        .map_err(|e| format!("Invalid to address: {}", e))?;
+    let data = Bytes::from(data);
+
+    let nonce = parse_quantity(
+        &rpc_request(
+            rpc_url,
+            "eth_getTransactionCount",
+            json!([format!("{:?}", from), "pending"]),
+        )
+        .await?,
+    )?;
+    let call = json!({
+        "from": format!("{:?}", from),
+        "to": format!("{:?}", to),
+        "value": format!("0x{:x}", value_wei),
+        "data": format!("0x{}", hex::encode(&data)),
+    });
+    let gas = parse_quantity(&rpc_request(rpc_url, "eth_estimateGas", json!([call])).await?)?;
+    let gas_price = parse_quantity(&rpc_request(rpc_url, "eth_gasPrice", json!([])).await?)?;
+
+    let tx: TypedTransaction = TransactionRequest::new()
+        .from(from)
+        .to(to)
+        .value(value_wei)
+        .data(data)
+        .nonce(nonce)
+        .gas(gas)
+        .gas_price(gas_price)
+        .chain_id(chain_id)
+        .into();
+    let signature = wallet
+        .with_chain_id(chain_id)
+        .sign_transaction_sync(&tx)
+        .map_err(|e| format!("Failed to sign transaction: {}", e))?;
+    let raw = tx.rlp_signed(&signature);
+
+    let result = rpc_request(
+        rpc_url,
+        "eth_sendRawTransaction",
+        json!([format!("0x{}", hex::encode(&raw))]),
+    )
+    .await?;
+    let tx_hash = result
+        .as_str()
+        .ok_or("Invalid eth_sendRawTransaction response")?
+        .to_string();
+
+    tracing::info!(
+        "✅ Raw transaction sent: {} from {} to {} (nonce {}, gas {}, gas price {} wei)",
+        tx_hash,
+        from_address,
+        to_address,
+        nonce,
+        gas,
+        gas_price
+    );
+    Ok(tx_hash)
+}
+
+/// POST a JSON-RPC request and return its `result`.
+async fn rpc_request(
+    rpc_url: &str,
+    method: &str,
+    params: serde_json::Value,
+) -> Result<serde_json::Value, String> {
+    let payload = json!({
+        "jsonrpc": "2.0",
+        "method": method,
+        "params": params,
+        "id": 1
+    });
+
+    let response = HTTP_CLIENT
+        .post(rpc_url)
+        .json(&payload)
+        .send()
+        .await
+        .map_err(|e| format!("Failed to send request: {}", e))?;
+
+    let mut json_response: serde_json::Value = response
+        .json()
+        .await
+        .map_err(|e| format!("Failed to parse response: {}", e))?;
+
+    if let Some(error) = json_response.get("error") {
+        return Err(format!("RPC error in {}: {}", method, error));
+    }
+
+    Ok(json_response["result"].take())
+}
+
+/// Blocks sampled by `estimate_gas_price`
+const FEE_HISTORY_BLOCKS: u64 = 20;
+/// Priority fee reward percentiles for the slow, standard and fast tiers
+const FEE_HISTORY_PERCENTILES: [f64; 3] = [10.0, 50.0, 90.0];
+
+/// Suggested EIP-1559 fees for one speed tier, in wei
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct FeeSuggestion {
+    pub base_fee: u64,
+    pub priority_fee: u64,
+    pub max_fee: u64,
+}
+
+/// Fee suggestions for showing costs before a transaction is sent
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct GasEstimate {
+    /// Legacy `eth_gasPrice`, in wei
+    pub gas_price: u64,
+    pub slow: FeeSuggestion,
+    pub standard: FeeSuggestion,
+    pub fast: FeeSuggestion,
+}
+
+/// Suggest fees from `eth_gasPrice` and the last blocks' `eth_feeHistory`.
+/// Falls back to the legacy gas price for every tier if the node doesn't
+/// support fee history.
+pub async fn estimate_gas_price() -> Result<GasEstimate, String> {
+    let rpc_url = &NETWORK_CONFIG.rpc_endpoint;
+    let gas_price = parse_quantity(&rpc_request(rpc_url, "eth_gasPrice", json!([])).await?)?;
+    let gas_price = u64::try_from(gas_price).map_err(|_| "Gas price out of range".to_string())?;
+
+    let fee_history = rpc_request(
+        rpc_url,
+        "eth_feeHistory",
+        json!([
+            format!("0x{:x}", FEE_HISTORY_BLOCKS),
+            "latest",
+            FEE_HISTORY_PERCENTILES
+        ]),
+    )
+    .await;
+    match fee_history {
+        Ok(history) => gas_estimate_from_fee_history(gas_price, &history),
+        Err(e) => {
+            tracing::warn!("eth_feeHistory unavailable, using legacy gas price: {}", e);
+            let legacy = FeeSuggestion {
+                base_fee: 0,
+                priority_fee: gas_price,
+                max_fee: gas_price,
+            };
+            Ok(GasEstimate {
+                gas_price,
+                slow: legacy.clone(),
+                standard: legacy.clone(),
+                fast: legacy,
+            })
+        }
+    }
+}
+
+/// Suggested gas fees for the send form
+#[tauri::command]
+pub async fn get_gas_estimate() -> Result<GasEstimate, String> {
+    estimate_gas_price().await
+}
+
+/// Turn an `eth_feeHistory` result into tiers. The base fee is the next block's
+/// (the last `baseFeePerGas` entry); each tier's priority fee is the median of
+/// that percentile's reward across the sampled blocks, and the max fee leaves
+/// room for the base fee to double.
+fn gas_estimate_from_fee_history(
+    gas_price: u64,
+    history: &serde_json::Value,
+) -> Result<GasEstimate, String> {
+    let to_u64 = |v: &serde_json::Value| -> Result<u64, String> {
+        u64::try_from(parse_quantity(v)?).map_err(|_| "Fee out of range".to_string())
+    };
+
+    let base_fee = history["baseFeePerGas"]
+        .as_array()
+        .and_then(|fees| fees.last())
+        .ok_or("eth_feeHistory response has no baseFeePerGas")
+        .and_then(|v| to_u64(v).map_err(|_| "Invalid baseFeePerGas"))?;
+    let rewards = history["reward"].as_array().cloned().unwrap_or_default();
+
+    let mut tiers = Vec::with_capacity(FEE_HISTORY_PERCENTILES.len());
+    for tier in 0..FEE_HISTORY_PERCENTILES.len() {
+        let mut samples = rewards
+            .iter()
+            .filter_map(|block| block.get(tier))
+            .map(to_u64)
+            .collect::<Result<Vec<_>, _>>()?;
+        samples.sort_unstable();
+        let priority_fee = samples.get(samples.len() / 2).copied().unwrap_or(0).max(1);
+        tiers.push(FeeSuggestion {
+            base_fee,
+            priority_fee,
+            max_fee: base_fee.saturating_mul(2).saturating_add(priority_fee),
+        });
+    }
+    let mut tiers = tiers.into_iter();
+
+    Ok(GasEstimate {
+        gas_price,
+        slow: tiers.next().ok_or("missing slow tier")?,
+        standard: tiers.next().ok_or("missing standard tier")?,
+        fast: tiers.next().ok_or("missing fast tier")?,
+    })
+}
+
+/// Parse a hex JSON-RPC quantity such as `"0x5208"`.
+fn parse_quantity(value: &serde_json::Value) -> Result<U256, String> {
+    let hex_str = value
+        .as_str()
+        .ok_or_else(|| format!("Expected hex quantity, got {}", value))?;
+    U256::from_str_radix(hex_str.trim_start_matches("0x"), 16)
+        .map_err(|e| format!("Invalid hex quantity {}: {}", hex_str, e))
+}
+
 /// Gets the transaction receipt to check if a transaction has been mined
 pub async fn get_transaction_receipt(tx_hash: String) -> Result<Option<serde_json::Value>, String> {
     let payload = json!({
@@ -3097,6 +3411,20 @@ pub struct TransactionHistoryItem {
     pub gas_price: Option<String>,
 }
 
+/// Blocks already scanned for one address, and the transactions found in them
+#[derive(Debug, Clone)]
+struct ScannedHistory {
+    from_block: u64,
+    /// Highest block scanned so far; later queries only scan above it
+    to_block: u64,
+    /// Newest to oldest, like `get_transaction_history` returns them
+    transactions: Vec<TransactionHistoryItem>,
+}
+
+/// Scanned history per (rpc endpoint, lowercase address)
+static TX_HISTORY_CACHE: Lazy<Mutex<HashMap<(String, String), ScannedHistory>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
 /// Scan blocks for transactions involving a specific address
 /// Returns transactions from newest to oldest
 pub async fn get_transaction_history(
@@ -3104,9 +3432,89 @@ pub async fn get_transaction_history(
     from_block: u64,
     to_block: u64,
 ) -> Result<Vec<TransactionHistoryItem>, String> {
+    get_transaction_history_via(&NETWORK_CONFIG.rpc_endpoint, address, from_block, to_block).await
+}
+
+/// Like `get_transaction_history`, but only scans the blocks of the range that
+/// aren't already cached for this address.
+async fn get_transaction_history_via(
+    rpc_url: &str,
+    address: &str,
+    from_block: u64,
+    to_block: u64,
+) -> Result<Vec<TransactionHistoryItem>, String> {
+    if from_block > to_block {
+        return Ok(Vec::new());
+    }
+    let key = (rpc_url.to_string(), address.to_lowercase());
+    // Scan with the lock released; the entry is only replaced once every scan
+    // succeeded, so an RPC error leaves the cached history as it was
+    let cached = TX_HISTORY_CACHE.lock().await.get(&key).cloned();
+
+    let scanned = match cached {
+        // Extend the cached range if the request overlaps or touches it
+        Some(mut scanned)
+            if from_block <= scanned.to_block.saturating_add(1)
+                && to_block.saturating_add(1) >= scanned.from_block =>
+        {
+            if to_block > scanned.to_block {
+                let (mut newer, highest) =
+                    scan_transaction_history(rpc_url, address, scanned.to_block + 1, to_block)
+                        .await?;
+                newer.append(&mut scanned.transactions);
+                scanned.transactions = newer;
+                // Blocks past the chain head aren't cached; they may still be mined
+                if let Some(highest) = highest {
+                    scanned.to_block = highest;
+                }
+            }
+            if from_block < scanned.from_block {
+                let (mut older, _) =
+                    scan_transaction_history(rpc_url, address, from_block, scanned.from_block - 1)
+                        .await?;
+                scanned.transactions.append(&mut older);
+                scanned.from_block = from_block;
+            }
+            Some(scanned)
+        }
+        _ => {
+            let (transactions, highest) =
+                scan_transaction_history(rpc_url, address, from_block, to_block).await?;
+            highest.map(|to_block| ScannedHistory {
+                from_block,
+                to_block,
+                transactions,
+            })
+        }
+    };
+
+    // Nothing to cache (or return) if the whole range is past the chain head
+    let Some(scanned) = scanned else {
+        return Ok(Vec::new());
+    };
+    let transactions = scanned
+        .transactions
+        .iter()
+        .filter(|tx| tx.block_number >= from_block && tx.block_number <= to_block)
+        .cloned()
+        .collect();
+    TX_HISTORY_CACHE.lock().await.insert(key, scanned);
+    Ok(transactions)
+}
+
+/// Fetch every block in the range and pick out transactions to or from
+/// `address`. Also returns the highest block the node had; blocks above it
+/// don't exist yet.
+async fn scan_transaction_history(
+    rpc_url: &str,
+    address: &str,
+    from_block: u64,
+    to_block: u64,
+) -> Result<(Vec<TransactionHistoryItem>, Option<u64>), String> {
     let client = reqwest::Client::new();
     let address_lower = address.to_lowercase();
     let mut transactions = Vec::new();
+    let mut highest = None;
 
     // Scan blocks from newest to oldest
     for block_num in (from_block..=to_block).rev() {
@@ -3119,7 +3527,7 @@ pub async fn get_transaction_history(
         });
 
         let response = client
-            .post(&NETWORK_CONFIG.rpc_endpoint)
+            .post(rpc_url)
             .json(&payload)
             .send()
             .await
@@ -3136,8 +3544,9 @@ pub async fn get_transaction_history(
 
         let block = match json_response["result"].as_object() {
             Some(b) => b,
-            None => continue, // No block data
+            None => continue, // Past the chain head
         };
+        highest = highest.max(Some(block_num));
 
         // Get block timestamp
         let timestamp = block.get("timestamp")
@@ -3193,7 +3602,7 @@ pub async fn get_transaction_history(
                 });
 
                 let receipt_response = client
-                    .post(&NETWORK_CONFIG.rpc_endpoint)
+                    .post(rpc_url)
                     .json(&receipt_payload)
                     .send()
                     .await
@@ -3265,7 +3674,7 @@ pub async fn get_transaction_history(
         }
     }
 
-    Ok(transactions)
+    Ok((transactions, highest))
 }
 
 /// Reset the incremental block scanning state
@@ -3279,4 +3688,322 @@ pub async fn reset_incremental_scanning() {
     static CUMULATIVE_COUNTS: Lazy<Mutex<HashMap<String, u64>>> = Lazy::new(|| Mutex::new(HashMap::new()));
     let mut counts = CUMULATIVE_COUNTS.lock().await;
     counts.clear();
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{extract::State, routing::post, Json, Router};
+    use ethers::utils::rlp::Rlp;
+    use std::sync::Arc;
+
+    type Requests = Arc<std::sync::Mutex<Vec<serde_json::Value>>>;
+
+    /// Minimal JSON-RPC node: canned answers, records every request and
+    /// returns keccak256(raw) from eth_sendRawTransaction like geth does.
+    async fn mock_rpc(
+        State(requests): State<Requests>,
+        Json(request): Json<serde_json::Value>,
+    ) -> Json<serde_json::Value> {
+        requests.lock().unwrap().push(request.clone());
+        if request["method"] == "eth_getBlockByNumber" && request["params"][0] == "0x64" {
+            return Json(json!({
+                "jsonrpc": "2.0",
+                "id": request["id"],
+                "error": { "code": -32000, "message": "block unavailable" }
+            }));
+        }
+        let result = match request["method"].as_str().unwrap_or_default() {
+            "eth_getTransactionCount" => json!("0x5"),
+            "eth_estimateGas" => json!("0x5208"),
+            "eth_gasPrice" => json!("0x3b9aca00"),
+            "eth_getBlockByNumber" => history_block(request["params"][0].as_str().unwrap()),
+            "eth_getTransactionReceipt" => json!({ "status": "0x1", "gasUsed": "0x5208" }),
+            "eth_sendRawTransaction" => {
+                let raw = hex::decode(
+                    request["params"][0]
+                        .as_str()
+                        .unwrap()
+                        .trim_start_matches("0x"),
+                )
+                .unwrap();
+                json!(format!("0x{}", hex::encode(Keccak256::digest(&raw))))
+            }
+            _ => json!(null),
+        };
+        Json(json!({ "jsonrpc": "2.0", "id": request["id"], "result": result }))
+    }
+
+    const HISTORY_ADDRESS: &str = "0x00000000000000000000000000000000000000a1";
+
+    /// Blocks 1-3 each hold one transaction for `HISTORY_ADDRESS` and block 2
+    /// also holds an unrelated one. Block 3 is the chain head, and fetching
+    /// block 100 is an RPC error.
+    fn history_block(number: &str) -> serde_json::Value {
+        let tx = |hash: &str, from: &str, to: &str, value: &str| {
+            json!({
+                "hash": hash,
+                "from": from,
+                "to": to,
+                "value": value,
+                "gasPrice": "0x1"
+            })
+        };
+        let other = "0x00000000000000000000000000000000000000b2";
+        let unrelated = "0x00000000000000000000000000000000000000c3";
+        let transactions = match number {
+            "0x1" => vec![tx("0x01", HISTORY_ADDRESS, other, "0x64")],
+            "0x2" => vec![
+                tx("0x02", other, unrelated, "0x1"),
+                tx("0x03", other, HISTORY_ADDRESS, "0xc8"),
+            ],
+            "0x3" => vec![tx("0x04", other, HISTORY_ADDRESS, "0x12c")],
+            _ => return json!(null),
+        };
+        json!({ "number": number, "timestamp": "0x65a1b2c3", "transactions": transactions })
+    }
+
+    async fn spawn_mock_rpc(requests: Requests) -> String {
+        let app = Router::new()
+            .route("/", post(mock_rpc))
+            .with_state(requests);
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let rpc_url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        rpc_url
+    }
+
+    #[tokio::test]
+    async fn test_transaction_history_extracts_matching_transactions() {
+        let requests: Requests = Arc::default();
+        let rpc_url = spawn_mock_rpc(requests.clone()).await;
+        let blocks_fetched = |requests: &Requests| {
+            requests
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|r| r["method"] == "eth_getBlockByNumber")
+                .map(|r| r["params"][0].as_str().unwrap().to_string())
+                .collect::<Vec<_>>()
+        };
+
+        let history = get_transaction_history_via(&rpc_url, HISTORY_ADDRESS, 1, 2)
+            .await
+            .unwrap();
+        let hashes: Vec<&str> = history.iter().map(|tx| tx.hash.as_str()).collect();
+        assert_eq!(hashes, vec!["0x03", "0x01"]);
+        assert_eq!(history[0].tx_type, "received");
+        assert_eq!(history[0].value, "0xc8");
+        assert_eq!(history[0].block_number, 2);
+        assert_eq!(history[0].timestamp, 0x65a1b2c3);
+        assert_eq!(history[0].status, "success");
+        assert_eq!(history[1].tx_type, "sent");
+        assert_eq!(
+            history[1].to.as_deref(),
+            Some("0x00000000000000000000000000000000000000b2")
+        );
+        assert_eq!(blocks_fetched(&requests), vec!["0x2", "0x1"]);
+
+        // Only the block above the cached range is fetched
+        let history = get_transaction_history_via(&rpc_url, HISTORY_ADDRESS, 1, 3)
+            .await
+            .unwrap();
+        let hashes: Vec<&str> = history.iter().map(|tx| tx.hash.as_str()).collect();
+        assert_eq!(hashes, vec!["0x04", "0x03", "0x01"]);
+        assert_eq!(blocks_fetched(&requests), vec!["0x2", "0x1", "0x3"]);
+
+        // Sub-ranges of the cache are answered without any RPC calls
+        let history = get_transaction_history_via(&rpc_url, HISTORY_ADDRESS, 2, 2)
+            .await
+            .unwrap();
+        assert_eq!(history.len(), 1);
+        assert_eq!(blocks_fetched(&requests).len(), 3);
+
+        // A failed scan keeps the cached history, so the cached range still
+        // needs no RPC calls afterwards
+        assert!(
+            get_transaction_history_via(&rpc_url, HISTORY_ADDRESS, 1, 100)
+                .await
+                .is_err()
+        );
+        let fetched = blocks_fetched(&requests).len();
+        let history = get_transaction_history_via(&rpc_url, HISTORY_ADDRESS, 1, 3)
+            .await
+            .unwrap();
+        assert_eq!(history.len(), 3);
+        assert_eq!(blocks_fetched(&requests).len(), fetched);
+
+        // Blocks past the head aren't cached as empty, so they are fetched
+        // again once they may have been mined
+        let history = get_transaction_history_via(&rpc_url, HISTORY_ADDRESS, 1, 5)
+            .await
+            .unwrap();
+        assert_eq!(history.len(), 3);
+        assert_eq!(blocks_fetched(&requests).len(), fetched + 2);
+        get_transaction_history_via(&rpc_url, HISTORY_ADDRESS, 1, 5)
+            .await
+            .unwrap();
+        assert_eq!(blocks_fetched(&requests).len(), fetched + 4);
+    }
+
+    #[tokio::test]
+    async fn test_send_raw_transaction_signs_and_submits() {
+        let requests: Requests = Arc::default();
+        let app = Router::new()
+            .route("/", post(mock_rpc))
+            .with_state(requests.clone());
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let rpc_url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let account = create_new_account().unwrap();
+        let to = "0x00000000000000000000000000000000000000aa";
+        let chain_id = 98765;
+        let tx_hash = send_raw_transaction_via(
+            &rpc_url,
+            chain_id,
+            &account.address,
+            to,
+            U256::from(1_000u64),
+            vec![0xde, 0xad, 0xbe, 0xef],
+            &account.private_key,
+        )
+        .await
+        .unwrap();
+
+        let requests = requests.lock().unwrap().clone();
+        let methods: Vec<&str> = requests
+            .iter()
+            .map(|r| r["method"].as_str().unwrap())
+            .collect();
+        assert_eq!(
+            methods,
+            vec![
+                "eth_getTransactionCount",
+                "eth_estimateGas",
+                "eth_gasPrice",
+                "eth_sendRawTransaction"
+            ]
+        );
+        assert_eq!(requests[0]["params"][1], "pending");
+        assert_eq!(requests[1]["params"][0]["data"], "0xdeadbeef");
+        assert_eq!(requests[1]["params"][0]["value"], "0x3e8");
+
+        let raw_hex = requests[3]["params"][0].as_str().unwrap();
+        let raw = hex::decode(raw_hex.trim_start_matches("0x")).unwrap();
+        assert_eq!(
+            tx_hash,
+            format!("0x{}", hex::encode(Keccak256::digest(&raw)))
+        );
+
+        let (tx, signature) = TypedTransaction::decode_signed(&Rlp::new(&raw)).unwrap();
+        assert_eq!(tx.nonce(), Some(&U256::from(5)));
+        assert_eq!(tx.gas(), Some(&U256::from(21_000)));
+        assert_eq!(tx.gas_price(), Some(U256::from(1_000_000_000u64)));
+        assert_eq!(tx.value(), Some(&U256::from(1_000u64)));
+        assert_eq!(
+            tx.data().map(|d| d.to_vec()),
+            Some(vec![0xde, 0xad, 0xbe, 0xef])
+        );
+        // EIP-155: v encodes the chain id
+        assert!(signature.v == chain_id * 2 + 35 || signature.v == chain_id * 2 + 36);
+        let signer = signature.recover(tx.sighash()).unwrap();
+        assert_eq!(format!("{:?}", signer), account.address);
+    }
+
+    #[test]
+    fn test_fee_history_parsed_into_tiers() {
+        // Trimmed geth response for eth_feeHistory(4, "latest", [10, 50, 90])
+        let history = json!({
+            "oldestBlock": "0x10",
+            "baseFeePerGas": ["0x3b9aca00", "0x3b9aca00", "0x4190ab00", "0x47868c00", "0x4a817c80"],
+            "gasUsedRatio": [0.5, 0.9, 0.95, 0.6],
+            "reward": [
+                ["0x1", "0x3b9aca00", "0x77359400"],
+                ["0x2", "0x59682f00", "0xb2d05e00"],
+                ["0x1", "0x3b9aca00", "0x77359400"],
+                ["0x5", "0x77359400", "0xee6b2800"]
+            ]
+        });
+
+        let estimate = gas_estimate_from_fee_history(1_500_000_000, &history).unwrap();
+        let base_fee = 1_250_000_000;
+        assert_eq!(estimate.gas_price, 1_500_000_000);
+        assert_eq!(
+            estimate.slow,
+            FeeSuggestion {
+                base_fee,
+                priority_fee: 2,
+                max_fee: 2 * base_fee + 2,
+            }
+        );
+        assert_eq!(estimate.standard.priority_fee, 1_500_000_000);
+        assert_eq!(estimate.standard.max_fee, 2 * base_fee + 1_500_000_000);
+        assert_eq!(estimate.fast.priority_fee, 3_000_000_000);
+        assert!(estimate.slow.max_fee < estimate.standard.max_fee);
+        assert!(estimate.standard.max_fee < estimate.fast.max_fee);
+
+        // Empty blocks report no rewards; priority fees floor at 1 wei
+        let empty = json!({ "baseFeePerGas": ["0x7", "0x7"], "reward": [] });
+        let estimate = gas_estimate_from_fee_history(7, &empty).unwrap();
+        assert_eq!(estimate.fast.priority_fee, 1);
+        assert_eq!(estimate.fast.max_fee, 15);
+    }
+
+    #[tokio::test]
+    async fn test_new_heads_subscription_fires_callback() {
+        use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+
+        async fn serve_heads(mut socket: WebSocket) {
+            while let Some(Ok(Message::Text(text))) = socket.recv().await {
+                let request: serde_json::Value = serde_json::from_str(&text).unwrap();
+                if request["method"] != "eth_subscribe" {
+                    continue;
+                }
+                assert_eq!(request["params"], json!(["newHeads"]));
+                let subscription = "0x9cef478923ff08bf67fde6c64013158d";
+                let reply =
+                    json!({ "jsonrpc": "2.0", "id": request["id"], "result": subscription });
+                socket.send(Message::Text(reply.to_string())).await.unwrap();
+                let head = json!({
+                    "jsonrpc": "2.0",
+                    "method": "eth_subscription",
+                    "params": {
+                        "subscription": subscription,
+                        "result": { "number": "0x1b4", "timestamp": "0x65a1b2c3" }
+                    }
+                });
+                socket.send(Message::Text(head.to_string())).await.unwrap();
+            }
+        }
+
+        let app = axum::Router::new().route(
+            "/",
+            axum::routing::get(|ws: WebSocketUpgrade| async move { ws.on_upgrade(serve_heads) }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        let ws_url = format!("ws://{}", addr);
+        tokio::spawn(async move {
+            let _ = subscribe_new_heads(&ws_url, move |block| {
+                let _ = tx.send(block);
+            })
+            .await;
+        });
+
+        let block = tokio::time::timeout(Duration::from_secs(5), rx.recv())
+            .await
+            .expect("no new-block event within 5s")
+            .unwrap();
+        assert_eq!(
+            block,
+            NewBlockEvent {
+                number: 436,
+                timestamp: 0x65a1b2c3,
+            }
+        );
+    }
+}
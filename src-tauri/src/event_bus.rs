@@ -0,0 +1,247 @@
+// Sequenced event bus shared by the long-running services.
+//
+// Every published event gets a monotonically increasing sequence number and is
+// kept in a bounded history, so a consumer that falls behind (or subscribes
+// late) can ask for "everything since seq N" instead of silently losing events
+// the way a full mpsc buffer would.
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use tokio::sync::{broadcast, mpsc};
+use tokio::task::JoinHandle;
+use tracing::warn;
+
+/// History retained per bus unless a service asks for something else
+pub const DEFAULT_EVENT_HISTORY: usize = 1024;
+
+/// The live channel buffers this fraction of the history, so the events a
+/// lagged receiver skipped are still retained when it asks for them
+const LIVE_BUFFER_DIVISOR: usize = 4;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SequencedEvent<T> {
+    /// Starts at 1 and increases by one per published event
+    pub seq: u64,
+    pub event: T,
+}
+
+#[derive(Debug, thiserror::Error, PartialEq, Eq)]
+pub enum EventBusError {
+    #[error("events before seq {oldest} are no longer retained (requested {requested})")]
+    Evicted { requested: u64, oldest: u64 },
+}
+
+struct BusState<T> {
+    history: VecDeque<SequencedEvent<T>>,
+    next_seq: u64,
+    /// Position of the shared `drain` consumer
+    drain_cursor: u64,
+}
+
+pub struct EventBus<T> {
+    state: Mutex<BusState<T>>,
+    live: broadcast::Sender<SequencedEvent<T>>,
+    capacity: usize,
+}
+
+impl<T: Clone> EventBus<T> {
+    /// Keep `capacity` events of history. The live channel is a quarter of
+    /// that so lagged subscribers can always be replayed from the history.
+    pub fn new(capacity: usize) -> Self {
+        let capacity = capacity.max(LIVE_BUFFER_DIVISOR);
+        let (live, _) = broadcast::channel(capacity / LIVE_BUFFER_DIVISOR);
+        EventBus {
+            state: Mutex::new(BusState {
+                history: VecDeque::with_capacity(capacity),
+                next_seq: 1,
+                drain_cursor: 1,
+            }),
+            live,
+            capacity,
+        }
+    }
+
+    /// Record `event` and push it to live subscribers. Returns its sequence number.
+    pub fn publish(&self, event: T) -> u64 {
+        let mut state = self.state.lock().unwrap();
+        let seq = state.next_seq;
+        state.next_seq += 1;
+        if state.history.len() == self.capacity {
+            state.history.pop_front();
+        }
+        let sequenced = SequencedEvent { seq, event };
+        state.history.push_back(sequenced.clone());
+        // Sent under the lock so subscribers see events in sequence order
+        let _ = self.live.send(sequenced);
+        seq
+    }
+
+    /// Live stream of new events. A receiver that lags can catch up with
+    /// `events_since(last_seen + 1, ..)`.
+    pub fn subscribe(&self) -> broadcast::Receiver<SequencedEvent<T>> {
+        self.live.subscribe()
+    }
+
+    /// Like `subscribe`, but also returns the sequence number of the last event
+    /// published before the receiver was created (0 if none). The receiver gets
+    /// exactly the events after it, so replay should start from there.
+    pub fn subscribe_with_seq(&self) -> (u64, broadcast::Receiver<SequencedEvent<T>>) {
+        // Publishing sends under this lock, so no event can fall between the two
+        let state = self.state.lock().unwrap();
+        (state.next_seq - 1, self.live.subscribe())
+    }
+
+    /// Sequence number the next published event will get
+    pub fn next_seq(&self) -> u64 {
+        self.state.lock().unwrap().next_seq
+    }
+
+    /// Up to `max` retained events with `seq >= from_seq`, oldest first. Errors
+    /// if some of the requested events have already been evicted.
+    pub fn events_since(
+        &self,
+        from_seq: u64,
+        max: usize,
+    ) -> Result<Vec<SequencedEvent<T>>, EventBusError> {
+        let state = self.state.lock().unwrap();
+        let from_seq = from_seq.max(1);
+        let oldest = state.history.front().map_or(state.next_seq, |e| e.seq);
+        if from_seq < oldest {
+            return Err(EventBusError::Evicted {
+                requested: from_seq,
+                oldest,
+            });
+        }
+        let skip = (from_seq - oldest) as usize;
+        Ok(state.history.iter().skip(skip).take(max).cloned().collect())
+    }
+
+    /// Take up to `max` events not yet returned by `drain`. Backs the older
+    /// poll-based `drain_events` APIs; if the drain consumer fell behind the
+    /// history, the gap is logged and draining resumes at the oldest event.
+    pub fn drain(&self, max: usize) -> Vec<T> {
+        let mut state = self.state.lock().unwrap();
+        let oldest = state.history.front().map_or(state.next_seq, |e| e.seq);
+        if state.drain_cursor < oldest {
+            warn!(
+                "Event drain fell behind; {} events were evicted before being drained",
+                oldest - state.drain_cursor
+            );
+            state.drain_cursor = oldest;
+        }
+        let skip = (state.drain_cursor - oldest) as usize;
+        let events: Vec<T> = state
+            .history
+            .iter()
+            .skip(skip)
+            .take(max)
+            .map(|e| e.event.clone())
+            .collect();
+        state.drain_cursor += events.len() as u64;
+        events
+    }
+}
+
+impl<T: Clone + Send + 'static> EventBus<T> {
+    /// Publish everything received on `rx` until its senders are dropped. Lets a
+    /// service keep its internal `mpsc::Sender` plumbing while publishing
+    /// through the bus.
+    pub fn forward_from(self: &Arc<Self>, mut rx: mpsc::Receiver<T>) -> JoinHandle<()> {
+        let bus = Arc::clone(self);
+        tokio::spawn(async move {
+            while let Some(event) = rx.recv().await {
+                bus.publish(event);
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_events_since_has_no_gaps_or_duplicates() {
+        let bus = EventBus::new(DEFAULT_EVENT_HISTORY);
+        for i in 0..150u32 {
+            bus.publish(i);
+        }
+
+        // Page through from an arbitrary starting seq in uneven batches
+        let mut cursor = 10;
+        let mut seen = Vec::new();
+        loop {
+            let batch = bus.events_since(cursor, 37).unwrap();
+            if batch.is_empty() {
+                break;
+            }
+            cursor = batch.last().unwrap().seq + 1;
+            seen.extend(batch);
+        }
+
+        let seqs: Vec<u64> = seen.iter().map(|e| e.seq).collect();
+        assert_eq!(seqs, (10..=150).collect::<Vec<u64>>());
+        for e in &seen {
+            assert_eq!(e.event as u64, e.seq - 1);
+        }
+        assert_eq!(bus.next_seq(), 151);
+    }
+
+    #[test]
+    fn test_evicted_events_are_reported() {
+        let bus = EventBus::new(100);
+        for i in 0..150u32 {
+            bus.publish(i);
+        }
+
+        assert_eq!(
+            bus.events_since(1, 10),
+            Err(EventBusError::Evicted {
+                requested: 1,
+                oldest: 51
+            })
+        );
+        assert_eq!(bus.events_since(51, 1).unwrap()[0].event, 50);
+
+        // The drain consumer skips to the oldest retained event
+        let drained = bus.drain(usize::MAX);
+        assert_eq!(drained.len(), 100);
+        assert_eq!(drained[0], 50);
+        assert!(bus.drain(10).is_empty());
+    }
+
+    #[test]
+    fn test_lagged_subscriber_can_replay_from_history() {
+        let bus = EventBus::new(DEFAULT_EVENT_HISTORY);
+        bus.publish(0u32);
+        let (last_seen, mut live) = bus.subscribe_with_seq();
+        assert_eq!(last_seen, 1);
+
+        for i in 1..=DEFAULT_EVENT_HISTORY as u32 / 2 {
+            bus.publish(i);
+        }
+        let skipped = match live.try_recv() {
+            Err(broadcast::error::TryRecvError::Lagged(n)) => n,
+            other => panic!("expected a lagged receiver, got {:?}", other),
+        };
+        let replay = bus.events_since(last_seen + 1, skipped as usize).unwrap();
+        assert_eq!(replay.len() as u64, skipped);
+        assert_eq!(replay[0].seq, 2);
+        // Replay ends right where the live channel picks up again
+        assert_eq!(live.try_recv().unwrap().seq, replay.last().unwrap().seq + 1);
+    }
+
+    #[tokio::test]
+    async fn test_forwarded_events_reach_subscribers() {
+        let bus = Arc::new(EventBus::new(16));
+        let mut live = bus.subscribe();
+        let (tx, rx) = mpsc::channel(4);
+        bus.forward_from(rx);
+
+        tx.send("hello").await.unwrap();
+        let received = live.recv().await.unwrap();
+        assert_eq!(received.seq, 1);
+        assert_eq!(received.event, "hello");
+    }
+}
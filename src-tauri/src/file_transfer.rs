@@ -1,4 +1,6 @@
+use crate::analytics::{AnalyticsService, TransferDirection, TransferRecord};
 use crate::encryption;
+use crate::event_bus::{EventBus, EventBusError, SequencedEvent, DEFAULT_EVENT_HISTORY};
 use crate::transfer_events::{
     TransferEventBus, TransferCompletedEvent, TransferFailedEvent,
     TransferStartedEvent, SourceInfo, SourceType, SourceSummary, ErrorCategory,
@@ -11,7 +13,7 @@ use std::path::PathBuf;
 use std::sync::Arc;
 use std::time::{Instant, SystemTime, UNIX_EPOCH};
 use tauri::AppHandle;
-use tokio::sync::{mpsc, Mutex};
+use tokio::sync::{broadcast, mpsc, Mutex};
 use tokio::time::{sleep, Duration};
 use tracing::{debug, error, info, info_span, warn};
 use x25519_dalek::StaticSecret;
@@ -156,7 +158,7 @@ static FAIL_WRITE_BEFORE_SUCCESS: AtomicU32 = AtomicU32::new(0);
 
 pub struct FileTransferService {
     cmd_tx: mpsc::Sender<FileTransferCommand>,
-    event_rx: Arc<Mutex<mpsc::Receiver<FileTransferEvent>>>,
+    events: Arc<EventBus<FileTransferEvent>>,
     storage_dir: PathBuf,
     download_metrics: Arc<Mutex<DownloadMetrics>>,
     event_bus: Option<Arc<TransferEventBus>>,
@@ -334,15 +336,24 @@ impl FileTransferService {
         encryption_enabled: bool,
         keystore: Arc<Mutex<crate::keystore::Keystore>>,
     ) -> Result<Self, String> {
-        Self::new_with_storage_dir(Self::get_storage_dir()?, encryption_enabled, keystore, None).await
+        Self::new_with_storage_dir(
+            Self::get_storage_dir()?,
+            encryption_enabled,
+            keystore,
+            None,
+            None,
+        )
+        .await
     }
 
-    /// Create with custom storage directory, encryption, keystore, and optional AppHandle
+    /// Create with custom storage directory, encryption, keystore, optional AppHandle,
+    /// and optional analytics that record every upload/download
     pub async fn new_with_storage_dir(
         storage_dir: PathBuf,
         encryption_enabled: bool,
         keystore: Arc<Mutex<crate::keystore::Keystore>>,
         app_handle: Option<AppHandle>,
+        analytics: Option<Arc<AnalyticsService>>,
     ) -> Result<Self, String> {
 
         // Create storage directory if it doesn't exist
@@ -354,6 +365,8 @@ impl FileTransferService {
 
         let (cmd_tx, cmd_rx) = mpsc::channel(100);
         let (event_tx, event_rx) = mpsc::channel(100);
+        let events = Arc::new(EventBus::new(DEFAULT_EVENT_HISTORY));
+        events.forward_from(event_rx);
         let download_metrics = Arc::new(Mutex::new(DownloadMetrics::default()));
 
         // Create TransferEventBus if app_handle is provided
@@ -368,11 +381,12 @@ impl FileTransferService {
             encryption_enabled,
             keystore.clone(),
             event_bus.clone(),
+            analytics,
         ));
 
         Ok(FileTransferService {
             cmd_tx,
-            event_rx: Arc::new(Mutex::new(event_rx)),
+            events,
             storage_dir,
             download_metrics,
             event_bus,
@@ -394,11 +408,21 @@ impl FileTransferService {
     }
 
     /// Create with app handle for TransferEventBus integration
-    pub async fn new_with_app_handle(app_handle: AppHandle) -> Result<Self, String> {
+    pub async fn new_with_app_handle(
+        app_handle: AppHandle,
+        analytics: Arc<AnalyticsService>,
+    ) -> Result<Self, String> {
         let keystore = Arc::new(Mutex::new(
             crate::keystore::Keystore::load().unwrap_or_default(),
         ));
-        Self::new_with_storage_dir(Self::get_storage_dir()?, false, keystore, Some(app_handle)).await
+        Self::new_with_storage_dir(
+            Self::get_storage_dir()?,
+            false,
+            keystore,
+            Some(app_handle),
+            Some(analytics),
+        )
+        .await
     }
 
     fn get_storage_dir() -> Result<PathBuf, String> {
@@ -415,6 +439,7 @@ impl FileTransferService {
         encryption_enabled: bool,
         keystore: Arc<Mutex<crate::keystore::Keystore>>,
         event_bus: Option<Arc<TransferEventBus>>,
+        analytics: Option<Arc<AnalyticsService>>,
     ) {
         while let Some(cmd) = cmd_rx.recv().await {
             match cmd {
@@ -423,36 +448,56 @@ impl FileTransferService {
                     file_name,
                     active_account,
                     active_private_key,
-                } => match Self::handle_upload_file(
-                    &file_path,
-                    &file_name,
-                    &storage_dir,
-                    encryption_enabled,
-                    None,
-                    &keystore,
-                    active_account.as_deref(),
-                    active_private_key.as_deref(),
-                )
-                .await
-                {
-                    Ok((file_hash, _encrypted_metadata)) => {
-                        let _ = event_tx
-                            .send(FileTransferEvent::FileUploaded {
-                                file_hash: file_hash.clone(),
-                                file_name: file_name.clone(),
+                } => {
+                    let started = Instant::now();
+                    let result = Self::handle_upload_file(
+                        &file_path,
+                        &file_name,
+                        &storage_dir,
+                        encryption_enabled,
+                        None,
+                        &keystore,
+                        active_account.as_deref(),
+                        active_private_key.as_deref(),
+                    )
+                    .await;
+                    if let Some(ref analytics) = analytics {
+                        let bytes = tokio::fs::metadata(&file_path)
+                            .await
+                            .map(|metadata| metadata.len())
+                            .unwrap_or(0);
+                        analytics
+                            .record_transfer_outcome(TransferRecord {
+                                transfer_id: file_path.clone(),
+                                direction: TransferDirection::Upload,
+                                bytes,
+                                duration_ms: started.elapsed().as_millis() as u64,
+                                source: "local-storage".to_string(),
+                                success: result.is_ok(),
+                                timestamp: current_timestamp_ms() / 1000,
                             })
                             .await;
                     }
-                    Err(e) => {
-                        let error_msg = format!("Upload failed: {}", e);
-                        let _ = event_tx
-                            .send(FileTransferEvent::Error {
-                                message: error_msg.clone(),
-                            })
-                            .await;
-                        error!("File upload failed: {}", error_msg);
+                    match result {
+                        Ok((file_hash, _encrypted_metadata)) => {
+                            let _ = event_tx
+                                .send(FileTransferEvent::FileUploaded {
+                                    file_hash: file_hash.clone(),
+                                    file_name: file_name.clone(),
+                                })
+                                .await;
+                        }
+                        Err(e) => {
+                            let error_msg = format!("Upload failed: {}", e);
+                            let _ = event_tx
+                                .send(FileTransferEvent::Error {
+                                    message: error_msg.clone(),
+                                })
+                                .await;
+                            error!("File upload failed: {}", error_msg);
+                        }
                     }
-                },
+                }
                 FileTransferCommand::DownloadFile {
                     file_hash,
                     output_path,
@@ -503,15 +548,33 @@ impl FileTransferService {
                                 })
                                 .await;
 
+                            let end_time = current_timestamp_ms();
+                            let file_size = tokio::fs::metadata(&output_path)
+                                .await
+                                .map(|metadata| metadata.len())
+                                .unwrap_or(0);
+                            if let Some(ref analytics) = analytics {
+                                analytics
+                                    .record_transfer_outcome(TransferRecord {
+                                        transfer_id: file_hash.clone(),
+                                        direction: TransferDirection::Download,
+                                        bytes: file_size,
+                                        duration_ms: end_time.saturating_sub(start_time),
+                                        source: "local-storage".to_string(),
+                                        success: true,
+                                        timestamp: end_time / 1000,
+                                    })
+                                    .await;
+                            }
+
                             // Emit completed event via TransferEventBus
                             if let Some(ref bus) = event_bus {
-                                let end_time = current_timestamp_ms();
                                 let duration_secs = (end_time - start_time) / 1000;
                                 bus.emit_completed(TransferCompletedEvent {
                                     transfer_id: file_hash.clone(),
                                     file_hash: file_hash.clone(),
                                     file_name: output_path.clone(),
-                                    file_size: 0, // Would need to track actual size
+                                    file_size,
                                     output_path: output_path.clone(),
                                     completed_at: end_time,
                                     duration_seconds: duration_secs,
@@ -541,12 +604,27 @@ impl FileTransferService {
                                 })
                                 .await;
 
+                            let failed_at = current_timestamp_ms();
+                            if let Some(ref analytics) = analytics {
+                                analytics
+                                    .record_transfer_outcome(TransferRecord {
+                                        transfer_id: file_hash.clone(),
+                                        direction: TransferDirection::Download,
+                                        bytes: 0,
+                                        duration_ms: failed_at.saturating_sub(start_time),
+                                        source: "local-storage".to_string(),
+                                        success: false,
+                                        timestamp: failed_at / 1000,
+                                    })
+                                    .await;
+                            }
+
                             // Emit failed event via TransferEventBus
                             if let Some(ref bus) = event_bus {
                                 bus.emit_failed(TransferFailedEvent {
                                     transfer_id: file_hash.clone(),
                                     file_hash: file_hash.clone(),
-                                    failed_at: current_timestamp_ms(),
+                                    failed_at,
                                     error: error_msg.clone(),
                                     error_category: ErrorCategory::Unknown,
                                     downloaded_bytes: 0,
@@ -585,28 +663,39 @@ impl FileTransferService {
         let original_file_hash = Self::calculate_file_hash(&file_data);
 
         let (final_file_hash, encrypted_metadata) = if encryption_enabled {
-            // Generate random encryption key
-            let encryption_key = encryption::FileEncryption::generate_random_key();
-
-            // Store the encryption key in keystore if we have an active account
-            if let (Some(account), Some(private_key)) = (active_account, active_private_key) {
-                match keystore.lock().await.store_file_encryption_key_with_private_key(
-                    account,
-                    original_file_hash.clone(),
-                    &encryption_key,
-                    private_key,
-                ) {
-                    Ok(_) => {
-                        info!("✅ Stored encryption key for file: {}", original_file_hash);
-                    }
-                    Err(e) => {
-                        warn!("⚠️  Failed to store encryption key (continuing anyway): {}", e);
-                        // Don't fail the upload - encryption key storage is optional for testing
+            // Derive the key from the active account so it can be re-derived
+            // later, reusing the one already stored for this file; without an
+            // account fall back to a random key
+            let encryption_key = match (active_account, active_private_key) {
+                (Some(account), Some(private_key)) => {
+                    let stored = keystore
+                        .lock()
+                        .await
+                        .get_or_create_file_key_with_private_key(
+                            account,
+                            &original_file_hash,
+                            private_key,
+                        );
+                    match stored {
+                        Ok(key) => {
+                            info!("✅ Stored encryption key for file: {}", original_file_hash);
+                            key
+                        }
+                        Err(e) => {
+                            warn!(
+                                "⚠️  Failed to store encryption key (continuing anyway): {}",
+                                e
+                            );
+                            // Don't fail the upload - the key can still be re-derived
+                            crate::keystore::derive_file_key(private_key, &original_file_hash)?
+                        }
                     }
                 }
-            } else {
-                warn!("⚠️  No active account - skipping encryption key storage");
-            }
+                _ => {
+                    warn!("⚠️  No active account - skipping encryption key storage");
+                    encryption::FileEncryption::generate_random_key()
+                }
+            };
 
             // Create temporary encrypted file path
             let temp_encrypted_path = storage_dir.join(format!("{}.enc", original_file_hash));
@@ -819,6 +908,20 @@ impl FileTransferService {
             }
         }
 
+        // Try the bundle wrapped for this user if they were added as a recipient
+        if let Some(private_key_hex) = active_private_key {
+            let private_key_bytes = hex::decode(private_key_hex.trim_start_matches("0x")).ok()?;
+            let private_key_array: [u8; 32] = private_key_bytes.try_into().ok()?;
+            let private_key = StaticSecret::from(private_key_array);
+            let public_key = x25519_dalek::PublicKey::from(&private_key);
+
+            if let Some(key_bundle) = metadata.encryption_info.key_bundle_for(&public_key) {
+                if let Ok(key) = crate::encryption::decrypt_aes_key(key_bundle, &private_key) {
+                    return Some(key);
+                }
+            }
+        }
+
         // Try to decrypt the key bundle if one exists and user has the right private key
         if let (Some(key_bundle), Some(private_key_hex), Some(user_pk)) = (
             &metadata.encrypted_key_bundle,
@@ -927,17 +1030,21 @@ impl FileTransferService {
     }
 
     pub async fn drain_events(&self, max: usize) -> Vec<FileTransferEvent> {
-        let mut events = Vec::new();
-        let mut event_rx = self.event_rx.lock().await;
+        self.events.drain(max)
+    }
 
-        for _ in 0..max {
-            match event_rx.try_recv() {
-                Ok(event) => events.push(event),
-                Err(_) => break,
-            }
-        }
+    /// Receive transfer events as they happen instead of polling `drain_events`
+    pub fn subscribe_events(&self) -> broadcast::Receiver<SequencedEvent<FileTransferEvent>> {
+        self.events.subscribe()
+    }
 
-        events
+    /// Up to `max` retained events with sequence number `>= from_seq`
+    pub fn events_since(
+        &self,
+        from_seq: u64,
+        max: usize,
+    ) -> Result<Vec<SequencedEvent<FileTransferEvent>>, EventBusError> {
+        self.events.events_since(from_seq, max)
     }
 
     pub async fn store_file_data(&self, file_hash: String, file_name: String, file_data: Vec<u8>) {
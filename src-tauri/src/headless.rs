@@ -1,17 +1,25 @@
 // Headless mode for running as a bootstrap node on servers
 use crate::commands::bootstrap::get_bootstrap_nodes;
-use crate::dht::{models::DhtMetricsSnapshot, models::FileMetadata, DhtService};
+use crate::dht::{
+    models::DhtMetricsSnapshot, models::FileMetadata, parse_listen_addrs, DhtConfig, DhtService,
+};
 use crate::download_restart::{DownloadRestartService, StartDownloadRequest};
 use crate::e2e_api_headless::{start_headless_e2e_api_server, HeadlessE2eState};
 use crate::ethereum::GethProcess;
 use crate::file_transfer::FileTransferService;
+use crate::headless_metrics::{start_metrics_server, HeadlessMetricsState};
 use crate::http_server;
 use crate::keystore::Keystore;
-use crate::webrtc_service::{set_webrtc_service, WebRTCService};
+use crate::logger::{init_logging, LogFormat, LogOptions};
+use crate::reputation::PaymentPromiseLedger;
+use crate::webrtc_service::{set_webrtc_service, WebRTCService, WebRtcConfig};
 use crate::{bandwidth::BandwidthController, manager::ChunkManager};
-use clap::Parser;
+use clap::parser::ValueSource;
+use clap::{ArgMatches, CommandFactory, FromArgMatches, Parser};
+use serde::Deserialize;
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
 use std::{sync::Arc, time::Duration};
-use tokio::signal;
 use tokio::sync::Mutex;
 
 use tracing::{error, info, warn};
@@ -36,6 +44,12 @@ pub struct CliArgs {
     #[arg(long, default_value = "4001")]
     pub dht_port: u16,
 
+    /// Multiaddr to listen on instead of /ip4/0.0.0.0, e.g. /ip6/:: or one
+    /// interface's address (can be specified multiple times). Addresses
+    /// without /tcp/<port> use --dht-port.
+    #[arg(long)]
+    pub listen_addr: Vec<String>,
+
     /// Bootstrap nodes to connect to (can be specified multiple times)
     #[arg(long)]
     pub bootstrap: Vec<String>,
@@ -60,6 +74,15 @@ pub struct CliArgs {
     #[arg(long, default_value = "info")]
     pub log_level: String,
 
+    /// Log output format: text, or json for one JSON object per line
+    #[arg(long, value_enum, default_value_t = LogFormat::Text)]
+    pub log_format: LogFormat,
+
+    /// Per-target log directive, e.g. libp2p_kad=debug (can be specified
+    /// multiple times; replaces the defaults, which keep libp2p at warn)
+    #[arg(long)]
+    pub log_directive: Vec<String>,
+
     /// Generate multiaddr for this node (shows the address others can connect to)
     #[arg(long)]
     pub show_multiaddr: bool,
@@ -122,6 +145,42 @@ pub struct CliArgs {
     #[arg(long)]
     pub force_server_mode: bool,
 
+    /// Kademlia replication factor (number of peers each record is stored on)
+    #[arg(long)]
+    pub kad_replication_factor: Option<usize>,
+
+    /// Kademlia query timeout in seconds
+    #[arg(long)]
+    pub kad_query_timeout: Option<u64>,
+
+    /// Maximum Kademlia message size in bytes
+    #[arg(long)]
+    pub kad_max_packet_size: Option<usize>,
+
+    /// Kademlia protocol name (nodes only talk to peers using the same one)
+    #[arg(long)]
+    pub kad_protocol: Option<String>,
+
+    /// File to save the DHT routing table to on shutdown and restore it from on start
+    #[arg(long)]
+    pub peer_cache: Option<std::path::PathBuf>,
+
+    /// Maximum number of established connections; extra connections are refused
+    #[arg(long)]
+    pub max_connections: Option<u32>,
+
+    /// Maximum number of established connections to a single peer
+    #[arg(long)]
+    pub max_connections_per_peer: Option<u32>,
+
+    /// Maximum number of connections still being negotiated, per direction
+    #[arg(long)]
+    pub max_pending_connections: Option<u32>,
+
+    /// Region reported to peers, e.g. "EU", for region-preferring peer selection
+    #[arg(long)]
+    pub region: Option<String>,
+
     /// Start a restartable HTTP download when the node boots
     #[arg(long)]
     pub download_url: Option<String>,
@@ -145,20 +204,248 @@ pub struct CliArgs {
     /// Resume a paused restartable download by ID
     #[arg(long)]
     pub resume_download: Option<String>,
+
+    /// TOML file with node settings; flags given on the command line take precedence
+    #[arg(long)]
+    pub config: Option<PathBuf>,
+
+    /// Serve GET /health and GET /metrics on this port
+    #[arg(long)]
+    pub metrics_port: Option<u16>,
+
+    /// Address the metrics endpoint binds to; use 0.0.0.0 to expose it
+    #[arg(long, default_value = "127.0.0.1")]
+    pub metrics_bind: IpAddr,
+
+    /// Browser origin allowed to fetch from the HTTP file server (can be
+    /// specified multiple times; "*" allows any origin, for local development)
+    #[arg(long)]
+    pub cors_origin: Vec<String>,
+
+    /// HTTP method allowed for cross-origin requests (default: GET and HEAD)
+    #[arg(long)]
+    pub cors_method: Vec<String>,
+
+    /// Request header allowed for cross-origin requests (default: Range)
+    #[arg(long)]
+    pub cors_header: Vec<String>,
+
+    /// PEM certificate for serving the HTTP file server over HTTPS
+    #[arg(long, requires = "http_tls_key")]
+    pub http_tls_cert: Option<PathBuf>,
+
+    /// PEM private key matching --http-tls-cert
+    #[arg(long, requires = "http_tls_cert")]
+    pub http_tls_key: Option<PathBuf>,
+
+    /// Only serve HTTP file downloads that carry a valid payment promise
+    /// addressed to the CHIRAL_PRIVATE_KEY wallet. Opt-in: the app's own
+    /// downloaders don't send payment promises yet.
+    #[arg(long)]
+    pub require_payment: bool,
+}
+
+/// Node settings loaded with `--config`. Keys mirror the CLI flag names
+/// (with underscores); unknown keys are rejected.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct HeadlessConfigFile {
+    pub dht_port: Option<u16>,
+    pub listen_addr: Option<Vec<String>>,
+    pub bootstrap: Option<Vec<String>>,
+    pub enable_geth: Option<bool>,
+    pub geth_data_dir: Option<String>,
+    pub miner_address: Option<String>,
+    pub log_level: Option<String>,
+    pub log_format: Option<LogFormat>,
+    pub log_directive: Option<Vec<String>>,
+    pub secret: Option<String>,
+    pub is_bootstrap: Option<bool>,
+    pub disable_autonat: Option<bool>,
+    pub enable_relay: Option<bool>,
+    pub autonat_probe_interval: Option<u64>,
+    pub autonat_server: Option<Vec<String>>,
+    pub socks5_proxy: Option<String>,
+    pub disable_autorelay: Option<bool>,
+    pub relay: Option<Vec<String>>,
+    pub pure_client_mode: Option<bool>,
+    pub force_server_mode: Option<bool>,
+    pub kad_replication_factor: Option<usize>,
+    pub kad_query_timeout: Option<u64>,
+    pub kad_max_packet_size: Option<usize>,
+    pub kad_protocol: Option<String>,
+    pub peer_cache: Option<PathBuf>,
+    pub max_connections: Option<u32>,
+    pub max_connections_per_peer: Option<u32>,
+    pub max_pending_connections: Option<u32>,
+    pub region: Option<String>,
+    pub metrics_port: Option<u16>,
+    pub metrics_bind: Option<IpAddr>,
+    pub cors_origin: Option<Vec<String>>,
+    pub cors_method: Option<Vec<String>>,
+    pub cors_header: Option<Vec<String>>,
+    pub http_tls_cert: Option<PathBuf>,
+    pub http_tls_key: Option<PathBuf>,
+    pub require_payment: Option<bool>,
+}
+
+impl HeadlessConfigFile {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let contents = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read config file {}: {}", path.display(), e))?;
+        Self::from_toml(&contents)
+            .map_err(|e| format!("Invalid config file {}: {}", path.display(), e))
+    }
+
+    pub fn from_toml(contents: &str) -> Result<Self, String> {
+        toml::from_str(contents).map_err(|e| e.to_string())
+    }
+
+    /// Fill every setting in `args` that wasn't given on the command line
+    pub fn apply(self, args: &mut CliArgs, matches: &ArgMatches) {
+        macro_rules! merge {
+            ($($field:ident),* $(,)?) => {
+                $(
+                    if let Some(value) = self.$field {
+                        let from_cli = matches.value_source(stringify!($field))
+                            == Some(ValueSource::CommandLine);
+                        if !from_cli {
+                            args.$field = value;
+                        }
+                    }
+                )*
+            };
+        }
+        macro_rules! merge_optional {
+            ($($field:ident),* $(,)?) => {
+                $(
+                    if args.$field.is_none() {
+                        args.$field = self.$field;
+                    }
+                )*
+            };
+        }
+
+        merge!(
+            dht_port,
+            listen_addr,
+            bootstrap,
+            enable_geth,
+            geth_data_dir,
+            log_level,
+            log_format,
+            log_directive,
+            is_bootstrap,
+            disable_autonat,
+            enable_relay,
+            autonat_probe_interval,
+            autonat_server,
+            disable_autorelay,
+            relay,
+            pure_client_mode,
+            force_server_mode,
+            cors_origin,
+            cors_method,
+            cors_header,
+            require_payment,
+            metrics_bind,
+        );
+        merge_optional!(
+            miner_address,
+            secret,
+            socks5_proxy,
+            kad_replication_factor,
+            kad_query_timeout,
+            kad_max_packet_size,
+            kad_protocol,
+            peer_cache,
+            max_connections,
+            max_connections_per_peer,
+            max_pending_connections,
+            region,
+            metrics_port,
+            http_tls_cert,
+            http_tls_key,
+        );
+    }
+}
+
+impl CliArgs {
+    /// Parse the process arguments, layering them over `--config` if given
+    pub fn parse_with_config() -> Result<Self, String> {
+        Self::parse_from_with_config(std::env::args_os())
+    }
+
+    pub fn parse_from_with_config<I, T>(itr: I) -> Result<Self, String>
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<std::ffi::OsString> + Clone,
+    {
+        let matches = Self::command().get_matches_from(itr);
+        let mut args = Self::from_arg_matches(&matches).map_err(|e| e.to_string())?;
+        if let Some(path) = args.config.clone() {
+            HeadlessConfigFile::load(&path)?.apply(&mut args, &matches);
+        }
+        Ok(args)
+    }
+
+    /// `--listen-addr` values, parsed
+    pub fn listen_addrs(&self) -> Result<Vec<libp2p::Multiaddr>, String> {
+        parse_listen_addrs(&self.listen_addr)
+    }
+
+    /// `--log-format`, `--log-level` and `--log-directive` as `init_logging` options
+    pub fn log_options(&self) -> LogOptions {
+        LogOptions::new(self.log_format, self.log_level.clone())
+            .with_directives(self.log_directive.clone())
+    }
+
+    /// `--http-tls-cert` and `--http-tls-key`; setting only one is an error
+    /// rather than a silent fallback to plain HTTP
+    pub fn http_tls_config(&self) -> Result<Option<http_server::TlsConfig>, String> {
+        match (&self.http_tls_cert, &self.http_tls_key) {
+            (Some(cert), Some(key)) => Ok(Some(http_server::TlsConfig::new(cert, key))),
+            (None, None) => Ok(None),
+            _ => Err("http_tls_cert and http_tls_key must be set together".to_string()),
+        }
+    }
+
+    /// Apply the `--kad-*` and connection limit overrides on top of `config`
+    pub fn apply_dht_overrides<'a>(&self, mut config: DhtConfig<'a>) -> DhtConfig<'a> {
+        if config.is_bootstrap {
+            config = config.with_bootstrap_connection_limits();
+        }
+        if let Some(replication_factor) = self.kad_replication_factor {
+            config.replication_factor = replication_factor;
+        }
+        if let Some(secs) = self.kad_query_timeout {
+            config.query_timeout = Duration::from_secs(secs);
+        }
+        if let Some(max_packet_size) = self.kad_max_packet_size {
+            config.max_packet_size = max_packet_size;
+        }
+        if let Some(protocol_name) = &self.kad_protocol {
+            config.protocol_name = protocol_name.clone();
+        }
+        if self.max_connections.is_some() {
+            config.max_established_total = self.max_connections;
+        }
+        if self.max_connections_per_peer.is_some() {
+            config.max_established_per_peer = self.max_connections_per_peer;
+        }
+        if self.max_pending_connections.is_some() {
+            config.max_pending = self.max_pending_connections;
+        }
+        if self.region.is_some() {
+            config.region = self.region.clone();
+        }
+        config
+    }
 }
 
 pub async fn run_headless(args: CliArgs) -> Result<(), Box<dyn std::error::Error>> {
-    use tracing_subscriber::{fmt, prelude::*, EnvFilter};
-    let _ = tracing_subscriber::registry()
-        .with(fmt::layer())
-        .with(
-            EnvFilter::from_default_env()
-                .add_directive("chiral_network=info".parse().unwrap())
-                .add_directive("libp2p=info".parse().unwrap())
-                .add_directive("libp2p_kad=debug".parse().unwrap())
-                .add_directive("libp2p_swarm=debug".parse().unwrap()),
-        )
-        .try_init();
+    // No-op when the binary already installed a subscriber from these args
+    let _ = init_logging(&args.log_options(), None);
 
     info!("Starting Chiral Network in headless mode");
     info!("CLI args: {:#?}", args);
@@ -242,7 +529,15 @@ pub async fn run_headless(args: CliArgs) -> Result<(), Box<dyn std::error::Error
         };
         let keystore = Arc::new(Mutex::new(Keystore::load().unwrap_or_default()));
         let bandwidth = Arc::new(BandwidthController::new());
-        match WebRTCService::new_headless(ft.clone(), keystore, bandwidth, None).await {
+        match WebRTCService::new_headless(
+            ft.clone(),
+            keystore,
+            bandwidth,
+            None,
+            WebRtcConfig::from_env(),
+        )
+        .await
+        {
             Ok(svc) => {
                 let arc = Arc::new(svc);
                 set_webrtc_service(arc.clone()).await;
@@ -276,29 +571,35 @@ pub async fn run_headless(args: CliArgs) -> Result<(), Box<dyn std::error::Error
     }
 
     // Start DHT node
-    let dht_service = DhtService::new(
-        args.dht_port,
-        bootstrap_nodes.clone(),
-        args.secret,
-        args.is_bootstrap,
+    let dht_config = args.apply_dht_overrides(DhtConfig {
+        port: args.dht_port,
+        listen_addrs: args.listen_addrs()?,
+        bootstrap_nodes: bootstrap_nodes.clone(),
+        secret: args.secret.clone(),
+        is_bootstrap: args.is_bootstrap,
         enable_autonat,
-        probe_interval,
-        args.autonat_server.clone(),
-        args.socks5_proxy,
+        autonat_probe_interval: probe_interval,
+        autonat_servers: args.autonat_server.clone(),
+        proxy_address: args.socks5_proxy.clone(),
+        chunk_size_kb: None, // use default
+        cache_size_mb: None, // use default
+        enable_autorelay: final_enable_autorelay,
+        preferred_relays: args.relay.clone(),
+        enable_relay_server: args.enable_relay,
+        enable_upnp: true,
+        blockstore_db_path: None,
+        last_autorelay_enabled_at: None,
+        last_autorelay_disabled_at: None,
+        pure_client_mode: args.pure_client_mode,
+        force_server_mode: args.force_server_mode,
+        peer_cache_path: args.peer_cache.clone(),
+        ..DhtConfig::default()
+    });
+    let dht_service = DhtService::new_with_config(
+        dht_config,
         file_transfer_service.clone(),
         webrtc_service.clone(),
         chunk_manager.clone(),
-        None, // chunk_size_kb: use default
-        None, // cache_size_mb: use default
-        final_enable_autorelay,
-        args.relay.clone(),
-        args.enable_relay,
-        true,
-        None,
-        None,
-        None,
-        args.pure_client_mode,
-        args.force_server_mode,
     )
     .await?;
     let dht_arc = Arc::new(dht_service);
@@ -338,11 +639,27 @@ pub async fn run_headless(args: CliArgs) -> Result<(), Box<dyn std::error::Error
         } else {
             info!("✅ Geth node started (full blockchain sync: ~10,000 blocks)");
         }
-        Some(geth)
+        Some(Arc::new(Mutex::new(geth)))
     } else {
         None
     };
 
+    // Keep the metrics server's shutdown sender alive for the node's lifetime
+    let mut _metrics_shutdown_tx = None;
+    if let Some(port) = args.metrics_port {
+        let state = HeadlessMetricsState {
+            dht: dht_arc.clone(),
+            geth: geth_handle.clone(),
+        };
+        match start_metrics_server(state, args.metrics_bind, port).await {
+            Ok((bound, shutdown_tx)) => {
+                info!("📈 Metrics endpoint listening on http://{}", bound);
+                _metrics_shutdown_tx = Some(shutdown_tx);
+            }
+            Err(e) => error!("Failed to start metrics endpoint on port {}: {}", port, e),
+        }
+    }
+
     // Add some example bootstrap data if this is a primary bootstrap node
     if !provided_bootstrap {
         info!("Running as primary bootstrap node (no peers specified)");
@@ -411,7 +728,48 @@ pub async fn run_headless(args: CliArgs) -> Result<(), Box<dyn std::error::Error
         .unwrap_or_else(|_| std::env::current_dir().unwrap().join("files"));
     let _ = std::fs::create_dir_all(&storage_dir);
 
-    let http_server_state = Arc::new(http_server::HttpServerState::new(storage_dir.clone()));
+    let cors = http_server::CorsPolicy::from_config(
+        &args.cors_origin,
+        &args.cors_method,
+        &args.cors_header,
+    )?;
+
+    // Load account from CHIRAL_PRIVATE_KEY (headless has no GUI login).
+    let (uploader_address, private_key) = match std::env::var("CHIRAL_PRIVATE_KEY") {
+        Ok(pk) if !pk.trim().is_empty() => match crate::ethereum::get_account_from_private_key(&pk) {
+            Ok(acct) => (Some(acct.address), Some(acct.private_key)),
+            Err(e) => {
+                warn!("Invalid CHIRAL_PRIVATE_KEY: {}", e);
+                (None, None)
+            }
+        },
+        _ => (None, None),
+    };
+
+    let mut http_server_state =
+        http_server::HttpServerState::new(storage_dir.clone()).with_cors(cors);
+    if let Some(tls) = args.http_tls_config()? {
+        http_server_state = http_server_state.with_tls(tls);
+    }
+    // Payment promises from downloaders are addressed to this node's wallet
+    match &uploader_address {
+        Some(address) => {
+            http_server_state = http_server_state.with_payment_ledger(
+                address.clone(),
+                PaymentPromiseLedger::new(args.require_payment),
+            );
+        }
+        None if args.require_payment => {
+            return Err("--require-payment needs a wallet; set CHIRAL_PRIVATE_KEY".into());
+        }
+        None => {}
+    }
+    let http_scheme = if http_server_state.tls.is_some() {
+        "https"
+    } else {
+        "http"
+    };
+    let http_server_state = Arc::new(http_server_state);
     http_server_state.set_dht(dht_arc.clone()).await;
 
     // Start HTTP file server on a free port in 8080..=8090 and keep shutdown sender alive.
@@ -423,9 +781,9 @@ pub async fn run_headless(args: CliArgs) -> Result<(), Box<dyn std::error::Error
         match http_server::start_server(http_server_state.clone(), bind_addr, shutdown_rx).await {
             Ok(bound) => {
                 let host = std::env::var("CHIRAL_PUBLIC_IP").unwrap_or_else(|_| "127.0.0.1".to_string());
-                http_base_url = Some(format!("http://{}:{}", host, bound.port()));
+                http_base_url = Some(format!("{}://{}:{}", http_scheme, host, bound.port()));
                 http_shutdown_tx_keepalive = Some(shutdown_tx);
-                info!("HTTP file server listening on http://{} (advertised host={})", bound, host);
+                info!("HTTP file server listening on {}://{} (advertised host={})", http_scheme, bound, host);
                 break;
             }
             Err(e) => {
@@ -439,18 +797,6 @@ pub async fn run_headless(args: CliArgs) -> Result<(), Box<dyn std::error::Error
         warn!("Could not start HTTP file server on any port (8080-8090). Downloads will fail.");
     }
 
-    // Load account from CHIRAL_PRIVATE_KEY (headless has no GUI login).
-    let (uploader_address, private_key) = match std::env::var("CHIRAL_PRIVATE_KEY") {
-        Ok(pk) if !pk.trim().is_empty() => match crate::ethereum::get_account_from_private_key(&pk) {
-            Ok(acct) => (Some(acct.address), Some(acct.private_key)),
-            Err(e) => {
-                warn!("Invalid CHIRAL_PRIVATE_KEY: {}", e);
-                (None, None)
-            }
-        },
-        _ => (None, None),
-    };
-
     // Start headless E2E API if requested.
     let mut e2e_shutdown_tx_keepalive: Option<tokio::sync::oneshot::Sender<()>> = None;
     if let Ok(port_str) = std::env::var("CHIRAL_E2E_API_PORT") {
@@ -564,13 +910,65 @@ pub async fn run_headless(args: CliArgs) -> Result<(), Box<dyn std::error::Error
             }
         }
     });
-    // Keep the service running
-    signal::ctrl_c().await?;
-
-    info!("Shutting down...");
+    // Keep the service running until SIGINT/SIGTERM
+    run_until_shutdown(&dht_arc, geth_handle, wait_for_shutdown_signal()).await?;
     Ok(())
 }
 
+/// Wait for `shutdown` to fire, then stop the services. Returns the name of
+/// the signal that fired.
+async fn run_until_shutdown(
+    dht: &DhtService,
+    geth: Option<Arc<Mutex<GethProcess>>>,
+    shutdown: impl std::future::Future<Output = std::io::Result<&'static str>>,
+) -> std::io::Result<&'static str> {
+    let signal_name = shutdown.await?;
+    info!("Received {}, shutting down...", signal_name);
+    graceful_shutdown(dht, geth).await;
+    Ok(signal_name)
+}
+
+/// Wait for SIGINT (Ctrl+C) or, on unix, SIGTERM. Returns the name of the
+/// signal that fired.
+async fn wait_for_shutdown_signal() -> std::io::Result<&'static str> {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        let mut sigint = signal(SignalKind::interrupt())?;
+        let mut sigterm = signal(SignalKind::terminate())?;
+        tokio::select! {
+            _ = sigint.recv() => Ok("SIGINT"),
+            _ = sigterm.recv() => Ok("SIGTERM"),
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        tokio::signal::ctrl_c().await?;
+        Ok("Ctrl+C")
+    }
+}
+
+/// Stop the services started by `run_headless`. Each step logs and carries on
+/// if it fails so a broken DHT can't leave geth running.
+async fn graceful_shutdown(dht: &DhtService, geth: Option<Arc<Mutex<GethProcess>>>) {
+    // DHT shutdown also writes the routing table to --peer-cache if one is set
+    info!("Stopping DHT service and flushing routing table...");
+    match dht.shutdown().await {
+        Ok(()) => info!("✅ DHT service stopped"),
+        Err(e) => error!("Failed to stop DHT service: {}", e),
+    }
+
+    if let Some(geth) = geth {
+        info!("Stopping geth node...");
+        match geth.lock().await.stop() {
+            Ok(()) => info!("✅ Geth node stopped"),
+            Err(e) => error!("Failed to stop geth node: {}", e),
+        }
+    }
+
+    info!("Shutdown complete");
+}
+
 fn log_reachability_snapshot(snapshot: &DhtMetricsSnapshot) {
     info!(
         "📡 Reachability: {:?} (confidence {:?})",
@@ -666,3 +1064,234 @@ pub async fn handle_download_cli_commands(
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE_CONFIG: &str = r#"
+dht_port = 5001
+bootstrap = ["/ip4/10.0.0.1/tcp/4001/p2p/12D3KooWExample"]
+enable_geth = true
+log_level = "debug"
+kad_replication_factor = 8
+peer_cache = "/var/lib/chiral/peers.json"
+"#;
+
+    fn parse_with_sample(cli: &[&str]) -> CliArgs {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("node.toml");
+        std::fs::write(&path, SAMPLE_CONFIG).unwrap();
+
+        let mut argv = vec!["chiral-network", "--config", path.to_str().unwrap()];
+        argv.extend_from_slice(cli);
+        CliArgs::parse_from_with_config(argv).unwrap()
+    }
+
+    #[test]
+    fn test_config_file_fills_unset_args() {
+        let args = parse_with_sample(&[]);
+
+        assert_eq!(args.dht_port, 5001);
+        assert_eq!(
+            args.bootstrap,
+            vec!["/ip4/10.0.0.1/tcp/4001/p2p/12D3KooWExample"]
+        );
+        assert!(args.enable_geth);
+        assert_eq!(args.log_level, "debug");
+        assert_eq!(args.kad_replication_factor, Some(8));
+        assert_eq!(
+            args.peer_cache,
+            Some(PathBuf::from("/var/lib/chiral/peers.json"))
+        );
+        // Not in the file: clap defaults still apply
+        assert_eq!(args.geth_data_dir, "./bin/geth-data");
+        assert_eq!(args.autonat_probe_interval, 30);
+        assert_eq!(args.metrics_bind, IpAddr::from([127, 0, 0, 1]));
+    }
+
+    #[test]
+    fn test_log_flags_build_log_options() {
+        let args = parse_with_sample(&[
+            "--log-format",
+            "json",
+            "--log-directive",
+            "libp2p_kad=debug",
+        ]);
+
+        let options = args.log_options();
+        assert_eq!(options.format, LogFormat::Json);
+        assert_eq!(options.level, "debug");
+        assert_eq!(options.directives, vec!["libp2p_kad=debug"]);
+
+        let defaults = parse_with_sample(&[]).log_options();
+        assert_eq!(defaults.format, LogFormat::Text);
+        assert_eq!(defaults.directives.len(), 4);
+    }
+
+    #[test]
+    fn test_cli_flags_override_config_file() {
+        let args = parse_with_sample(&[
+            "--dht-port",
+            "4001",
+            "--log-level",
+            "warn",
+            "--bootstrap",
+            "/ip4/10.0.0.2/tcp/4001",
+            "--kad-replication-factor",
+            "3",
+        ]);
+
+        assert_eq!(args.dht_port, 4001);
+        assert_eq!(args.log_level, "warn");
+        assert_eq!(args.bootstrap, vec!["/ip4/10.0.0.2/tcp/4001"]);
+        assert_eq!(args.kad_replication_factor, Some(3));
+        assert!(args.enable_geth);
+    }
+
+    #[test]
+    fn test_listen_addr_flags_are_parsed() {
+        let args = CliArgs::parse_from_with_config([
+            "chiral-network",
+            "--listen-addr",
+            "/ip6/::",
+            "--listen-addr",
+            "/ip4/10.0.0.5/tcp/4002",
+        ])
+        .unwrap();
+        assert_eq!(
+            args.listen_addrs().unwrap(),
+            vec![
+                "/ip6/::".parse::<libp2p::Multiaddr>().unwrap(),
+                "/ip4/10.0.0.5/tcp/4002".parse().unwrap(),
+            ]
+        );
+
+        let args =
+            CliArgs::parse_from_with_config(["chiral-network", "--listen-addr", "bogus"]).unwrap();
+        assert!(args.listen_addrs().is_err());
+    }
+
+    #[test]
+    fn test_connection_limit_flags_override_dht_config() {
+        let args = CliArgs::parse_from_with_config([
+            "chiral-network",
+            "--max-connections",
+            "200",
+            "--max-pending-connections",
+            "16",
+        ])
+        .unwrap();
+        let config = args.apply_dht_overrides(DhtConfig {
+            max_established_per_peer: Some(2),
+            ..DhtConfig::default()
+        });
+        assert_eq!(config.max_established_total, Some(200));
+        assert_eq!(config.max_established_per_peer, Some(2));
+        assert_eq!(config.max_pending, Some(16));
+    }
+
+    #[test]
+    fn test_http_tls_needs_both_cert_and_key() {
+        let dir = tempfile::tempdir().unwrap();
+        let config_path = dir.path().join("node.toml");
+        std::fs::write(&config_path, "http_tls_cert = \"cert.pem\"\n").unwrap();
+        let args = CliArgs::parse_from_with_config([
+            "chiral-network",
+            "--config",
+            config_path.to_str().unwrap(),
+        ])
+        .unwrap();
+        assert!(args.http_tls_config().is_err());
+
+        std::fs::write(
+            &config_path,
+            "http_tls_cert = \"cert.pem\"\nhttp_tls_key = \"key.pem\"\n",
+        )
+        .unwrap();
+        let args = CliArgs::parse_from_with_config([
+            "chiral-network",
+            "--config",
+            config_path.to_str().unwrap(),
+        ])
+        .unwrap();
+        assert_eq!(
+            args.http_tls_config().unwrap(),
+            Some(http_server::TlsConfig::new("cert.pem", "key.pem"))
+        );
+    }
+
+    #[test]
+    fn test_region_flag_sets_dht_region() {
+        let args = CliArgs::parse_from_with_config(["chiral-network", "--region", "EU"]).unwrap();
+        let config = args.apply_dht_overrides(DhtConfig::default());
+        assert_eq!(config.region.as_deref(), Some("EU"));
+    }
+
+    #[test]
+    fn test_bootstrap_node_gets_connection_caps_unless_overridden() {
+        let args = CliArgs::parse_from_with_config([
+            "chiral-network",
+            "--is-bootstrap",
+            "--max-connections",
+            "200",
+        ])
+        .unwrap();
+        let config = args.apply_dht_overrides(DhtConfig {
+            is_bootstrap: args.is_bootstrap,
+            ..DhtConfig::default()
+        });
+        let bootstrap = DhtConfig::default_bootstrap_config();
+        assert_eq!(config.max_established_total, Some(200));
+        assert_eq!(
+            config.max_established_per_peer,
+            bootstrap.max_established_per_peer
+        );
+        assert_eq!(config.max_pending, bootstrap.max_pending);
+        assert!(config.max_pending.is_some());
+
+        // Ordinary nodes stay unlimited
+        let args = CliArgs::parse_from_with_config(["chiral-network"]).unwrap();
+        let config = args.apply_dht_overrides(DhtConfig::default());
+        assert_eq!(config.max_established_total, None);
+        assert_eq!(config.max_pending, None);
+    }
+
+    #[test]
+    fn test_config_file_rejects_unknown_keys() {
+        let err = HeadlessConfigFile::from_toml("dht_port = 4001\ndht_prot = 4002\n").unwrap_err();
+        assert!(err.contains("dht_prot"), "unexpected error: {}", err);
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_signal_stops_headless_node() {
+        let dht = Arc::new(
+            DhtService::new_with_config(DhtConfig::client(), None, None, None)
+                .await
+                .expect("Failed to create DhtService"),
+        );
+
+        // Stands in for SIGTERM so the test never signals its own process
+        let (signal_tx, signal_rx) = tokio::sync::oneshot::channel::<()>();
+        let shutdown = async move {
+            signal_rx
+                .await
+                .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?;
+            Ok("SIGTERM")
+        };
+        let node = tokio::spawn({
+            let dht = dht.clone();
+            async move { run_until_shutdown(&dht, None, shutdown).await.unwrap() }
+        });
+
+        signal_tx.send(()).unwrap();
+        let signal_name = tokio::time::timeout(Duration::from_secs(10), node)
+            .await
+            .expect("headless node did not shut down within 10s")
+            .unwrap();
+        assert_eq!(signal_name, "SIGTERM");
+
+        // The node task is gone, so it can't take another command
+        assert!(dht.shutdown().await.is_err());
+    }
+}
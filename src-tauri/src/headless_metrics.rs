@@ -0,0 +1,200 @@
+// Health and metrics endpoint for headless nodes, so operators can scrape
+// bootstrap node status without the GUI.
+use axum::{
+    extract::State,
+    http::{header, StatusCode},
+    response::IntoResponse,
+    routing::get,
+    Json, Router,
+};
+use serde::Serialize;
+use std::fmt::Write;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use tokio::sync::{oneshot, Mutex};
+
+use crate::dht::DhtService;
+use crate::ethereum::GethProcess;
+
+#[derive(Clone)]
+pub struct HeadlessMetricsState {
+    pub dht: Arc<DhtService>,
+    /// `None` when the node was started without `--enable-geth`
+    pub geth: Option<Arc<Mutex<GethProcess>>>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NodeHealth {
+    /// Connected to at least one peer, no DHT error since the last peer event,
+    /// and geth running if it was enabled
+    pub ok: bool,
+    pub peer_id: String,
+    pub peer_count: usize,
+    pub bootstrap_failures: u64,
+    pub listen_addrs: Vec<String>,
+    pub last_error: Option<String>,
+    pub geth_running: bool,
+}
+
+impl HeadlessMetricsState {
+    async fn collect(&self) -> NodeHealth {
+        let snapshot = self.dht.metrics_snapshot().await;
+        let geth_running = match &self.geth {
+            Some(geth) => geth.lock().await.is_running(),
+            None => false,
+        };
+        // An error only counts until the next successful peer event
+        let dht_erroring = match (snapshot.last_error_at, snapshot.last_peer_event) {
+            (Some(error_at), Some(peer_event_at)) => error_at >= peer_event_at,
+            (error_at, _) => error_at.is_some() || snapshot.last_error.is_some(),
+        };
+        let geth_ok = self.geth.is_none() || geth_running;
+        NodeHealth {
+            ok: snapshot.peer_count > 0 && !dht_erroring && geth_ok,
+            peer_id: self.dht.get_peer_id().await,
+            peer_count: snapshot.peer_count,
+            bootstrap_failures: snapshot.bootstrap_failures,
+            listen_addrs: snapshot.listen_addrs,
+            last_error: snapshot.last_error,
+            geth_running,
+        }
+    }
+}
+
+/// Serve `GET /health` (JSON) and `GET /metrics` (Prometheus text) on
+/// `bind:port`. Port 0 binds an ephemeral port; the bound address is returned.
+pub async fn start_metrics_server(
+    state: HeadlessMetricsState,
+    bind: IpAddr,
+    port: u16,
+) -> Result<(SocketAddr, oneshot::Sender<()>), String> {
+    let router = create_router(state);
+
+    let bind_addr = SocketAddr::new(bind, port);
+    let listener = tokio::net::TcpListener::bind(bind_addr)
+        .await
+        .map_err(|e| e.to_string())?;
+    let bound = listener.local_addr().map_err(|e| e.to_string())?;
+
+    let (shutdown_tx, shutdown_rx) = oneshot::channel::<()>();
+    tokio::spawn(async move {
+        let server = axum::serve(listener, router).with_graceful_shutdown(async move {
+            let _ = shutdown_rx.await;
+        });
+        let _ = server.await;
+    });
+
+    Ok((bound, shutdown_tx))
+}
+
+fn create_router(state: HeadlessMetricsState) -> Router {
+    Router::new()
+        .route("/health", get(health))
+        .route("/metrics", get(metrics))
+        .with_state(Arc::new(state))
+}
+
+async fn health(State(state): State<Arc<HeadlessMetricsState>>) -> impl IntoResponse {
+    (StatusCode::OK, Json(state.collect().await))
+}
+
+async fn metrics(State(state): State<Arc<HeadlessMetricsState>>) -> impl IntoResponse {
+    let health = state.collect().await;
+    (
+        StatusCode::OK,
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        render_prometheus(&health),
+    )
+}
+
+fn render_prometheus(health: &NodeHealth) -> String {
+    let mut out = String::new();
+    let metrics = [
+        (
+            "chiral_dht_peer_count",
+            "gauge",
+            "Number of connected DHT peers",
+            health.peer_count as u64,
+        ),
+        (
+            "chiral_dht_bootstrap_failures_total",
+            "counter",
+            "Failed DHT bootstrap attempts",
+            health.bootstrap_failures,
+        ),
+        (
+            "chiral_dht_listen_addrs",
+            "gauge",
+            "Number of addresses the DHT is listening on",
+            health.listen_addrs.len() as u64,
+        ),
+        (
+            "chiral_dht_has_error",
+            "gauge",
+            "1 if the DHT has recorded an error",
+            health.last_error.is_some() as u64,
+        ),
+        (
+            "chiral_geth_running",
+            "gauge",
+            "1 if the geth node is running",
+            health.geth_running as u64,
+        ),
+    ];
+    for (name, kind, help, value) in metrics {
+        let _ = writeln!(out, "# HELP {} {}", name, help);
+        let _ = writeln!(out, "# TYPE {} {}", name, kind);
+        let _ = writeln!(out, "{} {}", name, value);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dht::DhtConfig;
+
+    #[tokio::test]
+    async fn test_health_endpoint_reports_peers_and_listen_addrs() {
+        let dht = DhtService::new_with_config(DhtConfig::client(), None, None, None)
+            .await
+            .expect("Failed to create DhtService");
+        let dht = Arc::new(dht);
+
+        // Wait for the swarm to report its listen address
+        for _ in 0..50 {
+            if !dht.metrics_snapshot().await.listen_addrs.is_empty() {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        }
+
+        let state = HeadlessMetricsState {
+            dht: dht.clone(),
+            geth: None,
+        };
+        let (addr, shutdown_tx) = start_metrics_server(state, [127, 0, 0, 1].into(), 0)
+            .await
+            .unwrap();
+        assert!(addr.ip().is_loopback());
+
+        let url = format!("http://127.0.0.1:{}/health", addr.port());
+        let body: serde_json::Value = reqwest::get(&url).await.unwrap().json().await.unwrap();
+        // A node without peers isn't healthy
+        assert_eq!(body["ok"], false);
+        assert_eq!(body["peerCount"], 0);
+        assert_eq!(body["gethRunning"], false);
+        let listen_addrs = body["listenAddrs"].as_array().unwrap();
+        assert!(!listen_addrs.is_empty());
+
+        let url = format!("http://127.0.0.1:{}/metrics", addr.port());
+        let text = reqwest::get(&url).await.unwrap().text().await.unwrap();
+        assert!(text.contains("chiral_dht_peer_count 0"));
+        assert!(text.contains("# TYPE chiral_dht_peer_count gauge"));
+        assert!(text.contains("# TYPE chiral_dht_bootstrap_failures_total counter"));
+
+        let _ = shutdown_tx.send(());
+        dht.shutdown().await.unwrap();
+    }
+}
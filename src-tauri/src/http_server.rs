@@ -1,6 +1,6 @@
 use axum::{
     extract::{Path, State},
-    http::StatusCode,
+    http::{header, HeaderName, HeaderValue, Method, StatusCode},
     response::{IntoResponse, Response},
     routing::get,
     Json, Router,
@@ -8,13 +8,18 @@ use axum::{
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::net::SocketAddr;
-use std::path::PathBuf;
+use std::path::{Path as FsPath, PathBuf};
 use std::sync::Arc;
 use tokio::sync::{RwLock, Mutex};
-use tower_http::cors::{Any, CorsLayer};
+use tower_http::compression::{
+    predicate::{DefaultPredicate, NotForContentType, Predicate},
+    CompressionLayer,
+};
+use tower_http::cors::{AllowOrigin, Any, CorsLayer};
 
 // Import DhtService for metrics tracking
 use crate::dht::DhtService;
+use crate::reputation::{PaymentPromiseLedger, SignedTransactionMessage};
 
 /// HTTP Server for serving files via Range requests
 ///
@@ -37,6 +42,9 @@ pub struct HttpFileMetadata {
     pub name: String,
     pub size: u64,
     pub encrypted: bool,
+    /// Smallest `amount` a payment promise for this file must carry
+    #[serde(default)]
+    pub price: u64,
 }
 
 #[derive(Clone)]
@@ -51,6 +59,143 @@ pub struct HttpServerState {
     
     /// DHT service for recording provider-side metrics
     pub dht: Arc<Mutex<Option<Arc<DhtService>>>>,
+
+    /// Which browser origins may read responses
+    pub cors: CorsPolicy,
+
+    /// Serve over HTTPS with this certificate; plain HTTP when `None`
+    pub tls: Option<TlsConfig>,
+
+    /// Payment promises checked before serving a file; files are served
+    /// without one when `None`
+    pub payments: Option<Arc<Mutex<PaymentPromiseLedger>>>,
+
+    /// Wallet address payment promises must be addressed to
+    pub seeder_address: String,
+}
+
+/// PEM certificate chain and private key for serving over HTTPS
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TlsConfig {
+    pub cert_path: PathBuf,
+    pub key_path: PathBuf,
+}
+
+impl TlsConfig {
+    pub fn new(cert_path: impl Into<PathBuf>, key_path: impl Into<PathBuf>) -> Self {
+        Self {
+            cert_path: cert_path.into(),
+            key_path: key_path.into(),
+        }
+    }
+
+    /// Write a self-signed certificate for `hosts` to `dir`/cert.pem and
+    /// `dir`/key.pem. Clients must be told to trust it, so this is only
+    /// meant for local testing.
+    pub fn generate_self_signed(dir: &FsPath, hosts: &[String]) -> Result<Self, String> {
+        let rcgen::CertifiedKey { cert, key_pair } =
+            rcgen::generate_simple_self_signed(hosts.to_vec())
+                .map_err(|e| format!("Failed to generate certificate: {}", e))?;
+
+        std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+        let config = Self::new(dir.join("cert.pem"), dir.join("key.pem"));
+        std::fs::write(&config.cert_path, cert.pem()).map_err(|e| e.to_string())?;
+        std::fs::write(&config.key_path, key_pair.serialize_pem()).map_err(|e| e.to_string())?;
+        Ok(config)
+    }
+}
+
+/// Cross-origin policy for browsers fetching files from this server
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum CorsPolicy {
+    /// No CORS headers are sent, so only same-origin pages can read responses
+    #[default]
+    SameOrigin,
+    /// Any origin, method and header. Meant for local development.
+    Permissive,
+    /// Only the listed origins, methods and request headers
+    AllowList {
+        origins: Vec<HeaderValue>,
+        methods: Vec<Method>,
+        headers: Vec<HeaderName>,
+    },
+}
+
+impl CorsPolicy {
+    /// Build a policy from configured values. No origins means same-origin
+    /// only and `"*"` means permissive. Methods default to GET and HEAD, and
+    /// headers to Range so browsers can fetch chunks.
+    pub fn from_config(
+        origins: &[String],
+        methods: &[String],
+        headers: &[String],
+    ) -> Result<Self, String> {
+        if origins.is_empty() {
+            return Ok(CorsPolicy::SameOrigin);
+        }
+        if origins.iter().any(|o| o == "*") {
+            return Ok(CorsPolicy::Permissive);
+        }
+
+        let origins = origins
+            .iter()
+            .map(|o| {
+                HeaderValue::from_str(o.trim_end_matches('/'))
+                    .map_err(|_| format!("Invalid CORS origin '{}'", o))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let methods = if methods.is_empty() {
+            vec![Method::GET, Method::HEAD]
+        } else {
+            methods
+                .iter()
+                .map(|m| {
+                    Method::from_bytes(m.to_ascii_uppercase().as_bytes())
+                        .map_err(|_| format!("Invalid CORS method '{}'", m))
+                })
+                .collect::<Result<Vec<_>, _>>()?
+        };
+        let headers = if headers.is_empty() {
+            vec![header::RANGE]
+        } else {
+            headers
+                .iter()
+                .map(|h| {
+                    HeaderName::from_bytes(h.as_bytes())
+                        .map_err(|_| format!("Invalid CORS header '{}'", h))
+                })
+                .collect::<Result<Vec<_>, _>>()?
+        };
+
+        Ok(CorsPolicy::AllowList {
+            origins,
+            methods,
+            headers,
+        })
+    }
+
+    /// The layer also answers preflight `OPTIONS` requests
+    fn layer(&self) -> CorsLayer {
+        // Range responses are unreadable cross-origin unless these are exposed
+        let exposed = [header::CONTENT_RANGE, header::ACCEPT_RANGES];
+        match self {
+            CorsPolicy::SameOrigin => CorsLayer::new(),
+            CorsPolicy::Permissive => CorsLayer::new()
+                .allow_origin(Any)
+                .allow_methods(Any)
+                .allow_headers(Any)
+                .expose_headers(exposed),
+            CorsPolicy::AllowList {
+                origins,
+                methods,
+                headers,
+            } => CorsLayer::new()
+                .allow_origin(AllowOrigin::list(origins.clone()))
+                .allow_methods(methods.clone())
+                .allow_headers(headers.clone())
+                .expose_headers(exposed),
+        }
+    }
 }
 
 impl HttpServerState {
@@ -62,8 +207,36 @@ impl HttpServerState {
             storage_dir,
             files: Arc::new(RwLock::new(HashMap::new())),
             dht: Arc::new(Mutex::new(None)),
+            cors: CorsPolicy::default(),
+            tls: None,
+            payments: None,
+            seeder_address: String::new(),
         }
     }
+
+    /// Set the cross-origin policy used by `create_router`
+    pub fn with_cors(mut self, cors: CorsPolicy) -> Self {
+        self.cors = cors;
+        self
+    }
+
+    /// Serve over HTTPS from `start_server`
+    pub fn with_tls(mut self, tls: TlsConfig) -> Self {
+        self.tls = Some(tls);
+        self
+    }
+
+    /// Check the payment promise attached to each file request against
+    /// `ledger`, which must be addressed to `seeder_address`
+    pub fn with_payment_ledger(
+        mut self,
+        seeder_address: impl Into<String>,
+        ledger: PaymentPromiseLedger,
+    ) -> Self {
+        self.seeder_address = seeder_address.into();
+        self.payments = Some(Arc::new(Mutex::new(ledger)));
+        self
+    }
     
     /// Set DHT service for metrics tracking
     pub async fn set_dht(&self, dht: Arc<DhtService>) {
@@ -187,6 +360,10 @@ async fn serve_file(
         }
     };
 
+    if let Err(response) = check_payment(&state, &metadata, &headers).await {
+        return response;
+    }
+
     // Build file path using the actual file_hash (SHA-256) used for storage
     let file_path = state.storage_dir.join(&metadata.file_hash);
 
@@ -211,13 +388,21 @@ async fn serve_file(
         .get("range")
         .and_then(|v| v.to_str().ok());
 
-    let response = if let Some(range_str) = range_header {
+    let mut response = if let Some(range_str) = range_header {
         // Serve partial content (Range request)
         serve_file_range(&file_path, range_str, metadata.size).await
     } else {
         // Serve entire file
         serve_entire_file(&file_path, metadata.size).await
     };
+
+    // Byte bodies default to application/octet-stream, which compression
+    // skips; plaintext files get their real type so text formats compress
+    if response.status().is_success() {
+        response
+            .headers_mut()
+            .insert(header::CONTENT_TYPE, content_type_for(&metadata));
+    }
     
     // Record provider-side metrics if downloader peer ID is available
     if let Some(ref peer_id) = downloader_peer_id {
@@ -237,6 +422,83 @@ async fn serve_file(
     response
 }
 
+/// Check the promise in `X-Payment-Promise` (JSON `SignedTransactionMessage`)
+/// against the downloader key in `X-Payer-Public-Key` (hex) and the file's
+/// price. The promise's `from` must be the address of that key, so a client
+/// can't vouch for someone else's promise. A promise pays the full price of
+/// the file, so the range requests of one download can all carry the same
+/// promise until its deadline; a new download needs a new nonce.
+///
+/// Payment checks are opt-in (`--require-payment`), and no downloader in this
+/// client sends these headers yet, so enabling them turns away every download
+/// made by the app itself.
+async fn check_payment(
+    state: &HttpServerState,
+    metadata: &HttpFileMetadata,
+    headers: &axum::http::HeaderMap,
+) -> Result<(), Response> {
+    let Some(ledger) = &state.payments else {
+        return Ok(());
+    };
+    let file_hash = metadata.hash.as_str();
+    let reject = |status: StatusCode, error: String| {
+        tracing::warn!("Rejected download of {}: {}", file_hash, error);
+        (status, Json(ErrorResponse { error })).into_response()
+    };
+
+    let Some(value) = headers.get("X-Payment-Promise") else {
+        if ledger.lock().await.require_payment() {
+            return Err(reject(
+                StatusCode::PAYMENT_REQUIRED,
+                "payment promise required".to_string(),
+            ));
+        }
+        return Ok(());
+    };
+    let parsed = value
+        .to_str()
+        .map_err(|e| e.to_string())
+        .and_then(|v| serde_json::from_str(v).map_err(|e| e.to_string()));
+    let promise: SignedTransactionMessage = match parsed {
+        Ok(promise) => promise,
+        Err(e) => {
+            return Err(reject(
+                StatusCode::BAD_REQUEST,
+                format!("Invalid payment promise: {}", e),
+            ))
+        }
+    };
+
+    let downloader_key = match headers
+        .get("X-Payer-Public-Key")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| hex::decode(v).ok())
+        .and_then(|bytes| <[u8; 32]>::try_from(bytes).ok())
+        .and_then(|bytes| ed25519_dalek::VerifyingKey::from_bytes(&bytes).ok())
+    {
+        Some(key) => key,
+        None => {
+            return Err(reject(
+                StatusCode::BAD_REQUEST,
+                "Missing or invalid X-Payer-Public-Key".to_string(),
+            ))
+        }
+    };
+
+    ledger
+        .lock()
+        .await
+        .accept(
+            Some(&promise),
+            file_hash,
+            metadata.price,
+            &state.seeder_address,
+            &downloader_key,
+        )
+        .map(|_| ())
+        .map_err(|e| reject(StatusCode::PAYMENT_REQUIRED, e))
+}
+
 /// Serve a byte range from a file (206 Partial Content)
 async fn serve_file_range(
     file_path: &PathBuf,
@@ -377,31 +639,68 @@ fn parse_range_header(range_str: &str, file_size: u64) -> Option<(u64, u64)> {
 
 /// GET /health
 ///
-/// Health check endpoint
-async fn health_check() -> impl IntoResponse {
-    (StatusCode::OK, "OK")
+/// Health check endpoint; also tells clients whether to use `https://`
+async fn health_check(State(state): State<Arc<HttpServerState>>) -> impl IntoResponse {
+    Json(serde_json::json!({
+        "status": "ok",
+        "tls": state.tls.is_some(),
+    }))
 }
 
 // ============================================================================
 // Server Setup
 // ============================================================================
 
+/// Content type of encrypted file bodies, which are never compressed
+const ENCRYPTED_CONTENT_TYPE: &str = "application/octet-stream";
+
+/// Content type to serve a registered file with: guessed from its name, or
+/// `ENCRYPTED_CONTENT_TYPE` for ciphertext
+fn content_type_for(metadata: &HttpFileMetadata) -> HeaderValue {
+    if metadata.encrypted {
+        return HeaderValue::from_static(ENCRYPTED_CONTENT_TYPE);
+    }
+    let guessed = mime_guess::from_path(&metadata.name).first_or_octet_stream();
+    HeaderValue::from_str(guessed.as_ref())
+        .unwrap_or_else(|_| HeaderValue::from_static(ENCRYPTED_CONTENT_TYPE))
+}
+
+/// Compress JSON and text-like files for clients sending `Accept-Encoding`.
+/// Encrypted and unknown binary files are sent as-is, as are Range responses,
+/// which must keep their byte offsets.
+fn compression_layer() -> CompressionLayer<impl Predicate> {
+    fn not_partial(
+        status: StatusCode,
+        _: axum::http::Version,
+        _: &axum::http::HeaderMap,
+        _: &axum::http::Extensions,
+    ) -> bool {
+        status != StatusCode::PARTIAL_CONTENT
+    }
+
+    let predicate = DefaultPredicate::new()
+        .and(NotForContentType::const_new(ENCRYPTED_CONTENT_TYPE))
+        .and(not_partial);
+    CompressionLayer::new()
+        .gzip(true)
+        .zstd(true)
+        .compress_when(predicate)
+}
+
 /// Creates the HTTP server router with all endpoints
 pub fn create_router(state: Arc<HttpServerState>) -> Router {
+    let cors = state.cors.layer();
     Router::new()
         .route("/health", get(health_check))
         .route("/files/:file_hash", get(serve_file))
         .route("/files/:file_hash/metadata", get(serve_metadata))
-        .layer(
-            CorsLayer::new()
-                .allow_origin(Any)
-                .allow_methods(Any)
-                .allow_headers(Any),
-        )
+        .layer(compression_layer())
+        .layer(cors)
         .with_state(state)
 }
 
-/// Starts the HTTP server on the specified address
+/// Starts the HTTP server on the specified address, over HTTPS if the state
+/// has a `TlsConfig`
 ///
 /// Returns the server's actual bound address (useful if port 0 was used)
 pub async fn start_server(
@@ -409,6 +708,9 @@ pub async fn start_server(
     addr: SocketAddr,
     shutdown_rx: tokio::sync::oneshot::Receiver<()>,
 ) -> Result<SocketAddr, String> {
+    if let Some(tls) = state.tls.clone() {
+        return start_tls_server(state, addr, &tls, shutdown_rx).await;
+    }
     let app = create_router(state);
 
     let listener = tokio::net::TcpListener::bind(addr)
@@ -434,6 +736,46 @@ pub async fn start_server(
     Ok(bound_addr)
 }
 
+async fn start_tls_server(
+    state: Arc<HttpServerState>,
+    addr: SocketAddr,
+    tls: &TlsConfig,
+    shutdown_rx: tokio::sync::oneshot::Receiver<()>,
+) -> Result<SocketAddr, String> {
+    // Fails only if another provider was installed first, which is fine
+    let _ = rustls::crypto::ring::default_provider().install_default();
+    let rustls_config =
+        axum_server::tls_rustls::RustlsConfig::from_pem_file(&tls.cert_path, &tls.key_path)
+            .await
+            .map_err(|e| format!("Failed to load TLS certificate: {}", e))?;
+
+    let listener = std::net::TcpListener::bind(addr).map_err(|e| e.to_string())?;
+    listener.set_nonblocking(true).map_err(|e| e.to_string())?;
+    let bound_addr = listener.local_addr().map_err(|e| e.to_string())?;
+
+    let handle = axum_server::Handle::new();
+    let shutdown_handle = handle.clone();
+    tokio::spawn(async move {
+        shutdown_rx.await.ok();
+        tracing::info!("HTTPS server received shutdown signal");
+        shutdown_handle.graceful_shutdown(None);
+    });
+
+    let app = create_router(state);
+    tokio::spawn(async move {
+        let server = axum_server::from_tcp_rustls(listener, rustls_config)
+            .handle(handle)
+            .serve(app.into_make_service());
+        if let Err(e) = server.await {
+            tracing::error!("HTTPS server error: {}", e);
+        } else {
+            tracing::info!("HTTPS server shut down gracefully");
+        }
+    });
+
+    Ok(bound_addr)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -456,6 +798,11 @@ mod tests {
             .unwrap();
 
         assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let health: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(health["tls"], false);
     }
 
     #[test]
@@ -482,4 +829,266 @@ mod tests {
         assert_eq!(parse_range_header("bytes=-500", 1000), None);
         assert_eq!(parse_range_header("bytes=2000-", 1000), None);
     }
+
+    async fn health_with_origin(cors: CorsPolicy, method: Method, origin: &str) -> Response {
+        let state = HttpServerState::new(PathBuf::from("/tmp/test_files")).with_cors(cors);
+        let mut request = axum::http::Request::builder()
+            .method(method.clone())
+            .uri("/health")
+            .header(header::ORIGIN, origin);
+        if method == Method::OPTIONS {
+            request = request.header(header::ACCESS_CONTROL_REQUEST_METHOD, "GET");
+        }
+        create_router(Arc::new(state))
+            .oneshot(request.body(axum::body::Body::empty()).unwrap())
+            .await
+            .unwrap()
+    }
+
+    fn allowed_origin(response: &Response) -> Option<&str> {
+        response
+            .headers()
+            .get(header::ACCESS_CONTROL_ALLOW_ORIGIN)
+            .and_then(|v| v.to_str().ok())
+    }
+
+    #[tokio::test]
+    async fn test_cors_allow_list() {
+        let cors = CorsPolicy::from_config(&["https://app.example".to_string()], &[], &[]).unwrap();
+
+        let allowed = health_with_origin(cors.clone(), Method::GET, "https://app.example").await;
+        assert_eq!(allowed_origin(&allowed), Some("https://app.example"));
+
+        let denied = health_with_origin(cors.clone(), Method::GET, "https://evil.example").await;
+        assert_eq!(allowed_origin(&denied), None);
+
+        // Preflight is answered without reaching the GET-only route
+        let preflight = health_with_origin(cors, Method::OPTIONS, "https://app.example").await;
+        assert_eq!(preflight.status(), StatusCode::OK);
+        assert_eq!(allowed_origin(&preflight), Some("https://app.example"));
+    }
+
+    #[tokio::test]
+    async fn test_cors_defaults_to_same_origin() {
+        let cors = CorsPolicy::default();
+        let response = health_with_origin(cors, Method::GET, "https://app.example").await;
+        assert_eq!(allowed_origin(&response), None);
+
+        assert_eq!(
+            CorsPolicy::from_config(&["*".to_string()], &[], &[]),
+            Ok(CorsPolicy::Permissive)
+        );
+        assert!(CorsPolicy::from_config(&["bad\norigin".to_string()], &[], &[]).is_err());
+    }
+
+    fn test_file(hash: &str, encrypted: bool) -> HttpFileMetadata {
+        HttpFileMetadata {
+            hash: hash.to_string(),
+            file_hash: hash.to_string(),
+            name: format!("{}.txt", hash),
+            size: 4096,
+            encrypted,
+            price: 0,
+        }
+    }
+
+    async fn get_with_gzip(app: Router, uri: &str) -> Response {
+        app.oneshot(
+            axum::http::Request::builder()
+                .uri(uri)
+                .header(header::ACCEPT_ENCODING, "gzip")
+                .body(axum::body::Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_text_file_is_gzip_compressed() {
+        use std::io::Read;
+
+        let dir = tempfile::tempdir().unwrap();
+        let text = "chiral ".repeat(4096 / 7);
+        std::fs::write(dir.path().join("notes"), &text).unwrap();
+        let state = Arc::new(HttpServerState::new(dir.path().to_path_buf()));
+        state.register_file(test_file("notes", false)).await;
+
+        let response = get_with_gzip(create_router(state), "/files/notes").await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "text/plain");
+        assert_eq!(response.headers()[header::CONTENT_ENCODING], "gzip");
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let mut decoded = String::new();
+        flate2::read::GzDecoder::new(&body[..])
+            .read_to_string(&mut decoded)
+            .unwrap();
+        assert_eq!(decoded, text);
+    }
+
+    #[tokio::test]
+    async fn test_encrypted_file_is_not_compressed() {
+        let dir = tempfile::tempdir().unwrap();
+        // Compressible bytes under a text name, so only `encrypted` keeps
+        // compression off
+        std::fs::write(dir.path().join("secret"), vec![0u8; 4096]).unwrap();
+        let state = Arc::new(HttpServerState::new(dir.path().to_path_buf()));
+        state.register_file(test_file("secret", true)).await;
+
+        let response = get_with_gzip(create_router(state), "/files/secret").await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers()[header::CONTENT_TYPE],
+            ENCRYPTED_CONTENT_TYPE
+        );
+        assert!(response.headers().get(header::CONTENT_ENCODING).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_file_listing_is_not_served() {
+        let state = Arc::new(HttpServerState::new(PathBuf::from("/tmp/test_files")));
+        state.register_file(test_file("hash00", false)).await;
+
+        let response = get_with_gzip(create_router(state), "/files").await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_payment_promise_required_to_serve_file() {
+        use ed25519_dalek::SigningKey;
+        use rand::rngs::OsRng;
+
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("paid"), vec![1u8; 4096]).unwrap();
+        let state = Arc::new(
+            HttpServerState::new(dir.path().to_path_buf())
+                .with_payment_ledger("0xSeeder", PaymentPromiseLedger::new(true)),
+        );
+        state
+            .register_file(HttpFileMetadata {
+                price: 1_000,
+                ..test_file("paid", false)
+            })
+            .await;
+        let app = create_router(state);
+
+        let downloader = SigningKey::generate(&mut OsRng);
+        let deadline = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs()
+            + 600;
+        let promise = |amount: u64| {
+            SignedTransactionMessage::new(
+                crate::reputation::payer_address(&downloader.verifying_key()),
+                "0xseeder".to_string(),
+                amount,
+                "paid".to_string(),
+                deadline,
+                &downloader,
+            )
+            .unwrap()
+        };
+        let request = |promise: Option<&SignedTransactionMessage>, range: Option<&str>| {
+            let mut builder = axum::http::Request::builder().uri("/files/paid");
+            if let Some(range) = range {
+                builder = builder.header(header::RANGE, range);
+            }
+            if let Some(promise) = promise {
+                builder = builder
+                    .header("X-Payment-Promise", serde_json::to_string(promise).unwrap())
+                    .header(
+                        "X-Payer-Public-Key",
+                        hex::encode(downloader.verifying_key().as_bytes()),
+                    );
+            }
+            builder.body(axum::body::Body::empty()).unwrap()
+        };
+
+        let response = app.clone().oneshot(request(None, None)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::PAYMENT_REQUIRED);
+
+        // Less than the file's price
+        let response = app
+            .clone()
+            .oneshot(request(Some(&promise(999)), None))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::PAYMENT_REQUIRED);
+
+        // One promise covers every range of the same download
+        let paid = promise(1_000);
+        for range in ["bytes=0-2047", "bytes=2048-4095"] {
+            let response = app
+                .clone()
+                .oneshot(request(Some(&paid), Some(range)))
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::PARTIAL_CONTENT);
+        }
+
+        // A different promise can't reuse its nonce
+        let mut replayed = promise(2_000);
+        replayed.nonce = paid.nonce.clone();
+        replayed.sign(&downloader).unwrap();
+        let response = app
+            .clone()
+            .oneshot(request(Some(&replayed), None))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::PAYMENT_REQUIRED);
+    }
+
+    #[tokio::test]
+    async fn test_serves_chunk_over_https_with_self_signed_cert() {
+        let dir = tempfile::tempdir().unwrap();
+        let tls = TlsConfig::generate_self_signed(&dir.path().join("tls"), &["localhost".into()])
+            .unwrap();
+
+        let storage = dir.path().join("files");
+        std::fs::create_dir_all(&storage).unwrap();
+        let contents: Vec<u8> = (0..4096u32).map(|i| (i % 251) as u8).collect();
+        std::fs::write(storage.join("chunked"), &contents).unwrap();
+
+        let state = HttpServerState::new(storage).with_tls(tls.clone());
+        state.register_file(test_file("chunked", false)).await;
+        let (shutdown_tx, shutdown_rx) = tokio::sync::oneshot::channel();
+        let addr = start_server(Arc::new(state), ([127, 0, 0, 1], 0).into(), shutdown_rx)
+            .await
+            .unwrap();
+
+        let cert = reqwest::Certificate::from_pem(&std::fs::read(&tls.cert_path).unwrap()).unwrap();
+        let client = reqwest::Client::builder()
+            .add_root_certificate(cert)
+            .build()
+            .unwrap();
+        let url = format!("https://localhost:{}/files/chunked", addr.port());
+        let response = client
+            .get(&url)
+            .header(header::RANGE, "bytes=1024-2047")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::PARTIAL_CONTENT);
+        assert_eq!(&response.bytes().await.unwrap()[..], &contents[1024..2048]);
+
+        let health: serde_json::Value = client
+            .get(format!("https://localhost:{}/health", addr.port()))
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(health["tls"], true);
+
+        // Plain HTTP is not accepted on the TLS port
+        let plain = format!("http://localhost:{}/health", addr.port());
+        assert!(reqwest::get(&plain).await.is_err());
+
+        let _ = shutdown_tx.send(());
+    }
 }
@@ -1,16 +1,62 @@
 use aes::cipher::{KeyIvInit, StreamCipher};
-use aes::Aes256;
+use aes::{Aes128, Aes256};
 use ctr::Ctr128BE;
 use directories::ProjectDirs;
+use ethers::signers::coins_bip39::{English, Mnemonic};
+use ethers::signers::MnemonicBuilder;
+use hkdf::Hkdf;
 use hmac::Hmac;
 use pbkdf2::pbkdf2;
 use rand::{thread_rng, RngCore};
 use serde::{Deserialize, Serialize};
-use sha3::Sha3_256;
+use sha2::Sha256;
+use sha3::{Digest, Keccak256, Sha3_256};
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use subtle::ConstantTimeEq;
+use totp_rs::{Algorithm, Secret, TOTP};
 
 type Aes256Ctr = Ctr128BE<Aes256>;
+type Aes128Ctr = Ctr128BE<Aes128>;
+
+/// BIP44 path of the first Ethereum account, as used by most wallets
+const ETH_DERIVATION_PATH: &str = "m/44'/60'/0'/0/0";
+
+/// HKDF info prefix for per-file keys derived from an account key
+const FILE_KEY_INFO: &[u8] = b"chiral-network-file-key:";
+
+/// Issuer shown in authenticator apps for account TOTP secrets.
+const TOTP_ISSUER: &str = "Chiral Network";
+
+/// PBKDF2 rounds used when exporting V3 keystores (geth's pbkdf2 default)
+const V3_PBKDF2_ROUNDS: u32 = 262_144;
+
+/// Upper bounds on the work an imported V3 file can ask for, so a hostile
+/// file can't hang the app or exhaust memory before its MAC is checked
+const V3_MAX_PBKDF2_ROUNDS: u32 = 10_000_000;
+const V3_MAX_SCRYPT_MEMORY: u64 = 1024 * 1024 * 1024;
+const V3_MAX_SCRYPT_P: u32 = 16;
+
+/// Password key derivation for a keystore account. Entries written before
+/// this field existed have no `kdf` and use PBKDF2.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(tag = "name", rename_all = "lowercase")]
+pub enum KeystoreKdf {
+    /// PBKDF2-HMAC-SHA3-256, 100k rounds
+    #[default]
+    Pbkdf2,
+    /// scrypt with N = 2^log_n
+    Scrypt { log_n: u8, r: u32, p: u32 },
+}
+
+impl KeystoreKdf {
+    /// scrypt with N = 2^17, r = 8, p = 1 (about 128MB and a few hundred ms)
+    pub const SCRYPT_DEFAULT: KeystoreKdf = KeystoreKdf::Scrypt {
+        log_n: 17,
+        r: 8,
+        p: 1,
+    };
+}
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct EncryptedKeystore {
@@ -18,6 +64,14 @@ pub struct EncryptedKeystore {
     pub encrypted_private_key: String,
     pub salt: String,
     pub iv: String,
+    #[serde(default)]
+    pub kdf: KeystoreKdf,
+    /// User-chosen name shown when listing accounts
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    /// Unix seconds when the account was added; 0 for entries that predate this field
+    #[serde(default)]
+    pub created_at: u64,
     // The 2FA secret, encrypted with the same key as the private key, but with its own IV.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub encrypted_two_fa_secret: Option<String>,
@@ -28,12 +82,38 @@ pub struct EncryptedKeystore {
     pub file_encryption_keys: std::collections::HashMap<String, EncryptedFileKey>,
 }
 
+/// What a stored file key is encrypted with
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FileKeyWrapping {
+    /// The account password; rewrapped when the password changes
+    Password,
+    /// The account's private key, which a password change leaves alone
+    PrivateKey,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct EncryptedFileKey {
     pub encrypted_key: String,
     pub key_iv: String,
     pub file_hash: String,
     pub created_at: u64,
+    /// `None` for keys stored before this field existed
+    #[serde(default)]
+    pub wrapping: Option<FileKeyWrapping>,
+}
+
+impl EncryptedFileKey {
+    /// How this key is wrapped. Older entries don't record it, so it is told
+    /// from the ciphertext length: a password-wrapped key is the hex key
+    /// encrypted again (128 hex chars), a private-key-wrapped one the raw key.
+    pub fn wrapping_kind(&self) -> FileKeyWrapping {
+        self.wrapping.unwrap_or(if self.encrypted_key.len() == 128 {
+            FileKeyWrapping::Password
+        } else {
+            FileKeyWrapping::PrivateKey
+        })
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Default)]
@@ -41,6 +121,77 @@ pub struct Keystore {
     pub accounts: Vec<EncryptedKeystore>,
 }
 
+/// Web3 Secret Storage (V3) keystore file, as written by geth and MetaMask
+#[derive(Debug, Serialize, Deserialize)]
+struct V3Keystore {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    address: Option<String>,
+    #[serde(alias = "Crypto")]
+    crypto: V3Crypto,
+    id: String,
+    version: u8,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct V3Crypto {
+    cipher: String,
+    cipherparams: V3CipherParams,
+    ciphertext: String,
+    kdf: String,
+    kdfparams: serde_json::Value,
+    mac: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct V3CipherParams {
+    iv: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct V3Pbkdf2Params {
+    c: u32,
+    dklen: usize,
+    prf: String,
+    salt: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct V3ScryptParams {
+    n: u64,
+    r: u32,
+    p: u32,
+    dklen: usize,
+    salt: String,
+}
+
+/// Non-secret details of a keystore account, for account pickers
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct AccountSummary {
+    pub address: String,
+    pub label: Option<String>,
+    pub created_at: u64,
+    /// Unlocking needs a code from the account's authenticator
+    pub two_fa_enabled: bool,
+}
+
+/// An account entry that could not be read from the keystore file
+#[derive(Debug, Clone, Serialize)]
+pub struct LoadError {
+    /// Position in the `accounts` array; `None` when the file itself is unreadable
+    pub index: Option<usize>,
+    pub address: Option<String>,
+    pub reason: String,
+}
+
+/// Result of checking (and optionally repairing) the keystore file
+#[derive(Debug, Clone, Serialize)]
+pub struct KeystoreIntegrityReport {
+    pub valid_accounts: Vec<String>,
+    pub errors: Vec<LoadError>,
+    /// Copy of the original file, written when a repair rewrote it
+    pub backup_path: Option<String>,
+}
+
 impl Keystore {
     pub fn new() -> Self {
         Keystore {
@@ -62,25 +213,129 @@ impl Keystore {
     }
 
     pub fn load() -> Result<Self, String> {
-        let path = Self::get_keystore_path()?;
+        Self::load_from_path(&Self::get_keystore_path()?)
+    }
 
+    pub fn load_from_path(path: &Path) -> Result<Self, String> {
         if !path.exists() {
             return Ok(Self::new());
         }
 
         let contents =
-            fs::read_to_string(&path).map_err(|e| format!("Failed to read keystore: {}", e))?;
+            fs::read_to_string(path).map_err(|e| format!("Failed to read keystore: {}", e))?;
 
         serde_json::from_str(&contents).map_err(|e| format!("Failed to parse keystore: {}", e))
     }
 
+    /// Load the keystore, skipping account entries that can't be read instead of
+    /// failing the whole file. Skipped entries are returned alongside.
+    pub fn load_lenient() -> (Self, Vec<LoadError>) {
+        match Self::get_keystore_path() {
+            Ok(path) => Self::load_lenient_from_path(&path),
+            Err(reason) => (
+                Self::new(),
+                vec![LoadError {
+                    index: None,
+                    address: None,
+                    reason,
+                }],
+            ),
+        }
+    }
+
+    pub fn load_lenient_from_path(path: &Path) -> (Self, Vec<LoadError>) {
+        let file_error = |reason: String| {
+            (
+                Self::new(),
+                vec![LoadError {
+                    index: None,
+                    address: None,
+                    reason,
+                }],
+            )
+        };
+
+        if !path.exists() {
+            return (Self::new(), Vec::new());
+        }
+
+        let contents = match fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(e) => return file_error(format!("Failed to read keystore: {}", e)),
+        };
+        let root: serde_json::Value = match serde_json::from_str(&contents) {
+            Ok(root) => root,
+            Err(e) => return file_error(format!("Failed to parse keystore: {}", e)),
+        };
+        let entries = match root.get("accounts").and_then(|a| a.as_array()) {
+            Some(entries) => entries,
+            None => return file_error("Keystore has no accounts list".to_string()),
+        };
+
+        let mut keystore = Self::new();
+        let mut errors = Vec::new();
+        for (index, entry) in entries.iter().enumerate() {
+            match parse_account_entry(entry) {
+                Ok(account) => keystore.accounts.push(account),
+                Err(reason) => errors.push(LoadError {
+                    index: Some(index),
+                    address: entry
+                        .get("address")
+                        .and_then(|a| a.as_str())
+                        .map(|a| a.to_string()),
+                    reason,
+                }),
+            }
+        }
+
+        (keystore, errors)
+    }
+
+    /// Check the keystore file and, if `repair` is set and some entries are
+    /// unreadable, rewrite it with only the valid entries after backing up the original.
+    pub fn check_integrity(repair: bool) -> Result<KeystoreIntegrityReport, String> {
+        Self::check_integrity_at(&Self::get_keystore_path()?, repair)
+    }
+
+    /// Rewrite the keystore file with only its valid entries, keeping a backup of the original
+    pub fn repair() -> Result<KeystoreIntegrityReport, String> {
+        Self::check_integrity(true)
+    }
+
+    pub fn check_integrity_at(
+        path: &Path,
+        repair: bool,
+    ) -> Result<KeystoreIntegrityReport, String> {
+        let (keystore, errors) = Self::load_lenient_from_path(path);
+
+        let mut backup_path = None;
+        if repair && !errors.is_empty() {
+            let timestamp = std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs();
+            let backup = path.with_extension(format!("json.bak.{}", timestamp));
+            fs::copy(path, &backup).map_err(|e| format!("Failed to back up keystore: {}", e))?;
+            keystore.save_to_path(path)?;
+            backup_path = Some(backup.to_string_lossy().to_string());
+        }
+
+        Ok(KeystoreIntegrityReport {
+            valid_accounts: keystore.list_accounts(),
+            errors,
+            backup_path,
+        })
+    }
+
     pub fn save(&self) -> Result<(), String> {
-        let path = Self::get_keystore_path()?;
+        self.save_to_path(&Self::get_keystore_path()?)
+    }
 
+    pub fn save_to_path(&self, path: &Path) -> Result<(), String> {
         let contents = serde_json::to_string_pretty(self)
             .map_err(|e| format!("Failed to serialize keystore: {}", e))?;
 
-        fs::write(&path, contents).map_err(|e| format!("Failed to write keystore: {}", e))?;
+        fs::write(path, contents).map_err(|e| format!("Failed to write keystore: {}", e))?;
 
         Ok(())
     }
@@ -91,23 +346,96 @@ impl Keystore {
         private_key: &str,
         password: &str,
     ) -> Result<(), String> {
-        let (encrypted, salt, iv) = encrypt_private_key(private_key, password)?;
-
-        // Remove existing account with same address
-        self.accounts.retain(|a| a.address != address);
+        self.add_account_with_kdf(address, private_key, password, KeystoreKdf::default())
+    }
 
-        self.accounts.push(EncryptedKeystore {
+    /// Like `add_account`, deriving the encryption key with `kdf` (e.g.
+    /// `KeystoreKdf::SCRYPT_DEFAULT`) instead of PBKDF2.
+    pub fn add_account_with_kdf(
+        &mut self,
+        address: String,
+        private_key: &str,
+        password: &str,
+        kdf: KeystoreKdf,
+    ) -> Result<(), String> {
+        self.add_account_at(
+            &Self::get_keystore_path()?,
             address,
+            private_key,
+            password,
+            kdf,
+        )
+    }
+
+    pub fn add_account_at(
+        &mut self,
+        path: &Path,
+        address: String,
+        private_key: &str,
+        password: &str,
+        kdf: KeystoreKdf,
+    ) -> Result<(), String> {
+        let (encrypted, salt, iv) = encrypt_private_key(private_key, password, kdf)?;
+
+        let mut account = EncryptedKeystore {
+            address: address.clone(),
             encrypted_private_key: encrypted,
             salt,
             iv,
+            kdf,
+            label: None,
+            created_at: 0,
             encrypted_two_fa_secret: None,
             two_fa_iv: None,
             file_encryption_keys: std::collections::HashMap::new(),
-        });
+        };
 
-        self.save()?;
-        Ok(())
+        // Replace an existing account with the same address, keeping its
+        // label, creation time, 2FA secret and file keys. Secrets under the
+        // password can only be carried over if it is unchanged.
+        if let Some(index) = self.accounts.iter().position(|a| a.address == address) {
+            let existing = &self.accounts[index];
+            let rewrapped = if unlock_private_key(existing, password).is_ok() {
+                rewrap_password_secrets(existing, password, password, &account.salt, kdf)?
+            } else if existing.encrypted_two_fa_secret.is_some()
+                || existing
+                    .file_encryption_keys
+                    .values()
+                    .any(|k| k.wrapping_kind() == FileKeyWrapping::Password)
+            {
+                return Err("Account already exists with 2FA or file keys under a different password. Import it with that password or change its password first.".to_string());
+            } else {
+                RewrappedSecrets::default()
+            };
+            let existing = self.accounts.remove(index);
+            account.label = existing.label;
+            account.created_at = existing.created_at;
+            account.file_encryption_keys = existing.file_encryption_keys;
+            rewrapped.apply(&mut account);
+        }
+        if account.created_at == 0 {
+            account.created_at = std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs();
+        }
+        self.accounts.retain(|a| a.address != address);
+        self.accounts.push(account);
+
+        self.save_to_path(path)
+    }
+
+    /// Derive the first Ethereum account from a BIP39 phrase (and optional
+    /// BIP39 passphrase), store it encrypted under `password` and return its address.
+    pub fn add_account_from_mnemonic(
+        &mut self,
+        phrase: &str,
+        passphrase: Option<&str>,
+        password: &str,
+    ) -> Result<String, String> {
+        let (address, private_key) = derive_account_from_mnemonic(phrase, passphrase)?;
+        self.add_account(address.clone(), &private_key, password)?;
+        Ok(address)
     }
 
     pub fn get_account(&self, address: &str, password: &str) -> Result<String, String> {
@@ -122,9 +450,137 @@ impl Keystore {
             &account.salt,
             &account.iv,
             password,
+            account.kdf,
         )
     }
 
+    /// Like `get_account`, but when the account has 2FA enabled a valid 6-digit
+    /// code from its authenticator is also required (one 30s step of skew).
+    pub fn get_account_with_2fa(
+        &self,
+        address: &str,
+        password: &str,
+        totp_code: Option<&str>,
+    ) -> Result<String, String> {
+        let private_key = self.get_account(address, password)?;
+        self.check_2fa(address, password, totp_code)?;
+        Ok(private_key)
+    }
+
+    /// Require a valid code when the account has 2FA enabled; accounts
+    /// without it pass with no code.
+    fn check_2fa(
+        &self,
+        address: &str,
+        password: &str,
+        totp_code: Option<&str>,
+    ) -> Result<(), String> {
+        if let Some(secret) = self.get_2fa_secret(address, password)? {
+            let code = totp_code.ok_or_else(|| "2FA code required".to_string())?;
+            if !account_totp(&secret, address)?
+                .check_current(code.trim())
+                .unwrap_or(false)
+            {
+                return Err("Invalid 2FA code".to_string());
+            }
+        }
+        Ok(())
+    }
+
+    /// Decrypt the account's private key, failing on a wrong password
+    fn unlock_account(&self, address: &str, password: &str) -> Result<String, String> {
+        let account = self
+            .accounts
+            .iter()
+            .find(|a| a.address == address)
+            .ok_or_else(|| "Account not found".to_string())?;
+        unlock_private_key(account, password)
+    }
+
+    /// Re-encrypt an account under a new password with a fresh salt and IV,
+    /// re-wrapping its 2FA secret and password-protected file keys as well.
+    /// Accounts with 2FA enabled also need a current code.
+    pub fn change_password(
+        &mut self,
+        address: &str,
+        old_password: &str,
+        new_password: &str,
+        totp_code: Option<&str>,
+    ) -> Result<(), String> {
+        self.change_password_at(
+            &Self::get_keystore_path()?,
+            address,
+            old_password,
+            new_password,
+            totp_code,
+        )
+    }
+
+    /// Like `change_password`, saving to `path`. Nothing is modified if the old
+    /// password or 2FA code is wrong or any secret fails to decrypt.
+    pub fn change_password_at(
+        &mut self,
+        path: &Path,
+        address: &str,
+        old_password: &str,
+        new_password: &str,
+        totp_code: Option<&str>,
+    ) -> Result<(), String> {
+        let private_key = {
+            let account = self
+                .accounts
+                .iter()
+                .find(|a| a.address == address)
+                .ok_or_else(|| "Account not found".to_string())?;
+            unlock_private_key(account, old_password)?
+        };
+        self.check_2fa(address, old_password, totp_code)?;
+
+        let account = self
+            .accounts
+            .iter_mut()
+            .find(|a| a.address == address)
+            .ok_or_else(|| "Account not found".to_string())?;
+        let (encrypted_private_key, salt, iv) =
+            encrypt_private_key(&private_key, new_password, account.kdf)?;
+        let rewrapped =
+            rewrap_password_secrets(account, old_password, new_password, &salt, account.kdf)?;
+
+        account.encrypted_private_key = encrypted_private_key;
+        account.salt = salt;
+        account.iv = iv;
+        rewrapped.apply(account);
+
+        self.save_to_path(path)
+    }
+
+    /// Export an account as Web3 Secret Storage V3 JSON encrypted under the
+    /// same password, for use in geth, MetaMask and other wallets. Accounts
+    /// with 2FA enabled also need a current code.
+    pub fn export_v3(
+        &self,
+        address: &str,
+        password: &str,
+        totp_code: Option<&str>,
+    ) -> Result<String, String> {
+        let account = self
+            .accounts
+            .iter()
+            .find(|a| a.address == address)
+            .ok_or_else(|| "Account not found".to_string())?;
+        let private_key = unlock_private_key(account, password)?;
+        self.check_2fa(address, password, totp_code)?;
+        encrypt_v3(&private_key, password)
+    }
+
+    /// Import a V3 keystore file from another wallet, storing the account
+    /// encrypted under the same password. Returns the account address.
+    pub fn import_v3(&mut self, json: &str, password: &str) -> Result<String, String> {
+        let (address, private_key) = decrypt_v3(json, password)?;
+        self.add_account(address.clone(), &private_key, password)?;
+        Ok(address)
+    }
+
     pub fn is_2fa_enabled(&self, address: &str) -> Result<bool, String> {
         let account = self
             .accounts
@@ -143,52 +599,116 @@ impl Keystore {
 
         match (&account.encrypted_two_fa_secret, &account.two_fa_iv) {
             (Some(encrypted_secret), Some(iv)) => {
-                let decrypted_secret = decrypt_data(encrypted_secret, &account.salt, iv, password)?;
+                let decrypted_secret =
+                    decrypt_data(encrypted_secret, &account.salt, iv, password, account.kdf)?;
                 Ok(Some(decrypted_secret))
             }
             _ => Ok(None),
         }
     }
 
+    /// Store `secret` as the account's TOTP secret, encrypted under
+    /// `password`. The password must unlock the account, and replacing an
+    /// existing secret needs a current code from it.
     pub fn set_2fa_secret(
         &mut self,
         address: &str,
         secret: &str,
         password: &str,
+        current_code: Option<&str>,
     ) -> Result<(), String> {
+        self.set_2fa_secret_at(
+            &Self::get_keystore_path()?,
+            address,
+            secret,
+            password,
+            current_code,
+        )
+    }
+
+    /// Like `set_2fa_secret`, saving to `path`.
+    pub fn set_2fa_secret_at(
+        &mut self,
+        path: &Path,
+        address: &str,
+        secret: &str,
+        password: &str,
+        current_code: Option<&str>,
+    ) -> Result<(), String> {
+        // A secret encrypted under a wrong password would lock the account out
+        self.unlock_account(address, password)?;
+        self.check_2fa(address, password, current_code)?;
+
         let account = self
             .accounts
             .iter_mut()
             .find(|a| a.address == address)
             .ok_or_else(|| "Account not found".to_string())?;
-
-        let (encrypted_secret, iv) = encrypt_data(secret, password, &account.salt)?;
+        let (encrypted_secret, iv) = encrypt_data(secret, password, &account.salt, account.kdf)?;
         account.encrypted_two_fa_secret = Some(encrypted_secret);
         account.two_fa_iv = Some(iv);
+        self.save_to_path(path)
+    }
 
-        self.save()
+    /// Generate a TOTP secret for the account, store it encrypted under
+    /// `password` and return the `otpauth://` provisioning URI. Re-enrolling
+    /// needs a current code from the old secret.
+    pub fn enable_2fa(
+        &mut self,
+        address: &str,
+        password: &str,
+        current_code: Option<&str>,
+    ) -> Result<String, String> {
+        self.enable_2fa_at(&Self::get_keystore_path()?, address, password, current_code)
     }
 
-    pub fn remove_2fa_secret(&mut self, address: &str, password: &str) -> Result<(), String> {
+    /// Like `enable_2fa`, saving to `path`.
+    pub fn enable_2fa_at(
+        &mut self,
+        path: &Path,
+        address: &str,
+        password: &str,
+        current_code: Option<&str>,
+    ) -> Result<String, String> {
+        let mut secret_bytes = [0u8; 20];
+        thread_rng().fill_bytes(&mut secret_bytes);
+        let secret = Secret::Raw(secret_bytes.to_vec()).to_encoded().to_string();
+        let uri = account_totp(&secret, address)?.get_url();
+
+        self.set_2fa_secret_at(path, address, &secret, password, current_code)?;
+        Ok(uri)
+    }
+
+    /// Turn 2FA off. Needs the account password and a current code.
+    pub fn remove_2fa_secret(
+        &mut self,
+        address: &str,
+        password: &str,
+        totp_code: Option<&str>,
+    ) -> Result<(), String> {
+        self.remove_2fa_secret_at(&Self::get_keystore_path()?, address, password, totp_code)
+    }
+
+    /// Like `remove_2fa_secret`, saving to `path`.
+    pub fn remove_2fa_secret_at(
+        &mut self,
+        path: &Path,
+        address: &str,
+        password: &str,
+        totp_code: Option<&str>,
+    ) -> Result<(), String> {
+        self.unlock_account(address, password)
+            .map_err(|_| "Invalid password. Cannot disable 2FA.".to_string())?;
+        self.check_2fa(address, password, totp_code)?;
+
         let account = self
             .accounts
             .iter_mut()
             .find(|a| a.address == address)
             .ok_or_else(|| "Account not found".to_string())?;
-
-        // To remove, we must first verify the password is correct.
-        // We can do this by trying to decrypt the existing secret.
-        if let (Some(encrypted_secret), Some(iv)) =
-            (&account.encrypted_two_fa_secret, &account.two_fa_iv)
-        {
-            decrypt_data(encrypted_secret, &account.salt, iv, password)
-                .map_err(|_| "Invalid password. Cannot disable 2FA.".to_string())?;
-        }
-
-        // Password is correct, so we can remove the secret.
         account.encrypted_two_fa_secret = None;
         account.two_fa_iv = None;
-        self.save()
+        self.save_to_path(path)
     }
 
     pub fn remove_account(&mut self, address: &str) -> Result<(), String> {
@@ -201,12 +721,178 @@ impl Keystore {
         self.accounts.iter().map(|a| a.address.clone()).collect()
     }
 
+    pub fn account_summaries(&self) -> Vec<AccountSummary> {
+        self.accounts
+            .iter()
+            .map(|a| AccountSummary {
+                address: a.address.clone(),
+                label: a.label.clone(),
+                created_at: a.created_at,
+                two_fa_enabled: a.encrypted_two_fa_secret.is_some(),
+            })
+            .collect()
+    }
+
+    /// Set or clear (`None` or blank) an account's label.
+    pub fn set_label(&mut self, address: &str, label: Option<String>) -> Result<(), String> {
+        self.set_label_at(&Self::get_keystore_path()?, address, label)
+    }
+
+    pub fn set_label_at(
+        &mut self,
+        path: &Path,
+        address: &str,
+        label: Option<String>,
+    ) -> Result<(), String> {
+        let account = self
+            .accounts
+            .iter_mut()
+            .find(|a| a.address == address)
+            .ok_or_else(|| "Account not found".to_string())?;
+        account.label = label
+            .map(|l| l.trim().to_string())
+            .filter(|l| !l.is_empty());
+        self.save_to_path(path)
+    }
+
     pub fn store_file_encryption_key(
         &mut self,
         address: &str,
         file_hash: String,
         encryption_key: &[u8; 32],
         password: &str,
+    ) -> Result<(), String> {
+        self.insert_file_encryption_key(address, file_hash, encryption_key, password)?;
+        self.save()
+    }
+
+    /// Return the account's key for `file_hash`, deriving it from the account's
+    /// private key and storing it wrapped under `password` the first time.
+    /// Accounts with 2FA enabled also need a current code.
+    pub fn get_or_create_file_key(
+        &mut self,
+        address: &str,
+        file_hash: &str,
+        password: &str,
+        totp_code: Option<&str>,
+    ) -> Result<[u8; 32], String> {
+        self.get_or_create_file_key_at(
+            &Self::get_keystore_path()?,
+            address,
+            file_hash,
+            password,
+            totp_code,
+        )
+    }
+
+    /// Like `get_or_create_file_key`, saving to `path`.
+    pub fn get_or_create_file_key_at(
+        &mut self,
+        path: &Path,
+        address: &str,
+        file_hash: &str,
+        password: &str,
+        totp_code: Option<&str>,
+    ) -> Result<[u8; 32], String> {
+        let private_key = self.unlock_account(address, password)?;
+        self.check_2fa(address, password, totp_code)?;
+        match self.stored_file_key_wrapping(address, file_hash)? {
+            Some(FileKeyWrapping::Password) => {
+                return self.get_file_encryption_key(address, file_hash, password)
+            }
+            Some(FileKeyWrapping::PrivateKey) => {
+                return self.get_file_encryption_key_with_private_key(
+                    address,
+                    file_hash,
+                    &private_key,
+                )
+            }
+            None => {}
+        }
+
+        let file_key = derive_file_key(&private_key, file_hash)?;
+        self.insert_file_encryption_key(address, file_hash.to_string(), &file_key, password)?;
+        self.save_to_path(path)?;
+        Ok(file_key)
+    }
+
+    /// `get_or_create_file_key` for a session that already holds the unlocked
+    /// private key, such as the upload path. New keys are wrapped with the
+    /// private key; keys wrapped with the password can't be read this way.
+    pub fn get_or_create_file_key_with_private_key(
+        &mut self,
+        address: &str,
+        file_hash: &str,
+        private_key: &str,
+    ) -> Result<[u8; 32], String> {
+        self.get_or_create_file_key_with_private_key_at(
+            &Self::get_keystore_path()?,
+            address,
+            file_hash,
+            private_key,
+        )
+    }
+
+    /// Like `get_or_create_file_key_with_private_key`, saving to `path`.
+    pub fn get_or_create_file_key_with_private_key_at(
+        &mut self,
+        path: &Path,
+        address: &str,
+        file_hash: &str,
+        private_key: &str,
+    ) -> Result<[u8; 32], String> {
+        let derived = crate::ethereum::get_account_from_private_key(private_key)?;
+        if !derived.address.eq_ignore_ascii_case(address) {
+            return Err("Private key does not belong to this account".to_string());
+        }
+        match self.stored_file_key_wrapping(address, file_hash)? {
+            Some(FileKeyWrapping::Password) => {
+                return Err("File key is protected by the account password".to_string())
+            }
+            Some(FileKeyWrapping::PrivateKey) => {
+                return self.get_file_encryption_key_with_private_key(
+                    address,
+                    file_hash,
+                    private_key,
+                )
+            }
+            None => {}
+        }
+
+        let file_key = derive_file_key(private_key, file_hash)?;
+        self.insert_file_encryption_key_with_private_key(
+            address,
+            file_hash.to_string(),
+            &file_key,
+            private_key,
+        )?;
+        self.save_to_path(path)?;
+        Ok(file_key)
+    }
+
+    /// How the key stored for `file_hash` is wrapped, or `None` if there is none
+    fn stored_file_key_wrapping(
+        &self,
+        address: &str,
+        file_hash: &str,
+    ) -> Result<Option<FileKeyWrapping>, String> {
+        let account = self
+            .accounts
+            .iter()
+            .find(|a| a.address == address)
+            .ok_or_else(|| "Account not found".to_string())?;
+        Ok(account
+            .file_encryption_keys
+            .get(file_hash)
+            .map(EncryptedFileKey::wrapping_kind))
+    }
+
+    fn insert_file_encryption_key(
+        &mut self,
+        address: &str,
+        file_hash: String,
+        encryption_key: &[u8; 32],
+        password: &str,
     ) -> Result<(), String> {
         let account = self
             .accounts
@@ -215,8 +901,12 @@ impl Keystore {
             .ok_or_else(|| "Account not found".to_string())?;
 
         // Encrypt the file encryption key using the account's password-derived key
-        let (encrypted_key, key_iv) =
-            encrypt_data(&hex::encode(encryption_key), password, &account.salt)?;
+        let (encrypted_key, key_iv) = encrypt_data(
+            &hex::encode(encryption_key),
+            password,
+            &account.salt,
+            account.kdf,
+        )?;
 
         let file_key = EncryptedFileKey {
             encrypted_key,
@@ -226,10 +916,11 @@ impl Keystore {
                 .duration_since(std::time::UNIX_EPOCH)
                 .unwrap_or_default()
                 .as_secs(),
+            wrapping: Some(FileKeyWrapping::Password),
         };
 
         account.file_encryption_keys.insert(file_hash, file_key);
-        self.save()
+        Ok(())
     }
 
     pub fn get_file_encryption_key(
@@ -255,6 +946,7 @@ impl Keystore {
             &account.salt,
             &file_key.key_iv,
             password,
+            account.kdf,
         )?;
         let key_bytes =
             hex::decode(decrypted_hex).map_err(|e| format!("Invalid key format: {}", e))?;
@@ -280,6 +972,22 @@ impl Keystore {
         file_hash: String,
         encryption_key: &[u8; 32],
         private_key: &str,
+    ) -> Result<(), String> {
+        self.insert_file_encryption_key_with_private_key(
+            address,
+            file_hash,
+            encryption_key,
+            private_key,
+        )?;
+        self.save()
+    }
+
+    fn insert_file_encryption_key_with_private_key(
+        &mut self,
+        address: &str,
+        file_hash: String,
+        encryption_key: &[u8; 32],
+        private_key: &str,
     ) -> Result<(), String> {
         let account = self
             .accounts
@@ -311,10 +1019,11 @@ impl Keystore {
                 .duration_since(std::time::UNIX_EPOCH)
                 .unwrap_or_default()
                 .as_secs(),
+            wrapping: Some(FileKeyWrapping::PrivateKey),
         };
 
         account.file_encryption_keys.insert(file_hash, file_key);
-        self.save()
+        Ok(())
     }
 
     pub fn get_file_encryption_key_with_private_key(
@@ -359,17 +1068,351 @@ impl Keystore {
     }
 }
 
-fn derive_key(password: &str, salt: &[u8]) -> Result<[u8; 32], String> {
-    let mut key = [0u8; 32];
-    // Increased iterations from 4096 to 100000 for better security
-    pbkdf2::<Hmac<Sha3_256>>(password.as_bytes(), salt, 100_000, &mut key)
+/// Generate a new English BIP39 recovery phrase of 12 or 24 words.
+pub fn generate_mnemonic(word_count: usize) -> Result<String, String> {
+    if word_count != 12 && word_count != 24 {
+        return Err("Mnemonic must be 12 or 24 words".to_string());
+    }
+    let mnemonic = Mnemonic::<English>::new_with_count(&mut thread_rng(), word_count)
+        .map_err(|e| format!("Failed to generate mnemonic: {}", e))?;
+    Ok(mnemonic.to_phrase())
+}
+
+/// Derive `(address, private_key_hex)` at `m/44'/60'/0'/0/0` from a BIP39 phrase.
+/// Unknown words and bad checksums are rejected.
+pub fn derive_account_from_mnemonic(
+    phrase: &str,
+    passphrase: Option<&str>,
+) -> Result<(String, String), String> {
+    let phrase = phrase.split_whitespace().collect::<Vec<_>>().join(" ");
+    Mnemonic::<English>::new_from_phrase(&phrase)
+        .map_err(|e| format!("Invalid mnemonic: {}", e))?;
+
+    let mut builder = MnemonicBuilder::<English>::default().phrase(phrase.as_str());
+    if let Some(passphrase) = passphrase {
+        builder = builder.password(passphrase);
+    }
+    let wallet = builder
+        .derivation_path(ETH_DERIVATION_PATH)
+        .map_err(|e| format!("Invalid derivation path: {}", e))?
+        .build()
+        .map_err(|e| format!("Failed to derive key: {}", e))?;
+
+    let private_key = hex::encode(wallet.signer().to_bytes());
+    let account = crate::ethereum::get_account_from_private_key(&private_key)?;
+    Ok((account.address, account.private_key))
+}
+
+/// Derive the AES-256 key for `file_hash` from an account's private key
+/// (HKDF-SHA256). The same account and file always give the same key.
+pub fn derive_file_key(private_key: &str, file_hash: &str) -> Result<[u8; 32], String> {
+    let private_key_bytes = hex::decode(private_key.trim_start_matches("0x"))
+        .map_err(|e| format!("Invalid private key: {}", e))?;
+    let info = [FILE_KEY_INFO, file_hash.as_bytes()].concat();
+
+    let mut file_key = [0u8; 32];
+    Hkdf::<Sha256>::new(None, &private_key_bytes)
+        .expand(&info, &mut file_key)
+        .map_err(|e| format!("HKDF expansion failed: {}", e))?;
+    Ok(file_key)
+}
+
+/// TOTP (SHA-1, 6 digits, 30s steps) for a base32 secret, matching the
+/// parameters of common authenticator apps.
+pub fn account_totp(secret_b32: &str, address: &str) -> Result<TOTP, String> {
+    let secret = Secret::Encoded(secret_b32.to_string())
+        .to_bytes()
+        .map_err(|e| format!("Invalid 2FA secret: {}", e))?;
+    TOTP::new(
+        Algorithm::SHA1,
+        6,
+        1,
+        30,
+        secret,
+        Some(TOTP_ISSUER.to_string()),
+        address.to_string(),
+    )
+    .map_err(|e| e.to_string())
+}
+
+/// An account's 2FA secret and password-wrapped file keys, encrypted again
+/// under a new password or salt
+#[derive(Default)]
+struct RewrappedSecrets {
+    two_fa: Option<(String, String)>,
+    file_keys: Vec<(String, (String, String))>,
+}
+
+impl RewrappedSecrets {
+    fn apply(self, account: &mut EncryptedKeystore) {
+        if let Some((encrypted_secret, two_fa_iv)) = self.two_fa {
+            account.encrypted_two_fa_secret = Some(encrypted_secret);
+            account.two_fa_iv = Some(two_fa_iv);
+        }
+        for (file_hash, (encrypted_key, key_iv)) in self.file_keys {
+            if let Some(file_key) = account.file_encryption_keys.get_mut(&file_hash) {
+                file_key.encrypted_key = encrypted_key;
+                file_key.key_iv = key_iv;
+                file_key.wrapping = Some(FileKeyWrapping::Password);
+            }
+        }
+    }
+}
+
+/// Decrypt the secrets `account` keeps under `old_password` and encrypt them
+/// again under `new_password` with `salt` and `kdf`. Keys wrapped by the
+/// private key don't depend on the password and are left out.
+fn rewrap_password_secrets(
+    account: &EncryptedKeystore,
+    old_password: &str,
+    new_password: &str,
+    salt: &str,
+    kdf: KeystoreKdf,
+) -> Result<RewrappedSecrets, String> {
+    let two_fa = match (&account.encrypted_two_fa_secret, &account.two_fa_iv) {
+        (Some(encrypted_secret), Some(two_fa_iv)) => {
+            let secret = decrypt_data(
+                encrypted_secret,
+                &account.salt,
+                two_fa_iv,
+                old_password,
+                account.kdf,
+            )?;
+            Some(encrypt_data(&secret, new_password, salt, kdf)?)
+        }
+        _ => None,
+    };
+
+    let mut file_keys = Vec::new();
+    for (file_hash, file_key) in &account.file_encryption_keys {
+        if file_key.wrapping_kind() != FileKeyWrapping::Password {
+            continue;
+        }
+        let key_hex = decrypt_data(
+            &file_key.encrypted_key,
+            &account.salt,
+            &file_key.key_iv,
+            old_password,
+            account.kdf,
+        )?;
+        file_keys.push((
+            file_hash.clone(),
+            encrypt_data(&key_hex, new_password, salt, kdf)?,
+        ));
+    }
+
+    Ok(RewrappedSecrets { two_fa, file_keys })
+}
+
+/// Decrypt an account's private key, checking it derives the account's address.
+/// CTR mode can't detect a wrong key by itself.
+fn unlock_private_key(account: &EncryptedKeystore, password: &str) -> Result<String, String> {
+    let private_key = decrypt_private_key(
+        &account.encrypted_private_key,
+        &account.salt,
+        &account.iv,
+        password,
+        account.kdf,
+    )
+    .map_err(|_| "Invalid password".to_string())?;
+    let derived = crate::ethereum::get_account_from_private_key(&private_key)
+        .map_err(|_| "Invalid password".to_string())?;
+    if !derived.address.eq_ignore_ascii_case(&account.address) {
+        return Err("Invalid password".to_string());
+    }
+    Ok(private_key)
+}
+
+/// Encrypt a hex private key as V3 JSON (PBKDF2-HMAC-SHA256, AES-128-CTR,
+/// Keccak-256 MAC).
+fn encrypt_v3(private_key: &str, password: &str) -> Result<String, String> {
+    let account = crate::ethereum::get_account_from_private_key(private_key)?;
+    let mut key_bytes =
+        hex::decode(&account.private_key).map_err(|e| format!("Invalid private key: {}", e))?;
+
+    let mut rng = thread_rng();
+    let mut salt = [0u8; 32];
+    rng.fill_bytes(&mut salt);
+    let mut iv = [0u8; 16];
+    rng.fill_bytes(&mut iv);
+
+    let mut derived = [0u8; 32];
+    pbkdf2::<Hmac<Sha256>>(password.as_bytes(), &salt, V3_PBKDF2_ROUNDS, &mut derived)
         .map_err(|e| format!("PBKDF2 key derivation failed: {}", e))?;
+
+    let mut cipher = Aes128Ctr::new_from_slices(&derived[..16], &iv)
+        .map_err(|e| format!("Invalid cipher parameters: {}", e))?;
+    cipher.apply_keystream(&mut key_bytes);
+    let mac = v3_mac(&derived, &key_bytes);
+
+    let keystore = V3Keystore {
+        address: Some(account.address.trim_start_matches("0x").to_string()),
+        crypto: V3Crypto {
+            cipher: "aes-128-ctr".to_string(),
+            cipherparams: V3CipherParams {
+                iv: hex::encode(iv),
+            },
+            ciphertext: hex::encode(key_bytes),
+            kdf: "pbkdf2".to_string(),
+            kdfparams: serde_json::json!({
+                "c": V3_PBKDF2_ROUNDS,
+                "dklen": 32,
+                "prf": "hmac-sha256",
+                "salt": hex::encode(salt),
+            }),
+            mac: hex::encode(mac),
+        },
+        id: uuid::Uuid::new_v4().to_string(),
+        version: 3,
+    };
+    serde_json::to_string_pretty(&keystore).map_err(|e| e.to_string())
+}
+
+/// Decrypt V3 JSON to `(address, private_key_hex)`, rejecting it if the MAC
+/// doesn't match (wrong password or tampered file).
+fn decrypt_v3(json: &str, password: &str) -> Result<(String, String), String> {
+    let keystore: V3Keystore =
+        serde_json::from_str(json).map_err(|e| format!("Invalid V3 keystore: {}", e))?;
+    if keystore.version != 3 {
+        return Err(format!("Unsupported keystore version {}", keystore.version));
+    }
+    let crypto = &keystore.crypto;
+    if crypto.cipher != "aes-128-ctr" {
+        return Err(format!("Unsupported cipher {}", crypto.cipher));
+    }
+
+    let derived = derive_v3_key(&crypto.kdf, &crypto.kdfparams, password)?;
+    if derived.len() < 32 {
+        return Err("Derived key too short".to_string());
+    }
+    let mut ciphertext =
+        hex::decode(&crypto.ciphertext).map_err(|_| "Invalid ciphertext".to_string())?;
+    let expected_mac = hex::decode(&crypto.mac).map_err(|_| "Invalid MAC".to_string())?;
+    let mac = v3_mac(&derived, &ciphertext);
+    if !bool::from(mac.as_slice().ct_eq(expected_mac.as_slice())) {
+        return Err("MAC mismatch: wrong password or corrupted keystore".to_string());
+    }
+
+    let iv = hex::decode(&crypto.cipherparams.iv).map_err(|_| "Invalid IV format".to_string())?;
+    let mut cipher = Aes128Ctr::new_from_slices(&derived[..16], &iv)
+        .map_err(|_| "Invalid IV length".to_string())?;
+    cipher.apply_keystream(&mut ciphertext);
+
+    let account = crate::ethereum::get_account_from_private_key(&hex::encode(ciphertext))?;
+    if let Some(address) = &keystore.address {
+        let derived_address = account.address.trim_start_matches("0x");
+        if !derived_address.eq_ignore_ascii_case(address.trim_start_matches("0x")) {
+            return Err("Keystore address does not match its key".to_string());
+        }
+    }
+    Ok((account.address, account.private_key))
+}
+
+fn derive_v3_key(kdf: &str, params: &serde_json::Value, password: &str) -> Result<Vec<u8>, String> {
+    match kdf {
+        "pbkdf2" => {
+            let params: V3Pbkdf2Params = serde_json::from_value(params.clone())
+                .map_err(|e| format!("Invalid pbkdf2 params: {}", e))?;
+            if params.prf != "hmac-sha256" {
+                return Err(format!("Unsupported prf {}", params.prf));
+            }
+            if params.c == 0 || params.c > V3_MAX_PBKDF2_ROUNDS {
+                return Err(format!("Unsupported pbkdf2 round count {}", params.c));
+            }
+            check_v3_dklen(params.dklen)?;
+            let salt = hex::decode(&params.salt).map_err(|_| "Invalid salt format".to_string())?;
+            let mut key = vec![0u8; params.dklen];
+            pbkdf2::<Hmac<Sha256>>(password.as_bytes(), &salt, params.c, &mut key)
+                .map_err(|e| format!("PBKDF2 key derivation failed: {}", e))?;
+            Ok(key)
+        }
+        "scrypt" => {
+            let params: V3ScryptParams = serde_json::from_value(params.clone())
+                .map_err(|e| format!("Invalid scrypt params: {}", e))?;
+            if !params.n.is_power_of_two() {
+                return Err("scrypt n must be a power of two".to_string());
+            }
+            // scrypt needs 128 * n * r bytes of memory
+            let memory = 128u64
+                .saturating_mul(params.n)
+                .saturating_mul(params.r as u64);
+            if memory > V3_MAX_SCRYPT_MEMORY || params.p > V3_MAX_SCRYPT_P {
+                return Err("scrypt params exceed supported limits".to_string());
+            }
+            check_v3_dklen(params.dklen)?;
+            let salt = hex::decode(&params.salt).map_err(|_| "Invalid salt format".to_string())?;
+            let scrypt_params =
+                scrypt::Params::new(params.n.trailing_zeros() as u8, params.r, params.p)
+                    .map_err(|e| format!("Invalid scrypt params: {}", e))?;
+            let mut key = vec![0u8; params.dklen];
+            scrypt::scrypt(password.as_bytes(), &salt, &scrypt_params, &mut key)
+                .map_err(|e| format!("scrypt key derivation failed: {}", e))?;
+            Ok(key)
+        }
+        other => Err(format!("Unsupported kdf {}", other)),
+    }
+}
+
+/// Every supported V3 cipher uses a 32-byte derived key
+fn check_v3_dklen(dklen: usize) -> Result<(), String> {
+    if dklen != 32 {
+        return Err(format!("Unsupported dklen {}", dklen));
+    }
+    Ok(())
+}
+
+/// V3 MAC: keccak256(derived_key[16..32] || ciphertext)
+fn v3_mac(derived_key: &[u8], ciphertext: &[u8]) -> [u8; 32] {
+    let mut hasher = Keccak256::new();
+    hasher.update(&derived_key[16..32]);
+    hasher.update(ciphertext);
+    hasher.finalize().into()
+}
+
+/// Parse a single account entry and check that its encrypted fields are well-formed.
+fn parse_account_entry(entry: &serde_json::Value) -> Result<EncryptedKeystore, String> {
+    let account: EncryptedKeystore =
+        serde_json::from_value(entry.clone()).map_err(|e| format!("Malformed entry: {}", e))?;
+
+    if account.address.is_empty() {
+        return Err("Missing address".to_string());
+    }
+    hex::decode(&account.salt).map_err(|_| "Invalid salt format".to_string())?;
+    let iv = hex::decode(&account.iv).map_err(|_| "Invalid IV format".to_string())?;
+    if iv.len() != 16 {
+        return Err("Invalid IV length".to_string());
+    }
+    let ciphertext = hex::decode(&account.encrypted_private_key)
+        .map_err(|_| "Invalid ciphertext".to_string())?;
+    if ciphertext.is_empty() {
+        return Err("Empty ciphertext".to_string());
+    }
+
+    Ok(account)
+}
+
+fn derive_key(kdf: KeystoreKdf, password: &str, salt: &[u8]) -> Result<[u8; 32], String> {
+    let mut key = [0u8; 32];
+    match kdf {
+        KeystoreKdf::Pbkdf2 => {
+            // Increased iterations from 4096 to 100000 for better security
+            pbkdf2::<Hmac<Sha3_256>>(password.as_bytes(), salt, 100_000, &mut key)
+                .map_err(|e| format!("PBKDF2 key derivation failed: {}", e))?;
+        }
+        KeystoreKdf::Scrypt { log_n, r, p } => {
+            let params = scrypt::Params::new(log_n, r, p)
+                .map_err(|e| format!("Invalid scrypt params: {}", e))?;
+            scrypt::scrypt(password.as_bytes(), salt, &params, &mut key)
+                .map_err(|e| format!("scrypt key derivation failed: {}", e))?;
+        }
+    }
     Ok(key)
 }
 
 fn encrypt_private_key(
     private_key: &str,
     password: &str,
+    kdf: KeystoreKdf,
 ) -> Result<(String, String, String), String> {
     let mut rng = thread_rng();
 
@@ -382,7 +1425,7 @@ fn encrypt_private_key(
     rng.fill_bytes(&mut iv);
 
     // Derive key from password
-    let key = derive_key(password, &salt)?;
+    let key = derive_key(kdf, password, &salt)?;
 
     // Encrypt
     let mut data = private_key.as_bytes().to_vec();
@@ -397,9 +1440,10 @@ fn encrypt_data(
     data_to_encrypt: &str,
     password: &str,
     salt_hex: &str,
+    kdf: KeystoreKdf,
 ) -> Result<(String, String), String> {
     let salt = hex::decode(salt_hex).map_err(|e| format!("Invalid salt: {}", e))?;
-    let key = derive_key(password, &salt)?;
+    let key = derive_key(kdf, password, &salt)?;
 
     let mut iv = [0u8; 16];
     rand::thread_rng().fill_bytes(&mut iv);
@@ -417,13 +1461,15 @@ fn decrypt_data(
     salt_hex: &str,
     iv_hex: &str,
     password: &str,
+    kdf: KeystoreKdf,
 ) -> Result<String, String> {
     // First, try with the current (higher) iteration count and new hash algorithm
     let result = try_decrypt(encrypted_hex, salt_hex, iv_hex, password, |p, s| {
-        derive_key(p, s).ok()
+        derive_key(kdf, p, s).ok()
     });
 
-    if result.is_ok() {
+    // Only PBKDF2 entries can predate the iteration bump
+    if result.is_ok() || kdf != KeystoreKdf::Pbkdf2 {
         return result;
     }
 
@@ -445,9 +1491,10 @@ fn decrypt_private_key(
     salt: &str,
     iv: &str,
     password: &str,
+    kdf: KeystoreKdf,
 ) -> Result<String, String> {
     // This function now simply wraps decrypt_data to ensure consistent decryption logic.
-    decrypt_data(encrypted, salt, iv, password)
+    decrypt_data(encrypted, salt, iv, password, kdf)
 }
 
 /// Helper function to perform decryption with a given key derivation function.
@@ -478,3 +1525,680 @@ where
     String::from_utf8(ciphertext)
         .map_err(|_| "Decryption failed: incorrect password or corrupted data".to_string())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    /// Small N keeps tests fast; the format is the same as SCRYPT_DEFAULT
+    const TEST_KDF: KeystoreKdf = KeystoreKdf::Scrypt {
+        log_n: 10,
+        r: 8,
+        p: 1,
+    };
+
+    /// A new random account and its keystore entry, encrypted under `password`
+    fn test_account(
+        kdf: KeystoreKdf,
+        password: &str,
+    ) -> (crate::ethereum::EthAccount, EncryptedKeystore) {
+        let account = crate::ethereum::create_new_account().unwrap();
+        let (encrypted, salt, iv) =
+            encrypt_private_key(&account.private_key, password, kdf).unwrap();
+        let entry = EncryptedKeystore {
+            address: account.address.clone(),
+            encrypted_private_key: encrypted,
+            salt,
+            iv,
+            kdf,
+            label: None,
+            created_at: 0,
+            encrypted_two_fa_secret: None,
+            two_fa_iv: None,
+            file_encryption_keys: std::collections::HashMap::new(),
+        };
+        (account, entry)
+    }
+
+    fn write_keystore_with_bad_entry(path: &Path) {
+        let (encrypted, salt, iv) =
+            encrypt_private_key("0xabc123", "password", KeystoreKdf::default()).unwrap();
+        let contents = serde_json::json!({
+            "accounts": [
+                {
+                    "address": "0xgood",
+                    "encrypted_private_key": encrypted,
+                    "salt": salt,
+                    "iv": iv,
+                },
+                {
+                    "address": "0xbad",
+                    "encrypted_private_key": "not-hex",
+                    "salt": 42,
+                }
+            ]
+        });
+        fs::write(path, contents.to_string()).unwrap();
+    }
+
+    #[test]
+    fn test_lenient_load_skips_malformed_entry() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("keystore.json");
+        write_keystore_with_bad_entry(&path);
+
+        assert!(Keystore::load_from_path(&path).is_err());
+
+        let (keystore, errors) = Keystore::load_lenient_from_path(&path);
+        assert_eq!(keystore.list_accounts(), vec!["0xgood".to_string()]);
+        assert_eq!(
+            keystore.get_account("0xgood", "password").unwrap(),
+            "0xabc123"
+        );
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].index, Some(1));
+        assert_eq!(errors[0].address.as_deref(), Some("0xbad"));
+    }
+
+    #[test]
+    fn test_repair_rewrites_valid_entries_and_keeps_backup() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("keystore.json");
+        write_keystore_with_bad_entry(&path);
+        let original = fs::read_to_string(&path).unwrap();
+
+        let report = Keystore::check_integrity_at(&path, true).unwrap();
+        assert_eq!(report.valid_accounts, vec!["0xgood".to_string()]);
+        assert_eq!(report.errors.len(), 1);
+
+        let backup = report.backup_path.expect("backup should be written");
+        assert_eq!(fs::read_to_string(backup).unwrap(), original);
+
+        let repaired = Keystore::load_from_path(&path).unwrap();
+        assert_eq!(repaired.list_accounts(), vec!["0xgood".to_string()]);
+
+        // A clean file is left untouched
+        let report = Keystore::check_integrity_at(&path, true).unwrap();
+        assert!(report.errors.is_empty());
+        assert!(report.backup_path.is_none());
+    }
+
+    #[test]
+    fn test_mnemonic_derives_known_addresses() {
+        let (address, private_key) = derive_account_from_mnemonic(
+            "test test test test test test test test test test test junk",
+            None,
+        )
+        .unwrap();
+        assert_eq!(address, "0xf39fd6e51aad88f6f4ce6ab8827279cfffb92266");
+        assert_eq!(
+            private_key,
+            "ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80"
+        );
+
+        let (address, _) = derive_account_from_mnemonic(
+            "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about",
+            None,
+        )
+        .unwrap();
+        assert_eq!(address, "0x9858effd232b4033e47d90003d41ec34ecaeda94");
+    }
+
+    #[test]
+    fn test_invalid_mnemonics_are_rejected() {
+        // Bad checksum: every word valid, last word wrong
+        assert!(derive_account_from_mnemonic(
+            "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon",
+            None,
+        )
+        .is_err());
+        // Not a BIP39 word
+        assert!(derive_account_from_mnemonic(
+            "test test test test test test test test test test test chiral",
+            None,
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_generated_mnemonic_round_trips() {
+        for count in [12, 24] {
+            let phrase = generate_mnemonic(count).unwrap();
+            assert_eq!(phrase.split_whitespace().count(), count);
+            assert!(derive_account_from_mnemonic(&phrase, Some("extra")).is_ok());
+        }
+        assert!(generate_mnemonic(15).is_err());
+    }
+
+    #[test]
+    fn test_change_password_rewraps_all_secrets() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("keystore.json");
+        let kdf = KeystoreKdf::default();
+        let (account, mut entry) = test_account(kdf, "old");
+        let salt = entry.salt.clone();
+        let (two_fa_secret, two_fa_iv) =
+            encrypt_data("JBSWY3DPEHPK3PXP", "old", &salt, kdf).unwrap();
+        let file_key = [7u8; 32];
+        let (encrypted_key, key_iv) =
+            encrypt_data(&hex::encode(file_key), "old", &salt, kdf).unwrap();
+        let mut file_encryption_keys = std::collections::HashMap::new();
+        file_encryption_keys.insert(
+            "filehash".to_string(),
+            EncryptedFileKey {
+                encrypted_key,
+                key_iv,
+                file_hash: "filehash".to_string(),
+                created_at: 0,
+                // Stored before the wrapping was recorded
+                wrapping: None,
+            },
+        );
+        // Wrapped by the private key; the length alone would say password
+        let private_key_wrapped = "ab".repeat(64);
+        file_encryption_keys.insert(
+            "otherhash".to_string(),
+            EncryptedFileKey {
+                encrypted_key: private_key_wrapped.clone(),
+                key_iv: "00".repeat(16),
+                file_hash: "otherhash".to_string(),
+                created_at: 0,
+                wrapping: Some(FileKeyWrapping::PrivateKey),
+            },
+        );
+        entry.encrypted_two_fa_secret = Some(two_fa_secret);
+        entry.two_fa_iv = Some(two_fa_iv);
+        entry.file_encryption_keys = file_encryption_keys;
+        let mut keystore = Keystore {
+            accounts: vec![entry],
+        };
+        keystore.save_to_path(&path).unwrap();
+
+        // A wrong old password leaves the stored blob untouched
+        let before = fs::read_to_string(&path).unwrap();
+        assert!(keystore
+            .change_password_at(&path, &account.address, "wrong", "new", None)
+            .is_err());
+        assert_eq!(fs::read_to_string(&path).unwrap(), before);
+
+        // So does a missing 2FA code
+        assert_eq!(
+            keystore.change_password_at(&path, &account.address, "old", "new", None),
+            Err("2FA code required".to_string())
+        );
+        assert_eq!(fs::read_to_string(&path).unwrap(), before);
+
+        let code = account_totp("JBSWY3DPEHPK3PXP", &account.address)
+            .unwrap()
+            .generate_current()
+            .unwrap();
+        keystore
+            .change_password_at(&path, &account.address, "old", "new", Some(&code))
+            .unwrap();
+        let reloaded = Keystore::load_from_path(&path).unwrap();
+        assert_ne!(reloaded.accounts[0].salt, salt);
+
+        assert_eq!(
+            reloaded.get_account(&account.address, "new").unwrap(),
+            account.private_key
+        );
+        assert_ne!(
+            reloaded.get_account(&account.address, "old").ok(),
+            Some(account.private_key.clone())
+        );
+        assert_eq!(
+            reloaded.get_2fa_secret(&account.address, "new").unwrap(),
+            Some("JBSWY3DPEHPK3PXP".to_string())
+        );
+        assert_eq!(
+            reloaded
+                .get_file_encryption_key(&account.address, "filehash", "new")
+                .unwrap(),
+            file_key
+        );
+        let file_keys = &reloaded.accounts[0].file_encryption_keys;
+        assert_eq!(
+            file_keys["filehash"].wrapping,
+            Some(FileKeyWrapping::Password)
+        );
+        assert_eq!(file_keys["otherhash"].encrypted_key, private_key_wrapped);
+    }
+
+    // Test vector from the Web3 Secret Storage definition (password "testpassword")
+    const GETH_V3_PBKDF2: &str = r#"{
+        "crypto": {
+            "cipher": "aes-128-ctr",
+            "cipherparams": { "iv": "6087dab2f9fdbbfaddc31a909735c1e6" },
+            "ciphertext": "5318b4d5bcd28de64ee5559e671353e16f075ecae9f99c7a79a38af5f869aa46",
+            "kdf": "pbkdf2",
+            "kdfparams": {
+                "c": 262144,
+                "dklen": 32,
+                "prf": "hmac-sha256",
+                "salt": "ae3cd4e7013836a3df6bd7241b12db061dbe2c6785853cce422d148a624ce0bd"
+            },
+            "mac": "517ead924a9d0dc3124507e3393d175ce3ff7c1e96529c6c555ce9e51205e9b2"
+        },
+        "id": "3198bc9c-6672-5ab3-d995-4942343ae5b6",
+        "version": 3
+    }"#;
+
+    #[test]
+    fn test_decrypt_known_geth_v3_keystore() {
+        let (address, private_key) = decrypt_v3(GETH_V3_PBKDF2, "testpassword").unwrap();
+        assert_eq!(address, "0x008aeeda4d805471df9b2a5b0f38a0c3bcba786b");
+        assert_eq!(
+            private_key,
+            "7a28b5ba57c53603b0b07b56bba752f7784bf506fa95edc395f5cf6c7514fe9d"
+        );
+
+        assert!(decrypt_v3(GETH_V3_PBKDF2, "wrongpassword").is_err());
+
+        let tampered = GETH_V3_PBKDF2.replace("5318b4d5", "5318b4d6");
+        let err = decrypt_v3(&tampered, "testpassword").unwrap_err();
+        assert!(err.contains("MAC"));
+    }
+
+    #[test]
+    fn test_v3_keystore_with_excessive_kdf_params_is_rejected() {
+        let huge_rounds = GETH_V3_PBKDF2.replace("\"c\": 262144", "\"c\": 4294967295");
+        let err = decrypt_v3(&huge_rounds, "testpassword").unwrap_err();
+        assert!(err.contains("round count"));
+
+        let huge_dklen = GETH_V3_PBKDF2.replace("\"dklen\": 32", "\"dklen\": 1000000000000");
+        let err = decrypt_v3(&huge_dklen, "testpassword").unwrap_err();
+        assert!(err.contains("dklen"));
+
+        let scrypt = |n: u64, r: u32, p: u32| {
+            GETH_V3_PBKDF2
+                .replace("\"kdf\": \"pbkdf2\"", "\"kdf\": \"scrypt\"")
+                .replace(
+                    "\"c\": 262144,",
+                    &format!("\"n\": {}, \"r\": {}, \"p\": {},", n, r, p),
+                )
+        };
+        for (n, r, p) in [
+            (1 << 40, 8, 1),
+            (1 << 18, 1 << 20, 1),
+            (1 << 10, 8, 1 << 30),
+        ] {
+            let err = decrypt_v3(&scrypt(n, r, p), "testpassword").unwrap_err();
+            assert!(err.contains("limits"), "{}", err);
+        }
+    }
+
+    #[test]
+    fn test_v3_export_round_trips() {
+        let account = crate::ethereum::create_new_account().unwrap();
+        let json = encrypt_v3(&account.private_key, "hunter2").unwrap();
+
+        let parsed: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed["version"], 3);
+        assert_eq!(parsed["crypto"]["cipher"], "aes-128-ctr");
+
+        let (address, private_key) = decrypt_v3(&json, "hunter2").unwrap();
+        assert_eq!(address, account.address);
+        assert_eq!(private_key, account.private_key);
+    }
+
+    #[test]
+    fn test_scrypt_account_round_trip() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("keystore.json");
+        let (account, entry) = test_account(TEST_KDF, "pw");
+        let keystore = Keystore {
+            accounts: vec![entry],
+        };
+        keystore.save_to_path(&path).unwrap();
+
+        let contents: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(contents["accounts"][0]["kdf"]["name"], "scrypt");
+
+        let reloaded = Keystore::load_from_path(&path).unwrap();
+        assert_eq!(reloaded.accounts[0].kdf, TEST_KDF);
+        assert_eq!(
+            reloaded.get_account(&account.address, "pw").unwrap(),
+            account.private_key
+        );
+        assert!(unlock_private_key(&reloaded.accounts[0], "wrong").is_err());
+    }
+
+    #[test]
+    fn test_legacy_entry_without_kdf_uses_pbkdf2() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("keystore.json");
+        let account = crate::ethereum::create_new_account().unwrap();
+        let (encrypted, salt, iv) =
+            encrypt_private_key(&account.private_key, "pw", KeystoreKdf::Pbkdf2).unwrap();
+        let contents = serde_json::json!({
+            "accounts": [{
+                "address": account.address,
+                "encrypted_private_key": encrypted,
+                "salt": salt,
+                "iv": iv,
+            }]
+        });
+        fs::write(&path, contents.to_string()).unwrap();
+
+        let keystore = Keystore::load_from_path(&path).unwrap();
+        assert_eq!(keystore.accounts[0].kdf, KeystoreKdf::Pbkdf2);
+        assert_eq!(
+            keystore.get_account(&account.address, "pw").unwrap(),
+            account.private_key
+        );
+    }
+
+    #[test]
+    fn test_labels_persist_across_save_and_load() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("keystore.json");
+        write_keystore_with_bad_entry(&path);
+        let (mut keystore, _) = Keystore::load_lenient_from_path(&path);
+
+        // Entries written before labels existed load with defaults
+        assert_eq!(
+            keystore.account_summaries(),
+            vec![AccountSummary {
+                address: "0xgood".to_string(),
+                label: None,
+                created_at: 0,
+                two_fa_enabled: false,
+            }]
+        );
+
+        keystore
+            .set_label_at(&path, "0xgood", Some("  Mining rig ".to_string()))
+            .unwrap();
+        let reloaded = Keystore::load_from_path(&path).unwrap();
+        assert_eq!(
+            reloaded.account_summaries()[0].label.as_deref(),
+            Some("Mining rig")
+        );
+
+        keystore
+            .set_label_at(&path, "0xgood", Some(" ".to_string()))
+            .unwrap();
+        let reloaded = Keystore::load_from_path(&path).unwrap();
+        assert_eq!(reloaded.account_summaries()[0].label, None);
+        assert!(keystore.set_label_at(&path, "0xmissing", None).is_err());
+    }
+
+    #[test]
+    fn test_readding_account_keeps_label_and_created_at() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("keystore.json");
+        let account = crate::ethereum::create_new_account().unwrap();
+
+        let mut keystore = Keystore::default();
+        keystore
+            .add_account_at(
+                &path,
+                account.address.clone(),
+                &account.private_key,
+                "pw",
+                TEST_KDF,
+            )
+            .unwrap();
+        keystore
+            .set_label_at(&path, &account.address, Some("Savings".to_string()))
+            .unwrap();
+        let created_at = 1_600_000_000;
+        keystore.accounts[0].created_at = created_at;
+
+        keystore
+            .add_account_at(
+                &path,
+                account.address.clone(),
+                &account.private_key,
+                "new-pw",
+                TEST_KDF,
+            )
+            .unwrap();
+        let reloaded = Keystore::load_from_path(&path).unwrap();
+        assert_eq!(
+            reloaded.account_summaries(),
+            vec![AccountSummary {
+                address: account.address.clone(),
+                label: Some("Savings".to_string()),
+                created_at,
+                two_fa_enabled: false,
+            }]
+        );
+        assert_eq!(
+            reloaded.get_account(&account.address, "new-pw").unwrap(),
+            account.private_key
+        );
+
+        // Re-importing under the same password keeps the 2FA secret and file keys
+        keystore
+            .enable_2fa_at(&path, &account.address, "new-pw", None)
+            .unwrap();
+        let secret = keystore
+            .get_2fa_secret(&account.address, "new-pw")
+            .unwrap()
+            .unwrap();
+        let code = account_totp(&secret, &account.address)
+            .unwrap()
+            .generate_current()
+            .unwrap();
+        let file_key = keystore
+            .get_or_create_file_key_at(&path, &account.address, "file-a", "new-pw", Some(&code))
+            .unwrap();
+        keystore
+            .add_account_at(
+                &path,
+                account.address.clone(),
+                &account.private_key,
+                "new-pw",
+                TEST_KDF,
+            )
+            .unwrap();
+        let reloaded = Keystore::load_from_path(&path).unwrap();
+        assert_eq!(
+            reloaded.get_2fa_secret(&account.address, "new-pw").unwrap(),
+            Some(secret)
+        );
+        assert_eq!(
+            reloaded
+                .get_file_encryption_key(&account.address, "file-a", "new-pw")
+                .unwrap(),
+            file_key
+        );
+
+        // A different password would lose them, so it is refused
+        assert!(keystore
+            .add_account_at(
+                &path,
+                account.address.clone(),
+                &account.private_key,
+                "other-pw",
+                TEST_KDF,
+            )
+            .is_err());
+        assert_eq!(
+            keystore.get_account(&account.address, "new-pw").unwrap(),
+            account.private_key
+        );
+    }
+
+    #[test]
+    fn test_file_keys_are_derived_per_account_and_file() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("keystore.json");
+        let (alice, alice_entry) = test_account(TEST_KDF, "pw");
+        let (bob, bob_entry) = test_account(TEST_KDF, "pw");
+        let mut keystore = Keystore {
+            accounts: vec![alice_entry, bob_entry],
+        };
+
+        let key = keystore
+            .get_or_create_file_key_at(&path, &alice.address, "file-1", "pw", None)
+            .unwrap();
+        assert_eq!(key, derive_file_key(&alice.private_key, "file-1").unwrap());
+
+        // Stored wrapped under the password and returned again after a reload
+        let mut reloaded = Keystore::load_from_path(&path).unwrap();
+        assert_eq!(
+            reloaded.list_file_encryption_keys(&alice.address).unwrap(),
+            vec!["file-1".to_string()]
+        );
+        assert_eq!(
+            reloaded
+                .get_or_create_file_key_at(&path, &alice.address, "file-1", "pw", None)
+                .unwrap(),
+            key
+        );
+
+        let other_file = reloaded
+            .get_or_create_file_key_at(&path, &alice.address, "file-2", "pw", None)
+            .unwrap();
+        let other_account = reloaded
+            .get_or_create_file_key_at(&path, &bob.address, "file-1", "pw", None)
+            .unwrap();
+        assert_ne!(other_file, key);
+        assert_ne!(other_account, key);
+
+        assert!(reloaded
+            .get_or_create_file_key_at(&path, &bob.address, "file-3", "wrong", None)
+            .is_err());
+
+        // The upload path wraps with the private key; both entry points then
+        // return the same key for that file
+        let uploaded = reloaded
+            .get_or_create_file_key_with_private_key_at(
+                &path,
+                &alice.address,
+                "file-4",
+                &alice.private_key,
+            )
+            .unwrap();
+        assert_eq!(
+            uploaded,
+            derive_file_key(&alice.private_key, "file-4").unwrap()
+        );
+        assert_eq!(
+            reloaded
+                .get_or_create_file_key_at(&path, &alice.address, "file-4", "pw", None)
+                .unwrap(),
+            uploaded
+        );
+        assert!(reloaded
+            .get_or_create_file_key_with_private_key_at(
+                &path,
+                &alice.address,
+                "file-5",
+                &bob.private_key,
+            )
+            .is_err());
+    }
+
+    #[test]
+    fn test_2fa_gates_account_unlock() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("keystore.json");
+        let (account, entry) = test_account(TEST_KDF, "pw");
+        let mut keystore = Keystore {
+            accounts: vec![entry],
+        };
+
+        // No code needed until 2FA is enabled
+        assert_eq!(
+            keystore
+                .get_account_with_2fa(&account.address, "pw", None)
+                .unwrap(),
+            account.private_key
+        );
+        assert!(keystore
+            .enable_2fa_at(&path, &account.address, "wrong", None)
+            .is_err());
+        assert!(!keystore.is_2fa_enabled(&account.address).unwrap());
+
+        let uri = keystore
+            .enable_2fa_at(&path, &account.address, "pw", None)
+            .unwrap();
+        assert!(uri.starts_with("otpauth://totp/"));
+
+        let reloaded = Keystore::load_from_path(&path).unwrap();
+        assert!(reloaded.is_2fa_enabled(&account.address).unwrap());
+        let secret = reloaded
+            .get_2fa_secret(&account.address, "pw")
+            .unwrap()
+            .unwrap();
+        assert!(uri.contains(&secret));
+        let totp = account_totp(&secret, &account.address).unwrap();
+
+        let code = totp.generate_current().unwrap();
+        assert_eq!(
+            reloaded
+                .get_account_with_2fa(&account.address, "pw", Some(&code))
+                .unwrap(),
+            account.private_key
+        );
+
+        // Avoid any code valid within the skew window, allowing for a step boundary
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        let valid: Vec<String> = [now - 60, now - 30, now, now + 30, now + 60]
+            .iter()
+            .map(|t| totp.generate(*t))
+            .collect();
+        let wrong = (0..1_000_000)
+            .map(|n| format!("{:06}", n))
+            .find(|c| !valid.contains(c))
+            .unwrap();
+        assert!(reloaded
+            .get_account_with_2fa(&account.address, "pw", Some(&wrong))
+            .is_err());
+        assert!(reloaded
+            .get_account_with_2fa(&account.address, "pw", None)
+            .is_err());
+
+        // Every other path that unlocks the account needs the code too
+        let mut reloaded = reloaded;
+        assert!(reloaded
+            .export_v3(&account.address, "pw", Some(&wrong))
+            .is_err());
+        assert!(reloaded
+            .export_v3(&account.address, "pw", Some(&code))
+            .is_ok());
+        assert!(reloaded
+            .get_or_create_file_key_at(&path, &account.address, "file", "pw", None)
+            .is_err());
+        assert!(reloaded
+            .get_or_create_file_key_at(&path, &account.address, "file", "pw", Some(&code))
+            .is_ok());
+        assert!(reloaded
+            .change_password_at(&path, &account.address, "pw", "new", Some(&wrong))
+            .is_err());
+
+        // Re-enrolling and disabling need the password and a current code
+        assert!(reloaded
+            .enable_2fa_at(&path, &account.address, "pw", None)
+            .is_err());
+        assert!(reloaded
+            .set_2fa_secret_at(
+                &path,
+                &account.address,
+                "JBSWY3DPEHPK3PXP",
+                "bad",
+                Some(&code)
+            )
+            .is_err());
+        assert!(reloaded
+            .remove_2fa_secret_at(&path, &account.address, "wrong", Some(&code))
+            .is_err());
+        assert!(reloaded
+            .remove_2fa_secret_at(&path, &account.address, "pw", Some(&wrong))
+            .is_err());
+        assert!(reloaded.is_2fa_enabled(&account.address).unwrap());
+        reloaded
+            .remove_2fa_secret_at(&path, &account.address, "pw", Some(&code))
+            .unwrap();
+        let reloaded = Keystore::load_from_path(&path).unwrap();
+        assert!(!reloaded.is_2fa_enabled(&account.address).unwrap());
+    }
+}
@@ -7,6 +7,7 @@ pub mod control_plane;
 pub mod multi_source_download;
 pub mod download_restart;
 pub mod transfer_events;
+pub mod event_bus;
 
 // Connection retry and resilience framework
 pub mod connection_retry;
@@ -32,18 +33,29 @@ pub mod peer_selection;
 pub mod peer_cache;
 pub mod webrtc_service;
 
+// Resume tokens for interrupted peer-to-peer transfers
+pub mod transfer_resume;
+
+// Crate-wide error type
+pub mod errors;
+
 // Required modules for encryption and keystore functionality
 pub mod encryption;
 pub mod keystore;
 pub mod manager;
+pub mod local_cache;
 
 // Proxy latency optimization module
 pub mod proxy_latency;
 
 // Stream authentication module
 pub mod stream_auth;
+// chiral:// share links
+pub mod share_uri;
 // Reputation system
 pub mod reputation;
+// Replica counts for published files
+pub mod replication;
 // Payment checkpoint module
 pub mod payment_checkpoint;
 
@@ -0,0 +1,253 @@
+// Content-addressed cache of plaintext chunks, shared by uploads and downloads.
+//
+// Entries are keyed by the SHA-256 of their data, so files that share chunks
+// share cache entries, and a repeated download is served without touching the
+// network. Entries are evicted least-recently-used once the cache exceeds its
+// byte cap.
+
+use crate::errors::ChiralError;
+use crate::multi_source_download::normalized_sha256_hex;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::SystemTime;
+use tracing::{debug, warn};
+
+/// Default cap for the on-disk chunk cache (1 GiB)
+pub const DEFAULT_LOCAL_CACHE_BYTES: u64 = 1024 * 1024 * 1024;
+
+/// Hit/miss counters and current usage, for diagnostics
+#[derive(serde::Serialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct LocalCacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub entries: usize,
+    pub total_bytes: u64,
+    pub max_bytes: u64,
+}
+
+#[derive(Default)]
+struct CacheIndex {
+    sizes: HashMap<String, u64>,
+    // Least recently used first
+    order: Vec<String>,
+    total_bytes: u64,
+    hits: u64,
+    misses: u64,
+}
+
+impl CacheIndex {
+    fn touch(&mut self, hash: &str) {
+        self.order.retain(|k| k != hash);
+        self.order.push(hash.to_string());
+    }
+
+    fn remove(&mut self, hash: &str) {
+        if let Some(size) = self.sizes.remove(hash) {
+            self.total_bytes -= size;
+            self.order.retain(|k| k != hash);
+        }
+    }
+}
+
+pub struct LocalCache {
+    dir: PathBuf,
+    max_bytes: u64,
+    index: Mutex<CacheIndex>,
+}
+
+impl LocalCache {
+    /// Open (creating if needed) the cache rooted at `dir`, indexing any
+    /// entries left by a previous run in modification-time order.
+    pub fn open(dir: PathBuf, max_bytes: u64) -> Result<Self, ChiralError> {
+        std::fs::create_dir_all(&dir)?;
+
+        let mut existing = Vec::new();
+        for entry in std::fs::read_dir(&dir)? {
+            let entry = entry?;
+            let name = entry.file_name().to_string_lossy().to_string();
+            let Some(hash) = normalized_sha256_hex(&name) else {
+                continue;
+            };
+            let metadata = entry.metadata()?;
+            if !metadata.is_file() {
+                continue;
+            }
+            let modified = metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+            existing.push((modified, hash, metadata.len()));
+        }
+        existing.sort();
+
+        let mut index = CacheIndex::default();
+        for (_, hash, size) in existing {
+            index.total_bytes += size;
+            index.sizes.insert(hash.clone(), size);
+            index.order.push(hash);
+        }
+
+        let cache = LocalCache {
+            dir,
+            max_bytes,
+            index: Mutex::new(index),
+        };
+        cache.evict_to_cap(&mut cache.index.lock().unwrap());
+        Ok(cache)
+    }
+
+    fn entry_path(&self, hash: &str) -> PathBuf {
+        self.dir.join(hash)
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Return the chunk whose SHA-256 is `hash`, if cached. Entries that fail
+    /// verification are dropped and reported as misses.
+    pub fn get(&self, hash: &str) -> Option<Vec<u8>> {
+        let hash = normalized_sha256_hex(hash)?;
+        {
+            let mut index = self.index.lock().unwrap();
+            if !index.sizes.contains_key(&hash) {
+                index.misses += 1;
+                return None;
+            }
+        }
+
+        // Read and verify without holding the index lock; the entry may be
+        // evicted meanwhile, in which case the read fails and counts as a miss
+        let path = self.entry_path(&hash);
+        let result = std::fs::read(&path);
+        let verified = matches!(&result, Ok(data) if hex::encode(Sha256::digest(data)) == hash);
+
+        let mut index = self.index.lock().unwrap();
+        match result {
+            Ok(data) if verified => {
+                if index.sizes.contains_key(&hash) {
+                    index.touch(&hash);
+                }
+                index.hits += 1;
+                Some(data)
+            }
+            Ok(_) => {
+                warn!("Cached chunk {} failed verification; dropping it", hash);
+                let _ = std::fs::remove_file(&path);
+                index.remove(&hash);
+                index.misses += 1;
+                None
+            }
+            Err(e) => {
+                debug!("Cached chunk {} unreadable: {}", hash, e);
+                index.remove(&hash);
+                index.misses += 1;
+                None
+            }
+        }
+    }
+
+    pub fn contains(&self, hash: &str) -> bool {
+        normalized_sha256_hex(hash)
+            .map(|hash| self.index.lock().unwrap().sizes.contains_key(&hash))
+            .unwrap_or(false)
+    }
+
+    /// Cache `data` under `hash`. Keys that aren't a SHA-256 are ignored;
+    /// data that doesn't match its key is rejected.
+    pub fn put(&self, hash: &str, data: &[u8]) -> Result<(), ChiralError> {
+        let Some(hash) = normalized_sha256_hex(hash) else {
+            return Ok(());
+        };
+        if hex::encode(Sha256::digest(data)) != hash {
+            return Err(ChiralError::Storage(format!(
+                "Chunk data does not match hash {}",
+                hash
+            )));
+        }
+        if data.len() as u64 > self.max_bytes {
+            return Ok(());
+        }
+
+        let mut index = self.index.lock().unwrap();
+        if index.sizes.contains_key(&hash) {
+            index.touch(&hash);
+            return Ok(());
+        }
+
+        std::fs::write(self.entry_path(&hash), data)?;
+        index.sizes.insert(hash.clone(), data.len() as u64);
+        index.total_bytes += data.len() as u64;
+        index.order.push(hash);
+        self.evict_to_cap(&mut index);
+        Ok(())
+    }
+
+    fn evict_to_cap(&self, index: &mut CacheIndex) {
+        while index.total_bytes > self.max_bytes && !index.order.is_empty() {
+            let oldest = index.order.remove(0);
+            if let Err(e) = std::fs::remove_file(self.entry_path(&oldest)) {
+                debug!("Failed to evict cached chunk {}: {}", oldest, e);
+            }
+            if let Some(size) = index.sizes.remove(&oldest) {
+                index.total_bytes -= size;
+            }
+        }
+    }
+
+    pub fn stats(&self) -> LocalCacheStats {
+        let index = self.index.lock().unwrap();
+        LocalCacheStats {
+            hits: index.hits,
+            misses: index.misses,
+            entries: index.sizes.len(),
+            total_bytes: index.total_bytes,
+            max_bytes: self.max_bytes,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn chunk(seed: u8, len: usize) -> (String, Vec<u8>) {
+        let data = vec![seed; len];
+        (hex::encode(Sha256::digest(&data)), data)
+    }
+
+    #[test]
+    fn test_evicts_least_recently_used_over_cap() {
+        let dir = tempdir().unwrap();
+        let cache = LocalCache::open(dir.path().to_path_buf(), 2500).unwrap();
+        let (a, a_data) = chunk(1, 1000);
+        let (b, b_data) = chunk(2, 1000);
+        let (c, c_data) = chunk(3, 1000);
+
+        cache.put(&a, &a_data).unwrap();
+        cache.put(&b, &b_data).unwrap();
+        assert!(cache.get(&a).is_some());
+        cache.put(&c, &c_data).unwrap();
+
+        assert!(cache.contains(&a));
+        assert!(!cache.contains(&b));
+        assert!(cache.contains(&c));
+        assert_eq!(cache.stats().total_bytes, 2000);
+        assert!(!dir.path().join(&b).exists());
+
+        // Reopening picks up the surviving entries
+        let reopened = LocalCache::open(dir.path().to_path_buf(), 2500).unwrap();
+        assert_eq!(reopened.stats().entries, 2);
+    }
+
+    #[test]
+    fn test_rejects_data_that_does_not_match_its_hash() {
+        let dir = tempdir().unwrap();
+        let cache = LocalCache::open(dir.path().to_path_buf(), 1024).unwrap();
+        let (hash, _) = chunk(1, 10);
+        assert!(cache.put(&hash, b"something else").is_err());
+        assert!(cache.put("not-a-sha256", b"ignored").is_ok());
+        assert_eq!(cache.stats().entries, 0);
+    }
+}
@@ -3,7 +3,118 @@ use std::io::{self, Write};
 use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
 use chrono::Local;
-use tracing_subscriber::fmt::MakeWriter;
+use tracing::Subscriber;
+use tracing_subscriber::fmt::{self as tracing_fmt, MakeWriter};
+use tracing_subscriber::prelude::*;
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::EnvFilter;
+
+/// Per-target directives used when none are configured: keep libp2p quiet
+pub const DEFAULT_LOG_DIRECTIVES: &[&str] = &[
+    "libp2p=warn",
+    "libp2p_kad=warn",
+    "libp2p_swarm=warn",
+    "libp2p_mdns=warn",
+];
+
+/// How log lines are written to the console and log files
+#[derive(clap::ValueEnum, serde::Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    /// Human-readable lines
+    #[default]
+    Text,
+    /// One JSON object per line, for log ingestion
+    Json,
+}
+
+/// Filtering and format for `init_logging`
+#[derive(Clone, Debug)]
+pub struct LogOptions {
+    pub format: LogFormat,
+    /// Level for this crate's own targets (trace, debug, info, warn, error)
+    pub level: String,
+    /// Extra directives such as `libp2p_kad=debug`, added on top of `RUST_LOG`
+    pub directives: Vec<String>,
+}
+
+impl LogOptions {
+    pub fn new(format: LogFormat, level: impl Into<String>) -> Self {
+        Self {
+            format,
+            level: level.into(),
+            directives: DEFAULT_LOG_DIRECTIVES
+                .iter()
+                .map(|d| d.to_string())
+                .collect(),
+        }
+    }
+
+    /// Replace the default per-target directives; an empty list keeps them.
+    pub fn with_directives(mut self, directives: Vec<String>) -> Self {
+        if !directives.is_empty() {
+            self.directives = directives;
+        }
+        self
+    }
+
+    fn env_filter(&self) -> Result<EnvFilter, String> {
+        let crate_directive = format!("chiral_network={}", self.level);
+        let mut filter = EnvFilter::from_default_env();
+        for directive in std::iter::once(&crate_directive).chain(&self.directives) {
+            let parsed = directive
+                .parse()
+                .map_err(|e| format!("Invalid log directive '{}': {}", directive, e))?;
+            filter = filter.add_directive(parsed);
+        }
+        Ok(filter)
+    }
+}
+
+/// Install the global tracing subscriber, logging to stdout and, when given,
+/// to `file`. Fails on a bad level or directive, or if a subscriber is
+/// already installed.
+pub fn init_logging(options: &LogOptions, file: Option<ThreadSafeWriter>) -> Result<(), String> {
+    build_subscriber(options, io::stdout, file)?
+        .try_init()
+        .map_err(|e| e.to_string())
+}
+
+fn build_subscriber<W>(
+    options: &LogOptions,
+    console: W,
+    file: Option<ThreadSafeWriter>,
+) -> Result<impl Subscriber + Send + Sync + 'static, String>
+where
+    W: for<'w> MakeWriter<'w> + Send + Sync + 'static,
+{
+    let mut layers = vec![fmt_layer(options.format, console)];
+    if let Some(file) = file {
+        layers.push(fmt_layer(options.format, file));
+    }
+    Ok(tracing_subscriber::registry()
+        .with(layers)
+        .with(options.env_filter()?))
+}
+
+fn fmt_layer<S, W>(
+    format: LogFormat,
+    writer: W,
+) -> Box<dyn tracing_subscriber::Layer<S> + Send + Sync>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    W: for<'w> MakeWriter<'w> + Send + Sync + 'static,
+{
+    match format {
+        LogFormat::Text => tracing_fmt::layer().with_writer(writer).boxed(),
+        LogFormat::Json => tracing_fmt::layer()
+            .json()
+            .with_current_span(false)
+            .with_span_list(true)
+            .with_writer(writer)
+            .boxed(),
+    }
+}
 
 /// Configuration for file logging
 #[derive(Clone, Debug)]
@@ -269,3 +380,63 @@ impl Write for ThreadSafeWriterGuard {
         writer.flush()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Clone, Default)]
+    struct Captured(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Captured {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_json_format_writes_parseable_records() {
+        let captured = Captured::default();
+        let writer = captured.clone();
+        let options = LogOptions::new(LogFormat::Json, "debug");
+        let subscriber = build_subscriber(&options, move || writer.clone(), None).unwrap();
+
+        tracing::subscriber::with_default(subscriber, || {
+            let span = tracing::info_span!("upload");
+            let _entered = span.enter();
+            tracing::info!(target: "chiral_network::manager", chunks = 4, "stored file");
+            tracing::warn!(target: "chiral_network::dht", peer = "12D3KooW", "peer \"a\" dropped");
+            // Below the default libp2p directive
+            tracing::debug!(target: "libp2p_swarm", "dial attempt");
+        });
+
+        let output = String::from_utf8(captured.0.lock().unwrap().clone()).unwrap();
+        let records: Vec<serde_json::Value> = output
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(records.len(), 2);
+
+        assert_eq!(records[0]["level"], "INFO");
+        assert_eq!(records[0]["target"], "chiral_network::manager");
+        assert_eq!(records[0]["fields"]["message"], "stored file");
+        assert_eq!(records[0]["fields"]["chunks"], 4);
+        assert_eq!(records[0]["spans"][0]["name"], "upload");
+        assert!(records[0]["timestamp"].is_string());
+
+        assert_eq!(records[1]["level"], "WARN");
+        assert_eq!(records[1]["fields"]["message"], "peer \"a\" dropped");
+        assert_eq!(records[1]["fields"]["peer"], "12D3KooW");
+    }
+
+    #[test]
+    fn test_rejects_invalid_level() {
+        let options = LogOptions::new(LogFormat::Text, "loud");
+        assert!(build_subscriber(&options, io::sink, None).is_err());
+    }
+}
@@ -18,6 +18,7 @@ pub mod ethereum;
 pub mod geth_bootstrap;
 pub mod geth_downloader;
 pub mod headless;
+pub mod headless_metrics;
 pub mod http_server;
 pub mod chiral_bittorrent_extension;
 pub mod net;
@@ -47,8 +48,8 @@ use protocols::{
 };
 
 use crate::commands::auth::{
-    cleanup_expired_proxy_auth_tokens, generate_proxy_auth_token, revoke_proxy_auth_token,
-    validate_proxy_auth_token,
+    cleanup_expired_proxy_auth_tokens, generate_proxy_auth_token, refresh_proxy_auth_token,
+    revoke_proxy_auth_token, validate_proxy_auth_token, ProxyAuthToken,
 };
 
 use crate::commands::bootstrap::get_bootstrap_nodes;
@@ -59,13 +60,14 @@ use crate::commands::proxy::{
     proxy_echo, proxy_remove, ProxyNode,
 };
 use chiral_network::download_paths;
+use chiral_network::local_cache::{LocalCache, DEFAULT_LOCAL_CACHE_BYTES};
 use chiral_network::payment_checkpoint::PaymentCheckpointService;
 use bandwidth::BandwidthController;
 use chiral_network::transfer_events::{
     current_timestamp_ms, ErrorCategory, SourceInfo, SourceType, TransferCompletedEvent,
     TransferEventBus, TransferFailedEvent, TransferStartedEvent,
 };
-use dht::{models::DhtMetricsSnapshot, models::FileMetadata, DhtEvent, DhtService};
+use dht::{models::DhtMetricsSnapshot, models::FileMetadata, DhtConfig, DhtEvent, DhtService};
 use directories::ProjectDirs;
 use ethereum::{
     // Bootstrap peer management functions
@@ -89,6 +91,7 @@ use ethereum::{
     get_txpool_status,
     get_txpool_content,
     debug_network_tx,
+    get_gas_estimate,
     reconnect_to_bootstrap_if_needed,
     start_mining,
     stop_mining,
@@ -129,9 +132,13 @@ use tokio::{
     task::JoinHandle,
     time::{sleep, timeout},
 };
-use totp_rs::{Algorithm, Secret, TOTP};
+use tokio_util::sync::CancellationToken;
+use totp_rs::Secret;
 use tracing::{error, info, warn};
-use webrtc_service::{set_webrtc_service, WebRTCFileRequest, WebRTCService};
+use webrtc_service::{set_webrtc_service, WebRTCFileRequest, WebRTCService, WebRtcConfig};
+use chiral_network::proxy_latency::ProxyLatencyMonitor;
+use chiral_network::replication::{self, ReplicationHealth};
+use chiral_network::share_uri::ChiralUri;
 
 use manager::ChunkManager; // Import the ChunkManager
                            // For key encoding
@@ -209,81 +216,19 @@ fn get_unique_filepath(path: &Path) -> PathBuf {
 
 /// Detect MIME type from file extension
 fn detect_mime_type_from_filename(filename: &str) -> Option<String> {
-    let extension = filename.rsplit('.').next()?.to_lowercase();
-
-    match extension.as_str() {
-        // Images
-        "jpg" | "jpeg" => Some("image/jpeg".to_string()),
-        "png" => Some("image/png".to_string()),
-        "gif" => Some("image/gif".to_string()),
-        "bmp" => Some("image/bmp".to_string()),
-        "webp" => Some("image/webp".to_string()),
-        "svg" => Some("image/svg+xml".to_string()),
-        "ico" => Some("image/x-icon".to_string()),
-
-        // Videos
-        "mp4" => Some("video/mp4".to_string()),
-        "avi" => Some("video/x-msvideo".to_string()),
-        "mkv" => Some("video/x-matroska".to_string()),
-        "mov" => Some("video/quicktime".to_string()),
-        "wmv" => Some("video/x-ms-wmv".to_string()),
-        "flv" => Some("video/x-flv".to_string()),
-        "webm" => Some("video/webm".to_string()),
-
-        // Audio
-        "mp3" => Some("audio/mpeg".to_string()),
-        "wav" => Some("audio/wav".to_string()),
-        "flac" => Some("audio/flac".to_string()),
-        "aac" => Some("audio/aac".to_string()),
-        "ogg" => Some("audio/ogg".to_string()),
-        "wma" => Some("audio/x-ms-wma".to_string()),
-
-        // Documents
-        "pdf" => Some("application/pdf".to_string()),
-        "doc" => Some("application/msword".to_string()),
-        "docx" => Some(
-            "application/vnd.openxmlformats-officedocument.wordprocessingml.document".to_string(),
-        ),
-        "xls" => Some("application/vnd.ms-excel".to_string()),
-        "xlsx" => {
-            Some("application/vnd.openxmlformats-officedocument.spreadsheetml.sheet".to_string())
-        }
-        "ppt" => Some("application/vnd.ms-powerpoint".to_string()),
-        "pptx" => Some(
-            "application/vnd.openxmlformats-officedocument.presentationml.presentation".to_string(),
-        ),
-        "txt" => Some("text/plain".to_string()),
-        "rtf" => Some("application/rtf".to_string()),
-
-        // Archives
-        "zip" => Some("application/zip".to_string()),
-        "rar" => Some("application/x-rar-compressed".to_string()),
-        "7z" => Some("application/x-7z-compressed".to_string()),
-        "tar" => Some("application/x-tar".to_string()),
-        "gz" => Some("application/gzip".to_string()),
-
-        // Code files
-        "html" | "htm" => Some("text/html".to_string()),
-        "css" => Some("text/css".to_string()),
-        "js" => Some("application/javascript".to_string()),
-        "json" => Some("application/json".to_string()),
-        "xml" => Some("application/xml".to_string()),
-        "py" => Some("text/x-python".to_string()),
-        "rs" => Some("text/rust".to_string()),
-        "java" => Some("text/x-java-source".to_string()),
-        "cpp" | "cc" | "cxx" => Some("text/x-c++src".to_string()),
-        "c" => Some("text/x-csrc".to_string()),
-        "h" => Some("text/x-chdr".to_string()),
-        "hpp" => Some("text/x-c++hdr".to_string()),
-
-        // Other common types
-        "exe" => Some("application/x-msdownload".to_string()),
-        "dll" => Some("application/x-msdownload".to_string()),
-        "iso" => Some("application/x-iso9660-image".to_string()),
-
-        // Default fallback
-        _ => Some("application/octet-stream".to_string()),
-    }
+    chiral_network::manager::mime_type_from_extension(filename)
+}
+
+/// Chunk size of the node's ChunkManager, so manifests from every upload path
+/// share the same chunk boundaries
+async fn configured_chunk_size(state: &AppState) -> usize {
+    state
+        .chunk_manager
+        .lock()
+        .await
+        .as_ref()
+        .map(|manager| manager.chunk_size())
+        .unwrap_or(chiral_network::manager::DEFAULT_CHUNK_SIZE)
 }
 
 #[derive(Clone)]
@@ -294,14 +239,6 @@ struct QueuedTransaction {
     timestamp: u64,
 }
 
-#[derive(Clone)]
-struct ProxyAuthToken {
-    token: String,
-    proxy_address: String,
-    expires_at: u64,
-    created_at: u64,
-}
-
 #[derive(Clone, Debug)]
 pub struct StreamingUploadSession {
     pub file_name: String,
@@ -318,6 +255,8 @@ pub struct StreamingUploadSession {
     pub chunk_hashes: Vec<String>,
     /// Chunk size used for this upload
     pub chunk_size: usize,
+    /// Bytes actually received, checked against the declared `file_size`
+    pub received_bytes: u64,
 }
 
 /// Session for streaming WebRTC downloads - writes chunks directly to disk
@@ -371,6 +310,8 @@ struct AppState {
 
     // Proxy authentication tokens storage
     proxy_auth_tokens: Arc<Mutex<std::collections::HashMap<String, ProxyAuthToken>>>,
+    // Signs proxy auth tokens so a tampered token is rejected
+    proxy_auth_signing_key: ed25519_dalek::SigningKey,
 
     // HTTP server for serving chunks and keys
     http_server_state: Arc<http_server::HttpServerState>,
@@ -383,6 +324,9 @@ struct AppState {
     // New field for storing canonical AES keys for files being seeded
     canonical_aes_keys: Arc<Mutex<std::collections::HashMap<String, [u8; 32]>>>,
 
+    // Cancellation tokens for uploads that are still being chunked, by upload id
+    upload_cancellations: Arc<Mutex<std::collections::HashMap<String, CancellationToken>>>,
+
     // Proof-of-Storage watcher background handle and contract address
     // make these clonable so we can .clone() and move into spawned tasks
     proof_watcher: Arc<Mutex<Option<JoinHandle<()>>>>,
@@ -409,6 +353,9 @@ struct AppState {
     // Chunk manager for file chunking operations
     chunk_manager: Mutex<Option<Arc<ChunkManager>>>,
 
+    // Settings the DHT node was last started with
+    dht_options: Mutex<Option<DhtNodeOptions>>,
+
     // Download restart service for pause/resume functionality
     download_restart: Mutex<Option<Arc<download_restart::DownloadRestartService>>>,
 
@@ -616,12 +563,13 @@ async fn save_account_to_keystore(
 async fn load_account_from_keystore(
     address: String,
     password: String,
+    totp_code: Option<String>,
     state: State<'_, AppState>,
 ) -> Result<EthAccount, String> {
     let keystore = Keystore::load()?;
 
-    // Get decrypted private key from keystore
-    let private_key = keystore.get_account(&address, &password)?;
+    // Get decrypted private key from keystore, checking the 2FA code if enabled
+    let private_key = keystore.get_account_with_2fa(&address, &password, totp_code.as_deref())?;
 
     // Set the active account in the app state
     {
@@ -647,9 +595,15 @@ async fn load_account_from_keystore(
 }
 
 #[tauri::command]
-async fn list_keystore_accounts() -> Result<Vec<String>, String> {
+async fn list_keystore_accounts() -> Result<Vec<keystore::AccountSummary>, String> {
     let keystore = Keystore::load()?;
-    Ok(keystore.list_accounts())
+    Ok(keystore.account_summaries())
+}
+
+#[tauri::command]
+async fn set_keystore_account_label(address: String, label: Option<String>) -> Result<(), String> {
+    let mut keystore = Keystore::load()?;
+    keystore.set_label(&address, label)
 }
 
 #[tauri::command]
@@ -659,6 +613,13 @@ async fn remove_account_from_keystore(address: String) -> Result<(), String> {
     Ok(())
 }
 
+#[tauri::command]
+async fn check_keystore_integrity(
+    repair: Option<bool>,
+) -> Result<keystore::KeystoreIntegrityReport, String> {
+    Keystore::check_integrity(repair.unwrap_or(false))
+}
+
 #[tauri::command]
 async fn get_disk_space(path: String) -> Result<u64, String> {
     match available_space(Path::new(&path)) {
@@ -1000,6 +961,7 @@ async fn send_webrtc_file_request(
                 }
             },
             recipient_public_key: None, // No encryption for basic downloads
+            resume_token: None,
         };
         webrtc.send_file_request(peer_id, request).await
     } else {
@@ -1539,12 +1501,12 @@ async fn get_transaction_history_range(
     ethereum::get_transaction_history(&address, from_block, to_block).await
 }
 
-#[tauri::command]
-async fn start_dht_node(
-    app: tauri::AppHandle,
-    state: State<'_, AppState>,
+/// Settings `start_dht_node` was called with, kept so the node can be
+/// rebuilt with the same proxy, relay and Kademlia settings later on
+#[derive(Debug, Clone, Default)]
+struct DhtNodeOptions {
     port: u16,
-    mut bootstrap_nodes: Vec<String>,
+    bootstrap_nodes: Vec<String>,
     enable_autonat: Option<bool>,
     autonat_probe_interval_secs: Option<u64>,
     autonat_servers: Option<Vec<String>>,
@@ -1552,20 +1514,46 @@ async fn start_dht_node(
     is_bootstrap: Option<bool>,
     chunk_size_kb: Option<usize>,
     cache_size_mb: Option<usize>,
-    // New optional relay controls
     enable_autorelay: Option<bool>,
     preferred_relays: Option<Vec<String>>,
-    enable_relay_server: Option<bool>,
     enable_upnp: Option<bool>,
     pure_client_mode: Option<bool>,
     force_server_mode: Option<bool>,
-) -> Result<String, String> {
-    {
-        let dht_guard = state.dht.lock().await;
-        if dht_guard.is_some() {
-            return Err("DHT node is already running".to_string());
-        }
-    }
+    kad_replication_factor: Option<usize>,
+    kad_query_timeout_secs: Option<u64>,
+    kad_max_packet_size: Option<usize>,
+    kad_protocol_name: Option<String>,
+    region: Option<String>,
+}
+
+/// Build the `DhtConfig` for `options`, applying the environment and CLI
+/// overrides and the AutoRelay history kept across restarts
+async fn dht_config_from_options<'a>(
+    state: &AppState,
+    options: DhtNodeOptions,
+    blockstore_db_path: &'a async_std::path::Path,
+) -> DhtConfig<'a> {
+    let DhtNodeOptions {
+        port,
+        mut bootstrap_nodes,
+        enable_autonat,
+        autonat_probe_interval_secs,
+        autonat_servers,
+        proxy_address,
+        is_bootstrap,
+        chunk_size_kb,
+        cache_size_mb,
+        enable_autorelay,
+        preferred_relays,
+        enable_upnp,
+        pure_client_mode,
+        force_server_mode,
+        kad_replication_factor,
+        kad_query_timeout_secs,
+        kad_max_packet_size,
+        kad_protocol_name,
+        region,
+    } = options;
 
     // AutoNAT disabled by default - users can enable in settings if needed for NAT detection
     // But if CHIRAL_ENABLE_AUTONAT env var is set, enable it automatically (useful for VM/headless mode)
@@ -1600,26 +1588,6 @@ async fn start_dht_node(
     // Prioritize the command-line argument. Fall back to the one from the UI.
     let final_proxy_address = cli_proxy.or(proxy_address.clone());
 
-    // Get the file transfer service for DHT integration
-    let file_transfer_service = {
-        let ft_guard = state.file_transfer.lock().await;
-        ft_guard.as_ref().cloned()
-    };
-
-    // Get the WebRTC service for DHT integration
-    let webrtc_service = {
-        let webrtc_guard = state.webrtc.lock().await;
-        webrtc_guard.as_ref().cloned()
-    };
-
-    // Create a ChunkManager instance
-    let app_data_dir = app
-        .path()
-        .app_data_dir()
-        .map_err(|e| format!("Could not get app data directory: {}", e))?;
-    let chunk_storage_path = app_data_dir.join("chunk_storage");
-    let chunk_manager = Arc::new(ChunkManager::new(chunk_storage_path));
-
     // --- AutoRelay is now disabled by default (can be enabled via config or env var)
     // Disable AutoRelay on bootstrap nodes (and via env var)
     let mut final_enable_autorelay = enable_autorelay.unwrap_or(false);
@@ -1648,11 +1616,6 @@ async fn start_dht_node(
         }
     }
 
-    let proj_dirs = ProjectDirs::from("com", "chiral-network", "chiral-network")
-        .ok_or("Failed to get project directories")?;
-    let blockstore_db_path = proj_dirs.data_dir().join("blockstore_db");
-    let async_blockstore_path = async_std::path::Path::new(blockstore_db_path.as_os_str());
-
     let previous_autorelay_enabled = {
         let guard = state.autorelay_last_enabled.lock().await;
         guard.clone()
@@ -1662,32 +1625,147 @@ async fn start_dht_node(
         guard.clone()
     };
 
-    // Clone bootstrap nodes for health monitor before moving to DhtService::new
-    let bootstrap_nodes_for_monitor = bootstrap_nodes.clone();
+    let defaults = DhtConfig::default();
+    DhtConfig {
+        port,
+        bootstrap_nodes,
+        secret: None,
+        is_bootstrap: is_bootstrap.unwrap_or(false),
+        enable_autonat: auto_enabled,
+        autonat_probe_interval: probe_interval,
+        autonat_servers: autonat_server_list,
+        proxy_address: final_proxy_address,
+        chunk_size_kb,
+        cache_size_mb,
+        enable_autorelay: final_enable_autorelay, // AutoRelay disabled by default
+        preferred_relays: preferred_relays.unwrap_or_default(),
+        enable_relay_server: is_bootstrap.unwrap_or(false), // only on bootstrap
+        enable_upnp: enable_upnp.unwrap_or(true),           // enable UPnP by default
+        blockstore_db_path: Some(blockstore_db_path),
+        last_autorelay_enabled_at: previous_autorelay_enabled,
+        last_autorelay_disabled_at: previous_autorelay_disabled,
+        pure_client_mode: pure_client_mode.unwrap_or(false), // disabled by default
+        force_server_mode: force_server_mode.unwrap_or(false), // disabled by default
+        replication_factor: kad_replication_factor.unwrap_or(defaults.replication_factor),
+        query_timeout: kad_query_timeout_secs
+            .map(Duration::from_secs)
+            .unwrap_or(defaults.query_timeout),
+        max_packet_size: kad_max_packet_size.unwrap_or(defaults.max_packet_size),
+        protocol_name: kad_protocol_name.unwrap_or_else(|| defaults.protocol_name.clone()),
+        // Rejoin through the peers known at the last shutdown
+        peer_cache_path: chiral_network::peer_cache::get_peer_cache_path().ok(),
+        region,
+        ..defaults
+    }
+}
 
-    let dht_service = DhtService::new(
+#[tauri::command]
+async fn start_dht_node(
+    app: tauri::AppHandle,
+    state: State<'_, AppState>,
+    port: u16,
+    bootstrap_nodes: Vec<String>,
+    enable_autonat: Option<bool>,
+    autonat_probe_interval_secs: Option<u64>,
+    autonat_servers: Option<Vec<String>>,
+    proxy_address: Option<String>,
+    is_bootstrap: Option<bool>,
+    chunk_size_kb: Option<usize>,
+    cache_size_mb: Option<usize>,
+    // New optional relay controls
+    enable_autorelay: Option<bool>,
+    preferred_relays: Option<Vec<String>>,
+    enable_relay_server: Option<bool>,
+    enable_upnp: Option<bool>,
+    pure_client_mode: Option<bool>,
+    force_server_mode: Option<bool>,
+    // Optional Kademlia tuning; unset values keep the defaults
+    kad_replication_factor: Option<usize>,
+    kad_query_timeout_secs: Option<u64>,
+    kad_max_packet_size: Option<usize>,
+    kad_protocol_name: Option<String>,
+    // Region reported to peers for region-preferring selection
+    region: Option<String>,
+) -> Result<String, String> {
+    {
+        let dht_guard = state.dht.lock().await;
+        if dht_guard.is_some() {
+            return Err("DHT node is already running".to_string());
+        }
+    }
+    // Settings saved before chunk sizes had to be powers of two may hold e.g. 100 KB
+    let chunk_size_kb = chunk_size_kb.map(|kb| {
+        let nearest = chiral_network::manager::nearest_chunk_size(kb.saturating_mul(1024)) / 1024;
+        if nearest != kb {
+            warn!(
+                "Chunk size {} KB is not supported; using {} KB",
+                kb, nearest
+            );
+        }
+        nearest
+    });
+    let options = DhtNodeOptions {
         port,
         bootstrap_nodes,
-        None,
-        is_bootstrap.unwrap_or(false),
-        auto_enabled,
-        probe_interval,
-        autonat_server_list,
-        final_proxy_address,
+        enable_autonat,
+        autonat_probe_interval_secs,
+        autonat_servers,
+        proxy_address,
+        is_bootstrap,
+        chunk_size_kb,
+        cache_size_mb,
+        enable_autorelay,
+        preferred_relays,
+        enable_upnp,
+        pure_client_mode,
+        force_server_mode,
+        kad_replication_factor,
+        kad_query_timeout_secs,
+        kad_max_packet_size,
+        kad_protocol_name,
+        region,
+    };
+
+    // Get the file transfer service for DHT integration
+    let file_transfer_service = {
+        let ft_guard = state.file_transfer.lock().await;
+        ft_guard.as_ref().cloned()
+    };
+
+    // Get the WebRTC service for DHT integration
+    let webrtc_service = {
+        let webrtc_guard = state.webrtc.lock().await;
+        webrtc_guard.as_ref().cloned()
+    };
+
+    // Create a ChunkManager instance
+    let app_data_dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Could not get app data directory: {}", e))?;
+    let chunk_storage_path = app_data_dir.join("chunk_storage");
+    let chunk_size = chunk_size_kb.map_or(chiral_network::manager::DEFAULT_CHUNK_SIZE, |kb| kb * 1024);
+    let local_cache = LocalCache::open(app_data_dir.join("chunk_cache"), DEFAULT_LOCAL_CACHE_BYTES)
+        .map_err(|e| format!("Failed to open chunk cache: {}", e))?;
+    let chunk_manager = Arc::new(
+        ChunkManager::new(chunk_storage_path)
+            .with_chunk_size(chunk_size)?
+            .with_local_cache(Arc::new(local_cache)),
+    );
+
+    let proj_dirs = ProjectDirs::from("com", "chiral-network", "chiral-network")
+        .ok_or("Failed to get project directories")?;
+    let blockstore_db_path = proj_dirs.data_dir().join("blockstore_db");
+    let async_blockstore_path = async_std::path::Path::new(blockstore_db_path.as_os_str());
+
+    let dht_config = dht_config_from_options(&state, options.clone(), async_blockstore_path).await;
+    // Clone bootstrap nodes for health monitor before moving to DhtService::new
+    let bootstrap_nodes_for_monitor = dht_config.bootstrap_nodes.clone();
+    let dht_service = DhtService::new_with_config(
+        dht_config,
         file_transfer_service,
         webrtc_service,
         Some(chunk_manager.clone()), // Pass the chunk manager
-        chunk_size_kb,
-        cache_size_mb,
-        /* enable AutoRelay (disabled by default) */ final_enable_autorelay,
-        preferred_relays.unwrap_or_default(),
-        is_bootstrap.unwrap_or(false), // enable_relay_server only on bootstrap
-        enable_upnp.unwrap_or(true),   // enable UPnP by default
-        Some(&async_blockstore_path),
-        previous_autorelay_enabled,
-        previous_autorelay_disabled,
-        pure_client_mode.unwrap_or(false), // Pure client mode disabled by default
-        force_server_mode.unwrap_or(false), // Force server mode disabled by default
     )
     .await
     .map_err(|e| format!("Failed to start DHT: {}", e))?;
@@ -1707,266 +1785,408 @@ async fn start_dht_node(
     // DHT node is already running in a spawned background task
     let dht_arc = Arc::new(dht_service);
 
-    // Spawn the event pump
+    spawn_dht_event_pump(&app, &state, dht_arc.clone());
+
+    {
+        let mut dht_guard = state.dht.lock().await;
+        *dht_guard = Some(dht_arc.clone());
+    }
+
+    // Store chunk manager in AppState
+    {
+        let mut chunk_guard = state.chunk_manager.lock().await;
+        *chunk_guard = Some(chunk_manager.clone());
+    }
+    {
+        let mut options_guard = state.dht_options.lock().await;
+        *options_guard = Some(options);
+    }
+
+    // Also attach DHT to HTTP server state for provider-side metrics
+    state.http_server_state.set_dht(dht_arc.clone()).await;
+
+    spawn_dht_health_monitor(&app, dht_arc.clone(), bootstrap_nodes_for_monitor);
+
+    Ok(peer_id)
+}
+
+/// Chunk size of the running node's ChunkManager, which `start_dht_node` sets
+/// from Settings, or the default before the node starts
+async fn configured_chunk_size(state: &AppState) -> usize {
+    state
+        .chunk_manager
+        .lock()
+        .await
+        .as_ref()
+        .map_or(chiral_network::manager::DEFAULT_CHUNK_SIZE, |m| {
+            m.chunk_size()
+        })
+}
+
+/// Forward a DHT node's events to the frontend until the node shuts down.
+fn spawn_dht_event_pump(app: &tauri::AppHandle, state: &AppState, dht: Arc<DhtService>) {
     let app_handle = app.clone();
     let proxies_arc = state.proxies.clone();
     let relay_reputation_arc = state.relay_reputation.clone();
-    let dht_clone_for_pump = dht_arc.clone();
+    let dht_clone_for_pump = dht;
     let analytics_arc = state.analytics.clone();
 
     tokio::spawn(async move {
+        use chiral_network::event_bus::EventBusError;
         use std::time::Duration;
+        use tokio::sync::broadcast::error::RecvError;
+        // Sequence number of the last event handled (starting from the last one
+        // published before we subscribed), and events replayed from the bus
+        // history after falling behind
+        let (mut last_seq, mut dht_events) = dht_clone_for_pump.subscribe_events_with_seq();
+        let mut missed = VecDeque::new();
         loop {
-            // If the DHT service has been shut down, the weak reference will be None
-            let events = dht_clone_for_pump.drain_events(64).await;
-            if events.is_empty() {
-                // Avoid busy-waiting
-                tokio::time::sleep(Duration::from_millis(200)).await;
-                // Check if the DHT is still alive before continuing
-                if Arc::strong_count(&dht_clone_for_pump) <= 1 {
-                    // 1 is the pump itself
-                    info!("DHT service appears to be shut down. Exiting event pump.");
-                    break;
-                }
-                continue;
-            }
-
-            for ev in events {
-                match ev {
-                    DhtEvent::PeerDiscovered { peer_id, addresses } => {
-                        let payload = serde_json::json!({
-                            "peerId": peer_id,
-                            "addresses": addresses,
-                        });
-                        let _ = app_handle.emit("dht_peer_discovered", payload);
+            // Events are pushed as they happen; the timeout only bounds how long
+            // we go without checking whether the DHT service is still alive.
+            let received = match missed.pop_front() {
+                Some(sequenced) => Ok(Ok(sequenced)),
+                None => timeout(Duration::from_secs(1), dht_events.recv()).await,
+            };
+            let ev = match received {
+                Ok(Ok(sequenced)) => {
+                    // Replayed events can overlap the ones still queued live
+                    if sequenced.seq <= last_seq {
+                        continue;
                     }
-                    DhtEvent::PeerConnected { peer_id, address } => {
-                        let payload = serde_json::json!({
-                            "peerId": peer_id,
-                            "address": address,
-                        });
-                        let _ = app_handle.emit("dht_peer_connected", payload);
+                    last_seq = sequenced.seq;
+                    sequenced.event
+                }
+                Ok(Err(RecvError::Lagged(skipped))) => {
+                    let max = skipped as usize;
+                    let replay = match dht_clone_for_pump.events_since(last_seq + 1, max) {
+                        Err(EventBusError::Evicted { oldest, .. }) => {
+                            warn!(
+                                "DHT event pump lost {} events that are no longer retained",
+                                oldest - (last_seq + 1)
+                            );
+                            dht_clone_for_pump.events_since(oldest, max)
+                        }
+                        replay => replay,
+                    };
+                    match replay {
+                        Ok(events) => {
+                            warn!(
+                                "DHT event pump fell behind; replaying {} events",
+                                events.len()
+                            );
+                            missed.extend(events);
+                        }
+                        Err(e) => warn!("DHT event pump skipped {} events: {}", skipped, e),
                     }
-                    DhtEvent::PeerDisconnected { peer_id } => {
-                        let payload = serde_json::json!({ "peerId": peer_id });
-                        let _ = app_handle.emit("dht_peer_disconnected", payload);
+                    continue;
+                }
+                Ok(Err(RecvError::Closed)) => {
+                    info!("DHT event stream closed. Exiting event pump.");
+                    break;
+                }
+                Err(_) => {
+                    if Arc::strong_count(&dht_clone_for_pump) <= 1 {
+                        // 1 is the pump itself
+                        info!("DHT service appears to be shut down. Exiting event pump.");
+                        break;
                     }
-                    DhtEvent::ProxyStatus {
-                        id,
-                        address,
-                        status,
-                        latency_ms,
-                        error,
-                    } => {
-                        let to_emit: ProxyNode = {
-                            let mut proxies = proxies_arc.lock().await;
+                    continue;
+                }
+            };
 
-                            if let Some(i) = proxies.iter().position(|p| p.id == id) {
-                                let p = &mut proxies[i];
-                                if p.id != id {
-                                    p.id = id.clone();
-                                }
-                                if !address.is_empty() {
-                                    p.address = address.clone();
-                                }
-                                p.status = status.clone();
-                                if let Some(ms) = latency_ms {
-                                    p.latency = ms as u32;
-                                }
-                                p.error = error.clone();
-                                p.clone()
-                            } else {
-                                let node = ProxyNode {
-                                    id: id.clone(),
-                                    address: address.clone(),
-                                    status,
-                                    latency: latency_ms.unwrap_or(0) as u32,
-                                    error,
-                                };
-                                proxies.push(node.clone());
-                                node
-                            }
-                        };
+            // Raw event stream for the frontend, replacing get_dht_events polling.
+            // "dht_event" keeps carrying `error:<msg>` strings for older listeners.
+            if let Err(e) = app_handle.emit("dht_raw_event", &ev) {
+                warn!("Failed to emit dht_raw_event: {}", e);
+            }
 
-                        let _ = app_handle.emit("proxy_status_update", to_emit);
-                    }
-                    DhtEvent::NatStatus {
-                        state,
-                        confidence,
-                        last_error,
-                        summary,
-                    } => {
-                        let payload = serde_json::json!({
-                            "state": state,
-                            "confidence": confidence,
-                            "lastError": last_error,
-                            "summary": summary,
-                        });
-                        let _ = app_handle.emit("nat_status_update", payload);
-                    }
-                    DhtEvent::EchoReceived { from, utf8, bytes } => {
-                        // Sending inbox event to frontend
-                        let payload =
-                            serde_json::json!({ "from": from, "text": utf8, "bytes": bytes });
-                        let _ = app_handle.emit("proxy_echo_rx", payload);
-                    }
-                    DhtEvent::PeerRtt { peer, rtt_ms } => {
-                        // NOTE: if from dht.rs only sends rtt for known proxies, then this is fine.
-                        // If it can send rtt for any peer, we need to first check if it's generated from ProxyStatus
+            match ev {
+                DhtEvent::PeerDiscovered { peer_id, addresses } => {
+                    let payload = serde_json::json!({
+                        "peerId": peer_id,
+                        "addresses": addresses,
+                    });
+                    let _ = app_handle.emit("dht_peer_discovered", payload);
+                }
+                DhtEvent::PeerConnected { peer_id, address } => {
+                    let payload = serde_json::json!({
+                        "peerId": peer_id,
+                        "address": address,
+                    });
+                    let _ = app_handle.emit("dht_peer_connected", payload);
+                }
+                DhtEvent::PeerDisconnected { peer_id } => {
+                    let payload = serde_json::json!({ "peerId": peer_id });
+                    let _ = app_handle.emit("dht_peer_disconnected", payload);
+                }
+                DhtEvent::ProxyStatus {
+                    id,
+                    address,
+                    status,
+                    latency_ms,
+                    error,
+                } => {
+                    let to_emit: ProxyNode = {
                         let mut proxies = proxies_arc.lock().await;
-                        if let Some(p) = proxies.iter_mut().find(|p| p.id == peer) {
-                            p.latency = rtt_ms as u32;
-                            let _ = app_handle.emit("proxy_status_update", p.clone());
-                        }
-                    }
-                    DhtEvent::DownloadedFile(metadata) => {
-                        info!("Emitting file_content event for completed download: {} ({})", metadata.file_name, metadata.merkle_root);
-                        let payload = serde_json::json!(metadata);
-                        let _ = app_handle.emit("file_content", payload);
-
-                        let file_size = metadata.file_size;
-
-                        // Immediately re-publish the downloaded file so this node becomes a seeder.
-                        let promote_metadata = metadata.clone();
-                        let dht_for_promotion = dht_clone_for_pump.clone();
-                        tokio::spawn(async move {
-                            if let Err(err) = dht_for_promotion
-                                .promote_downloaded_file(promote_metadata)
-                                .await
-                            {
-                                warn!("Failed to promote downloaded file to seeder: {}", err);
+
+                        if let Some(i) = proxies.iter().position(|p| p.id == id) {
+                            let p = &mut proxies[i];
+                            if p.id != id {
+                                p.id = id.clone();
                             }
-                        });
+                            if !address.is_empty() {
+                                p.address = address.clone();
+                            }
+                            p.status = status.clone();
+                            if let Some(ms) = latency_ms {
+                                p.latency = ms as u32;
+                            }
+                            p.error = error.clone();
+                            p.clone()
+                        } else {
+                            let node = ProxyNode {
+                                id: id.clone(),
+                                address: address.clone(),
+                                status,
+                                latency: latency_ms.unwrap_or(0) as u32,
+                                error,
+                            };
+                            proxies.push(node.clone());
+                            node
+                        }
+                    };
 
-                        // Update analytics: record download completion and bandwidth
-                        analytics_arc.record_download_completed().await;
-                        analytics_arc.record_download(file_size).await;
-                        analytics_arc.decrement_active_downloads().await;
-                    }
-                    DhtEvent::PublishedFile(metadata) => {
-                        println!("🔍 DEBUG MAIN: PublishedFile event received");
-                        println!("🔍 DEBUG MAIN: metadata.seeders = {:?}", metadata.seeders);
-                        let payload = serde_json::json!(metadata);
-                        println!("🔍 DEBUG MAIN: Emitting published_file event to frontend");
-                        let _ = app_handle.emit("published_file", payload);
-                        // Update analytics: record upload completion
-                        analytics_arc.record_upload_completed().await;
-                        analytics_arc.decrement_active_uploads().await;
-                    }
-                    DhtEvent::FileDiscovered(metadata) => {
-                        info!("📡 Emitting found_file event to frontend for: {}", metadata.file_name);
-                        let payload = serde_json::json!(metadata);
-                        let _ = app_handle.emit("found_file", payload);
+                    let _ = app_handle.emit("proxy_status_update", to_emit);
+                }
+                DhtEvent::NatStatus {
+                    state,
+                    confidence,
+                    last_error,
+                    summary,
+                } => {
+                    let payload = serde_json::json!({
+                        "state": state,
+                        "confidence": confidence,
+                        "lastError": last_error,
+                        "summary": summary,
+                    });
+                    let _ = app_handle.emit("nat_status_update", payload);
+                }
+                DhtEvent::PeerBlocked {
+                    peer_id,
+                    address,
+                    reason,
+                } => {
+                    let payload = serde_json::json!({
+                        "peerId": peer_id,
+                        "address": address,
+                        "reason": reason,
+                    });
+                    let _ = app_handle.emit("dht_peer_blocked", payload);
+                }
+                DhtEvent::BootstrapCompleted { num_remaining, ok } => {
+                    let payload = serde_json::json!({
+                        "numRemaining": num_remaining,
+                        "ok": ok,
+                    });
+                    let _ = app_handle.emit("dht_bootstrap_completed", payload);
+                }
+                DhtEvent::NatStatusChanged { previous, current } => {
+                    let payload = serde_json::json!({
+                        "previous": previous,
+                        "current": current,
+                    });
+                    let _ = app_handle.emit("nat_status_changed", payload);
+                }
+                DhtEvent::EchoReceived { from, utf8, bytes } => {
+                    // Sending inbox event to frontend
+                    let payload =
+                        serde_json::json!({ "from": from, "text": utf8, "bytes": bytes });
+                    let _ = app_handle.emit("proxy_echo_rx", payload);
+                }
+                DhtEvent::PeerRtt { peer, rtt_ms } => {
+                    // NOTE: if from dht.rs only sends rtt for known proxies, then this is fine.
+                    // If it can send rtt for any peer, we need to first check if it's generated from ProxyStatus
+                    let mut proxies = proxies_arc.lock().await;
+                    if let Some(p) = proxies.iter_mut().find(|p| p.id == peer) {
+                        p.latency = rtt_ms as u32;
+                        let _ = app_handle.emit("proxy_status_update", p.clone());
                     }
-                    DhtEvent::ReputationEvent {
-                        peer_id,
-                        event_type,
-                        impact,
-                        data,
-                    } => {
-                        // Update relay reputation statistics
-                        let mut stats = relay_reputation_arc.lock().await;
-                        let entry = stats.entry(peer_id.clone()).or_insert(RelayNodeStats {
-                            peer_id: peer_id.clone(),
-                            alias: None,
-                            reputation_score: 0.0,
-                            reservations_accepted: 0,
-                            circuits_established: 0,
-                            circuits_successful: 0,
-                            total_events: 0,
-                            last_seen: 0,
-                        });
-
-                        // Update statistics based on event type
-                        entry.reputation_score += impact;
-                        entry.total_events += 1;
-                        entry.last_seen = data
-                            .get("timestamp")
-                            .and_then(|v| v.as_u64())
-                            .unwrap_or_else(|| {
-                                std::time::SystemTime::now()
-                                    .duration_since(std::time::UNIX_EPOCH)
-                                    .unwrap_or(std::time::Duration::from_secs(0))
-                                    .as_secs()
-                            });
-
-                        match event_type.as_str() {
-                            "RelayReservationAccepted" => entry.reservations_accepted += 1,
-                            "RelayCircuitEstablished" => entry.circuits_established += 1,
-                            "RelayCircuitSuccessful" => entry.circuits_successful += 1,
-                            _ => {}
+                }
+                DhtEvent::DownloadedFile(metadata) => {
+                    info!("Emitting file_content event for completed download: {} ({})", metadata.file_name, metadata.merkle_root);
+                    let payload = serde_json::json!(metadata);
+                    let _ = app_handle.emit("file_content", payload);
+
+                    let file_size = metadata.file_size;
+
+                    // Immediately re-publish the downloaded file so this node becomes a seeder.
+                    let promote_metadata = metadata.clone();
+                    let dht_for_promotion = dht_clone_for_pump.clone();
+                    tokio::spawn(async move {
+                        if let Err(err) = dht_for_promotion
+                            .promote_downloaded_file(promote_metadata)
+                            .await
+                        {
+                            warn!("Failed to promote downloaded file to seeder: {}", err);
                         }
+                    });
 
-                        // Emit event to frontend
-                        let payload = serde_json::json!({
-                            "peerId": peer_id,
-                            "eventType": event_type,
-                            "impact": impact,
-                            "data": data,
+                    // Update analytics: record download completion and bandwidth
+                    analytics_arc.record_download_completed().await;
+                    analytics_arc.record_download(file_size).await;
+                    analytics_arc.decrement_active_downloads().await;
+                }
+                DhtEvent::PublishedFile(metadata) => {
+                    println!("🔍 DEBUG MAIN: PublishedFile event received");
+                    println!("🔍 DEBUG MAIN: metadata.seeders = {:?}", metadata.seeders);
+                    let payload = serde_json::json!(metadata);
+                    println!("🔍 DEBUG MAIN: Emitting published_file event to frontend");
+                    let _ = app_handle.emit("published_file", payload);
+                    // Update analytics: record upload completion
+                    analytics_arc.record_upload_completed().await;
+                    analytics_arc.decrement_active_uploads().await;
+                }
+                DhtEvent::FileDiscovered(metadata) => {
+                    info!("📡 Emitting found_file event to frontend for: {}", metadata.file_name);
+                    let payload = serde_json::json!(metadata);
+                    let _ = app_handle.emit("found_file", payload);
+                }
+                DhtEvent::ReputationEvent {
+                    peer_id,
+                    event_type,
+                    impact,
+                    data,
+                } => {
+                    // Update relay reputation statistics
+                    let mut stats = relay_reputation_arc.lock().await;
+                    let entry = stats.entry(peer_id.clone()).or_insert(RelayNodeStats {
+                        peer_id: peer_id.clone(),
+                        alias: None,
+                        reputation_score: 0.0,
+                        reservations_accepted: 0,
+                        circuits_established: 0,
+                        circuits_successful: 0,
+                        total_events: 0,
+                        last_seen: 0,
+                    });
+
+                    // Update statistics based on event type
+                    entry.reputation_score += impact;
+                    entry.total_events += 1;
+                    entry.last_seen = data
+                        .get("timestamp")
+                        .and_then(|v| v.as_u64())
+                        .unwrap_or_else(|| {
+                            std::time::SystemTime::now()
+                                .duration_since(std::time::UNIX_EPOCH)
+                                .unwrap_or(std::time::Duration::from_secs(0))
+                                .as_secs()
                         });
-                        let _ = app_handle.emit("relay_reputation_event", payload);
+
+                    match event_type.as_str() {
+                        "RelayReservationAccepted" => entry.reservations_accepted += 1,
+                        "RelayCircuitEstablished" => entry.circuits_established += 1,
+                        "RelayCircuitSuccessful" => entry.circuits_successful += 1,
+                        _ => {}
                     }
-                    DhtEvent::BitswapChunkDownloaded {
-                        file_hash,
-                        chunk_index,
-                        total_chunks,
-                        chunk_size,
-                    } => {
-                        let payload = serde_json::json!({
-                            "fileHash": file_hash,
-                            "chunkIndex": chunk_index,
-                            "totalChunks": total_chunks,
-                            "chunkSize": chunk_size,
+
+                    // Emit event to frontend
+                    let payload = serde_json::json!({
+                        "peerId": peer_id,
+                        "eventType": event_type,
+                        "impact": impact,
+                        "data": data,
+                    });
+                    let _ = app_handle.emit("relay_reputation_event", payload);
+                }
+                DhtEvent::BitswapChunkDownloaded {
+                    file_hash,
+                    chunk_index,
+                    total_chunks,
+                    chunk_size,
+                } => {
+                    let payload = serde_json::json!({
+                        "fileHash": file_hash,
+                        "chunkIndex": chunk_index,
+                        "totalChunks": total_chunks,
+                        "chunkSize": chunk_size,
+                    });
+                    let _ = app_handle.emit("bitswap_chunk_downloaded", payload);
+                }
+                DhtEvent::PaymentNotificationReceived { from_peer, payload } => {
+                    println!(
+                        "💰 Payment notification received from peer {}: {:?}",
+                        from_peer, payload
+                    );
+                    // Convert payload to match the expected format for seeder_payment_received
+                    if let Ok(notification) =
+                        serde_json::from_value::<serde_json::Value>(payload.clone())
+                    {
+                        let formatted_payload = serde_json::json!({
+                            "file_hash": notification.get("file_hash").and_then(|v| v.as_str()).unwrap_or(""),
+                            "file_name": notification.get("file_name").and_then(|v| v.as_str()).unwrap_or(""),
+                            "file_size": notification.get("file_size").and_then(|v| v.as_u64()).unwrap_or(0),
+                            "downloader_address": notification.get("downloader_address").and_then(|v| v.as_str()).unwrap_or(""),
+                            "downloader_peer_id": notification.get("downloader_peer_id").and_then(|v| v.as_str()).unwrap_or(""),
+                            "seeder_wallet_address": notification.get("seeder_wallet_address").and_then(|v| v.as_str()).unwrap_or(""),
+                            "amount": notification.get("amount").and_then(|v| v.as_f64()).unwrap_or(0.0),
+                            "transaction_id": notification.get("transaction_id").and_then(|v| v.as_u64()).unwrap_or(0),
+                            "transaction_hash": notification.get("transaction_hash").and_then(|v| v.as_str()).unwrap_or(""),
                         });
-                        let _ = app_handle.emit("bitswap_chunk_downloaded", payload);
+                        // Emit the same event that local payments use
+                        let _ = app_handle.emit("seeder_payment_received", formatted_payload);
+                        println!("✅ Payment notification forwarded to frontend with transaction_hash and downloader_peer_id");
                     }
-                    DhtEvent::PaymentNotificationReceived { from_peer, payload } => {
-                        println!(
-                            "💰 Payment notification received from peer {}: {:?}",
-                            from_peer, payload
-                        );
-                        // Convert payload to match the expected format for seeder_payment_received
-                        if let Ok(notification) =
-                            serde_json::from_value::<serde_json::Value>(payload.clone())
-                        {
-                            let formatted_payload = serde_json::json!({
-                                "file_hash": notification.get("file_hash").and_then(|v| v.as_str()).unwrap_or(""),
-                                "file_name": notification.get("file_name").and_then(|v| v.as_str()).unwrap_or(""),
-                                "file_size": notification.get("file_size").and_then(|v| v.as_u64()).unwrap_or(0),
-                                "downloader_address": notification.get("downloader_address").and_then(|v| v.as_str()).unwrap_or(""),
-                                "downloader_peer_id": notification.get("downloader_peer_id").and_then(|v| v.as_str()).unwrap_or(""),
-                                "seeder_wallet_address": notification.get("seeder_wallet_address").and_then(|v| v.as_str()).unwrap_or(""),
-                                "amount": notification.get("amount").and_then(|v| v.as_f64()).unwrap_or(0.0),
-                                "transaction_id": notification.get("transaction_id").and_then(|v| v.as_u64()).unwrap_or(0),
-                                "transaction_hash": notification.get("transaction_hash").and_then(|v| v.as_str()).unwrap_or(""),
-                            });
-                            // Emit the same event that local payments use
-                            let _ = app_handle.emit("seeder_payment_received", formatted_payload);
-                            println!("✅ Payment notification forwarded to frontend with transaction_hash and downloader_peer_id");
-                        }
-                    }
-                    _ => {}
                 }
+                DhtEvent::IdentityRotated { old, new } => {
+                    let payload = serde_json::json!({ "oldPeerId": old, "newPeerId": new });
+                    let _ = app_handle.emit("dht_identity_rotated", payload);
+                }
+                DhtEvent::ProvidersFound {
+                    file_hash,
+                    providers,
+                } => {
+                    let payload =
+                        serde_json::json!({ "fileHash": file_hash, "providers": providers });
+                    let _ = app_handle.emit("dht_providers_found", payload);
+                }
+                DhtEvent::PeerUnresponsive { peer_id, failures } => {
+                    let payload = serde_json::json!({ "peerId": peer_id, "failures": failures });
+                    let _ = app_handle.emit("dht_peer_unresponsive", payload);
+                }
+                DhtEvent::SwarmReset {
+                    idle_secs,
+                    peer_count,
+                } => {
+                    let payload =
+                        serde_json::json!({ "idleSecs": idle_secs, "peerCount": peer_count });
+                    let _ = app_handle.emit("dht_swarm_reset", payload);
+                }
+                DhtEvent::QueryTimedOut { file_hash, reason } => {
+                    let payload = serde_json::json!({ "fileHash": file_hash, "reason": reason });
+                    let _ = app_handle.emit("dht_query_timed_out", payload);
+                }
+                DhtEvent::RelayReservation { relay, ok } => {
+                    let payload = serde_json::json!({ "relay": relay, "ok": ok });
+                    let _ = app_handle.emit("dht_relay_reservation", payload);
+                }
+                DhtEvent::Error(err) => {
+                    let _ = app_handle.emit("dht_event", format!("error:{}", err));
+                }
+                _ => {}
             }
         }
     });
+}
 
-    {
-        let mut dht_guard = state.dht.lock().await;
-        *dht_guard = Some(dht_arc.clone());
-    }
-
-    // Store chunk manager in AppState
-    {
-        let mut chunk_guard = state.chunk_manager.lock().await;
-        *chunk_guard = Some(chunk_manager.clone());
-    }
-
-    // Also attach DHT to HTTP server state for provider-side metrics
-    state.http_server_state.set_dht(dht_arc.clone()).await;
-
-    // Monitor peer health and auto-reconnect to bootstrap when needed
-    let dht_for_monitor = dht_arc.clone();
+/// Monitor peer health and auto-reconnect to bootstrap when needed.
+fn spawn_dht_health_monitor(
+    app: &tauri::AppHandle,
+    dht: Arc<DhtService>,
+    bootstrap_nodes_for_monitor: Vec<String>,
+) {
+    let dht_for_monitor = dht;
     let app_for_monitor = app.clone();
     
     tokio::spawn(async move {
@@ -2021,8 +2241,6 @@ async fn start_dht_node(
             }
         }
     });
-
-    Ok(peer_id)
 }
 
 #[tauri::command]
@@ -2056,7 +2274,80 @@ async fn stop_dht_node(app: tauri::AppHandle, state: State<'_, AppState>) -> Res
     }
     let _ = app.emit("proxy_reset", ());
 
-    Ok(())
+    Ok(())
+}
+
+/// Restart the running DHT node under `new_secret`, with the settings it was
+/// started with, and move the records it published over to the new PeerId.
+/// Returns the new PeerId.
+#[tauri::command]
+async fn rotate_dht_identity(
+    app: tauri::AppHandle,
+    state: State<'_, AppState>,
+    new_secret: String,
+    grace_period_secs: Option<u64>,
+) -> Result<String, String> {
+    // The old node stays registered until rotation settles, so nothing else
+    // can start a node in the meantime
+    let dht = {
+        let dht_guard = state.dht.lock().await;
+        dht_guard.as_ref().cloned()
+    }
+    .ok_or("DHT node is not running")?;
+    let options = state
+        .dht_options
+        .lock()
+        .await
+        .clone()
+        .ok_or("DHT node settings are unknown")?;
+    let chunk_manager = state.chunk_manager.lock().await.clone();
+
+    // Carry the AutoRelay history over, as stop_dht_node does
+    let (last_enabled, last_disabled) = dht.autorelay_history().await;
+    {
+        let mut guard = state.autorelay_last_enabled.lock().await;
+        *guard = last_enabled;
+    }
+    {
+        let mut guard = state.autorelay_last_disabled.lock().await;
+        *guard = last_disabled;
+    }
+
+    let proj_dirs = ProjectDirs::from("com", "chiral-network", "chiral-network")
+        .ok_or("Failed to get project directories")?;
+    let blockstore_db_path = proj_dirs.data_dir().join("blockstore_db");
+    let async_blockstore_path = async_std::path::Path::new(blockstore_db_path.as_os_str());
+    let config = dht_config_from_options(&state, options, async_blockstore_path).await;
+    let bootstrap_nodes = config.bootstrap_nodes.clone();
+
+    let grace_period = grace_period_secs
+        .map(Duration::from_secs)
+        .unwrap_or(dht::DEFAULT_IDENTITY_ROTATION_GRACE);
+    let rotated = match dht
+        .rotate_identity(new_secret, config, chunk_manager, grace_period)
+        .await
+    {
+        Ok(rotated) => rotated,
+        Err(e) => {
+            if matches!(e, dht::IdentityRotationError::Stopped(_)) {
+                let mut dht_guard = state.dht.lock().await;
+                *dht_guard = None;
+            }
+            return Err(e.to_string());
+        }
+    };
+    let peer_id = rotated.get_peer_id().await;
+
+    let dht_arc = Arc::new(rotated);
+    spawn_dht_event_pump(&app, &state, dht_arc.clone());
+    {
+        let mut dht_guard = state.dht.lock().await;
+        *dht_guard = Some(dht_arc.clone());
+    }
+    state.http_server_state.set_dht(dht_arc.clone()).await;
+    spawn_dht_health_monitor(&app, dht_arc, bootstrap_nodes);
+
+    Ok(peer_id)
 }
 
 #[tauri::command]
@@ -2340,6 +2631,24 @@ async fn get_dht_events(state: State<'_, AppState>) -> Result<Vec<String>, Strin
                     Ok(json) => format!("nat_status:{json}"),
                     Err(_) => "nat_status:{}".to_string(),
                 },
+                DhtEvent::PeerBlocked {
+                    peer_id,
+                    address,
+                    reason,
+                } => format!(
+                    "peer_blocked:{}:{}:{}",
+                    peer_id.unwrap_or_default(),
+                    address.unwrap_or_default(),
+                    reason
+                ),
+                DhtEvent::BootstrapCompleted { num_remaining, ok } => format!(
+                    "bootstrap_completed:{}:{}",
+                    ok,
+                    num_remaining.map(|n| n.to_string()).unwrap_or_default()
+                ),
+                DhtEvent::NatStatusChanged { previous, current } => {
+                    format!("nat_status_changed:{:?}:{:?}", previous, current)
+                }
                 DhtEvent::PeerRtt { peer, rtt_ms } => format!("peer_rtt:{peer}:{rtt_ms}"),
                 DhtEvent::EchoReceived { from, utf8, bytes } => format!(
                     "echo_received:{}:{}:{}",
@@ -2370,6 +2679,28 @@ async fn get_dht_events(state: State<'_, AppState>) -> Result<Vec<String>, Strin
                 DhtEvent::PaymentNotificationReceived { from_peer, payload } => {
                     format!("payment_notification_received:{}:{:?}", from_peer, payload)
                 }
+                DhtEvent::IdentityRotated { old, new } => {
+                    format!("identity_rotated:{}:{}", old, new)
+                }
+                DhtEvent::ProvidersFound {
+                    file_hash,
+                    providers,
+                } => {
+                    format!("providers_found:{}:{}", file_hash, providers.join(","))
+                }
+                DhtEvent::PeerUnresponsive { peer_id, failures } => {
+                    format!("peer_unresponsive:{}:{}", peer_id, failures)
+                }
+                DhtEvent::SwarmReset {
+                    idle_secs,
+                    peer_count,
+                } => format!("swarm_reset:{}:{}", idle_secs, peer_count),
+                DhtEvent::QueryTimedOut { file_hash, reason } => {
+                    format!("query_timed_out:{}:{}", file_hash, reason)
+                }
+                DhtEvent::RelayReservation { relay, ok } => {
+                    format!("relay_reservation:{}:{}", relay, ok)
+                }
                 DhtEvent::ReputationEvent {
                     peer_id,
                     event_type,
@@ -3596,9 +3927,10 @@ async fn start_file_transfer_service(
 
     // Use the internal app data directory for file storage (hash-named files + metadata)
     // NOT the user's download directory - that's only for final downloaded files
-    let file_transfer_service = FileTransferService::new_with_app_handle(app.clone())
-        .await
-        .map_err(|e| format!("Failed to start file transfer service: {}", e))?;
+    let file_transfer_service =
+        FileTransferService::new_with_app_handle(app.clone(), state.analytics.clone())
+            .await
+            .map_err(|e| format!("Failed to start file transfer service: {}", e))?;
 
     let ft_arc = Arc::new(file_transfer_service);
     {
@@ -3612,6 +3944,7 @@ async fn start_file_transfer_service(
         ft_arc.clone(),
         state.keystore.clone(),
         state.bandwidth.clone(),
+        WebRtcConfig::from_env(),
     )
     .await
     .map_err(|e| format!("Failed to start WebRTC service: {}", e))?;
@@ -3653,6 +3986,15 @@ async fn start_file_transfer_service(
             chunk_manager,
         );
         let multi_source_arc = Arc::new(multi_source_service);
+
+        // Rank configured proxy/relay endpoints by measured latency
+        let proxy_endpoints = ProxyLatencyMonitor::endpoints_from_env();
+        if let Some(proxy_latency) = multi_source_arc.proxy_latency_service() {
+            if !proxy_endpoints.is_empty() {
+                let monitor = ProxyLatencyMonitor::new(proxy_endpoints, proxy_latency);
+                tokio::spawn(monitor.run(app.app_handle().clone()));
+            }
+        }
         
         // Update WebRTCService with MultiSourceDownloadService for hash verification
         // Since WebRTCService is already created and may have active connections,
@@ -3665,6 +4007,7 @@ async fn start_file_transfer_service(
             state.bandwidth.clone(),
             Some(multi_source_arc.clone()),
             Some(state.payment_checkpoint.clone()),
+            WebRtcConfig::from_env(),
         )
         .await
         .map_err(|e| format!("Failed to recreate WebRTC service with multi-source: {}", e))?;
@@ -3795,6 +4138,7 @@ async fn upload_file_to_network(
                         name: original_file_name.clone(),
                         size: file_size,
                         encrypted: false,
+                        price: reputation::price_to_amount(price),
                     })
                     .await;
             }
@@ -4016,6 +4360,8 @@ async fn upload_file_to_network(
                     .await
                     .map_err(|e| format!("Failed to read file: {}", e))?;
                 let file_size = file_data.len() as u64;
+                let mime_type =
+                    crate::manager::ChunkManager::detect_mime_type(&file_data, &original_file_name);
 
                 // Generate a manifest with per-chunk SHA-256 hashes so FTP downloads can be validated
                 // by MultiSourceDownloadService (manifest-based chunk hash verification).
@@ -4023,7 +4369,7 @@ async fn upload_file_to_network(
                 // NOTE:
                 // - For FTP we keep `metadata.merkle_root` as the overall file hash (sha256(file)),
                 //   and set the manifest merkle_root to the same value for consistency with E2E verification.
-                let chunk_size: usize = 256 * 1024; // match ChunkManager default
+                let chunk_size = configured_chunk_size(&state).await;
                 let mut manifest_chunks: Vec<crate::manager::ChunkInfo> = Vec::new();
                 {
                     use sha2::{Digest as _, Sha256};
@@ -4042,6 +4388,9 @@ async fn upload_file_to_network(
                             size,
                             encrypted_hash: hash,
                             encrypted_size: size,
+                            compression_type: crate::manager::COMPRESSION_NONE,
+                            compressed_size: size,
+                            cipher_suite: crate::manager::CipherSuite::Aes256Gcm,
                         });
                         offset = end;
                         index += 1;
@@ -4051,6 +4400,12 @@ async fn upload_file_to_network(
                     merkle_root: file_hash.clone(),
                     chunks: manifest_chunks,
                     encrypted_key_bundle: None,
+                    hash_algorithm: crate::manager::HashAlgorithm::Sha256,
+                    encryption_info: None,
+                    mime_type: Some(mime_type.clone()),
+                    chunk_size,
+                    signature: None,
+                    file_hash: Some(file_hash.clone()),
                 };
                 let manifest_json = serde_json::to_string(&file_manifest)
                     .map_err(|e| format!("Failed to serialize FileManifest: {}", e))?;
@@ -4075,7 +4430,7 @@ async fn upload_file_to_network(
                         .duration_since(std::time::UNIX_EPOCH)
                         .unwrap_or_default()
                         .as_secs(),
-                    mime_type: None,
+                    mime_type: Some(mime_type),
                     is_encrypted: false,
                     encryption_method: None,
                     key_fingerprint: None,
@@ -4112,7 +4467,11 @@ async fn upload_file_to_network(
                 };
 
                 if let Some(dht) = dht {
-                    if let Err(e) = dht.publish_file(metadata.clone(), None).await {
+                    // Re-hash the file on disk so the published hash and size match its content
+                    if let Err(e) = dht
+                        .publish_local_file(metadata.clone(), Path::new(&file_path), None)
+                        .await
+                    {
                         warn!("Failed to publish FTP file metadata to DHT: {}", e);
                     }
                 }
@@ -4127,13 +4486,8 @@ async fn upload_file_to_network(
                     protocol_name
                 );
 
-                // For very large files, use a smaller chunk size to reduce memory pressure
-                let chunk_size = if file_size > 1024 * 1024 * 1024 {
-                    // > 1GB
-                    256 * 1024 // 256KB chunks
-                } else {
-                    1024 * 1024 // 1MB chunks
-                };
+                // Same chunk size as every other upload path, so manifests agree
+                let chunk_size = configured_chunk_size(&state).await as u64;
 
                 let total_chunks = ((file_size + chunk_size - 1) / chunk_size) as usize;
 
@@ -4210,6 +4564,14 @@ async fn upload_file_to_network(
                 let mut upload_sessions = state.upload_sessions.lock().await;
                 if let Some(session) = upload_sessions.get_mut(&upload_id) {
                     if session.is_complete {
+                        // Publish the size we actually received, not the one we were told
+                        if session.received_bytes != session.file_size {
+                            return Err(format!(
+                                "Upload size mismatch: declared {} bytes, received {}",
+                                session.file_size, session.received_bytes
+                            ));
+                        }
+
                         // Calculate Merkle root for integrity verification
                         let hasher = std::mem::replace(&mut session.hasher, sha2::Sha256::new());
                         let merkle_root = format!("{:x}", hasher.finalize());
@@ -4274,6 +4636,9 @@ async fn upload_file_to_network(
                                 size,
                                 encrypted_hash: String::new(), // Not encrypted in Bitswap
                                 encrypted_size: size,
+                                compression_type: crate::manager::COMPRESSION_NONE,
+                                compressed_size: size,
+                                cipher_suite: crate::manager::CipherSuite::Aes256Gcm,
                             });
                         }
                         
@@ -4282,6 +4647,12 @@ async fn upload_file_to_network(
                             merkle_root: merkle_root.clone(),
                             chunks: manifest_chunks,
                             encrypted_key_bundle: None,
+                            hash_algorithm: crate::manager::HashAlgorithm::Sha256,
+                            encryption_info: None,
+                            mime_type: None,
+                            chunk_size,
+                            signature: None,
+                            file_hash: None,
                         };
                         
                         // Serialize manifest to JSON
@@ -4384,27 +4755,41 @@ async fn upload_file_to_network(
                         let file_data = tokio::fs::read(&file_path)
                             .await
                             .map_err(|e| format!("Failed to read file: {}", e))?;
-                        let file_hash = file_transfer::FileTransferService::calculate_file_hash(&file_data);
 
                         // Create FileManifest using ChunkManager
                         let chunk_storage_path = app.path()
                             .app_data_dir()
                             .map_err(|e| format!("Failed to get app data directory: {}", e))?
-                            .join("chunks");
-                        let manager = ChunkManager::new(chunk_storage_path);
+                            .join("chunk_storage");
+                        let mut manager = ChunkManager::new(chunk_storage_path)
+                            .with_chunk_size(configured_chunk_size(&state).await)?;
+                        let local_cache = {
+                            let chunk_guard = state.chunk_manager.lock().await;
+                            chunk_guard.as_ref().and_then(|m| m.local_cache())
+                        };
+                        if let Some(cache) = local_cache {
+                            manager = manager.with_local_cache(cache);
+                        }
                         
-                        // Use chunk_and_encrypt_file_canonical to generate FileManifest
+                        // Chunk with the canonical key and save the FileManifest, so gc keeps the chunks
                         // This will calculate chunk hashes even without encryption
                         let file_manifest_result = tokio::task::spawn_blocking({
                             let file_path_clone = file_path.clone();
                             move || {
-                                manager.chunk_and_encrypt_file_canonical(Path::new(&file_path_clone))
+                                manager.store_file_with_manifest(Path::new(&file_path_clone))
                             }
                         }).await
                         .map_err(|e| format!("Failed to spawn blocking task: {}", e))?;
                         
                         let file_manifest = file_manifest_result
                             .map_err(|e| format!("Failed to create FileManifest: {}", e))?;
+                        // Keep the key so integrity checks and range reads can decrypt the chunks
+                        state.canonical_aes_keys.lock().await.insert(
+                            file_manifest.manifest.merkle_root.clone(),
+                            file_manifest.canonical_aes_key,
+                        );
+                        // SHA-256 of the file, computed while chunking
+                        let file_hash = file_manifest.file_hash.clone();
                         
                         // Serialize manifest to JSON
                         let manifest_json = serde_json::to_string(&file_manifest.manifest)
@@ -5398,6 +5783,7 @@ async fn download_file_from_network(
                                     file_size: metadata.file_size,
                                     requester_peer_id: dht_service.get_peer_id().await,
                                     recipient_public_key: None,
+                                    resume_token: None,
                                 };
 
                                 match webrtc_service.send_file_request(selected_peer.clone(), file_request).await {
@@ -5476,6 +5862,7 @@ async fn download_file_from_network(
                                                                         .get_peer_id()
                                                                         .await,
                                                                     recipient_public_key: None,
+                                                                    resume_token: None,
                                                                 };
 
                                                             match webrtc_service
@@ -5781,6 +6168,7 @@ async fn start_streaming_upload(
             is_complete: false,
             chunk_hashes: Vec::new(),
             chunk_size: 0, // Will be set when first chunk arrives
+            received_bytes: 0,
         },
     );
 
@@ -5803,6 +6191,7 @@ async fn upload_file_chunk(
     // Update hasher with chunk data
     session.hasher.update(&chunk_data);
     session.received_chunks += 1;
+    session.received_bytes += chunk_data.len() as u64;
 
     // Calculate and store chunk hash for FileManifest
     use sha2::{Digest, Sha256};
@@ -6521,6 +6910,85 @@ async fn search_file_metadata(
     }
 }
 
+/// Build a `chiral://` share link for `file_hash`, filling in the name, size
+/// and MIME type from local metadata or, failing that, a DHT lookup.
+#[tauri::command]
+async fn make_share_uri(state: State<'_, AppState>, file_hash: String) -> Result<String, String> {
+    let dht = {
+        let dht_guard = state.dht.lock().await;
+        dht_guard.as_ref().cloned()
+    };
+    let dht = dht.ok_or("DHT node is not running")?;
+
+    let local = dht
+        .get_all_file_metadata()
+        .await?
+        .into_iter()
+        .find(|m| m.merkle_root == file_hash);
+    let metadata = match local {
+        Some(metadata) => Some(metadata),
+        None => dht.synchronous_search_metadata(file_hash.clone(), 10_000).await?,
+    };
+
+    let uri = match metadata {
+        Some(metadata) => ChiralUri::from_metadata(&metadata),
+        None => ChiralUri::new(file_hash),
+    };
+    Ok(uri.to_string())
+}
+
+/// Report how many reachable providers hold each chunk of a published file.
+/// `target` defaults to `replication::DEFAULT_REPLICATION_TARGET`.
+#[tauri::command]
+async fn get_replication_health(
+    state: State<'_, AppState>,
+    file_hash: String,
+    target: Option<usize>,
+) -> Result<ReplicationHealth, String> {
+    let dht = {
+        let dht_guard = state.dht.lock().await;
+        dht_guard.as_ref().cloned()
+    };
+    let dht = dht.ok_or("DHT node is not running")?;
+
+    let local = dht
+        .get_all_file_metadata()
+        .await?
+        .into_iter()
+        .find(|m| m.merkle_root == file_hash);
+    let metadata = match local {
+        Some(metadata) => metadata,
+        None => dht
+            .synchronous_search_metadata(file_hash.clone(), 10_000)
+            .await?
+            .ok_or_else(|| format!("No metadata found for file {}", file_hash))?,
+    };
+
+    Ok(replication::check_replication_health(
+        &dht,
+        &metadata,
+        target.unwrap_or(replication::DEFAULT_REPLICATION_TARGET),
+    )
+    .await)
+}
+
+/// Parse a `chiral://` share link and download the file it points to into
+/// `output_path`. Returns the same result as `download_file_from_network`.
+#[tauri::command]
+async fn resolve_share_uri(
+    state: State<'_, AppState>,
+    uri: String,
+    output_path: String,
+) -> Result<String, String> {
+    let uri = ChiralUri::parse(&uri).map_err(|e| e.to_string())?;
+    info!(
+        "Resolving share link for {} ({})",
+        uri.file_hash,
+        uri.name.as_deref().unwrap_or("unnamed")
+    );
+    download_file_from_network(state, uri.file_hash, output_path).await
+}
+
 #[tauri::command]
 async fn get_file_seeders(
     state: State<'_, AppState>,
@@ -7121,9 +7589,7 @@ struct TotpSetup {
 
 #[tauri::command]
 fn generate_totp_secret() -> Result<TotpSetup, String> {
-    // Customize the issuer and account name.
     // The account name should ideally be the user's identifier (e.g., email or username).
-    let issuer = "Chiral Network".to_string();
     let account_name = "Chiral User".to_string(); // Generic name, as it's not tied to a specific account yet
 
     // Generate a new secret using random bytes
@@ -7133,21 +7599,9 @@ fn generate_totp_secret() -> Result<TotpSetup, String> {
     rng.fill_bytes(&mut secret_bytes);
     let secret = Secret::Raw(secret_bytes.to_vec());
 
-    // Create a TOTP object.
-    let totp = TOTP::new(
-        Algorithm::SHA1,
-        6,  // 6 digits
-        1,  // 1 second tolerance
-        30, // 30 second step
-        secret.to_bytes().map_err(|e| e.to_string())?,
-        Some(issuer),
-        account_name,
-    )
-    .map_err(|e| e.to_string())?;
-
-    let otpauth_url = totp.get_url();
     // For totp-rs v5+, use to_encoded() to get the base32 string
     let secret_string = secret.to_encoded().to_string();
+    let otpauth_url = keystore::account_totp(&secret_string, &account_name)?.get_url();
 
     Ok(TotpSetup {
         secret: secret_string,
@@ -7166,32 +7620,21 @@ async fn is_2fa_enabled(state: State<'_, AppState>) -> Result<bool, String> {
 async fn verify_and_enable_totp(
     secret: String,
     code: String,
-    password: String, // Password needed to encrypt the secret
+    password: String,             // Password needed to encrypt the secret
+    current_code: Option<String>, // Code from the old secret when re-enrolling
     state: State<'_, AppState>,
 ) -> Result<bool, String> {
     let address = get_active_account(&state).await?;
 
     // 1. Verify the code against the provided secret first.
-    // Create a Secret enum from the base32 string, then get its raw bytes.
-    let secret_bytes = Secret::Encoded(secret.clone());
-    let totp = TOTP::new(
-        Algorithm::SHA1,
-        6,
-        1,
-        30,
-        secret_bytes.to_bytes().map_err(|e| e.to_string())?,
-        Some("Chiral Network".to_string()),
-        address.clone(),
-    )
-    .map_err(|e| e.to_string())?;
-
-    if !totp.check_current(&code).unwrap_or(false) {
+    let totp = keystore::account_totp(&secret, &address)?;
+    if !totp.check_current(code.trim()).unwrap_or(false) {
         return Ok(false); // Code is invalid, don't enable.
     }
 
     // 2. Code is valid, so save the secret to the keystore.
     let mut keystore = Keystore::load()?;
-    keystore.set_2fa_secret(&address, &secret, &password)?;
+    keystore.set_2fa_secret(&address, &secret, &password, current_code.as_deref())?;
 
     Ok(true)
 }
@@ -7211,27 +7654,19 @@ async fn verify_totp_code(
         .ok_or_else(|| "2FA is not enabled for this account.".to_string())?;
 
     // 2. Verify the provided code against the stored secret.
-    // Create a Secret enum from the base32 string, then get its raw bytes.
-    let secret_bytes = Secret::Encoded(secret_b32);
-    let totp = TOTP::new(
-        Algorithm::SHA1,
-        6,
-        1,
-        30,
-        secret_bytes.to_bytes().map_err(|e| e.to_string())?,
-        Some("Chiral Network".to_string()),
-        address.clone(),
-    )
-    .map_err(|e| e.to_string())?;
-
-    Ok(totp.check_current(&code).unwrap_or(false))
+    let totp = keystore::account_totp(&secret_b32, &address)?;
+    Ok(totp.check_current(code.trim()).unwrap_or(false))
 }
 
 #[tauri::command]
-async fn disable_2fa(password: String, state: State<'_, AppState>) -> Result<(), String> {
+async fn disable_2fa(
+    password: String,
+    code: String,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
     let address = get_active_account(&state).await?;
     let mut keystore = Keystore::load()?;
-    keystore.remove_2fa_secret(&address, &password)?;
+    keystore.remove_2fa_secret(&address, &password, Some(&code))?;
     Ok(())
 }
 
@@ -7286,6 +7721,42 @@ async fn record_transfer_failure(
     }
 }
 
+#[tauri::command]
+async fn list_verdict_drafts(
+    state: State<'_, AppState>,
+) -> Result<Vec<reputation::VerdictDraft>, String> {
+    let dht_guard = state.dht.lock().await;
+    if let Some(ref dht) = *dht_guard {
+        Ok(dht.verdict_drafts().await)
+    } else {
+        Err("DHT service not available".to_string())
+    }
+}
+
+#[tauri::command]
+async fn confirm_verdict_draft(state: State<'_, AppState>, peer_id: String) -> Result<(), String> {
+    let dht = state
+        .dht
+        .lock()
+        .await
+        .clone()
+        .ok_or_else(|| "DHT service not available".to_string())?;
+    dht.confirm_verdict_draft(&peer_id).await
+}
+
+#[tauri::command]
+async fn discard_verdict_draft(
+    state: State<'_, AppState>,
+    peer_id: String,
+) -> Result<bool, String> {
+    let dht_guard = state.dht.lock().await;
+    if let Some(ref dht) = *dht_guard {
+        Ok(dht.discard_verdict_draft(&peer_id).await)
+    } else {
+        Err("DHT service not available".to_string())
+    }
+}
+
 #[tauri::command]
 async fn get_peer_metrics(
     state: State<'_, AppState>,
@@ -7343,6 +7814,9 @@ async fn select_peers_with_strategy(
         "balanced" => SelectionStrategy::Balanced,
         "encryption" => SelectionStrategy::EncryptionPreferred,
         "load_balanced" => SelectionStrategy::LoadBalanced,
+        s if s.starts_with("region:") => {
+            SelectionStrategy::RegionPreferred(s["region:".len()..].to_string())
+        }
         _ => SelectionStrategy::Balanced,
     };
 
@@ -7381,6 +7855,21 @@ async fn set_peer_encryption_support(
     }
 }
 
+#[tauri::command]
+async fn set_peer_region(
+    state: State<'_, AppState>,
+    peer_id: String,
+    region: Option<String>,
+) -> Result<(), String> {
+    let dht_guard = state.dht.lock().await;
+    if let Some(ref dht) = *dht_guard {
+        dht.set_peer_region(&peer_id, region).await;
+        Ok(())
+    } else {
+        Err("DHT service not available".to_string())
+    }
+}
+
 #[tauri::command]
 async fn cleanup_inactive_peers(
     state: State<'_, AppState>,
@@ -7622,6 +8111,11 @@ async fn get_bandwidth_stats(
     Ok(state.analytics.get_bandwidth_stats().await)
 }
 
+#[tauri::command]
+async fn get_analytics(state: State<'_, AppState>) -> Result<analytics::AnalyticsSummary, String> {
+    Ok(state.analytics.get_analytics_summary().await)
+}
+
 #[tauri::command]
 async fn get_bandwidth_history(
     state: State<'_, AppState>,
@@ -8525,29 +9019,35 @@ async fn run_interactive_mode(args: headless::CliArgs) -> Result<(), Box<dyn std
     }
 
     // Start DHT node
-    let dht_service = DhtService::new(
-        args.dht_port,
-        bootstrap_nodes.clone(),
-        args.secret,
-        args.is_bootstrap,
+    let dht_config = args.apply_dht_overrides(DhtConfig {
+        port: args.dht_port,
+        listen_addrs: args.listen_addrs()?,
+        bootstrap_nodes: bootstrap_nodes.clone(),
+        secret: args.secret.clone(),
+        is_bootstrap: args.is_bootstrap,
         enable_autonat,
-        probe_interval,
-        args.autonat_server.clone(),
-        args.socks5_proxy,
+        autonat_probe_interval: probe_interval,
+        autonat_servers: args.autonat_server.clone(),
+        proxy_address: args.socks5_proxy.clone(),
+        enable_autorelay: final_enable_autorelay,
+        preferred_relays: args.relay.clone(),
+        enable_relay_server: args.enable_relay,
+        enable_upnp: true,
+        pure_client_mode: false,
+        force_server_mode: false,
+        peer_cache_path: args.peer_cache.clone(),
+        ..DhtConfig::default()
+    });
+    // Same chunk storage as headless mode, so the REPL lists what this node serves
+    let chunk_storage_path = std::env::temp_dir().join("chiral-chunks");
+    let _ = std::fs::create_dir_all(&chunk_storage_path);
+    let chunk_manager = Arc::new(ChunkManager::new(chunk_storage_path));
+
+    let dht_service = DhtService::new_with_config(
+        dht_config,
         file_transfer_service.clone(),
         None, // webrtc_service
-        None, // chunk_manager
-        None, // chunk_size_kb: use default
-        None, // cache_size_mb: use default
-        final_enable_autorelay,
-        args.relay.clone(),
-        args.enable_relay,
-        true, // enable_upnp
-        None, // blockstore_db_path
-        None, // last_autorelay_enabled_at
-        None, // last_autorelay_disabled_at
-        false, // pure_client_mode
-        false, // force_server_mode
+        Some(chunk_manager.clone()),
     )
     .await?;
 
@@ -8575,6 +9075,7 @@ async fn run_interactive_mode(args: headless::CliArgs) -> Result<(), Box<dyn std
     let context = repl::ReplContext {
         dht_service: dht_arc.clone(),
         file_transfer_service,
+        chunk_manager: Some(chunk_manager),
         geth_process,
         peer_id,
         miner_address: args.miner_address.clone(),
@@ -8618,29 +9119,30 @@ async fn run_tui_mode(args: headless::CliArgs) -> Result<(), Box<dyn std::error:
     }
 
     // Start DHT node
-    let dht_service = DhtService::new(
-        args.dht_port,
-        bootstrap_nodes.clone(),
-        args.secret,
-        args.is_bootstrap,
+    let dht_config = args.apply_dht_overrides(DhtConfig {
+        port: args.dht_port,
+        listen_addrs: args.listen_addrs()?,
+        bootstrap_nodes: bootstrap_nodes.clone(),
+        secret: args.secret.clone(),
+        is_bootstrap: args.is_bootstrap,
         enable_autonat,
-        probe_interval,
-        args.autonat_server.clone(),
-        args.socks5_proxy,
+        autonat_probe_interval: probe_interval,
+        autonat_servers: args.autonat_server.clone(),
+        proxy_address: args.socks5_proxy.clone(),
+        enable_autorelay: final_enable_autorelay,
+        preferred_relays: args.relay.clone(),
+        enable_relay_server: args.enable_relay,
+        enable_upnp: true,
+        pure_client_mode: false,
+        force_server_mode: false,
+        peer_cache_path: args.peer_cache.clone(),
+        ..DhtConfig::default()
+    });
+    let dht_service = DhtService::new_with_config(
+        dht_config,
         file_transfer_service.clone(),
         None, // webrtc_service
         None, // chunk_manager
-        None, // chunk_size_kb: use default
-        None, // cache_size_mb: use default
-        final_enable_autorelay,
-        args.relay.clone(),
-        args.enable_relay,
-        true, // enable_upnp
-        None, // blockstore_db_path
-        None, // last_autorelay_enabled_at
-        None, // last_autorelay_disabled_at
-        false, // pure_client_mode
-        false, // force_server_mode
     )
     .await?;
 
@@ -8821,9 +9323,11 @@ fn main() {
     // Don't initialize tracing subscriber here - we'll do it in setup() after loading settings
     // so we can configure file logging properly
 
-    // Parse command line arguments
-    use clap::Parser;
-    let args = headless::CliArgs::parse();
+    // Parse command line arguments (layered over --config, if given)
+    let args = headless::CliArgs::parse_with_config().unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        std::process::exit(2);
+    });
 
     // Handle --download-geth flag
     if args.download_geth {
@@ -8865,31 +9369,11 @@ fn main() {
 
     // For headless mode, initialize basic console logging
     if args.headless {
-        use tracing_subscriber::{fmt, prelude::*, EnvFilter};
-        let mut filter = EnvFilter::from_default_env();
-
-        // Add directives with safe fallback
-        if let Ok(directive) = "chiral_network=info".parse() {
-            filter = filter.add_directive(directive);
-        }
-        if let Ok(directive) = "libp2p=warn".parse() {
-            filter = filter.add_directive(directive);
-        }
-        if let Ok(directive) = "libp2p_kad=warn".parse() {
-            filter = filter.add_directive(directive);
-        }
-        if let Ok(directive) = "libp2p_swarm=warn".parse() {
-            filter = filter.add_directive(directive);
-        }
-        if let Ok(directive) = "libp2p_mdns=warn".parse() {
-            filter = filter.add_directive(directive);
+        if let Err(e) = logger::init_logging(&args.log_options(), None) {
+            eprintln!("❌ Failed to initialize logging: {}", e);
+            std::process::exit(1);
         }
 
-        tracing_subscriber::registry()
-            .with(fmt::layer())
-            .with(filter)
-            .init();
-
         println!("Running in headless mode...");
 
         // Create a tokio runtime for async operations
@@ -8935,6 +9419,7 @@ fn main() {
         return;
     }
 
+    let log_options = args.log_options();
     let runtime = tokio::runtime::Runtime::new().expect("Failed to create tokio runtime");
 
     // --- Single Instance Check ---
@@ -9172,6 +9657,7 @@ fn main() {
 
             // Initialize proxy authentication tokens
             proxy_auth_tokens: Arc::new(Mutex::new(std::collections::HashMap::new())),
+            proxy_auth_signing_key: ed25519_dalek::SigningKey::generate(&mut rand::rngs::OsRng),
 
             // Initialize HTTP server state (uses same storage as FileTransferService)
             http_server_state: Arc::new(http_server::HttpServerState::new({
@@ -9190,6 +9676,8 @@ fn main() {
             // Initialize the new map for AES keys
             canonical_aes_keys: Arc::new(Mutex::new(std::collections::HashMap::new())),
 
+            upload_cancellations: Arc::new(Mutex::new(std::collections::HashMap::new())),
+
             // Proof-of-Storage watcher background handle and contract address
             // make these clonable so we can .clone() and move into spawned tasks
             proof_watcher: Arc::new(Mutex::new(None)),
@@ -9217,6 +9705,9 @@ fn main() {
             // Chunk manager (will be initialized when DHT starts)
             chunk_manager: Mutex::new(None),
 
+            // DHT start settings (recorded when DHT starts)
+            dht_options: Mutex::new(None),
+
             // Download restart service (will be initialized in setup)
             download_restart: Mutex::new(None),
 
@@ -9246,6 +9737,8 @@ fn main() {
             save_account_to_keystore,
             load_account_from_keystore,
             list_keystore_accounts,
+            set_keystore_account_label,
+            check_keystore_integrity,
             remove_account_from_keystore,
             pool::discover_mining_pools,
             pool::create_mining_pool,
@@ -9263,6 +9756,7 @@ fn main() {
             get_txpool_content,
             get_peer_info,
             debug_network_tx,
+            get_gas_estimate,
             get_cpu_temperature,
             get_power_consumption,
             download,
@@ -9310,6 +9804,7 @@ fn main() {
             get_cpu_temperature,
             start_dht_node,
             stop_dht_node,
+            rotate_dht_identity,
             stop_publishing_file,
             search_file_metadata,
             search_by_infohash,
@@ -9330,6 +9825,9 @@ fn main() {
             get_dht_connected_peers,
             start_file_transfer_service,
             download_file_from_network,
+            make_share_uri,
+            get_replication_health,
+            resolve_share_uri,
             upload_file_to_network,
             list_ftp_directory,
             delete_ftp_file,
@@ -9383,11 +9881,15 @@ fn main() {
             get_recommended_peers_for_file,
             record_transfer_success,
             record_transfer_failure,
+            list_verdict_drafts,
+            confirm_verdict_draft,
+            discard_verdict_draft,
             get_peer_metrics,
             get_connected_peer_metrics,
             report_malicious_peer,
             select_peers_with_strategy,
             set_peer_encryption_support,
+            set_peer_region,
             cleanup_inactive_peers,
             test_backend_connection,
             set_bandwidth_limits,
@@ -9401,8 +9903,10 @@ fn main() {
             start_streaming_upload,
             upload_file_chunk,
             cancel_streaming_upload,
+            cancel_upload,
             get_bandwidth_stats,
             get_bandwidth_history,
+            get_analytics,
             get_performance_metrics,
             get_network_activity,
             get_resource_contribution,
@@ -9441,6 +9945,11 @@ fn main() {
             encrypt_file_for_recipient,
             //request_file_access,
             decrypt_and_reassemble_file,
+            verify_file_integrity,
+            download_file_range,
+            list_stored_files,
+            get_dedup_stats,
+            download_file_with_progress,
             create_auth_session,
             verify_stream_auth,
             generate_hmac_key,
@@ -9453,6 +9962,7 @@ fn main() {
             get_active_hmac_exchanges,
             generate_proxy_auth_token,
             validate_proxy_auth_token,
+            refresh_proxy_auth_token,
             revoke_proxy_auth_token,
             cleanup_expired_proxy_auth_tokens,
             get_file_data,
@@ -9564,27 +10074,6 @@ fn main() {
                 settings
             };
 
-            // Initialize tracing subscriber with console output and optionally file output
-            use tracing_subscriber::{fmt, prelude::*, EnvFilter};
-
-            let env_filter = {
-                #[cfg(debug_assertions)]
-                {
-                    EnvFilter::from_default_env()
-                        .add_directive("chiral_network=info".parse().unwrap())
-                        .add_directive("libp2p=warn".parse().unwrap())
-                        .add_directive("libp2p_kad=warn".parse().unwrap())
-                        .add_directive("libp2p_swarm=warn".parse().unwrap())
-                        .add_directive("libp2p_mdns=warn".parse().unwrap())
-                }
-                #[cfg(not(debug_assertions))]
-                {
-                    EnvFilter::from_default_env()
-                        .add_directive("chiral_network=warn".parse().unwrap())
-                        .add_directive("libp2p=error".parse().unwrap())
-                }
-            };
-
             // Always create file logger (even if disabled) so it can be enabled/disabled later
             let app_data_dir = app
                 .path()
@@ -9611,17 +10100,8 @@ fn main() {
 
             // Initialize tracing subscriber with both console and file output
             // File output will only write if enabled in config
-            if let Some(ref file_writer) = file_logger_writer {
-                tracing_subscriber::registry()
-                    .with(fmt::layer()) // Console output
-                    .with(fmt::layer().with_writer(file_writer.clone())) // File output (respects enabled flag)
-                    .with(env_filter)
-                    .init();
-            } else {
-                tracing_subscriber::registry()
-                    .with(fmt::layer()) // Console output only
-                    .with(env_filter)
-                    .init();
+            if let Err(e) = logger::init_logging(&log_options, file_logger_writer.clone()) {
+                eprintln!("Failed to initialize logging: {}", e);
             }
 
             // Store the file logger in app state so it can be updated later
@@ -9854,6 +10334,12 @@ fn main() {
                 });
             }
 
+            // Push new-block events from geth's newHeads subscription
+            {
+                let app_handle = app.handle().clone();
+                tauri::async_runtime::spawn(ethereum::watch_new_blocks(app_handle));
+            }
+
             // Load and restore torrent state on startup
             {
                 let app_handle = app.handle().clone();
@@ -9971,6 +10457,9 @@ fn main() {
                         bandwidth_controller
                             .set_app_handle(app_handle_for_bandwidth)
                             .await;
+                        bandwidth_controller
+                            .report_usage_periodically(Duration::from_secs(5))
+                            .await;
                     });
                 }
             }
@@ -10116,6 +10605,11 @@ pub struct FileManifestForJs {
     merkle_root: String,
     chunks: Vec<manager::ChunkInfo>,
     encrypted_key_bundle: String, // Serialized JSON of the bundle
+    #[serde(default)]
+    hash_algorithm: manager::HashAlgorithm,
+    /// Bundles for recipients added with `add_recipient`
+    #[serde(default)]
+    encryption_info: Option<encryption::EncryptionInfo>,
 }
 
 #[tauri::command]
@@ -10123,6 +10617,7 @@ async fn encrypt_file_for_self_upload(
     app: tauri::AppHandle,
     state: State<'_, AppState>,
     file_path: String,
+    upload_id: Option<String>,
 ) -> Result<FileManifestForJs, String> {
     // 1. Get the active user's private key from state to derive the public key.
     let private_key_hex = state
@@ -10138,9 +10633,20 @@ async fn encrypt_file_for_self_upload(
         .app_data_dir()
         .map_err(|e| format!("Could not get app data directory: {}", e))?;
     let chunk_storage_path = app_data_dir.join("chunk_storage");
+    let chunk_size = configured_chunk_size(&state).await;
+
+    // With an upload id, `cancel_upload` can stop the chunking part-way
+    let cancel = CancellationToken::new();
+    if let Some(id) = &upload_id {
+        state
+            .upload_cancellations
+            .lock()
+            .await
+            .insert(id.clone(), cancel.clone());
+    }
 
     // Run the encryption in a blocking task to avoid blocking the async runtime
-    tokio::task::spawn_blocking(move || {
+    let result = tokio::task::spawn_blocking(move || -> Result<FileManifestForJs, String> {
         let pk_bytes = hex::decode(private_key_hex.trim_start_matches("0x"))
             .map_err(|_| "Invalid private key format".to_string())?;
         let secret_key = StaticSecret::from(
@@ -10149,10 +10655,16 @@ async fn encrypt_file_for_self_upload(
         let public_key = PublicKey::from(&secret_key);
 
         // 2. Initialize ChunkManager with proper app data directory
-        let manager = ChunkManager::new(chunk_storage_path);
+        let manager = ChunkManager::new(chunk_storage_path).with_chunk_size(chunk_size)?;
 
         // 3. Call the existing backend function to perform the encryption.
-        let manifest = manager.chunk_and_encrypt_file(Path::new(&file_path), &public_key)?;
+        let manifest = manager.chunk_and_encrypt_file_cancellable(
+            Path::new(&file_path),
+            &public_key,
+            &cancel,
+        )?;
+        // Saved so gc keeps its chunks and the file can be verified and listed
+        manager.store_manifest(&manifest)?;
 
         // 4. Serialize the key bundle to a JSON string so it can be sent to the frontend easily.
         let bundle_json =
@@ -10162,10 +10674,30 @@ async fn encrypt_file_for_self_upload(
             merkle_root: manifest.merkle_root,
             chunks: manifest.chunks,
             encrypted_key_bundle: bundle_json,
+            hash_algorithm: manifest.hash_algorithm,
+            encryption_info: manifest.encryption_info,
         })
     })
     .await
-    .map_err(|e| format!("Encryption task failed: {}", e))?
+    .map_err(|e| format!("Encryption task failed: {}", e));
+
+    if let Some(id) = &upload_id {
+        state.upload_cancellations.lock().await.remove(id);
+    }
+    result?
+}
+
+/// Stop an upload started with `encrypt_file_for_self_upload` while it is
+/// still being chunked. Returns false if no such upload is in progress.
+#[tauri::command]
+async fn cancel_upload(upload_id: String, state: State<'_, AppState>) -> Result<bool, String> {
+    match state.upload_cancellations.lock().await.remove(&upload_id) {
+        Some(cancel) => {
+            cancel.cancel();
+            Ok(true)
+        }
+        None => Ok(false),
+    }
 }
 
 /// Encrypt a file for upload with optional recipient public key
@@ -10214,6 +10746,8 @@ async fn encrypt_file_for_recipient(
         .clone()
         .ok_or("No account is currently active. Please log in.")?;
 
+    let chunk_size = configured_chunk_size(&state).await;
+
     // Run the encryption in a blocking task to avoid blocking the async runtime
     tokio::task::spawn_blocking(move || {
         let pk_bytes = hex::decode(private_key_hex.trim_start_matches("0x"))
@@ -10223,10 +10757,11 @@ async fn encrypt_file_for_recipient(
         );
 
         // Initialize ChunkManager with proper app data directory
-        let manager = ChunkManager::new(chunk_storage_path);
+        let manager = ChunkManager::new(chunk_storage_path).with_chunk_size(chunk_size)?;
 
         // Call the existing backend function to perform the encryption with recipient's public key
         let manifest = manager.chunk_and_encrypt_file(Path::new(&file_path), &recipient_pk)?;
+        manager.store_manifest(&manifest)?;
 
         // Serialize the key bundle to a JSON string so it can be sent to the frontend easily.
         let bundle_json = match manifest.encrypted_key_bundle {
@@ -10238,6 +10773,8 @@ async fn encrypt_file_for_recipient(
             merkle_root: manifest.merkle_root,
             chunks: manifest.chunks,
             encrypted_key_bundle: bundle_json,
+            hash_algorithm: manifest.hash_algorithm,
+            encryption_info: manifest.encryption_info,
         })
     })
     .await
@@ -10288,6 +10825,46 @@ async fn decrypt_and_reassemble_file(
     state: State<'_, AppState>,
     manifest_js: FileManifestForJs,
     output_path: String,
+) -> Result<(), String> {
+    reassemble_manifest_file(app, state, manifest_js, output_path, None).await
+}
+
+/// Same as `decrypt_and_reassemble_file`, emitting a `reassemble_progress` event
+/// (`{ merkleRoot, chunksDone, totalChunks, bytesWritten }`) after each chunk.
+#[tauri::command]
+async fn download_file_with_progress(
+    app: tauri::AppHandle,
+    state: State<'_, AppState>,
+    manifest_js: FileManifestForJs,
+    output_path: String,
+) -> Result<(), String> {
+    let (progress_tx, mut progress_rx) =
+        tokio::sync::mpsc::channel::<manager::ReassembleProgress>(64);
+    let merkle_root = manifest_js.merkle_root.clone();
+    let app_handle = app.clone();
+    tokio::spawn(async move {
+        while let Some(progress) = progress_rx.recv().await {
+            let _ = app_handle.emit(
+                "reassemble_progress",
+                serde_json::json!({
+                    "merkleRoot": merkle_root,
+                    "chunksDone": progress.chunks_done,
+                    "totalChunks": progress.total_chunks,
+                    "bytesWritten": progress.bytes_written,
+                }),
+            );
+        }
+    });
+
+    reassemble_manifest_file(app, state, manifest_js, output_path, Some(progress_tx)).await
+}
+
+async fn reassemble_manifest_file(
+    app: tauri::AppHandle,
+    state: State<'_, AppState>,
+    manifest_js: FileManifestForJs,
+    output_path: String,
+    progress: Option<tokio::sync::mpsc::Sender<manager::ReassembleProgress>>,
 ) -> Result<(), String> {
     // 1. Get the active user's private key for decryption.
     let private_key_hex = state
@@ -10303,9 +10880,18 @@ async fn decrypt_and_reassemble_file(
         <[u8; 32]>::try_from(pk_bytes).map_err(|_| "Private key is not 32 bytes")?,
     );
 
-    // 2. Deserialize the key bundle from the string.
-    let encrypted_key_bundle: encryption::EncryptedAesKeyBundle =
-        serde_json::from_str(&manifest_js.encrypted_key_bundle).map_err(|e| e.to_string())?;
+    // 2. Use the bundle wrapped for us if we were added as a recipient,
+    // otherwise the primary one deserialized from the string.
+    let encrypted_key_bundle: encryption::EncryptedAesKeyBundle = match manifest_js
+        .encryption_info
+        .as_ref()
+        .and_then(|info| info.key_bundle_for(&PublicKey::from(&secret_key)))
+    {
+        Some(bundle) => bundle.clone(),
+        None => {
+            serde_json::from_str(&manifest_js.encrypted_key_bundle).map_err(|e| e.to_string())?
+        }
+    };
 
     // Get the app data directory for chunk storage
     let app_data_dir = app
@@ -10316,25 +10902,176 @@ async fn decrypt_and_reassemble_file(
 
     // 3. Clone the data we need for the blocking task
     let chunks = manifest_js.chunks.clone();
+    let hash_algorithm = manifest_js.hash_algorithm;
     let output_path_clone = output_path.clone();
 
     // Run the decryption in a blocking task to avoid blocking the async runtime
     tokio::task::spawn_blocking(move || {
         // 4. Initialize ChunkManager with proper app data directory
-        let manager = ChunkManager::new(chunk_storage_path);
+        let manager = ChunkManager::new(chunk_storage_path).with_hash_algorithm(hash_algorithm);
 
         // 5. Call the existing backend function to decrypt and save the file.
-        manager.reassemble_and_decrypt_file(
-            &chunks,
-            Path::new(&output_path_clone),
-            &Some(encrypted_key_bundle),
-            &secret_key, // Pass the secret key
-        )
+        match progress {
+            Some(progress) => manager.reassemble_and_decrypt_file_with_progress(
+                &chunks,
+                Path::new(&output_path_clone),
+                &Some(encrypted_key_bundle),
+                &secret_key,
+                progress,
+            ),
+            None => manager.reassemble_and_decrypt_file(
+                &chunks,
+                Path::new(&output_path_clone),
+                &Some(encrypted_key_bundle),
+                &secret_key, // Pass the secret key
+            ),
+        }
     })
     .await
     .map_err(|e| format!("Decryption task failed: {}", e))?
 }
 
+/// Check every chunk of a locally stored file against its manifest. Chunks are
+/// also decrypted and re-hashed when this session holds the file's AES key.
+#[tauri::command]
+async fn verify_file_integrity(
+    app: tauri::AppHandle,
+    state: State<'_, AppState>,
+    file_hash: String,
+) -> Result<manager::IntegrityReport, String> {
+    let aes_key = state
+        .canonical_aes_keys
+        .lock()
+        .await
+        .get(&file_hash)
+        .copied();
+    let chunk_storage_path = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Could not get app data directory: {}", e))?
+        .join("chunk_storage");
+
+    let report = tokio::task::spawn_blocking(move || {
+        ChunkManager::new(chunk_storage_path).verify_file_integrity(&file_hash, aes_key.as_ref())
+    })
+    .await
+    .map_err(|e| format!("Integrity check failed: {}", e))??;
+    Ok(report)
+}
+
+/// Write plaintext bytes `start..end` of a published file to `output_path`
+/// (e.g. for a media preview). A file stored here is read locally, decrypting
+/// only the chunks that cover the range; otherwise just those chunks are
+/// fetched by a multi-source range download, which reports completion through
+/// the usual download events. Returns the number of bytes written, or to be
+/// written once the download completes.
+#[tauri::command]
+async fn download_file_range(
+    app: tauri::AppHandle,
+    state: State<'_, AppState>,
+    file_hash: String,
+    start: u64,
+    end: u64,
+    output_path: String,
+) -> Result<u64, String> {
+    let aes_key = state
+        .canonical_aes_keys
+        .lock()
+        .await
+        .get(&file_hash)
+        .copied();
+    let chunk_storage_path = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Could not get app data directory: {}", e))?
+        .join("chunk_storage");
+
+    if let Some(aes_key) = aes_key {
+        let manager = ChunkManager::new(chunk_storage_path);
+        let written = tokio::task::spawn_blocking({
+            let file_hash = file_hash.clone();
+            let output_path = output_path.clone();
+            move || -> Result<Option<u64>, String> {
+                if !manager.has_range(&file_hash, start, end)? {
+                    return Ok(None);
+                }
+                let written = manager.download_file_range(
+                    &file_hash,
+                    start,
+                    end,
+                    Path::new(&output_path),
+                    &aes_key,
+                )?;
+                Ok(Some(written))
+            }
+        })
+        .await
+        .map_err(|e| format!("Range download failed: {}", e))??;
+        if let Some(written) = written {
+            return Ok(written);
+        }
+    }
+
+    let ms = {
+        let ms_guard = state.multi_source_download.lock().await;
+        ms_guard.as_ref().cloned()
+    };
+    let multi_source_service =
+        ms.ok_or_else(|| "Multi-source download service not available".to_string())?;
+    multi_source_service
+        .start_range_download(file_hash, output_path, start, end)
+        .await?;
+    Ok(end.saturating_sub(start))
+}
+
+/// List the files whose manifests are saved in local chunk storage, named
+/// from their published metadata where this node has it.
+#[tauri::command]
+async fn list_stored_files(
+    app: tauri::AppHandle,
+    state: State<'_, AppState>,
+) -> Result<Vec<manager::ManifestSummary>, String> {
+    let chunk_storage_path = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Could not get app data directory: {}", e))?
+        .join("chunk_storage");
+
+    let mut files =
+        tokio::task::spawn_blocking(move || ChunkManager::new(chunk_storage_path).list_manifests())
+            .await
+            .map_err(|e| format!("Listing stored files failed: {}", e))??;
+
+    let dht = { state.dht.lock().await.as_ref().cloned() };
+    if let Some(dht) = dht {
+        let metadata = dht.get_all_file_metadata().await?;
+        for file in &mut files {
+            file.file_name = metadata
+                .iter()
+                .find(|m| m.merkle_root == file.file_hash)
+                .map(|m| m.file_name.clone());
+        }
+    }
+    Ok(files)
+}
+
+/// Space saved by chunks shared between the files in local chunk storage.
+#[tauri::command]
+async fn get_dedup_stats(app: tauri::AppHandle) -> Result<manager::DedupStats, String> {
+    let chunk_storage_path = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Could not get app data directory: {}", e))?
+        .join("chunk_storage");
+
+    let stats = tokio::task::spawn_blocking(move || {
+        ChunkManager::new(chunk_storage_path).get_dedup_stats()
+    })
+    .await
+    .map_err(|e| format!("Reading dedup stats failed: {}", e))??;
+    Ok(stats)
+}
+
 #[tauri::command]
 async fn get_file_data(state: State<'_, AppState>, file_hash: String) -> Result<String, String> {
     let ft = {
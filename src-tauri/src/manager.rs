@@ -1,19 +1,32 @@
 use aes_gcm::aead::{Aead, AeadCore, OsRng};
 use aes_gcm::{Aes256Gcm, Key, KeyInit, Nonce};
+use chacha20poly1305::ChaCha20Poly1305;
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
 use rand::RngCore;
+use rayon::prelude::*;
 use rs_merkle::{Hasher, MerkleTree};
 use sha2::Digest;
+use std::borrow::Cow;
 use std::fs::{self, File};
-use std::io::{Error, Read, Write};
+use std::io::{Error, Read, Seek, SeekFrom, Write};
 use std::path::{Path, PathBuf};
-use std::sync::Mutex;
-use x25519_dalek::PublicKey;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant, SystemTime};
+use tokio::sync::mpsc;
+use tokio_util::sync::CancellationToken;
+use x25519_dalek::{PublicKey, StaticSecret};
 
 // Import the new encryption functions and the bundle struct
-use crate::encryption::{decrypt_aes_key, encrypt_aes_key, DiffieHellman, EncryptedAesKeyBundle};
+use crate::encryption::{
+    decrypt_aes_key, encrypt_aes_key, DiffieHellman, EncryptedAesKeyBundle, EncryptionInfo,
+    FileEncryption,
+};
+use crate::errors::ChiralError;
+use crate::local_cache::LocalCache;
 
 use lazy_static::lazy_static;
-use std::collections::HashMap;
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeSet, HashMap, HashSet};
 
 // Simple thread-safe LRU cache implementation
 const L1_CACHE_CAPACITY: usize = 128;
@@ -44,6 +57,12 @@ impl LruCache {
         }
     }
 
+    fn remove(&mut self, key: &str) {
+        if self.map.remove(key).is_some() {
+            self.order.retain(|k| k != key);
+        }
+    }
+
     fn put(&mut self, key: String, value: Vec<u8>) {
         if self.map.contains_key(&key) {
             self.order.retain(|k| k != &key);
@@ -63,15 +82,132 @@ impl LruCache {
 
 lazy_static! {
     static ref L1_CACHE: Mutex<LruCache> = Mutex::new(LruCache::new(L1_CACHE_CAPACITY));
+    // One lock per storage directory. Chunk writes share it; garbage collection
+    // and refcount updates hold it exclusively, so a chunk can't be deleted
+    // between the existence check in `save_chunk` and its use.
+    static ref CHUNK_STORE_LOCKS: Mutex<HashMap<PathBuf, Arc<RwLock<()>>>> =
+        Mutex::new(HashMap::new());
+    // Running total of chunk bytes per storage directory, seeded by one scan
+    // the first time a size-limited manager writes there.
+    static ref CHUNK_STORE_BYTES: Mutex<HashMap<PathBuf, u64>> = Mutex::new(HashMap::new());
+}
+
+/// `gc` leaves chunks written (or reused) more recently than this alone, since
+/// the file they belong to may still be chunking and have no manifest yet.
+pub const GC_GRACE_PERIOD: Duration = Duration::from_secs(60 * 60);
+
+/// Chunk plaintext is stored as-is.
+pub const COMPRESSION_NONE: u8 = 0;
+/// Chunk plaintext was zstd-compressed before encryption.
+pub const COMPRESSION_ZSTD: u8 = 1;
+
+const ZSTD_LEVEL: i32 = 3;
+
+/// Chunks read and encrypted concurrently; bounds memory while chunking to
+/// roughly this many chunks of plaintext plus ciphertext.
+const PARALLEL_CHUNK_WINDOW: usize = 16;
+
+/// Files at least this large are memory-mapped for hashing and chunking instead
+/// of being streamed through a heap buffer.
+const MMAP_THRESHOLD: u64 = 8 * 1024 * 1024;
+
+/// Bytes read from the start of a file for content sniffing; covers the magic
+/// numbers `infer` knows about, including container formats like ZIP and MP4.
+const MIME_SNIFF_LEN: usize = 8192;
+
+/// Chunks reassembled between saves of the `.chiralpart` sidecar. It is also
+/// saved once `PART_STATE_SAVE_INTERVAL` has passed and when reassembly fails,
+/// so a crash costs at most this much work to redo.
+const PART_STATE_SAVE_CHUNKS: usize = 64;
+const PART_STATE_SAVE_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Chunk size used when none is configured, and assumed for manifests that
+/// predate the `chunk_size` field.
+pub const DEFAULT_CHUNK_SIZE: usize = 256 * 1024;
+pub const MIN_CHUNK_SIZE: usize = 16 * 1024;
+pub const MAX_CHUNK_SIZE: usize = 16 * 1024 * 1024;
+
+/// Chunk sizes must be a power of two between `MIN_CHUNK_SIZE` and
+/// `MAX_CHUNK_SIZE` so chunk boundaries line up across every transfer path.
+pub fn validate_chunk_size(chunk_size: usize) -> Result<usize, String> {
+    if !chunk_size.is_power_of_two() {
+        return Err(format!("Chunk size {} is not a power of two", chunk_size));
+    }
+    if !(MIN_CHUNK_SIZE..=MAX_CHUNK_SIZE).contains(&chunk_size) {
+        return Err(format!(
+            "Chunk size {} is outside {}..={} bytes",
+            chunk_size, MIN_CHUNK_SIZE, MAX_CHUNK_SIZE
+        ));
+    }
+    Ok(chunk_size)
+}
+
+/// The valid chunk size closest to `chunk_size`: clamped to the allowed range
+/// and rounded to the nearer power of two (up on a tie).
+pub fn nearest_chunk_size(chunk_size: usize) -> usize {
+    let clamped = chunk_size.clamp(MIN_CHUNK_SIZE, MAX_CHUNK_SIZE);
+    let upper = clamped.next_power_of_two();
+    if upper == clamped {
+        return clamped;
+    }
+    let lower = upper / 2;
+    if clamped - lower < upper - clamped {
+        lower
+    } else {
+        upper
+    }
+}
+
+fn default_chunk_size() -> usize {
+    DEFAULT_CHUNK_SIZE
 }
 
 #[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+#[serde(from = "ChunkInfoRecord")]
 pub struct ChunkInfo {
     pub index: u32,
     pub hash: String,
     pub size: usize,
     pub encrypted_hash: String,
     pub encrypted_size: usize,
+    /// `COMPRESSION_NONE` or `COMPRESSION_ZSTD`
+    pub compression_type: u8,
+    /// Size of the plaintext that was encrypted (equal to `size` when uncompressed)
+    pub compressed_size: usize,
+    /// AEAD the chunk was encrypted with; chunks from older manifests are AES-GCM
+    pub cipher_suite: CipherSuite,
+}
+
+/// On-disk form of `ChunkInfo`; manifests written before compression existed
+/// have no `compressed_size`, which means the chunk was stored at `size`.
+#[derive(serde::Deserialize)]
+struct ChunkInfoRecord {
+    index: u32,
+    hash: String,
+    size: usize,
+    encrypted_hash: String,
+    encrypted_size: usize,
+    #[serde(default)]
+    compression_type: u8,
+    #[serde(default)]
+    compressed_size: Option<usize>,
+    #[serde(default)]
+    cipher_suite: CipherSuite,
+}
+
+impl From<ChunkInfoRecord> for ChunkInfo {
+    fn from(record: ChunkInfoRecord) -> Self {
+        ChunkInfo {
+            index: record.index,
+            hash: record.hash,
+            size: record.size,
+            encrypted_hash: record.encrypted_hash,
+            encrypted_size: record.encrypted_size,
+            compression_type: record.compression_type,
+            compressed_size: record.compressed_size.unwrap_or(record.size),
+            cipher_suite: record.cipher_suite,
+        }
+    }
 }
 
 /// Contains all metadata required to find, verify, and decrypt a file.
@@ -84,6 +220,252 @@ pub struct FileManifest {
     pub chunks: Vec<ChunkInfo>,
     /// The encrypted AES key bundle needed for decryption (None for unencrypted files).
     pub encrypted_key_bundle: Option<EncryptedAesKeyBundle>,
+    /// The hash used for chunk hashes and the Merkle root. Reassemble with a
+    /// `ChunkManager` configured for the same algorithm.
+    #[serde(default)]
+    pub hash_algorithm: HashAlgorithm,
+    /// The same AES key wrapped for additional recipients in
+    /// `encrypted_key_bundles`. See `add_recipient`.
+    #[serde(default)]
+    pub encryption_info: Option<EncryptionInfo>,
+    /// MIME type detected from the file's content (or extension) when chunked.
+    #[serde(default)]
+    pub mime_type: Option<String>,
+    /// Plaintext bytes per chunk; every chunk but the last has exactly this size.
+    #[serde(default = "default_chunk_size")]
+    pub chunk_size: usize,
+    /// Hash of the whole plaintext file under `hash_algorithm`. Manifests
+    /// written before this field existed don't have it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file_hash: Option<String>,
+    /// Publisher's signature over the rest of the manifest. See `sign`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature: Option<ManifestSignature>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ManifestSignature {
+    /// libp2p peer ID of the publisher; ed25519 peer IDs embed the public key
+    pub signer_peer_id: String,
+    /// Hex-encoded ed25519 signature
+    pub signature: String,
+}
+
+impl FileManifest {
+    /// The chunks holding plaintext bytes `start..end`, each with the file
+    /// offset it begins at. The range is half-open and must lie in the file.
+    pub fn chunks_for_range(&self, start: u64, end: u64) -> Result<Vec<(u64, &ChunkInfo)>, String> {
+        let file_size: u64 = self.chunks.iter().map(|c| c.size as u64).sum();
+        if start > end || end > file_size {
+            return Err(format!(
+                "Invalid range {}..{} for a {}-byte file",
+                start, end, file_size
+            ));
+        }
+
+        let mut covering = Vec::new();
+        let mut offset = 0u64;
+        for chunk in &self.chunks {
+            let chunk_end = offset + chunk.size as u64;
+            if chunk_end > start && offset < end {
+                covering.push((offset, chunk));
+            }
+            if chunk_end >= end {
+                break;
+            }
+            offset = chunk_end;
+        }
+        Ok(covering)
+    }
+
+    /// The key bundle `recipient_public_key` should decrypt with: its entry in
+    /// `encryption_info`, or else the primary `encrypted_key_bundle`.
+    pub fn key_bundle_for(
+        &self,
+        recipient_public_key: &PublicKey,
+    ) -> Option<&EncryptedAesKeyBundle> {
+        self.encryption_info
+            .as_ref()
+            .and_then(|info| info.key_bundle_for(recipient_public_key))
+            .or(self.encrypted_key_bundle.as_ref())
+    }
+
+    /// Sign the manifest as publisher `signer_peer_id`. Any later change,
+    /// including adding a recipient, invalidates the signature.
+    pub fn sign(&mut self, signing_key: &SigningKey, signer_peer_id: &str) -> Result<(), String> {
+        self.signature = None;
+        let signature = signing_key.sign(&self.signable_bytes()?);
+        self.signature = Some(ManifestSignature {
+            signer_peer_id: signer_peer_id.to_string(),
+            signature: hex::encode(signature.to_bytes()),
+        });
+        Ok(())
+    }
+
+    /// Whether the manifest is signed by `verifying_key` and unchanged since.
+    pub fn verify_signature(&self, verifying_key: &VerifyingKey) -> Result<bool, String> {
+        let signed = self.signature.as_ref().ok_or("Manifest is not signed")?;
+        let signature_bytes =
+            hex::decode(&signed.signature).map_err(|e| format!("Invalid signature: {}", e))?;
+        let signature = Signature::from_slice(&signature_bytes).map_err(|e| e.to_string())?;
+        Ok(verifying_key
+            .verify(&self.signable_bytes()?, &signature)
+            .is_ok())
+    }
+
+    /// Check a signed manifest against the key in its signer's peer ID.
+    /// Unsigned manifests pass.
+    pub fn verify_signer(&self) -> Result<(), String> {
+        let Some(signed) = &self.signature else {
+            return Ok(());
+        };
+        let verifying_key = crate::reputation::verifying_key_for_peer(&signed.signer_peer_id)?;
+        if !self.verify_signature(&verifying_key)? {
+            return Err(format!(
+                "Manifest {} signature does not match signer {}",
+                self.merkle_root, signed.signer_peer_id
+            ));
+        }
+        Ok(())
+    }
+
+    /// The manifest serialized without its signature
+    fn signable_bytes(&self) -> Result<Vec<u8>, String> {
+        let mut value = serde_json::to_value(self).map_err(|e| e.to_string())?;
+        if let Some(fields) = value.as_object_mut() {
+            fields.remove("signature");
+        }
+        serde_json::to_vec(&value).map_err(|e| e.to_string())
+    }
+
+    /// The whole-file hash as an IPFS CIDv1 (base32, raw codec), e.g.
+    /// `bafkrei...`. A raw CID's digest is the hash of the file's bytes, so
+    /// this needs `file_hash`; the Merkle root isn't one.
+    pub fn cid(&self) -> Result<String, String> {
+        let file_hash = self
+            .file_hash
+            .as_deref()
+            .ok_or("Manifest has no whole-file hash")?;
+        let digest = hex::decode(file_hash)
+            .map_err(|e| format!("Invalid content hash {}: {}", file_hash, e))?;
+        let multihash =
+            cid::multihash::Multihash::<64>::wrap(self.hash_algorithm.multihash_code(), &digest)
+                .map_err(|e| e.to_string())?;
+        Ok(cid::Cid::new_v1(CID_CODEC_RAW, multihash).to_string())
+    }
+}
+
+/// Multicodec for raw binary content
+pub const CID_CODEC_RAW: u64 = 0x55;
+
+/// Parse a CID (any version or multibase) back to the hex content hash used for
+/// lookups. Only SHA-256 and BLAKE3 multihashes are accepted.
+pub fn cid_to_hash(cid: &str) -> Result<String, String> {
+    let cid = cid::Cid::try_from(cid.trim()).map_err(|e| format!("Invalid CID: {}", e))?;
+    let multihash = cid.hash();
+    HashAlgorithm::from_multihash_code(multihash.code())
+        .ok_or_else(|| format!("Unsupported multihash code 0x{:x}", multihash.code()))?;
+    if multihash.digest().len() != 32 {
+        return Err(format!(
+            "Unexpected digest length {}",
+            multihash.digest().len()
+        ));
+    }
+    Ok(hex::encode(multihash.digest()))
+}
+
+/// Hash function used for chunk hashes, content addresses and the Merkle tree.
+/// Both produce 32-byte digests, so hashes keep the 64-hex-char layout.
+/// BLAKE3 is typically several times faster than SHA-256 on large files since it
+/// uses SIMD and doesn't need SHA extensions to be fast.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum HashAlgorithm {
+    #[default]
+    Sha256,
+    Blake3,
+}
+
+impl HashAlgorithm {
+    /// Multihash code from the multiformats table
+    pub fn multihash_code(self) -> u64 {
+        match self {
+            HashAlgorithm::Sha256 => 0x12,
+            HashAlgorithm::Blake3 => 0x1e,
+        }
+    }
+
+    pub fn from_multihash_code(code: u64) -> Option<Self> {
+        match code {
+            0x12 => Some(HashAlgorithm::Sha256),
+            0x1e => Some(HashAlgorithm::Blake3),
+            _ => None,
+        }
+    }
+
+    pub fn hash(self, data: &[u8]) -> [u8; 32] {
+        match self {
+            HashAlgorithm::Sha256 => Sha256Hasher::hash(data),
+            HashAlgorithm::Blake3 => Blake3Hasher::hash(data),
+        }
+    }
+
+    /// Incremental hasher for content fed in pieces
+    fn streaming(self) -> StreamingHash {
+        match self {
+            HashAlgorithm::Sha256 => StreamingHash::Sha256(sha2::Sha256::default()),
+            HashAlgorithm::Blake3 => StreamingHash::Blake3(Box::new(blake3::Hasher::new())),
+        }
+    }
+
+    /// Merkle root over `leaves`, built with this algorithm
+    pub fn merkle_root(self, leaves: &[[u8; 32]]) -> Option<[u8; 32]> {
+        match self {
+            HashAlgorithm::Sha256 => MerkleTree::<Sha256Hasher>::from_leaves(leaves).root(),
+            HashAlgorithm::Blake3 => MerkleTree::<Blake3Hasher>::from_leaves(leaves).root(),
+        }
+    }
+
+    fn merkle_proof(self, leaves: &[[u8; 32]], index: usize) -> Vec<[u8; 32]> {
+        match self {
+            HashAlgorithm::Sha256 => MerkleTree::<Sha256Hasher>::from_leaves(leaves)
+                .proof(&[index])
+                .proof_hashes()
+                .to_vec(),
+            HashAlgorithm::Blake3 => MerkleTree::<Blake3Hasher>::from_leaves(leaves)
+                .proof(&[index])
+                .proof_hashes()
+                .to_vec(),
+        }
+    }
+
+    fn verify_merkle_proof(
+        self,
+        root: [u8; 32],
+        leaf: [u8; 32],
+        indices: &[usize],
+        total_leaves: usize,
+        proof: &[[u8; 32]],
+    ) -> bool {
+        match self {
+            HashAlgorithm::Sha256 => rs_merkle::MerkleProof::<Sha256Hasher>::new(proof.to_vec())
+                .verify(root, indices, &[leaf], total_leaves),
+            HashAlgorithm::Blake3 => rs_merkle::MerkleProof::<Blake3Hasher>::new(proof.to_vec())
+                .verify(root, indices, &[leaf], total_leaves),
+        }
+    }
+}
+
+/// AEAD used for chunk encryption. Both take the same 32-byte canonical key and
+/// a 12-byte nonce prepended to the ciphertext. ChaCha20-Poly1305 is faster on
+/// CPUs without AES instructions (many ARM storage nodes).
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum CipherSuite {
+    #[default]
+    Aes256Gcm,
+    ChaCha20Poly1305,
 }
 
 /// A simple Sha256 hasher implementation for the Merkle tree.
@@ -100,31 +482,360 @@ impl Hasher for Sha256Hasher {
     }
 }
 
+/// BLAKE3 counterpart of `Sha256Hasher` (32-byte output).
+#[derive(Clone)]
+pub struct Blake3Hasher;
+
+impl Hasher for Blake3Hasher {
+    type Hash = [u8; 32];
+
+    fn hash(data: &[u8]) -> [u8; 32] {
+        blake3::hash(data).into()
+    }
+}
+
+/// Outcome of a `ChunkManager::gc` pass.
+#[derive(serde::Serialize, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct GcReport {
+    pub manifests_scanned: usize,
+    pub chunks_scanned: usize,
+    pub chunks_removed: usize,
+    pub bytes_reclaimed: u64,
+    /// Unreferenced chunks kept because they're within `GC_GRACE_PERIOD`
+    pub chunks_recent: usize,
+}
+
+/// Space saved by storing chunks shared between manifests once, as returned
+/// by `ChunkManager::get_dedup_stats`. Chunks are shared by encrypted hash,
+/// and each upload encrypts under a fresh random key and nonce, so only
+/// manifests referencing the same stored ciphertext count; the same
+/// plaintext uploaded twice as different files isn't deduplicated.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct DedupStats {
+    /// Chunk bytes on disk for the stored manifests
+    pub bytes_written: u64,
+    /// Chunk bytes the stored manifests reference, shared chunks counted per manifest
+    pub bytes_logical: u64,
+    /// References to a chunk that was already stored
+    pub chunks_reused: u64,
+}
+
+impl DedupStats {
+    pub fn bytes_saved(&self) -> u64 {
+        self.bytes_logical.saturating_sub(self.bytes_written)
+    }
+}
+
+/// Contents of the `refcounts.json` sidecar.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Default)]
+struct Refcounts {
+    counts: HashMap<String, u32>,
+    dedup: DedupStats,
+}
+
 pub struct ChunkManager {
     chunk_size: usize,
     storage_path: PathBuf,
+    compress_chunks: bool,
+    hash_algorithm: HashAlgorithm,
+    cipher_suite: CipherSuite,
+    max_storage_bytes: Option<u64>,
+    local_cache: Option<Arc<LocalCache>>,
+}
+
+/// A stored file, as listed by `ChunkManager::list_manifests`
+#[derive(serde::Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ManifestSummary {
+    pub file_hash: String,
+    /// Name from the file's published metadata; manifests don't record one
+    pub file_name: Option<String>,
+    /// Plaintext size, summed over the chunks
+    pub file_size: u64,
+    pub chunk_count: usize,
+    pub mime_type: Option<String>,
+    /// Whether every chunk is present in chunk storage
+    pub chunks_available: bool,
+}
+
+/// Result of `ChunkManager::verify_file_integrity`
+#[derive(serde::Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct IntegrityReport {
+    pub file_hash: String,
+    pub total_chunks: usize,
+    pub chunks_ok: usize,
+    /// Whether chunks were also decrypted and checked against the manifest's
+    /// plaintext hashes (requires the file's AES key)
+    pub decrypted: bool,
+    pub failures: Vec<ChunkIntegrityFailure>,
+}
+
+impl IntegrityReport {
+    pub fn is_ok(&self) -> bool {
+        self.failures.is_empty()
+    }
+}
+
+#[derive(serde::Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ChunkIntegrityFailure {
+    pub index: u32,
+    pub reason: String,
+}
+
+/// Reported after each chunk written by `reassemble_and_decrypt_file_with_progress`
+#[derive(serde::Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ReassembleProgress {
+    pub chunks_done: usize,
+    pub total_chunks: usize,
+    pub bytes_written: u64,
+}
+
+/// Chunks already written to a reassembly's output file, kept in an
+/// `<output>.chiralpart` sidecar so an interrupted download can resume
+#[derive(serde::Serialize, serde::Deserialize, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+struct PartState {
+    total_chunks: usize,
+    written: BTreeSet<u32>,
+}
+
+fn part_state_path(output_path: &Path) -> PathBuf {
+    let mut path = output_path.as_os_str().to_owned();
+    path.push(".chiralpart");
+    PathBuf::from(path)
+}
+
+/// Write the sidecar to a temporary file and rename it into place, so a crash
+/// leaves either the previous state or the new one.
+fn save_part_state(part_path: &Path, state: &PartState) -> Result<(), String> {
+    let json = serde_json::to_vec(state).map_err(|e| e.to_string())?;
+    let mut tmp_path = part_path.as_os_str().to_owned();
+    tmp_path.push(".tmp");
+    fs::write(&tmp_path, json)
+        .and_then(|()| fs::rename(&tmp_path, part_path))
+        .map_err(|e| e.to_string())
+}
+
+/// Whole-file hash built up as the file is read, so chunking doesn't need a
+/// second pass over the file to hash it.
+enum StreamingHash {
+    Sha256(sha2::Sha256),
+    Blake3(Box<blake3::Hasher>),
+}
+
+impl StreamingHash {
+    fn update(&mut self, data: &[u8]) {
+        match self {
+            StreamingHash::Sha256(hasher) => hasher.update(data),
+            StreamingHash::Blake3(hasher) => {
+                hasher.update(data);
+            }
+        }
+    }
+
+    /// Lowercase hex digest, the same as `ChunkManager::hash_file`
+    fn finalize_hex(self) -> String {
+        match self {
+            StreamingHash::Sha256(hasher) => format!("{:x}", hasher.finalize()),
+            StreamingHash::Blake3(hasher) => hasher.finalize().to_hex().to_string(),
+        }
+    }
+}
+
+/// Chunks written by one chunking pass over a file
+struct ChunkedFile {
+    chunks: Vec<ChunkInfo>,
+    /// Plaintext chunk hashes, the Merkle tree leaves
+    chunk_hashes: Vec<[u8; 32]>,
+    /// Hash of the whole file, computed in the same pass
+    file_hash: String,
 }
 
 /// The result of a canonical, one-time encryption of a file.
 pub struct CanonicalEncryptionResult {
     pub manifest: FileManifest,
     pub canonical_aes_key: [u8; 32],
+    /// Hash of the whole plaintext file in the manifest's hash algorithm,
+    /// equal to `ChunkManager::hash_file` on the same file
+    pub file_hash: String,
+}
+
+/// MIME type for `filename`'s extension, `application/octet-stream` if unknown.
+pub fn mime_type_from_extension(filename: &str) -> Option<String> {
+    let extension = filename.rsplit('.').next()?.to_lowercase();
+
+    match extension.as_str() {
+        // Images
+        "jpg" | "jpeg" => Some("image/jpeg".to_string()),
+        "png" => Some("image/png".to_string()),
+        "gif" => Some("image/gif".to_string()),
+        "bmp" => Some("image/bmp".to_string()),
+        "webp" => Some("image/webp".to_string()),
+        "svg" => Some("image/svg+xml".to_string()),
+        "ico" => Some("image/x-icon".to_string()),
+
+        // Videos
+        "mp4" => Some("video/mp4".to_string()),
+        "avi" => Some("video/x-msvideo".to_string()),
+        "mkv" => Some("video/x-matroska".to_string()),
+        "mov" => Some("video/quicktime".to_string()),
+        "wmv" => Some("video/x-ms-wmv".to_string()),
+        "flv" => Some("video/x-flv".to_string()),
+        "webm" => Some("video/webm".to_string()),
+
+        // Audio
+        "mp3" => Some("audio/mpeg".to_string()),
+        "wav" => Some("audio/wav".to_string()),
+        "flac" => Some("audio/flac".to_string()),
+        "aac" => Some("audio/aac".to_string()),
+        "ogg" => Some("audio/ogg".to_string()),
+        "wma" => Some("audio/x-ms-wma".to_string()),
+
+        // Documents
+        "pdf" => Some("application/pdf".to_string()),
+        "doc" => Some("application/msword".to_string()),
+        "docx" => Some(
+            "application/vnd.openxmlformats-officedocument.wordprocessingml.document".to_string(),
+        ),
+        "xls" => Some("application/vnd.ms-excel".to_string()),
+        "xlsx" => {
+            Some("application/vnd.openxmlformats-officedocument.spreadsheetml.sheet".to_string())
+        }
+        "ppt" => Some("application/vnd.ms-powerpoint".to_string()),
+        "pptx" => Some(
+            "application/vnd.openxmlformats-officedocument.presentationml.presentation".to_string(),
+        ),
+        "txt" => Some("text/plain".to_string()),
+        "rtf" => Some("application/rtf".to_string()),
+
+        // Archives
+        "zip" => Some("application/zip".to_string()),
+        "rar" => Some("application/x-rar-compressed".to_string()),
+        "7z" => Some("application/x-7z-compressed".to_string()),
+        "tar" => Some("application/x-tar".to_string()),
+        "gz" => Some("application/gzip".to_string()),
+
+        // Code files
+        "html" | "htm" => Some("text/html".to_string()),
+        "css" => Some("text/css".to_string()),
+        "js" => Some("application/javascript".to_string()),
+        "json" => Some("application/json".to_string()),
+        "xml" => Some("application/xml".to_string()),
+        "py" => Some("text/x-python".to_string()),
+        "rs" => Some("text/rust".to_string()),
+        "java" => Some("text/x-java-source".to_string()),
+        "cpp" | "cc" | "cxx" => Some("text/x-c++src".to_string()),
+        "c" => Some("text/x-csrc".to_string()),
+        "h" => Some("text/x-chdr".to_string()),
+        "hpp" => Some("text/x-c++hdr".to_string()),
+
+        // Other common types
+        "exe" => Some("application/x-msdownload".to_string()),
+        "dll" => Some("application/x-msdownload".to_string()),
+        "iso" => Some("application/x-iso9660-image".to_string()),
+
+        // Default fallback
+        _ => Some("application/octet-stream".to_string()),
+    }
 }
 
 impl ChunkManager {
+    /// Detect the MIME type from the magic bytes at the start of the file,
+    /// falling back to `file_name`'s extension.
+    pub fn detect_mime_type(first_chunk: &[u8], file_name: &str) -> String {
+        if let Some(kind) = infer::get(first_chunk) {
+            return kind.mime_type().to_string();
+        }
+        mime_type_from_extension(file_name)
+            .unwrap_or_else(|| "application/octet-stream".to_string())
+    }
+
+    /// Sniff the MIME type of the file at `file_path`
+    pub fn detect_file_mime_type(file_path: &Path) -> Result<String, String> {
+        let mut head = Vec::with_capacity(MIME_SNIFF_LEN);
+        File::open(file_path)
+            .map_err(|e| e.to_string())?
+            .take(MIME_SNIFF_LEN as u64)
+            .read_to_end(&mut head)
+            .map_err(|e| e.to_string())?;
+        let file_name = file_path
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        Ok(Self::detect_mime_type(&head, &file_name))
+    }
+
     pub fn new(storage_path: PathBuf) -> Self {
+        Self::with_compression(storage_path, false)
+    }
+
+    /// Like `new`, but with `compress_chunks` set chunks are zstd-compressed
+    /// before encryption whenever that makes them smaller.
+    pub fn with_compression(storage_path: PathBuf, compress_chunks: bool) -> Self {
         ChunkManager {
-            chunk_size: 256 * 1024, // 256KB
+            chunk_size: DEFAULT_CHUNK_SIZE,
             storage_path,
+            compress_chunks,
+            hash_algorithm: HashAlgorithm::default(),
+            cipher_suite: CipherSuite::default(),
+            max_storage_bytes: None,
+            local_cache: None,
         }
     }
 
+    /// Split files into chunks of `chunk_size` bytes; see `validate_chunk_size`.
+    pub fn with_chunk_size(mut self, chunk_size: usize) -> Result<Self, String> {
+        self.chunk_size = validate_chunk_size(chunk_size)?;
+        Ok(self)
+    }
+
+    pub fn chunk_size(&self) -> usize {
+        self.chunk_size
+    }
+
+    /// Refuse to store new chunks once stored chunks would exceed `bytes`.
+    pub fn with_max_storage_bytes(mut self, bytes: u64) -> Self {
+        self.max_storage_bytes = Some(bytes);
+        self
+    }
+
+    /// Also write each plaintext chunk into `cache` as it's chunked, so a later
+    /// download of the same content is served locally. Only SHA-256 chunk
+    /// hashes can be cached.
+    pub fn with_local_cache(mut self, cache: Arc<LocalCache>) -> Self {
+        self.local_cache = Some(cache);
+        self
+    }
+
+    pub fn local_cache(&self) -> Option<Arc<LocalCache>> {
+        self.local_cache.clone()
+    }
+
+    /// Use `algorithm` for chunk hashes when chunking and for verification when
+    /// reassembling (pass the manifest's `hash_algorithm` for the latter).
+    pub fn with_hash_algorithm(mut self, algorithm: HashAlgorithm) -> Self {
+        self.hash_algorithm = algorithm;
+        self
+    }
+
+    /// Encrypt new chunks with `cipher_suite`. Reassembly doesn't need this; each
+    /// chunk's `ChunkInfo` records the cipher it was encrypted with.
+    pub fn with_cipher_suite(mut self, cipher_suite: CipherSuite) -> Self {
+        self.cipher_suite = cipher_suite;
+        self
+    }
+
     pub fn chunk_and_encrypt_file(
         &self,
         file_path: &Path,
         recipient_public_key: &PublicKey,
     ) -> Result<FileManifest, String> {
-        let canonical_result = self.chunk_and_encrypt_file_canonical(file_path)?;
+        let canonical_result = self.encrypt_file_canonical(file_path, None, false)?;
         let mut manifest = canonical_result.manifest;
         let canonical_aes_key = canonical_result.canonical_aes_key;
 
@@ -139,98 +850,344 @@ impl ChunkManager {
     /// Encrypts a file once with a new, canonical AES key.
     /// This function is the first step in publishing a new encrypted file. It returns the manifest
     /// (which is public and key-agnostic) and the raw AES key, which the caller MUST store securely.
+    /// The plaintext chunks are added to the local cache, if one is set.
     pub fn chunk_and_encrypt_file_canonical(
         &self,
         file_path: &Path,
     ) -> Result<CanonicalEncryptionResult, String> {
+        Ok(self.encrypt_file_canonical(file_path, None, true)?)
+    }
+
+    /// `cache_plaintext` adds the plaintext chunks to the local cache; uploads
+    /// encrypted for a recipient pass `false` so their contents stay encrypted
+    /// at rest.
+    fn encrypt_file_canonical(
+        &self,
+        file_path: &Path,
+        cancel: Option<&CancellationToken>,
+        cache_plaintext: bool,
+    ) -> Result<CanonicalEncryptionResult, ChiralError> {
         // 1. Generate a new, single-use canonical AES key for the entire file.
         let mut key_bytes = [0u8; 32];
         OsRng.fill_bytes(&mut key_bytes);
         let key = Key::<Aes256Gcm>::from_slice(&key_bytes);
 
-        let mut file = File::open(file_path).map_err(|e| e.to_string())?;
-        let mut chunks_info = Vec::new();
-        let mut chunk_hashes: Vec<[u8; 32]> = Vec::new();
-        let mut buffer = vec![0u8; self.chunk_size];
-        let mut index = 0;
-
-        loop {
-            let bytes_read = file.read(&mut buffer).map_err(|e| e.to_string())?;
-            if bytes_read == 0 {
-                break;
-            }
-
-            let chunk_data = &buffer[..bytes_read];
-            // Hash the original, unencrypted chunk for the Merkle root.
-            let chunk_hash_bytes = Sha256Hasher::hash(chunk_data);
-            chunk_hashes.push(chunk_hash_bytes);
-            let chunk_hash_hex = hex::encode(chunk_hash_bytes);
-
-            // Encrypt the chunk with the canonical key.
-            let encrypted_chunk_with_nonce = self.encrypt_chunk(chunk_data, &key)?;
-            let encrypted_chunk_hash = Self::hash_data(&encrypted_chunk_with_nonce);
-            self.save_chunk(&encrypted_chunk_hash, &encrypted_chunk_with_nonce)
-                .map_err(|e| e.to_string())?;
-
-            chunks_info.push(ChunkInfo {
-                index,
-                hash: chunk_hash_hex.clone(),
-                size: bytes_read,
-                encrypted_hash: encrypted_chunk_hash,
-                encrypted_size: encrypted_chunk_with_nonce.len(),
-            });
-
-            index += 1;
-        }
+        let chunked = self.chunk_and_encrypt_windowed(
+            file_path,
+            key,
+            PARALLEL_CHUNK_WINDOW,
+            cancel,
+            cache_plaintext,
+        )?;
 
         // Build the Merkle tree from the original chunk hashes.
-        let merkle_tree = MerkleTree::<Sha256Hasher>::from_leaves(&chunk_hashes);
-        let merkle_root = merkle_tree.root().ok_or("Failed to compute Merkle root")?;
+        let merkle_root = self
+            .hash_algorithm
+            .merkle_root(&chunked.chunk_hashes)
+            .ok_or_else(|| ChiralError::Storage("Failed to compute Merkle root".to_string()))?;
 
         // Create a key-agnostic manifest. The key bundle will be added later for each recipient.
         let manifest = FileManifest {
             merkle_root: hex::encode(merkle_root),
-            chunks: chunks_info,
+            chunks: chunked.chunks,
             encrypted_key_bundle: None,
+            hash_algorithm: self.hash_algorithm,
+            encryption_info: None,
+            mime_type: Some(Self::detect_file_mime_type(file_path).map_err(ChiralError::Storage)?),
+            chunk_size: self.chunk_size,
+            signature: None,
+            file_hash: Some(chunked.file_hash.clone()),
         };
 
         // Return the manifest AND the raw AES key for secure storage by the caller.
         Ok(CanonicalEncryptionResult {
             manifest,
             canonical_aes_key: key_bytes,
+            file_hash: chunked.file_hash,
+        })
+    }
+
+    /// Like `chunk_and_encrypt_file`, but stops when `cancel` fires, removing
+    /// the chunks already written and returning `ChiralError::Cancelled`.
+    pub fn chunk_and_encrypt_file_cancellable(
+        &self,
+        file_path: &Path,
+        recipient_public_key: &PublicKey,
+        cancel: &CancellationToken,
+    ) -> Result<FileManifest, ChiralError> {
+        let canonical_result = self.encrypt_file_canonical(file_path, Some(cancel), false)?;
+        let mut manifest = canonical_result.manifest;
+        let encrypted_bundle =
+            encrypt_aes_key(&canonical_result.canonical_aes_key, recipient_public_key)
+                .map_err(ChiralError::Crypto)?;
+        manifest.encrypted_key_bundle = Some(encrypted_bundle);
+        Ok(manifest)
+    }
+
+    /// Chunk, encrypt under `key` and store a file, checking `cancel` between
+    /// windows of chunks. On cancel every chunk written so far is removed and
+    /// `ChiralError::Cancelled` is returned.
+    pub fn chunk_file_cancellable(
+        &self,
+        file_path: &Path,
+        key: &[u8; 32],
+        cancel: &CancellationToken,
+    ) -> Result<Vec<ChunkInfo>, ChiralError> {
+        let key = Key::<Aes256Gcm>::from_slice(key);
+        let chunked = self.chunk_and_encrypt_windowed(
+            file_path,
+            key,
+            PARALLEL_CHUNK_WINDOW,
+            Some(cancel),
+            false,
+        )?;
+        Ok(chunked.chunks)
+    }
+
+    /// Read, hash, compress, encrypt and store the file's chunks, `window` chunks
+    /// at a time across the rayon thread pool. At most `window` chunks are held
+    /// in memory at once; results come back in file order with contiguous indices.
+    /// The whole-file hash is computed from the same reads. `cancel` is checked
+    /// before each window.
+    fn chunk_and_encrypt_windowed(
+        &self,
+        file_path: &Path,
+        key: &Key<Aes256Gcm>,
+        window: usize,
+        cancel: Option<&CancellationToken>,
+        cache_plaintext: bool,
+    ) -> Result<ChunkedFile, ChiralError> {
+        let mut file = File::open(file_path)?;
+        let window = window.max(1);
+        let mut chunks_info = Vec::new();
+        let mut chunk_hashes: Vec<[u8; 32]> = Vec::new();
+        let mut file_hasher = self.hash_algorithm.streaming();
+        let is_cancelled = || cancel.is_some_and(|token| token.is_cancelled());
+
+        if let Some(mmap) = map_large_file(&file) {
+            let slices: Vec<&[u8]> = mmap.chunks(self.chunk_size).collect();
+            for batch in slices.chunks(window) {
+                if is_cancelled() {
+                    return Err(self.abort_chunking(&chunks_info));
+                }
+                batch.iter().for_each(|chunk| file_hasher.update(chunk));
+                self.process_chunk_batch(
+                    batch,
+                    key,
+                    cache_plaintext,
+                    &mut chunks_info,
+                    &mut chunk_hashes,
+                )
+                .map_err(ChiralError::Storage)?;
+            }
+            return Ok(ChunkedFile {
+                chunks: chunks_info,
+                chunk_hashes,
+                file_hash: file_hasher.finalize_hex(),
+            });
+        }
+
+        loop {
+            if is_cancelled() {
+                return Err(self.abort_chunking(&chunks_info));
+            }
+            let mut batch: Vec<Vec<u8>> = Vec::with_capacity(window);
+            while batch.len() < window {
+                let mut buffer = vec![0u8; self.chunk_size];
+                let bytes_read = fill_buffer(&mut file, &mut buffer)?;
+                if bytes_read == 0 {
+                    break;
+                }
+                buffer.truncate(bytes_read);
+                batch.push(buffer);
+            }
+            if batch.is_empty() {
+                break;
+            }
+            batch.iter().for_each(|chunk| file_hasher.update(chunk));
+            self.process_chunk_batch(
+                &batch,
+                key,
+                cache_plaintext,
+                &mut chunks_info,
+                &mut chunk_hashes,
+            )
+            .map_err(ChiralError::Storage)?;
+        }
+
+        Ok(ChunkedFile {
+            chunks: chunks_info,
+            chunk_hashes,
+            file_hash: file_hasher.finalize_hex(),
         })
     }
 
+    /// Remove the chunks a cancelled chunking run wrote. Each run encrypts
+    /// under a fresh key and nonces, so none of them can be shared with
+    /// another file.
+    fn abort_chunking(&self, written: &[ChunkInfo]) -> ChiralError {
+        let lock = self.store_lock();
+        let _guard = lock.write().unwrap_or_else(|e| e.into_inner());
+        let hashes: Vec<String> = written.iter().map(|c| c.encrypted_hash.clone()).collect();
+        match self.remove_chunks(&hashes) {
+            Ok(()) => ChiralError::Cancelled,
+            Err(e) => e,
+        }
+    }
+
+    /// Encrypt and store one window of chunks in parallel, appending the results
+    /// in order.
+    fn process_chunk_batch<T: AsRef<[u8]> + Sync>(
+        &self,
+        batch: &[T],
+        key: &Key<Aes256Gcm>,
+        cache_plaintext: bool,
+        chunks_info: &mut Vec<ChunkInfo>,
+        chunk_hashes: &mut Vec<[u8; 32]>,
+    ) -> Result<(), String> {
+        let first_index = chunks_info.len() as u32;
+        let processed = batch
+            .par_iter()
+            .enumerate()
+            .map(|(offset, data)| {
+                self.encrypt_and_store_chunk(
+                    first_index + offset as u32,
+                    data.as_ref(),
+                    key,
+                    cache_plaintext,
+                )
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        for (info, hash) in processed {
+            chunks_info.push(info);
+            chunk_hashes.push(hash);
+        }
+        Ok(())
+    }
+
+    /// Hash the original chunk for the Merkle root, then compress (if enabled and
+    /// worthwhile), encrypt with the canonical key and save it. The plaintext is
+    /// only cached when `cache_plaintext` is set.
+    fn encrypt_and_store_chunk(
+        &self,
+        index: u32,
+        chunk_data: &[u8],
+        key: &Key<Aes256Gcm>,
+        cache_plaintext: bool,
+    ) -> Result<(ChunkInfo, [u8; 32]), String> {
+        let chunk_hash_bytes = self.hash_algorithm.hash(chunk_data);
+
+        let (compression_type, plaintext) = self.compress_chunk(chunk_data)?;
+        let encrypted_chunk_with_nonce = self.encrypt_chunk(&plaintext, key)?;
+        let encrypted_chunk_hash = self.hash_data(&encrypted_chunk_with_nonce);
+        self.save_chunk(&encrypted_chunk_hash, &encrypted_chunk_with_nonce)?;
+        let cache = self.local_cache.as_ref().filter(|_| cache_plaintext);
+        if let (Some(cache), HashAlgorithm::Sha256) = (cache, self.hash_algorithm) {
+            if let Err(e) = cache.put(&hex::encode(chunk_hash_bytes), chunk_data) {
+                tracing::warn!("Failed to cache chunk {}: {}", index, e);
+            }
+        }
+
+        let info = ChunkInfo {
+            index,
+            hash: hex::encode(chunk_hash_bytes),
+            size: chunk_data.len(),
+            encrypted_hash: encrypted_chunk_hash,
+            encrypted_size: encrypted_chunk_with_nonce.len(),
+            compression_type,
+            compressed_size: plaintext.len(),
+            cipher_suite: self.cipher_suite,
+        };
+        Ok((info, chunk_hash_bytes))
+    }
+
+    /// Compress a chunk with zstd when enabled, keeping the original bytes if
+    /// compression doesn't make them smaller.
+    fn compress_chunk<'a>(&self, data: &'a [u8]) -> Result<(u8, Cow<'a, [u8]>), String> {
+        if !self.compress_chunks {
+            return Ok((COMPRESSION_NONE, Cow::Borrowed(data)));
+        }
+        let compressed = zstd::bulk::compress(data, ZSTD_LEVEL)
+            .map_err(|e| format!("Chunk compression failed: {}", e))?;
+        if compressed.len() < data.len() {
+            Ok((COMPRESSION_ZSTD, Cow::Owned(compressed)))
+        } else {
+            Ok((COMPRESSION_NONE, Cow::Borrowed(data)))
+        }
+    }
+
+    /// Decrypt a stored chunk and undo its compression, if any
+    fn decode_chunk(
+        &self,
+        chunk_info: &ChunkInfo,
+        data_with_nonce: &[u8],
+        key: &Key<Aes256Gcm>,
+    ) -> Result<Vec<u8>, String> {
+        let decrypted = self.decrypt_chunk(data_with_nonce, key, chunk_info.cipher_suite)?;
+        match chunk_info.compression_type {
+            COMPRESSION_NONE => Ok(decrypted),
+            COMPRESSION_ZSTD => zstd::bulk::decompress(&decrypted, chunk_info.size)
+                .map_err(|e| format!("Chunk {} decompression failed: {}", chunk_info.index, e)),
+            other => Err(format!(
+                "Chunk {} uses unknown compression type {}",
+                chunk_info.index, other
+            )),
+        }
+    }
+
     // This function now returns the nonce and ciphertext combined for easier storage
     fn encrypt_chunk(&self, data: &[u8], key: &Key<Aes256Gcm>) -> Result<Vec<u8>, String> {
-        let cipher = Aes256Gcm::new(key);
-        let nonce = Aes256Gcm::generate_nonce(&mut OsRng); // Generate a unique nonce for each chunk
-        let ciphertext = cipher.encrypt(&nonce, data).map_err(|e| e.to_string())?;
+        // Generate a unique nonce for each chunk
+        let (nonce, ciphertext) = match self.cipher_suite {
+            CipherSuite::Aes256Gcm => {
+                let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+                (nonce, Aes256Gcm::new(key).encrypt(&nonce, data))
+            }
+            CipherSuite::ChaCha20Poly1305 => {
+                let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+                (nonce, ChaCha20Poly1305::new(key).encrypt(&nonce, data))
+            }
+        };
+        let ciphertext = ciphertext.map_err(|e| e.to_string())?;
         let mut result = nonce.to_vec();
         result.extend_from_slice(&ciphertext);
         Ok(result)
     }
 
-    fn hash_data(data: &[u8]) -> String {
-        let mut hasher = sha2::Sha256::default();
-        hasher.update(data);
-        format!("{:x}", hasher.finalize())
+    fn hash_data(&self, data: &[u8]) -> String {
+        hex::encode(self.hash_algorithm.hash(data))
     }
 
     // This function now saves the combined [nonce][ciphertext] blob
-    pub fn save_chunk(&self, hash: &str, data_with_nonce: &[u8]) -> Result<(), Error> {
+    pub fn save_chunk(&self, hash: &str, data_with_nonce: &[u8]) -> Result<(), ChiralError> {
+        let lock = self.store_lock();
+        let _guard = lock.read().unwrap_or_else(|e| e.into_inner());
         fs::create_dir_all(&self.storage_path)?;
         let chunk_path = self.storage_path.join(hash);
         // --- Deduplication: Only write if the chunk does not already exist ---
         if chunk_path.exists() {
-            // Already present, skip writing
+            // Already present, skip writing. Refresh its mtime so `gc` treats
+            // it as in use until this file's manifest is saved.
+            File::options()
+                .write(true)
+                .open(&chunk_path)?
+                .set_modified(SystemTime::now())?;
             // Prime the L1 cache anyway
             if let Ok(mut cache) = L1_CACHE.lock() {
                 cache.put(hash.to_string(), data_with_nonce.to_vec());
             }
             return Ok(());
         }
-        fs::write(&chunk_path, data_with_nonce)?;
+        let needed = data_with_nonce.len() as u64;
+        self.reserve_chunk_bytes(needed)?;
+        // Write to a temp file and rename so a crash never leaves a truncated chunk
+        let tmp_path = self.storage_path.join(format!("{}.tmp", hash));
+        let written =
+            fs::write(&tmp_path, data_with_nonce).and_then(|()| fs::rename(&tmp_path, &chunk_path));
+        if let Err(e) = written {
+            self.release_chunk_bytes(needed);
+            return Err(e.into());
+        }
         // Prime the L1 cache
         {
             if let Ok(mut cache) = L1_CACHE.lock() {
@@ -240,7 +1197,7 @@ impl ChunkManager {
         Ok(())
     }
 
-    pub fn read_chunk(&self, hash: &str) -> Result<Vec<u8>, Error> {
+    pub fn read_chunk(&self, hash: &str) -> Result<Vec<u8>, ChiralError> {
         // Check L1 cache first
         {
             if let Ok(mut cache) = L1_CACHE.lock() {
@@ -250,7 +1207,13 @@ impl ChunkManager {
             }
         }
         // Fallback to disk
-        let data = fs::read(self.storage_path.join(hash))?;
+        let data = match fs::read(self.storage_path.join(hash)) {
+            Ok(data) => data,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                return Err(ChiralError::NotFound(format!("chunk {}", hash)))
+            }
+            Err(e) => return Err(e.into()),
+        };
         // Populate L1 cache
         {
             if let Ok(mut cache) = L1_CACHE.lock() {
@@ -260,24 +1223,455 @@ impl ChunkManager {
         Ok(data)
     }
 
-    fn decrypt_chunk(
-        &self,
-        data_with_nonce: &[u8],
-        key: &Key<Aes256Gcm>,
-    ) -> Result<Vec<u8>, String> {
-        let cipher = Aes256Gcm::new(key);
-        // AES-GCM nonce is 12 bytes. The nonce is prepended to the ciphertext.
-        if data_with_nonce.len() < 12 {
-            return Err("Encrypted data is too short to contain a nonce".to_string());
+    /// Count `needed` bytes against the running total, refusing with
+    /// `CapacityFull` if that would go over `max_storage_bytes`.
+    fn reserve_chunk_bytes(&self, needed: u64) -> Result<(), ChiralError> {
+        let mut totals = CHUNK_STORE_BYTES.lock().unwrap_or_else(|e| e.into_inner());
+        let used = match totals.entry(self.storage_path.clone()) {
+            Entry::Occupied(used) => used.into_mut(),
+            // Without a limit there's nothing to check, so don't pay for the scan
+            Entry::Vacant(_) if self.max_storage_bytes.is_none() => return Ok(()),
+            Entry::Vacant(entry) => entry.insert(self.stored_chunk_bytes()?),
+        };
+        if let Some(limit) = self.max_storage_bytes {
+            if *used + needed > limit {
+                return Err(ChiralError::CapacityFull {
+                    needed,
+                    available: limit.saturating_sub(*used),
+                });
+            }
+        }
+        *used += needed;
+        Ok(())
+    }
+
+    fn release_chunk_bytes(&self, bytes: u64) {
+        let mut totals = CHUNK_STORE_BYTES.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(used) = totals.get_mut(&self.storage_path) {
+            *used = used.saturating_sub(bytes);
+        }
+    }
+
+    /// Total size of the chunk files in storage.
+    fn stored_chunk_bytes(&self) -> Result<u64, ChiralError> {
+        let entries = match fs::read_dir(&self.storage_path) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+            Err(e) => return Err(e.into()),
+        };
+        let mut total = 0;
+        for entry in entries {
+            let entry = entry?;
+            if entry.file_name().to_str().is_some_and(is_chunk_file_name) {
+                total += entry.metadata()?.len();
+            }
+        }
+        Ok(total)
+    }
+
+    /// Manifests saved with `save_manifest` live here, one `<merkle_root>.json` each.
+    pub fn manifests_dir(&self) -> PathBuf {
+        self.storage_path.join("manifests")
+    }
+
+    fn manifest_path(&self, file_hash: &str) -> PathBuf {
+        self.manifests_dir().join(format!("{}.json", file_hash))
+    }
+
+    /// Persist a manifest so `gc` treats its chunks as live.
+    pub fn save_manifest(&self, manifest: &FileManifest) -> Result<(), ChiralError> {
+        fs::create_dir_all(self.manifests_dir())?;
+        let json = serde_json::to_vec_pretty(manifest)?;
+        fs::write(self.manifest_path(&manifest.merkle_root), json)?;
+        Ok(())
+    }
+
+    /// Load every manifest in `manifests_dir`. Fails on any unreadable manifest
+    /// rather than skipping it, since its chunks would otherwise look orphaned.
+    fn load_manifests(&self) -> Result<Vec<FileManifest>, ChiralError> {
+        let entries = match fs::read_dir(self.manifests_dir()) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+        let mut manifests = Vec::new();
+        for entry in entries {
+            let path = entry?.path();
+            if path.extension().and_then(|ext| ext.to_str()) != Some("json") {
+                continue;
+            }
+            let bytes = fs::read(&path)?;
+            let manifest = serde_json::from_slice(&bytes).map_err(|e| {
+                ChiralError::Serialization(format!("Invalid manifest {}: {}", path.display(), e))
+            })?;
+            manifests.push(manifest);
+        }
+        Ok(manifests)
+    }
+
+    /// Summaries of every saved manifest, sorted by file hash.
+    pub fn list_manifests(&self) -> Result<Vec<ManifestSummary>, ChiralError> {
+        let mut summaries: Vec<ManifestSummary> = self
+            .load_manifests()?
+            .iter()
+            .map(|manifest| ManifestSummary {
+                file_hash: manifest.merkle_root.clone(),
+                file_name: None,
+                file_size: manifest.chunks.iter().map(|c| c.size as u64).sum(),
+                chunk_count: manifest.chunks.len(),
+                mime_type: manifest.mime_type.clone(),
+                chunks_available: self.verify_chunks_available(manifest),
+            })
+            .collect();
+        summaries.sort_by(|a, b| a.file_hash.cmp(&b.file_hash));
+        Ok(summaries)
+    }
+
+    /// Whether every chunk of `manifest` is present in chunk storage. Only
+    /// checks existence; see `verify_file_integrity` for contents.
+    pub fn verify_chunks_available(&self, manifest: &FileManifest) -> bool {
+        manifest
+            .chunks
+            .iter()
+            .all(|chunk| self.storage_path.join(&chunk.encrypted_hash).is_file())
+    }
+
+    fn store_lock(&self) -> Arc<RwLock<()>> {
+        CHUNK_STORE_LOCKS
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .entry(self.storage_path.clone())
+            .or_default()
+            .clone()
+    }
+
+    /// Delete every stored chunk that no manifest in `manifests_dir` references
+    /// and that is older than `GC_GRACE_PERIOD`.
+    pub fn gc(&self) -> Result<GcReport, ChiralError> {
+        self.gc_older_than(GC_GRACE_PERIOD)
+    }
+
+    fn gc_older_than(&self, grace_period: Duration) -> Result<GcReport, ChiralError> {
+        let lock = self.store_lock();
+        let _guard = lock.write().unwrap_or_else(|e| e.into_inner());
+        let cutoff = SystemTime::now() - grace_period;
+
+        let manifests = self.load_manifests()?;
+        let referenced: HashSet<&str> = manifests
+            .iter()
+            .flat_map(|m| m.chunks.iter().map(|c| c.encrypted_hash.as_str()))
+            .collect();
+
+        let mut report = GcReport {
+            manifests_scanned: manifests.len(),
+            ..GcReport::default()
+        };
+        let entries = match fs::read_dir(&self.storage_path) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(report),
+            Err(e) => return Err(e.into()),
+        };
+        for entry in entries {
+            let entry = entry?;
+            let metadata = entry.metadata()?;
+            let name = entry.file_name();
+            // Only chunk files (named by their 64-hex-char hash) are candidates
+            let hash = match name.to_str() {
+                Some(n) if metadata.is_file() && is_chunk_file_name(n) => n,
+                _ => continue,
+            };
+            report.chunks_scanned += 1;
+            if referenced.contains(hash) {
+                continue;
+            }
+            if metadata.modified()? > cutoff {
+                report.chunks_recent += 1;
+                continue;
+            }
+            fs::remove_file(entry.path())?;
+            self.release_chunk_bytes(metadata.len());
+            if let Ok(mut cache) = L1_CACHE.lock() {
+                cache.remove(hash);
+            }
+            report.chunks_removed += 1;
+            report.bytes_reclaimed += metadata.len();
+        }
+        Ok(report)
+    }
+
+    fn refcounts_path(&self) -> PathBuf {
+        self.storage_path.join("refcounts.json")
+    }
+
+    fn load_refcounts(&self) -> Result<Refcounts, ChiralError> {
+        let bytes = match fs::read(self.refcounts_path()) {
+            Ok(bytes) => bytes,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Refcounts::default()),
+            Err(e) => return Err(e.into()),
+        };
+        Ok(serde_json::from_slice(&bytes)?)
+    }
+
+    fn save_refcounts(&self, refcounts: &Refcounts) -> Result<(), ChiralError> {
+        fs::create_dir_all(&self.storage_path)?;
+        let json = serde_json::to_vec(refcounts)?;
+        let tmp_path = self.storage_path.join("refcounts.json.tmp");
+        fs::write(&tmp_path, json)?;
+        fs::rename(&tmp_path, self.refcounts_path())?;
+        Ok(())
+    }
+
+    /// Number of stored manifests referencing the chunk with this encrypted hash.
+    pub fn chunk_refcount(&self, encrypted_hash: &str) -> Result<u32, ChiralError> {
+        Ok(self
+            .load_refcounts()?
+            .counts
+            .get(encrypted_hash)
+            .copied()
+            .unwrap_or(0))
+    }
+
+    /// How much chunk storage the stored manifests share. See `DedupStats`
+    /// for what counts as shared.
+    pub fn get_dedup_stats(&self) -> Result<DedupStats, ChiralError> {
+        Ok(self.load_refcounts()?.dedup)
+    }
+
+    /// Chunk, encrypt and store a file, then save its manifest with
+    /// `store_manifest`.
+    pub fn store_file_with_manifest(
+        &self,
+        file_path: &Path,
+    ) -> Result<CanonicalEncryptionResult, ChiralError> {
+        let result = self
+            .chunk_and_encrypt_file_canonical(file_path)
+            .map_err(ChiralError::Storage)?;
+        self.store_manifest(&result.manifest)?;
+        Ok(result)
+    }
+
+    /// Save a manifest whose chunks are already in storage and count one more
+    /// reference for each of its chunks. Storing a file that is already stored
+    /// replaces the old manifest, releasing the chunks it referenced.
+    pub fn store_manifest(&self, manifest: &FileManifest) -> Result<(), ChiralError> {
+        if !self.verify_chunks_available(manifest) {
+            return Err(ChiralError::NotFound(format!(
+                "chunks of file {}",
+                manifest.merkle_root
+            )));
+        }
+
+        let lock = self.store_lock();
+        let _guard = lock.write().unwrap_or_else(|e| e.into_inner());
+        let mut refcounts = self.load_refcounts()?;
+        retain_chunks(&mut refcounts, manifest);
+        // Release the previous copy after counting the new one so chunks both
+        // share never drop to zero in between.
+        let released = match self.read_manifest(&manifest.merkle_root)? {
+            Some(previous) => release_chunks(&mut refcounts, &previous),
+            None => Vec::new(),
+        };
+        self.save_manifest(manifest)?;
+        self.save_refcounts(&refcounts)?;
+        self.remove_chunks(&released)
+    }
+
+    /// Remove a stored file's manifest and drop its chunk references, deleting
+    /// chunks from disk once no manifest references them.
+    pub fn delete_file(&self, file_hash: &str) -> Result<(), ChiralError> {
+        let lock = self.store_lock();
+        let _guard = lock.write().unwrap_or_else(|e| e.into_inner());
+        let manifest = self
+            .read_manifest(file_hash)?
+            .ok_or_else(|| ChiralError::NotFound(format!("manifest for file {}", file_hash)))?;
+
+        let mut refcounts = self.load_refcounts()?;
+        let released = release_chunks(&mut refcounts, &manifest);
+        fs::remove_file(self.manifest_path(file_hash))?;
+        self.save_refcounts(&refcounts)?;
+        self.remove_chunks(&released)
+    }
+
+    /// Check a stored file without reassembling it. Every chunk in its saved
+    /// manifest must exist, have the recorded size and hash to its
+    /// `encrypted_hash`; given the file's AES key, each chunk is also decrypted
+    /// and compared with its plaintext hash. All bad chunks are reported.
+    pub fn verify_file_integrity(
+        &self,
+        file_hash: &str,
+        aes_key: Option<&[u8; 32]>,
+    ) -> Result<IntegrityReport, ChiralError> {
+        let manifest = self
+            .read_manifest(file_hash)?
+            .ok_or_else(|| ChiralError::NotFound(format!("manifest for file {}", file_hash)))?;
+        let key = aes_key.map(|k| *Key::<Aes256Gcm>::from_slice(k));
+
+        let failures: Vec<ChunkIntegrityFailure> = manifest
+            .chunks
+            .iter()
+            .filter_map(|chunk| {
+                self.verify_stored_chunk(chunk, manifest.hash_algorithm, key.as_ref())
+                    .err()
+                    .map(|reason| ChunkIntegrityFailure {
+                        index: chunk.index,
+                        reason,
+                    })
+            })
+            .collect();
+
+        Ok(IntegrityReport {
+            file_hash: file_hash.to_string(),
+            total_chunks: manifest.chunks.len(),
+            chunks_ok: manifest.chunks.len() - failures.len(),
+            decrypted: key.is_some(),
+            failures,
+        })
+    }
+
+    fn verify_stored_chunk(
+        &self,
+        chunk_info: &ChunkInfo,
+        hash_algorithm: HashAlgorithm,
+        key: Option<&Key<Aes256Gcm>>,
+    ) -> Result<(), String> {
+        // Read from disk rather than the L1 cache so on-disk corruption is seen
+        let data = match fs::read(self.storage_path.join(&chunk_info.encrypted_hash)) {
+            Ok(data) => data,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                return Err("Chunk is missing".to_string())
+            }
+            Err(e) => return Err(format!("Failed to read chunk: {}", e)),
+        };
+        if data.len() != chunk_info.encrypted_size {
+            return Err(format!(
+                "Chunk is {} bytes, expected {}",
+                data.len(),
+                chunk_info.encrypted_size
+            ));
+        }
+        if hex::encode(hash_algorithm.hash(&data)) != chunk_info.encrypted_hash {
+            return Err("Stored bytes do not match the chunk checksum".to_string());
+        }
+
+        if let Some(key) = key {
+            let mut plaintext = self.decode_chunk(chunk_info, &data, key)?;
+            plaintext.truncate(chunk_info.size);
+            if hex::encode(hash_algorithm.hash(&plaintext)) != chunk_info.hash {
+                return Err("Decrypted data does not match the manifest hash".to_string());
+            }
+        }
+        Ok(())
+    }
+
+    /// Decrypt plaintext bytes `start..end` of a stored file into `output_path`,
+    /// reading only the chunks that overlap the range. Returns the number of
+    /// bytes written.
+    pub fn download_file_range(
+        &self,
+        file_hash: &str,
+        start: u64,
+        end: u64,
+        output_path: &Path,
+        aes_key: &[u8; 32],
+    ) -> Result<u64, ChiralError> {
+        let manifest = self
+            .read_manifest(file_hash)?
+            .ok_or_else(|| ChiralError::NotFound(format!("manifest for file {}", file_hash)))?;
+        let covering = manifest
+            .chunks_for_range(start, end)
+            .map_err(ChiralError::Storage)?;
+        let key = Key::<Aes256Gcm>::from_slice(aes_key);
+
+        let mut output_file = File::create(output_path)?;
+        let mut bytes_written = 0u64;
+        for (offset, chunk_info) in covering {
+            let encrypted_chunk = self.read_chunk(&chunk_info.encrypted_hash)?;
+            let mut plaintext = self
+                .decode_chunk(chunk_info, &encrypted_chunk, key)
+                .map_err(ChiralError::Crypto)?;
+            plaintext.truncate(chunk_info.size);
+            if hex::encode(manifest.hash_algorithm.hash(&plaintext)) != chunk_info.hash {
+                return Err(ChiralError::Storage(format!(
+                    "Hash mismatch for chunk {}",
+                    chunk_info.index
+                )));
+            }
+
+            // Trim the first and last chunks to the requested bytes
+            let from = start.saturating_sub(offset) as usize;
+            let to = (end - offset).min(chunk_info.size as u64) as usize;
+            output_file.write_all(&plaintext[from..to])?;
+            bytes_written += (to - from) as u64;
+        }
+        Ok(bytes_written)
+    }
+
+    /// Whether the manifest and every chunk covering plaintext bytes
+    /// `start..end` of a stored file are on disk.
+    pub fn has_range(&self, file_hash: &str, start: u64, end: u64) -> Result<bool, ChiralError> {
+        let manifest = match self.read_manifest(file_hash)? {
+            Some(manifest) => manifest,
+            None => return Ok(false),
+        };
+        let covering = manifest
+            .chunks_for_range(start, end)
+            .map_err(ChiralError::Storage)?;
+        Ok(covering
+            .iter()
+            .all(|(_, chunk)| self.storage_path.join(&chunk.encrypted_hash).is_file()))
+    }
+
+    /// Load a saved manifest, rejecting it if its signature doesn't verify
+    fn read_manifest(&self, file_hash: &str) -> Result<Option<FileManifest>, ChiralError> {
+        match fs::read(self.manifest_path(file_hash)) {
+            Ok(bytes) => {
+                let manifest: FileManifest = serde_json::from_slice(&bytes)?;
+                manifest.verify_signer().map_err(ChiralError::Crypto)?;
+                Ok(Some(manifest))
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn remove_chunks(&self, hashes: &[String]) -> Result<(), ChiralError> {
+        for hash in hashes {
+            let path = self.storage_path.join(hash);
+            let size = fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+            match fs::remove_file(&path) {
+                Ok(()) => self.release_chunk_bytes(size),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => return Err(e.into()),
+            }
+            if let Ok(mut cache) = L1_CACHE.lock() {
+                cache.remove(hash);
+            }
+        }
+        Ok(())
+    }
+
+    fn decrypt_chunk(
+        &self,
+        data_with_nonce: &[u8],
+        key: &Key<Aes256Gcm>,
+        cipher_suite: CipherSuite,
+    ) -> Result<Vec<u8>, String> {
+        // Both ciphers use a 12-byte nonce. The nonce is prepended to the ciphertext.
+        if data_with_nonce.len() < 12 {
+            return Err("Encrypted data is too short to contain a nonce".to_string());
         }
         let (nonce_bytes, ciphertext) = data_with_nonce.split_at(12);
         let nonce = Nonce::from_slice(nonce_bytes);
 
-        cipher
-            .decrypt(nonce, ciphertext)
-            .map_err(|e| format!("Chunk decryption failed: {}", e))
+        match cipher_suite {
+            CipherSuite::Aes256Gcm => Aes256Gcm::new(key).decrypt(nonce, ciphertext),
+            CipherSuite::ChaCha20Poly1305 => ChaCha20Poly1305::new(key).decrypt(nonce, ciphertext),
+        }
+        .map_err(|e| format!("Chunk decryption failed: {}", e))
     }
 
+    /// Decrypt `chunks` into `output_path`. An interrupted reassembly leaves an
+    /// `<output>.chiralpart` sidecar, and calling this again resumes from it.
     pub fn reassemble_and_decrypt_file<S: DiffieHellman>(
         &self,
         chunks: &[ChunkInfo],
@@ -285,45 +1679,186 @@ impl ChunkManager {
         encrypted_key_bundle: &Option<EncryptedAesKeyBundle>,
         recipient_secret_key: S,
     ) -> Result<(), String> {
+        self.reassemble_and_decrypt_file_inner(
+            chunks,
+            output_path,
+            encrypted_key_bundle,
+            recipient_secret_key,
+            None,
+        )
+        .map(|_| ())
+    }
+
+    /// Like `reassemble_and_decrypt_file`, but sends a `ReassembleProgress` after
+    /// each chunk is written. Must be called off the async runtime (e.g. from
+    /// `spawn_blocking`). A dropped receiver doesn't stop the reassembly.
+    pub fn reassemble_and_decrypt_file_with_progress<S: DiffieHellman>(
+        &self,
+        chunks: &[ChunkInfo],
+        output_path: &Path,
+        encrypted_key_bundle: &Option<EncryptedAesKeyBundle>,
+        recipient_secret_key: S,
+        progress: mpsc::Sender<ReassembleProgress>,
+    ) -> Result<(), String> {
+        self.reassemble_and_decrypt_file_inner(
+            chunks,
+            output_path,
+            encrypted_key_bundle,
+            recipient_secret_key,
+            Some(&progress),
+        )
+        .map(|_| ())
+    }
+
+    /// Reassemble into `output_path`, resuming from its `.chiralpart` sidecar:
+    /// chunks recorded there are kept if their bytes in the output still hash
+    /// to the manifest's chunk hash, and only the rest are read and decrypted.
+    /// Returns the number of chunks decrypted.
+    fn reassemble_and_decrypt_file_inner<S: DiffieHellman>(
+        &self,
+        chunks: &[ChunkInfo],
+        output_path: &Path,
+        encrypted_key_bundle: &Option<EncryptedAesKeyBundle>,
+        recipient_secret_key: S,
+        progress: Option<&mpsc::Sender<ReassembleProgress>>,
+    ) -> Result<usize, String> {
         let key_bytes = match encrypted_key_bundle {
             Some(bundle) => decrypt_aes_key(bundle, recipient_secret_key)?,
             None => return Err("No encryption key bundle provided for encrypted file".to_string()),
         };
         let key = Key::<Aes256Gcm>::from_slice(&key_bytes);
 
-        let mut output_file = File::create(output_path).map_err(|e| e.to_string())?;
-
-        // Assuming chunks are ordered by index. If not, they should be sorted first.
-        let result: Result<(), String> = (|| {
-            for chunk_info in chunks {
-                // Read the encrypted chunk from storage
-                let encrypted_chunk = self.read_chunk(&chunk_info.encrypted_hash).map_err(|e| {
-                    format!("Failed to read encrypted chunk {}: {}", chunk_info.index, e)
-                })?;
-
-                // Decrypt the chunk
-                let decrypted_data = self.decrypt_chunk(&encrypted_chunk, &key)?;
-
-                // Trim padding to original size
-                let mut decrypted_data = decrypted_data;
-                decrypted_data.truncate(chunk_info.size);
-
-                // Verify that the decrypted data matches the original hash
-                let calculated_hash_hex = hex::encode(Sha256Hasher::hash(&decrypted_data));
-                if calculated_hash_hex != chunk_info.hash {
-                    return Err(format!(
-                        "Hash mismatch for chunk {}. Data may be corrupt. Expected: {}, Got: {}",
-                        chunk_info.index, chunk_info.hash, calculated_hash_hex
-                    ));
+        // Chunks are written at their offset in manifest order
+        let mut offsets = Vec::with_capacity(chunks.len());
+        let mut total_size = 0u64;
+        for chunk_info in chunks {
+            offsets.push(total_size);
+            total_size += chunk_info.size as u64;
+        }
+
+        let part_path = part_state_path(output_path);
+        let mut output_file = fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(output_path)
+            .map_err(|e| e.to_string())?;
+        let mut state = self.verified_part_state(&part_path, &mut output_file, chunks, &offsets);
+        if state.written.is_empty() {
+            output_file.set_len(0).map_err(|e| e.to_string())?;
+        }
+
+        let total_chunks = chunks.len();
+        let mut bytes_written = 0u64;
+        let mut fetched = 0;
+        let mut unsaved = 0;
+        let mut last_saved = Instant::now();
+        for (done, chunk_info) in chunks.iter().enumerate() {
+            if !state.written.contains(&chunk_info.index) {
+                if let Err(e) =
+                    self.write_reassembled_chunk(chunk_info, key, &mut output_file, offsets[done])
+                {
+                    // Keep what was written for the next attempt
+                    if unsaved > 0 {
+                        let _ = save_part_state(&part_path, &state);
+                    }
+                    return Err(e);
                 }
+                fetched += 1;
+                unsaved += 1;
+
+                state.written.insert(chunk_info.index);
+                if unsaved >= PART_STATE_SAVE_CHUNKS
+                    || last_saved.elapsed() >= PART_STATE_SAVE_INTERVAL
+                {
+                    save_part_state(&part_path, &state)?;
+                    unsaved = 0;
+                    last_saved = Instant::now();
+                }
+            }
+
+            bytes_written += chunk_info.size as u64;
+            if let Some(progress) = progress {
+                // Progress is best-effort; a closed channel is ignored
+                let _ = progress.blocking_send(ReassembleProgress {
+                    chunks_done: done + 1,
+                    total_chunks,
+                    bytes_written,
+                });
+            }
+        }
+
+        output_file.set_len(total_size).map_err(|e| e.to_string())?;
+        let _ = fs::remove_file(&part_path);
+        Ok(fetched)
+    }
 
-                output_file
-                    .write_all(&decrypted_data)
-                    .map_err(|e| e.to_string())?;
+    /// Read, decrypt and verify one chunk, then write it at `offset` in `output_file`
+    fn write_reassembled_chunk(
+        &self,
+        chunk_info: &ChunkInfo,
+        key: &Key<Aes256Gcm>,
+        output_file: &mut File,
+        offset: u64,
+    ) -> Result<(), String> {
+        // Read the encrypted chunk from storage
+        let encrypted_chunk = self
+            .read_chunk(&chunk_info.encrypted_hash)
+            .map_err(|e| format!("Failed to read encrypted chunk {}: {}", chunk_info.index, e))?;
+
+        // Decrypt (and decompress) the chunk, trimming padding to original size
+        let mut decrypted_data = self.decode_chunk(chunk_info, &encrypted_chunk, key)?;
+        decrypted_data.truncate(chunk_info.size);
+
+        // Verify that the decrypted data matches the original hash
+        let calculated_hash_hex = hex::encode(self.hash_algorithm.hash(&decrypted_data));
+        if calculated_hash_hex != chunk_info.hash {
+            return Err(format!(
+                "Hash mismatch for chunk {}. Data may be corrupt. Expected: {}, Got: {}",
+                chunk_info.index, chunk_info.hash, calculated_hash_hex
+            ));
+        }
+
+        output_file
+            .seek(SeekFrom::Start(offset))
+            .and_then(|_| output_file.write_all(&decrypted_data))
+            .map_err(|e| e.to_string())
+    }
+
+    /// The sidecar's written chunks whose bytes in `output_file` still match
+    /// the manifest; empty if there's no sidecar or it's for another layout.
+    fn verified_part_state(
+        &self,
+        part_path: &Path,
+        output_file: &mut File,
+        chunks: &[ChunkInfo],
+        offsets: &[u64],
+    ) -> PartState {
+        let mut state = fs::read(part_path)
+            .ok()
+            .and_then(|bytes| serde_json::from_slice::<PartState>(&bytes).ok())
+            .filter(|state| state.total_chunks == chunks.len())
+            .unwrap_or_else(|| PartState {
+                total_chunks: chunks.len(),
+                written: BTreeSet::new(),
+            });
+
+        for (chunk_info, &offset) in chunks.iter().zip(offsets) {
+            if !state.written.contains(&chunk_info.index) {
+                continue;
             }
-            Ok(())
-        })();
-        result
+            let mut data = vec![0u8; chunk_info.size];
+            let intact = output_file
+                .seek(SeekFrom::Start(offset))
+                .and_then(|_| output_file.read_exact(&mut data))
+                .is_ok()
+                && hex::encode(self.hash_algorithm.hash(&data)) == chunk_info.hash;
+            if !intact {
+                state.written.remove(&chunk_info.index);
+            }
+        }
+        state
     }
 
     /// Decrypts and reassembles chunks into an in-memory byte vector.
@@ -347,167 +1882,977 @@ impl ChunkManager {
                 format!("Failed to read encrypted chunk {}: {}", chunk_info.index, e)
             })?;
 
-            // Decrypt the chunk
-            let mut decrypted_data = self.decrypt_chunk(&encrypted_chunk, &key)?;
-            decrypted_data.truncate(chunk_info.size);
+            // Decrypt (and decompress) the chunk
+            let mut decrypted_data = self.decode_chunk(chunk_info, &encrypted_chunk, &key)?;
+            decrypted_data.truncate(chunk_info.size);
+
+            // Verify that the decrypted data matches the original hash
+            let calculated_hash_hex = hex::encode(self.hash_algorithm.hash(&decrypted_data));
+            if calculated_hash_hex != chunk_info.hash {
+                return Err(format!(
+                    "Hash mismatch for chunk {}. Data may be corrupt. Expected: {}, Got: {}",
+                    chunk_info.index, chunk_info.hash, calculated_hash_hex
+                ));
+            }
+
+            file_data.extend_from_slice(&decrypted_data);
+        }
+
+        Ok(file_data)
+    }
+
+    pub fn hash_file(&self, file_path: &Path) -> Result<String, Error> {
+        let file = File::open(file_path)?;
+        match map_large_file(&file) {
+            Some(mmap) => Ok(hex::encode(self.hash_algorithm.hash(&mmap))),
+            None => self.hash_file_streaming(file),
+        }
+    }
+
+    fn hash_file_streaming(&self, mut file: File) -> Result<String, Error> {
+        let mut buffer = vec![0; 1024 * 1024]; // 1MB buffer on the heap
+        let mut hasher = self.hash_algorithm.streaming();
+        loop {
+            let bytes_read = file.read(&mut buffer)?;
+            if bytes_read == 0 {
+                break;
+            }
+            hasher.update(&buffer[..bytes_read]);
+        }
+        Ok(hasher.finalize_hex())
+    }
+
+    /// Generates a Merkle proof for a specific chunk, with this manager's hash
+    /// algorithm. This would be called by a seeder node when a peer requests a chunk.
+    pub fn generate_merkle_proof(
+        &self,
+        all_chunk_hashes_hex: &[String],
+        chunk_index_to_prove: usize,
+    ) -> Result<(Vec<usize>, Vec<String>, usize), String> {
+        if chunk_index_to_prove >= all_chunk_hashes_hex.len() {
+            return Err(format!(
+                "Chunk index {} out of range ({} chunks)",
+                chunk_index_to_prove,
+                all_chunk_hashes_hex.len()
+            ));
+        }
+        let all_chunk_hashes: Vec<[u8; 32]> = all_chunk_hashes_hex
+            .iter()
+            .map(|h| {
+                hex::decode(h)
+                    .map_err(|e| e.to_string())?
+                    .try_into()
+                    .map_err(|_| "Invalid chunk hash length".to_string())
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+
+        let proof = self
+            .hash_algorithm
+            .merkle_proof(&all_chunk_hashes, chunk_index_to_prove);
+
+        let proof_indices = vec![chunk_index_to_prove];
+        // Convert proof hashes to Vec<String> (hex)
+        let proof_hashes_hex: Vec<String> = proof.iter().map(|h| hex::encode(h)).collect();
+
+        Ok((proof_indices, proof_hashes_hex, all_chunk_hashes.len()))
+    }
+
+    /// Verifies a downloaded chunk against the file's Merkle root using a proof. (DEPRECATED)
+    /// This is now a wrapper around the standalone `verify_chunk_with_proof` function.
+    #[deprecated(
+        since = "0.1.0",
+        note = "Please use the standalone `verify_chunk_with_proof` function instead"
+    )]
+    pub fn verify_chunk(&self, merkle_root_hex: &str, chunk_info: &ChunkInfo, chunk_data: &[u8], proof_indices: &[usize], proof_hashes_hex: &[String], total_leaves_count: usize) -> Result<bool, String> {
+        verify_chunk_with_proof(merkle_root_hex, &chunk_info.hash, chunk_data, proof_indices, proof_hashes_hex, total_leaves_count, self.hash_algorithm)
+    }
+}
+
+/// Read until `buffer` is full or the reader is exhausted, so every chunk but
+/// the last is exactly `buffer.len()` bytes.
+fn fill_buffer<R: Read>(reader: &mut R, buffer: &mut [u8]) -> std::io::Result<usize> {
+    let mut filled = 0;
+    while filled < buffer.len() {
+        match reader.read(&mut buffer[filled..]) {
+            Ok(0) => break,
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(filled)
+}
+
+/// Map `file` read-only if it is at least `MMAP_THRESHOLD` bytes. Returns `None`
+/// for smaller files or if mapping fails, in which case callers stream instead.
+fn map_large_file(file: &File) -> Option<memmap2::Mmap> {
+    let len = file.metadata().ok()?.len();
+    if len < MMAP_THRESHOLD {
+        return None;
+    }
+    // SAFETY: The map is read-only and dropped before the caller returns. If
+    // another process truncates the file meanwhile we may read garbage or fault,
+    // which the chunk hashes and Merkle root would expose as a corrupt file.
+    unsafe { memmap2::Mmap::map(file) }.ok()
+}
+
+/// Each distinct chunk of `manifest`, by encrypted hash, with its stored size.
+fn unique_chunks(manifest: &FileManifest) -> HashMap<&str, u64> {
+    manifest
+        .chunks
+        .iter()
+        .map(|c| (c.encrypted_hash.as_str(), c.encrypted_size as u64))
+        .collect()
+}
+
+/// Add one reference per chunk of `manifest`, counting chunks that were
+/// already referenced as reused.
+fn retain_chunks(refcounts: &mut Refcounts, manifest: &FileManifest) {
+    for (hash, size) in unique_chunks(manifest) {
+        let count = refcounts.counts.entry(hash.to_string()).or_insert(0);
+        if *count == 0 {
+            refcounts.dedup.bytes_written += size;
+        } else {
+            refcounts.dedup.chunks_reused += 1;
+        }
+        refcounts.dedup.bytes_logical += size;
+        *count += 1;
+    }
+}
+
+/// Drop one reference per chunk of `manifest`, returning the chunks whose count
+/// reached zero. Chunks without a count are left alone.
+fn release_chunks(refcounts: &mut Refcounts, manifest: &FileManifest) -> Vec<String> {
+    let mut released = Vec::new();
+    let dedup = &mut refcounts.dedup;
+    for (hash, size) in unique_chunks(manifest) {
+        if let Some(count) = refcounts.counts.get_mut(hash) {
+            *count = count.saturating_sub(1);
+            dedup.bytes_logical = dedup.bytes_logical.saturating_sub(size);
+            if *count == 0 {
+                refcounts.counts.remove(hash);
+                dedup.bytes_written = dedup.bytes_written.saturating_sub(size);
+                released.push(hash.to_string());
+            } else {
+                dedup.chunks_reused = dedup.chunks_reused.saturating_sub(1);
+            }
+        }
+    }
+    released
+}
+
+/// Share an encrypted file with another peer without re-chunking: unwrap the
+/// file's AES key with `our_secret_key` (which must already be a recipient) and
+/// wrap it again for `recipient_public_key`. Replaces any earlier bundle for
+/// the same recipient.
+pub fn add_recipient(
+    manifest: &mut FileManifest,
+    recipient_public_key: &PublicKey,
+    our_secret_key: &StaticSecret,
+) -> Result<(), String> {
+    let our_bundle = manifest
+        .key_bundle_for(&PublicKey::from(our_secret_key))
+        .ok_or("Manifest has no encryption key bundle")?;
+    let aes_key = decrypt_aes_key(our_bundle, our_secret_key)?;
+    let bundle = encrypt_aes_key(&aes_key, recipient_public_key)?;
+
+    let recipient_id = hex::encode(recipient_public_key.as_bytes());
+    let info = manifest
+        .encryption_info
+        .get_or_insert_with(|| EncryptionInfo {
+            method: "AES-256-GCM".to_string(),
+            key_fingerprint: FileEncryption::generate_key_fingerprint(&aes_key),
+            nonce: Vec::new(),
+            salt: Vec::new(),
+            encrypted_key_bundles: Vec::new(),
+        });
+    info.encrypted_key_bundles
+        .retain(|(id, _)| *id != recipient_id);
+    info.encrypted_key_bundles.push((recipient_id, bundle));
+    Ok(())
+}
+
+fn is_chunk_file_name(name: &str) -> bool {
+    name.len() == 64 && name.bytes().all(|b| b.is_ascii_hexdigit())
+}
+
+/// Verifies a downloaded chunk against its expected hash and a Merkle root using a proof.
+/// This is a standalone utility function for ensuring chunk integrity.
+pub fn verify_chunk_with_proof(
+    merkle_root_hex: &str,
+    expected_chunk_hash_hex: &str,
+    chunk_data: &[u8],
+    proof_indices: &[usize],
+    proof_hashes_hex: &[String],
+    total_leaves_count: usize,
+    algorithm: HashAlgorithm,
+) -> Result<bool, String> {
+    // 1. Verify the chunk's own hash.
+    let calculated_hash_bytes = algorithm.hash(chunk_data);
+    if hex::encode(calculated_hash_bytes) != expected_chunk_hash_hex {
+        return Ok(false); // The chunk data does not match its expected hash.
+    }
+
+    // 2. Decode hex strings to bytes for Merkle proof verification.
+    let merkle_root: [u8; 32] = hex::decode(merkle_root_hex)
+        .map_err(|e| e.to_string())?
+        .try_into()
+        .map_err(|_| "Invalid Merkle root length".to_string())?;
+
+    let proof_hashes: Vec<[u8; 32]> = proof_hashes_hex
+        .iter()
+        .map(|h| {
+            hex::decode(h)
+                .map_err(|e| e.to_string())?
+                .try_into()
+                .map_err(|_| "Invalid proof hash length".to_string())
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+
+    // 3. Verify the proof against the root with the file's hash algorithm.
+    Ok(algorithm.verify_merkle_proof(
+        merkle_root,
+        calculated_hash_bytes,
+        proof_indices,
+        total_leaves_count,
+        &proof_hashes,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::io::Seek;
+    use tempfile::tempdir;
+    use x25519_dalek::StaticSecret;
+
+    #[test]
+    fn test_encrypted_uploads_skip_the_plaintext_cache() {
+        let dir = tempdir().unwrap();
+        let cache = Arc::new(LocalCache::open(dir.path().join("cache"), 16 * 1024 * 1024).unwrap());
+        let manager = ChunkManager::new(dir.path().join("chunks")).with_local_cache(cache.clone());
+        let file_path = dir.path().join("private.bin");
+        fs::write(&file_path, vec![42u8; 300 * 1024]).unwrap();
+
+        let recipient_public = PublicKey::from(&StaticSecret::random_from_rng(OsRng));
+        manager
+            .chunk_and_encrypt_file(&file_path, &recipient_public)
+            .unwrap();
+        assert_eq!(cache.stats().entries, 0);
+
+        // Public uploads still populate it
+        manager
+            .chunk_and_encrypt_file_canonical(&file_path)
+            .unwrap();
+        assert!(cache.stats().entries > 0);
+    }
+
+    #[test]
+    fn test_chunk_encrypt_reassemble_decrypt() {
+        // 1. Setup
+        let dir = tempdir().unwrap();
+        let storage_path = dir.path().to_path_buf();
+        let manager = ChunkManager::new(storage_path.clone());
+
+        let original_file_path = dir.path().join("original.txt");
+        let reassembled_file_path = dir.path().join("reassembled.txt");
+        let file_content = "This is a test file for chunking and encryption.".repeat(1000);
+        fs::write(&original_file_path, &file_content).unwrap();
+
+        let recipient_secret = StaticSecret::random_from_rng(OsRng);
+        let recipient_public = PublicKey::from(&recipient_secret);
+
+        // 2. Chunk and encrypt the file
+        let manifest = manager
+            .chunk_and_encrypt_file(&original_file_path, &recipient_public)
+            .unwrap();
+
+        // 3. Reassemble and decrypt
+        manager
+            .reassemble_and_decrypt_file(
+                &manifest.chunks,
+                &reassembled_file_path,
+                &manifest.encrypted_key_bundle,
+                &recipient_secret,
+            )
+            .unwrap();
+
+        // 4. Verify
+        let reassembled_content = fs::read_to_string(&reassembled_file_path).unwrap();
+        assert_eq!(file_content, reassembled_content);
+
+        // 5. Cleanup is handled by tempdir dropping
+    }
+
+    #[test]
+    fn test_interrupted_reassembly_resumes_with_missing_chunks_only() {
+        let dir = tempdir().unwrap();
+        let manager = ChunkManager::new(dir.path().join("chunks"));
+        let file_path = dir.path().join("large.bin");
+        let data: Vec<u8> = (0..8 * 256 * 1024 + 77).map(|i| (i % 239) as u8).collect();
+        fs::write(&file_path, &data).unwrap();
+        let secret = StaticSecret::random_from_rng(OsRng);
+        let manifest = manager
+            .chunk_and_encrypt_file(&file_path, &PublicKey::from(&secret))
+            .unwrap();
+        let total = manifest.chunks.len();
+        let half = total / 2;
+        let output = dir.path().join("out.bin");
+
+        // First attempt aborts at the first chunk past the halfway point
+        let mut broken = manifest.chunks.clone();
+        broken[half].hash = "0".repeat(64);
+        assert!(manager
+            .reassemble_and_decrypt_file(&broken, &output, &manifest.encrypted_key_bundle, &secret)
+            .is_err());
+        assert!(part_state_path(&output).exists());
+
+        // Re-run: the written half verifies against the manifest and is skipped
+        let fetched = manager
+            .reassemble_and_decrypt_file_inner(
+                &manifest.chunks,
+                &output,
+                &manifest.encrypted_key_bundle,
+                &secret,
+                None,
+            )
+            .unwrap();
+        assert_eq!(fetched, total - half);
+        assert_eq!(
+            sha2::Sha256::digest(fs::read(&output).unwrap()),
+            sha2::Sha256::digest(&data)
+        );
+        assert!(!part_state_path(&output).exists());
+
+        // Bytes that no longer match the manifest are fetched again
+        fs::write(
+            part_state_path(&output),
+            serde_json::to_vec(&PartState {
+                total_chunks: total,
+                written: (0..total as u32).collect(),
+            })
+            .unwrap(),
+        )
+        .unwrap();
+        let mut corrupted = fs::read(&output).unwrap();
+        corrupted[10] ^= 0xff;
+        fs::write(&output, corrupted).unwrap();
+        let fetched = manager
+            .reassemble_and_decrypt_file_inner(
+                &manifest.chunks,
+                &output,
+                &manifest.encrypted_key_bundle,
+                &secret,
+                None,
+            )
+            .unwrap();
+        assert_eq!(fetched, 1);
+        assert_eq!(fs::read(&output).unwrap(), data);
+    }
+
+    #[test]
+    fn test_download_file_range_returns_exact_bytes() {
+        let dir = tempdir().unwrap();
+        let manager = ChunkManager::new(dir.path().join("chunks"));
+        let file_path = dir.path().join("media.bin");
+        let data: Vec<u8> = (0..4 * 256 * 1024 + 123).map(|i| (i % 251) as u8).collect();
+        fs::write(&file_path, &data).unwrap();
+        let stored = manager.store_file_with_manifest(&file_path).unwrap();
+        let file_hash = stored.manifest.merkle_root.clone();
+
+        // [300 KiB, 800 KiB) starts mid chunk 1 and ends mid chunk 3
+        let (start, end) = (300 * 1024, 800 * 1024);
+        let covering: Vec<u32> = stored
+            .manifest
+            .chunks_for_range(start, end)
+            .unwrap()
+            .iter()
+            .map(|(_, c)| c.index)
+            .collect();
+        assert_eq!(covering, vec![1, 2, 3]);
+
+        let output = dir.path().join("range.bin");
+        let written = manager
+            .download_file_range(&file_hash, start, end, &output, &stored.canonical_aes_key)
+            .unwrap();
+        assert_eq!(written, end - start);
+        assert_eq!(
+            fs::read(&output).unwrap(),
+            &data[start as usize..end as usize]
+        );
+
+        // Within a single chunk, and the very end of the file
+        let file_size = data.len() as u64;
+        for (start, end) in [(10, 20), (file_size - 5, file_size)] {
+            manager
+                .download_file_range(&file_hash, start, end, &output, &stored.canonical_aes_key)
+                .unwrap();
+            assert_eq!(
+                fs::read(&output).unwrap(),
+                &data[start as usize..end as usize]
+            );
+        }
+
+        assert!(stored.manifest.chunks_for_range(0, file_size + 1).is_err());
+
+        // A missing covering chunk sends the range to the network instead
+        assert!(manager.has_range(&file_hash, start, end).unwrap());
+        let chunk_2 = &stored.manifest.chunks[2].encrypted_hash;
+        fs::remove_file(dir.path().join("chunks").join(chunk_2)).unwrap();
+        assert!(!manager.has_range(&file_hash, start, end).unwrap());
+        assert!(manager.has_range(&file_hash, 0, 100).unwrap());
+        assert!(!manager.has_range("unknown", 0, 100).unwrap());
+    }
+
+    #[test]
+    fn test_verify_file_integrity_reports_corrupted_chunk() {
+        let dir = tempdir().unwrap();
+        let manager = ChunkManager::new(dir.path().join("chunks"));
+        let file_path = dir.path().join("stored.bin");
+        let data: Vec<u8> = (0..3 * 256 * 1024 + 10).map(|i| (i % 227) as u8).collect();
+        fs::write(&file_path, &data).unwrap();
+
+        let stored = manager.store_file_with_manifest(&file_path).unwrap();
+        let file_hash = stored.manifest.merkle_root.clone();
+        let key = stored.canonical_aes_key;
+
+        let report = manager
+            .verify_file_integrity(&file_hash, Some(&key))
+            .unwrap();
+        assert!(report.is_ok());
+        assert!(report.decrypted);
+        assert_eq!(report.total_chunks, 4);
+        assert_eq!(report.chunks_ok, 4);
+
+        // Flip one byte of chunk 2 on disk
+        let bad_chunk = &stored.manifest.chunks[2];
+        let chunk_path = dir.path().join("chunks").join(&bad_chunk.encrypted_hash);
+        let mut bytes = fs::read(&chunk_path).unwrap();
+        bytes[20] ^= 0xff;
+        fs::write(&chunk_path, bytes).unwrap();
+
+        for aes_key in [Some(&key), None] {
+            let report = manager.verify_file_integrity(&file_hash, aes_key).unwrap();
+            assert_eq!(report.chunks_ok, 3);
+            let bad: Vec<u32> = report.failures.iter().map(|f| f.index).collect();
+            assert_eq!(bad, vec![2]);
+        }
+
+        assert!(manager.verify_file_integrity("unknown", None).is_err());
+    }
+
+    #[test]
+    fn test_signed_manifest_detects_tampering() {
+        let dir = tempdir().unwrap();
+        let manager = ChunkManager::new(dir.path().to_path_buf());
+        let file_path = dir.path().join("signed.bin");
+        fs::write(&file_path, vec![9u8; 1000]).unwrap();
+        let mut manifest = manager
+            .chunk_and_encrypt_file_canonical(&file_path)
+            .unwrap()
+            .manifest;
+
+        let seed = [3u8; 32];
+        let keypair = libp2p::identity::Keypair::ed25519_from_bytes(seed).unwrap();
+        let peer_id = libp2p::PeerId::from(keypair.public()).to_string();
+        let signing_key = SigningKey::from_bytes(&seed);
+        manifest.sign(&signing_key, &peer_id).unwrap();
+
+        assert!(manifest
+            .verify_signature(&signing_key.verifying_key())
+            .unwrap());
+        assert!(manifest.verify_signer().is_ok());
+
+        // Survives a save/load round trip
+        manager.save_manifest(&manifest).unwrap();
+        let loaded = manager
+            .read_manifest(&manifest.merkle_root)
+            .unwrap()
+            .unwrap();
+        assert_eq!(loaded.signature, manifest.signature);
+
+        // Any edit breaks it, and the tampered manifest is refused on load
+        manifest.chunks[0].size += 1;
+        assert!(!manifest
+            .verify_signature(&signing_key.verifying_key())
+            .unwrap());
+        assert!(manifest.verify_signer().is_err());
+        manager.save_manifest(&manifest).unwrap();
+        assert!(manager.read_manifest(&manifest.merkle_root).is_err());
+
+        // A valid signature under someone else's name is refused as well
+        manifest.chunks[0].size -= 1;
+        let other = SigningKey::from_bytes(&[4u8; 32]);
+        manifest.sign(&other, &peer_id).unwrap();
+        assert!(manifest.verify_signer().is_err());
+    }
+
+    #[test]
+    fn test_list_manifests_reports_availability() {
+        let dir = tempdir().unwrap();
+        let manager = ChunkManager::new(dir.path().join("chunks"));
+        assert!(manager.list_manifests().unwrap().is_empty());
+
+        let mut stored = Vec::new();
+        for (name, len) in [("a.txt", 1000), ("b.bin", 300 * 1024)] {
+            let path = dir.path().join(name);
+            fs::write(&path, vec![name.len() as u8; len]).unwrap();
+            stored.push(manager.store_file_with_manifest(&path).unwrap().manifest);
+        }
+        // Lose one chunk of the two-chunk file
+        let lost_chunk = &stored[1].chunks[1].encrypted_hash;
+        fs::remove_file(dir.path().join("chunks").join(lost_chunk)).unwrap();
+
+        let listed = manager.list_manifests().unwrap();
+        assert_eq!(listed.len(), 2);
+        for manifest in &stored {
+            let summary = listed
+                .iter()
+                .find(|s| s.file_hash == manifest.merkle_root)
+                .unwrap();
+            assert_eq!(summary.chunk_count, manifest.chunks.len());
+            assert_eq!(
+                summary.chunks_available,
+                manager.verify_chunks_available(manifest)
+            );
+        }
+        let small = listed.iter().find(|s| s.chunk_count == 1).unwrap();
+        assert_eq!(small.file_size, 1000);
+        assert!(small.chunks_available);
+        let large = listed.iter().find(|s| s.chunk_count == 2).unwrap();
+        assert_eq!(large.file_size, 300 * 1024);
+        assert!(!large.chunks_available);
+    }
+
+    #[test]
+    fn test_chunk_size_validation() {
+        assert_eq!(validate_chunk_size(512 * 1024), Ok(512 * 1024));
+        assert!(validate_chunk_size(300 * 1024).is_err());
+        assert!(validate_chunk_size(MIN_CHUNK_SIZE / 2).is_err());
+        assert!(validate_chunk_size(MAX_CHUNK_SIZE * 2).is_err());
+
+        assert_eq!(nearest_chunk_size(512 * 1024), 512 * 1024);
+        assert_eq!(nearest_chunk_size(100 * 1024), 128 * 1024);
+        assert_eq!(nearest_chunk_size(80 * 1024), 64 * 1024);
+        assert_eq!(nearest_chunk_size(96 * 1024), 128 * 1024);
+        assert_eq!(nearest_chunk_size(1), MIN_CHUNK_SIZE);
+        assert_eq!(nearest_chunk_size(usize::MAX), MAX_CHUNK_SIZE);
+
+        let dir = tempdir().unwrap();
+        assert!(ChunkManager::new(dir.path().to_path_buf())
+            .with_chunk_size(1000)
+            .is_err());
+
+        // Manifests written before chunk_size was recorded used the default
+        let legacy = r#"{"merkle_root":"ab","chunks":[],"encrypted_key_bundle":null}"#;
+        let manifest: FileManifest = serde_json::from_str(legacy).unwrap();
+        assert_eq!(manifest.chunk_size, DEFAULT_CHUNK_SIZE);
+    }
+
+    #[test]
+    fn test_chunk_cipher_round_trip() {
+        let dir = tempdir().unwrap();
+        let mut key_bytes = [0u8; 32];
+        OsRng.fill_bytes(&mut key_bytes);
+        let key = Key::<Aes256Gcm>::from_slice(&key_bytes);
+        let data = b"chunk plaintext for both ciphers".repeat(100);
+
+        for cipher_suite in [CipherSuite::Aes256Gcm, CipherSuite::ChaCha20Poly1305] {
+            let manager =
+                ChunkManager::new(dir.path().join("chunks")).with_cipher_suite(cipher_suite);
+            let encrypted = manager.encrypt_chunk(&data, key).unwrap();
+            assert_eq!(encrypted.len(), 12 + data.len() + 16);
+            assert_eq!(
+                manager
+                    .decrypt_chunk(&encrypted, key, cipher_suite)
+                    .unwrap(),
+                data
+            );
+        }
+
+        // A chunk only decrypts with the cipher it was encrypted with
+        let manager = ChunkManager::new(dir.path().join("chunks"))
+            .with_cipher_suite(CipherSuite::ChaCha20Poly1305);
+        let encrypted = manager.encrypt_chunk(&data, key).unwrap();
+        assert!(manager
+            .decrypt_chunk(&encrypted, key, CipherSuite::Aes256Gcm)
+            .is_err());
+    }
+
+    #[test]
+    fn test_chacha_manifest_reassembles() {
+        let dir = tempdir().unwrap();
+        let manager = ChunkManager::new(dir.path().join("chunks"))
+            .with_cipher_suite(CipherSuite::ChaCha20Poly1305);
+
+        let data: Vec<u8> = (0..3 * 256 * 1024 + 999).map(|i| (i % 239) as u8).collect();
+        let original_file_path = dir.path().join("chacha.bin");
+        let reassembled_file_path = dir.path().join("chacha.out");
+        fs::write(&original_file_path, &data).unwrap();
+
+        let recipient_secret = StaticSecret::random_from_rng(OsRng);
+        let recipient_public = PublicKey::from(&recipient_secret);
+        let manifest = manager
+            .chunk_and_encrypt_file(&original_file_path, &recipient_public)
+            .unwrap();
+        assert!(manifest
+            .chunks
+            .iter()
+            .all(|chunk| chunk.cipher_suite == CipherSuite::ChaCha20Poly1305));
+
+        // The manifest survives serialization, and a default (AES) manager
+        // reassembles it from the per-chunk cipher
+        let manifest: FileManifest =
+            serde_json::from_str(&serde_json::to_string(&manifest).unwrap()).unwrap();
+        ChunkManager::new(dir.path().join("chunks"))
+            .reassemble_and_decrypt_file(
+                &manifest.chunks,
+                &reassembled_file_path,
+                &manifest.encrypted_key_bundle,
+                &recipient_secret,
+            )
+            .unwrap();
+        assert_eq!(fs::read(&reassembled_file_path).unwrap(), data);
+
+        // Chunk metadata written before the cipher was recorded is AES-GCM
+        let mut legacy = serde_json::to_value(&manifest.chunks[0]).unwrap();
+        legacy.as_object_mut().unwrap().remove("cipher_suite");
+        let legacy: ChunkInfo = serde_json::from_value(legacy).unwrap();
+        assert_eq!(legacy.cipher_suite, CipherSuite::Aes256Gcm);
+    }
+
+    #[test]
+    fn test_added_recipients_can_each_reconstruct_file() {
+        let dir = tempdir().unwrap();
+        let manager = ChunkManager::new(dir.path().join("chunks"));
+        let original_file_path = dir.path().join("shared.txt");
+        let file_content = "Shared with several peers.".repeat(20_000);
+        fs::write(&original_file_path, &file_content).unwrap();
+
+        let owner_secret = StaticSecret::random_from_rng(OsRng);
+        let mut manifest = manager
+            .chunk_and_encrypt_file(&original_file_path, &PublicKey::from(&owner_secret))
+            .unwrap();
+        let chunk_files = || -> HashSet<String> {
+            chunk_files_in(&dir.path().join("chunks"))
+                .into_iter()
+                .collect()
+        };
+        let chunks_before = chunk_files();
+
+        let recipients: Vec<StaticSecret> = (0..2)
+            .map(|_| StaticSecret::random_from_rng(OsRng))
+            .collect();
+        for recipient in &recipients {
+            add_recipient(&mut manifest, &PublicKey::from(recipient), &owner_secret).unwrap();
+        }
+        let recipient_count = |manifest: &FileManifest| {
+            manifest
+                .encryption_info
+                .as_ref()
+                .map_or(0, |info| info.encrypted_key_bundles.len())
+        };
+        assert_eq!(recipient_count(&manifest), 2);
+        // Nothing was re-chunked
+        assert_eq!(chunk_files(), chunks_before);
+
+        for (i, recipient) in recipients.iter().enumerate() {
+            let bundle = manifest
+                .key_bundle_for(&PublicKey::from(recipient))
+                .cloned();
+            let output_path = dir.path().join(format!("recipient_{}.txt", i));
+            manager
+                .reassemble_and_decrypt_file(&manifest.chunks, &output_path, &bundle, recipient)
+                .unwrap();
+            assert_eq!(fs::read_to_string(&output_path).unwrap(), file_content);
+        }
+
+        // A peer that can't open any bundle can't share the file onwards
+        let stranger = StaticSecret::random_from_rng(OsRng);
+        let someone = PublicKey::from(&StaticSecret::random_from_rng(OsRng));
+        assert!(add_recipient(&mut manifest, &someone, &stranger).is_err());
+        assert_eq!(recipient_count(&manifest), 2);
+    }
+
+    #[test]
+    fn test_compressed_chunks_round_trip() {
+        let dir = tempdir().unwrap();
+        let manager = ChunkManager::with_compression(dir.path().join("chunks"), true);
+
+        let original_file_path = dir.path().join("compressible.txt");
+        let reassembled_file_path = dir.path().join("compressible_out.txt");
+        let file_content = "aaaaaaaaaaaaaaaa".repeat(40_000);
+        fs::write(&original_file_path, &file_content).unwrap();
 
-            // Verify that the decrypted data matches the original hash
-            let calculated_hash_hex = hex::encode(Sha256Hasher::hash(&decrypted_data));
-            if calculated_hash_hex != chunk_info.hash {
-                return Err(format!(
-                    "Hash mismatch for chunk {}. Data may be corrupt. Expected: {}, Got: {}",
-                    chunk_info.index, chunk_info.hash, calculated_hash_hex
-                ));
-            }
+        let recipient_secret = StaticSecret::random_from_rng(OsRng);
+        let recipient_public = PublicKey::from(&recipient_secret);
 
-            file_data.extend_from_slice(&decrypted_data);
+        let manifest = manager
+            .chunk_and_encrypt_file(&original_file_path, &recipient_public)
+            .unwrap();
+        for chunk in &manifest.chunks {
+            assert_eq!(chunk.compression_type, COMPRESSION_ZSTD);
+            assert!(chunk.compressed_size < chunk.size);
+            assert!(chunk.encrypted_size < chunk.size);
         }
 
-        Ok(file_data)
+        manager
+            .reassemble_and_decrypt_file(
+                &manifest.chunks,
+                &reassembled_file_path,
+                &manifest.encrypted_key_bundle,
+                &recipient_secret,
+            )
+            .unwrap();
+        assert_eq!(
+            fs::read(&reassembled_file_path).unwrap(),
+            file_content.as_bytes()
+        );
     }
 
-    pub fn hash_file(&self, file_path: &Path) -> Result<String, Error> {
-        let mut file = File::open(file_path)?;
-        let mut hasher = sha2::Sha256::default();
-        let mut buffer = vec![0; 1024 * 1024]; // 1MB buffer on the heap
+    #[test]
+    fn test_incompressible_chunks_stored_uncompressed() {
+        let dir = tempdir().unwrap();
+        let manager = ChunkManager::with_compression(dir.path().join("chunks"), true);
 
-        loop {
-            let bytes_read = file.read(&mut buffer)?;
-            if bytes_read == 0 {
-                break;
-            }
-            hasher.update(&buffer[..bytes_read]);
-        }
-        Ok(format!("{:x}", hasher.finalize()))
+        let mut random = vec![0u8; 64 * 1024];
+        OsRng.fill_bytes(&mut random);
+        let original_file_path = dir.path().join("random.bin");
+        fs::write(&original_file_path, &random).unwrap();
+
+        let result = manager
+            .chunk_and_encrypt_file_canonical(&original_file_path)
+            .unwrap();
+        let chunk = &result.manifest.chunks[0];
+        assert_eq!(chunk.compression_type, COMPRESSION_NONE);
+        assert_eq!(chunk.compressed_size, chunk.size);
     }
 
-    /// Generates a Merkle proof for a specific chunk.
-    /// This would be called by a seeder node when a peer requests a chunk.
-    pub fn generate_merkle_proof(
-        &self,
-        all_chunk_hashes_hex: &[String],
-        chunk_index_to_prove: usize,
-    ) -> Result<(Vec<usize>, Vec<String>, usize), String> {
-        let all_chunk_hashes: Vec<[u8; 32]> = all_chunk_hashes_hex
-            .iter()
-            .map(|h| {
-                hex::decode(h)
-                    .map_err(|e| e.to_string())?
-                    .try_into()
-                    .map_err(|_| "Invalid chunk hash length".to_string())
-            })
-            .collect::<Result<Vec<_>, String>>()?;
+    #[test]
+    fn test_legacy_chunk_info_defaults_compressed_size_to_size() {
+        let chunk: ChunkInfo = serde_json::from_str(
+            r#"{"index":0,"hash":"aa","size":1234,"encrypted_hash":"bb","encrypted_size":1262}"#,
+        )
+        .unwrap();
+        assert_eq!(chunk.compression_type, COMPRESSION_NONE);
+        assert_eq!(chunk.compressed_size, 1234);
+
+        let round_trip: ChunkInfo =
+            serde_json::from_str(&serde_json::to_string(&chunk).unwrap()).unwrap();
+        assert_eq!(round_trip.compressed_size, 1234);
+    }
 
-        let merkle_tree = MerkleTree::<Sha256Hasher>::from_leaves(&all_chunk_hashes);
-        let proof = merkle_tree.proof(&[chunk_index_to_prove]);
+    #[test]
+    fn test_blake3_chunking_64mb() {
+        let dir = tempdir().unwrap();
+        let manager =
+            ChunkManager::new(dir.path().join("chunks")).with_hash_algorithm(HashAlgorithm::Blake3);
 
-        let proof_indices = vec![chunk_index_to_prove];
-        // Convert proof hashes to Vec<String> (hex)
-        let proof_hashes_hex: Vec<String> = proof
-            .proof_hashes()
+        let size = 64 * 1024 * 1024;
+        let data: Vec<u8> = (0..size).map(|i| (i % 251) as u8).collect();
+        let original_file_path = dir.path().join("large.bin");
+        fs::write(&original_file_path, &data).unwrap();
+
+        let result = manager
+            .chunk_and_encrypt_file_canonical(&original_file_path)
+            .unwrap();
+
+        let manifest = result.manifest;
+        assert_eq!(manifest.hash_algorithm, HashAlgorithm::Blake3);
+        // Same chunk layout as SHA-256: one chunk per 256KB
+        assert_eq!(manifest.chunks.len(), size / (256 * 1024));
+        assert_eq!(manifest.merkle_root.len(), 64);
+
+        let leaves: Vec<[u8; 32]> = manifest
+            .chunks
             .iter()
-            .map(|h| hex::encode(h))
+            .enumerate()
+            .map(|(i, chunk)| {
+                assert_eq!(chunk.index as usize, i);
+                assert_eq!(chunk.hash.len(), 64);
+                let start = i * 256 * 1024;
+                let expected = Blake3Hasher::hash(&data[start..start + chunk.size]);
+                assert_eq!(chunk.hash, hex::encode(expected));
+                expected
+            })
             .collect();
-
-        Ok((proof_indices, proof_hashes_hex, all_chunk_hashes.len()))
+        assert_eq!(
+            manifest.merkle_root,
+            hex::encode(HashAlgorithm::Blake3.merkle_root(&leaves).unwrap())
+        );
     }
 
-    /// Verifies a downloaded chunk against the file's Merkle root using a proof. (DEPRECATED)
-    /// This is now a wrapper around the standalone `verify_chunk_with_proof` function.
-    #[deprecated(
-        since = "0.1.0",
-        note = "Please use the standalone `verify_chunk_with_proof` function instead"
-    )]
-    pub fn verify_chunk(&self, merkle_root_hex: &str, chunk_info: &ChunkInfo, chunk_data: &[u8], proof_indices: &[usize], proof_hashes_hex: &[String], total_leaves_count: usize) -> Result<bool, String> {
-        verify_chunk_with_proof(merkle_root_hex, &chunk_info.hash, chunk_data, proof_indices, proof_hashes_hex, total_leaves_count)
-    }
-}
+    #[test]
+    fn test_parallel_chunking_matches_sequential() {
+        let dir = tempdir().unwrap();
+        let manager = ChunkManager::new(dir.path().join("chunks"));
 
-/// Verifies a downloaded chunk against its expected hash and a Merkle root using a proof.
-/// This is a standalone utility function for ensuring chunk integrity.
-pub fn verify_chunk_with_proof(
-    merkle_root_hex: &str,
-    expected_chunk_hash_hex: &str,
-    chunk_data: &[u8],
-    proof_indices: &[usize],
-    proof_hashes_hex: &[String],
-    total_leaves_count: usize,
-) -> Result<bool, String> {
-    // 1. Verify the chunk's own hash.
-    let calculated_hash_bytes = Sha256Hasher::hash(chunk_data);
-    if hex::encode(calculated_hash_bytes) != expected_chunk_hash_hex {
-        return Ok(false); // The chunk data does not match its expected hash.
+        // 32MB plus a partial trailing chunk
+        let size = 32 * 1024 * 1024 + 1234;
+        let data: Vec<u8> = (0..size).map(|i| (i * 31 % 253) as u8).collect();
+        let original_file_path = dir.path().join("parallel.bin");
+        fs::write(&original_file_path, &data).unwrap();
+
+        let mut key_bytes = [0u8; 32];
+        OsRng.fill_bytes(&mut key_bytes);
+        let key = Key::<Aes256Gcm>::from_slice(&key_bytes);
+
+        let sequential = manager
+            .chunk_and_encrypt_windowed(&original_file_path, key, 1, None, false)
+            .unwrap();
+        let parallel = manager
+            .chunk_and_encrypt_windowed(
+                &original_file_path,
+                key,
+                PARALLEL_CHUNK_WINDOW,
+                None,
+                false,
+            )
+            .unwrap();
+
+        assert_eq!(parallel.chunks.len(), size.div_ceil(256 * 1024));
+        assert_eq!(parallel.chunk_hashes, sequential.chunk_hashes);
+        assert_eq!(parallel.file_hash, sequential.file_hash);
+        for (i, (p, s)) in parallel.chunks.iter().zip(&sequential.chunks).enumerate() {
+            assert_eq!(p.index as usize, i);
+            assert_eq!(p.hash, s.hash);
+            assert_eq!(p.size, s.size);
+            assert_eq!(p.encrypted_size, s.encrypted_size);
+            // Ciphertexts differ only by their random nonces; both decrypt to the same chunk
+            let stored = manager.read_chunk(&p.encrypted_hash).unwrap();
+            let start = i * 256 * 1024;
+            assert_eq!(
+                manager
+                    .decrypt_chunk(&stored, key, CipherSuite::Aes256Gcm)
+                    .unwrap(),
+                &data[start..start + p.size]
+            );
+        }
     }
 
-    // 2. Decode hex strings to bytes for Merkle proof verification.
-    let merkle_root: [u8; 32] = hex::decode(merkle_root_hex)
-        .map_err(|e| e.to_string())?
-        .try_into()
-        .map_err(|_| "Invalid Merkle root length".to_string())?;
+    #[test]
+    fn test_manifest_merkle_proof_validates_and_rejects_tampering() {
+        for algorithm in [HashAlgorithm::Sha256, HashAlgorithm::Blake3] {
+            let dir = tempdir().unwrap();
+            let manager =
+                ChunkManager::new(dir.path().join("chunks")).with_hash_algorithm(algorithm);
+
+            let data: Vec<u8> = (0..5 * 256 * 1024 + 77).map(|i| (i % 241) as u8).collect();
+            let original_file_path = dir.path().join("proof.bin");
+            fs::write(&original_file_path, &data).unwrap();
+            let manifest = manager
+                .chunk_and_encrypt_file_canonical(&original_file_path)
+                .unwrap()
+                .manifest;
+            let total = manifest.chunks.len();
+
+            let index = 3;
+            let start = index * 256 * 1024;
+            let chunk = &data[start..start + manifest.chunks[index].size];
+            let hashes: Vec<String> = manifest.chunks.iter().map(|c| c.hash.clone()).collect();
+            let (indices, proof, leaves) = manager.generate_merkle_proof(&hashes, index).unwrap();
+            assert_eq!(leaves, total);
+            assert!(verify_chunk_with_proof(
+                &manifest.merkle_root,
+                &hashes[index],
+                chunk,
+                &indices,
+                &proof,
+                leaves,
+                algorithm
+            )
+            .unwrap());
 
-    let proof_hashes: Vec<[u8; 32]> = proof_hashes_hex
-        .iter()
-        .map(|h| {
-            hex::decode(h)
-                .map_err(|e| e.to_string())?
-                .try_into()
-                .map_err(|_| "Invalid proof hash length".to_string())
-        })
-        .collect::<Result<Vec<_>, String>>()?;
+            // A forged sibling hash breaks the proof
+            let mut tampered = proof.clone();
+            tampered[0] = hex::encode([0u8; 32]);
+            assert!(!verify_chunk_with_proof(
+                &manifest.merkle_root,
+                &hashes[index],
+                chunk,
+                &indices,
+                &tampered,
+                leaves,
+                algorithm
+            )
+            .unwrap());
 
-    // 3. Construct a Merkle proof object and verify it against the root.
-    let proof = rs_merkle::MerkleProof::<Sha256Hasher>::new(proof_hashes);
-    Ok(proof.verify(
-        merkle_root,
-        proof_indices,
-        &[calculated_hash_bytes],
-        total_leaves_count,
-    ))
-}
+            // So does presenting another chunk's data at this index
+            assert!(!verify_chunk_with_proof(
+                &manifest.merkle_root,
+                &hashes[0],
+                &data[..256 * 1024],
+                &indices,
+                &proof,
+                leaves,
+                algorithm
+            )
+            .unwrap());
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use std::fs;
-    use std::io::Seek;
-    use tempfile::tempdir;
-    use x25519_dalek::StaticSecret;
+            assert!(manager.generate_merkle_proof(&hashes, total).is_err());
+        }
+    }
 
     #[test]
-    fn test_chunk_encrypt_reassemble_decrypt() {
-        // 1. Setup
+    fn test_reassembly_reports_progress_per_chunk() {
         let dir = tempdir().unwrap();
-        let storage_path = dir.path().to_path_buf();
-        let manager = ChunkManager::new(storage_path.clone());
+        let manager = ChunkManager::new(dir.path().join("chunks"));
 
-        let original_file_path = dir.path().join("original.txt");
-        let reassembled_file_path = dir.path().join("reassembled.txt");
-        let file_content = "This is a test file for chunking and encryption.".repeat(1000);
+        let original_file_path = dir.path().join("progress.txt");
+        let reassembled_file_path = dir.path().join("progress_out.txt");
+        let file_content = "progress reporting test ".repeat(40_000);
         fs::write(&original_file_path, &file_content).unwrap();
 
         let recipient_secret = StaticSecret::random_from_rng(OsRng);
         let recipient_public = PublicKey::from(&recipient_secret);
-
-        // 2. Chunk and encrypt the file
         let manifest = manager
             .chunk_and_encrypt_file(&original_file_path, &recipient_public)
             .unwrap();
+        let total = manifest.chunks.len();
+        assert!(total > 1);
 
-        // 3. Reassemble and decrypt
+        let (tx, mut rx) = mpsc::channel(total);
         manager
-            .reassemble_and_decrypt_file(
+            .reassemble_and_decrypt_file_with_progress(
                 &manifest.chunks,
                 &reassembled_file_path,
                 &manifest.encrypted_key_bundle,
                 &recipient_secret,
+                tx,
             )
             .unwrap();
 
-        // 4. Verify
-        let reassembled_content = fs::read_to_string(&reassembled_file_path).unwrap();
-        assert_eq!(file_content, reassembled_content);
+        let mut updates = Vec::new();
+        while let Ok(update) = rx.try_recv() {
+            updates.push(update);
+        }
+        assert_eq!(updates.len(), total);
+        assert!(updates
+            .iter()
+            .enumerate()
+            .all(|(i, u)| u.chunks_done == i + 1 && u.total_chunks == total));
+        assert_eq!(
+            updates.last().unwrap().bytes_written,
+            file_content.len() as u64
+        );
 
-        // 5. Cleanup is handled by tempdir dropping
+        // A dropped receiver must not abort the reassembly
+        let (tx, rx) = mpsc::channel(1);
+        drop(rx);
+        manager
+            .reassemble_and_decrypt_file_with_progress(
+                &manifest.chunks,
+                &reassembled_file_path,
+                &manifest.encrypted_key_bundle,
+                &recipient_secret,
+                tx,
+            )
+            .unwrap();
+        assert_eq!(
+            fs::read_to_string(&reassembled_file_path).unwrap(),
+            file_content
+        );
     }
 
     #[test]
@@ -733,6 +3078,7 @@ mod tests {
                 &proof_indices,
                 &proof_hashes,
                 total_leaves,
+                HashAlgorithm::Sha256,
             )
             .unwrap();
 
@@ -751,6 +3097,7 @@ mod tests {
                 &proof_indices,
                 &proof_hashes,
                 total_leaves,
+                HashAlgorithm::Sha256,
             )
             .unwrap();
         assert!(
@@ -758,4 +3105,464 @@ mod tests {
             "Merkle proof verification should fail for tampered data."
         );
     }
+
+    #[test]
+    fn test_gc_removes_only_unreferenced_chunks() {
+        let dir = tempdir().unwrap();
+        let manager = ChunkManager::new(dir.path().to_path_buf());
+
+        let shared = save_test_chunk(&manager, 0, b"chunk shared by both files");
+        let unique_a = save_test_chunk(&manager, 1, b"chunk only in file a");
+        let unique_b = save_test_chunk(&manager, 1, b"chunk only in file b");
+
+        for (root, unique) in [("a", &unique_a), ("b", &unique_b)] {
+            manager
+                .save_manifest(&FileManifest {
+                    merkle_root: root.to_string(),
+                    chunks: vec![shared.clone(), unique.clone()],
+                    encrypted_key_bundle: None,
+                    hash_algorithm: HashAlgorithm::default(),
+                    encryption_info: None,
+                    mime_type: None,
+                    chunk_size: DEFAULT_CHUNK_SIZE,
+                    signature: None,
+                    file_hash: None,
+                })
+                .unwrap();
+        }
+
+        // Nothing is orphaned while both manifests exist
+        let report = manager.gc_older_than(Duration::ZERO).unwrap();
+        assert_eq!(report.manifests_scanned, 2);
+        assert_eq!(report.chunks_scanned, 3);
+        assert_eq!(report.chunks_removed, 0);
+
+        fs::remove_file(manager.manifests_dir().join("a.json")).unwrap();
+        // A just-written chunk may belong to a file still being chunked
+        let report = manager.gc().unwrap();
+        assert_eq!(report.chunks_removed, 0);
+        assert_eq!(report.chunks_recent, 1);
+
+        let report = manager.gc_older_than(Duration::ZERO).unwrap();
+        assert_eq!(report.manifests_scanned, 1);
+        assert_eq!(report.chunks_removed, 1);
+        assert_eq!(report.bytes_reclaimed, unique_a.encrypted_size as u64);
+
+        assert!(dir.path().join(&shared.encrypted_hash).exists());
+        assert!(dir.path().join(&unique_b.encrypted_hash).exists());
+        assert!(!dir.path().join(&unique_a.encrypted_hash).exists());
+        assert!(manager.read_chunk(&unique_a.encrypted_hash).is_err());
+    }
+
+    #[test]
+    fn test_storage_errors_distinguish_missing_chunk_from_full_store() {
+        let dir = tempdir().unwrap();
+        let manager = ChunkManager::new(dir.path().to_path_buf()).with_max_storage_bytes(100);
+
+        let missing = manager.read_chunk(&"0".repeat(64)).unwrap_err();
+        assert!(matches!(missing, ChiralError::NotFound(_)), "{:?}", missing);
+        let missing = manager.delete_file("unknown").unwrap_err();
+        assert!(matches!(missing, ChiralError::NotFound(_)), "{:?}", missing);
+
+        let first = [1u8; 60];
+        let first_hash = manager.hash_data(&first);
+        manager.save_chunk(&first_hash, &first).unwrap();
+        // Re-saving an existing chunk takes no extra space
+        manager.save_chunk(&first_hash, &first).unwrap();
+
+        let second = [2u8; 60];
+        match manager.save_chunk(&manager.hash_data(&second), &second) {
+            Err(ChiralError::CapacityFull { needed, available }) => {
+                assert_eq!(needed, 60);
+                assert_eq!(available, 40);
+            }
+            other => panic!("expected CapacityFull, got {:?}", other),
+        }
+        assert!(!dir.path().join(manager.hash_data(&second)).exists());
+        // Removing a chunk frees its bytes in the running total
+        manager.remove_chunks(&[first_hash]).unwrap();
+        manager
+            .save_chunk(&manager.hash_data(&second), &second)
+            .unwrap();
+
+        let message: String = ChiralError::CapacityFull {
+            needed: 60,
+            available: 40,
+        }
+        .into();
+        assert_eq!(message, "Storage full: need 60 bytes, 40 available");
+    }
+
+    fn chunk_files_in(dir: &Path) -> Vec<String> {
+        fs::read_dir(dir)
+            .unwrap()
+            .filter_map(|e| e.unwrap().file_name().into_string().ok())
+            .filter(|name| is_chunk_file_name(name))
+            .collect()
+    }
+
+    /// Store `data` as-is as a chunk, keyed by its hash, and describe it as
+    /// chunk `index` of a manifest
+    fn save_test_chunk(manager: &ChunkManager, index: u32, data: &[u8]) -> ChunkInfo {
+        let hash = manager.hash_data(data);
+        manager.save_chunk(&hash, data).unwrap();
+        ChunkInfo {
+            index,
+            hash: hash.clone(),
+            size: data.len(),
+            encrypted_hash: hash,
+            encrypted_size: data.len(),
+            compression_type: COMPRESSION_NONE,
+            compressed_size: data.len(),
+            cipher_suite: CipherSuite::Aes256Gcm,
+        }
+    }
+
+    #[test]
+    fn test_cancelled_chunking_removes_written_chunks() {
+        let dir = tempdir().unwrap();
+        let storage = dir.path().join("chunks");
+        let manager = ChunkManager::new(storage.clone())
+            .with_chunk_size(MIN_CHUNK_SIZE)
+            .unwrap();
+        let file_path = dir.path().join("large.bin");
+        let data: Vec<u8> = (0..32 * 1024 * 1024).map(|i| (i % 251) as u8).collect();
+        fs::write(&file_path, &data).unwrap();
+        let key = [7u8; 32];
+
+        let cancelled = CancellationToken::new();
+        cancelled.cancel();
+        assert!(matches!(
+            manager.chunk_file_cancellable(&file_path, &key, &cancelled),
+            Err(ChiralError::Cancelled)
+        ));
+
+        // Cancel once the first chunks are on disk
+        let cancel = CancellationToken::new();
+        let result = std::thread::scope(|scope| {
+            let worker = scope.spawn(|| manager.chunk_file_cancellable(&file_path, &key, &cancel));
+            while !worker.is_finished()
+                && (!storage.exists() || chunk_files_in(&storage).is_empty())
+            {
+                std::thread::yield_now();
+            }
+            cancel.cancel();
+            worker.join().unwrap()
+        });
+        assert!(
+            matches!(result, Err(ChiralError::Cancelled)),
+            "{:?}",
+            result.map(|chunks| chunks.len())
+        );
+        assert!(chunk_files_in(&storage).is_empty());
+
+        // An uncancelled run stores every chunk
+        let chunks = manager
+            .chunk_file_cancellable(&file_path, &key, &CancellationToken::new())
+            .unwrap();
+        assert_eq!(chunks.len(), data.len() / MIN_CHUNK_SIZE);
+        assert_eq!(chunk_files_in(&storage).len(), chunks.len());
+    }
+
+    #[test]
+    fn test_refcounts_track_stored_and_deleted_files() {
+        let dir = tempdir().unwrap();
+        let storage = dir.path().join("chunks");
+        let manager = ChunkManager::new(storage.clone());
+
+        let file_path = dir.path().join("input.bin");
+        let mut data = vec![0u8; 600 * 1024];
+        OsRng.fill_bytes(&mut data);
+        fs::write(&file_path, &data).unwrap();
+
+        let stored = manager.store_file_with_manifest(&file_path).unwrap();
+        let manifest = &stored.manifest;
+        assert_eq!(manifest.chunks.len(), 3);
+        for chunk in &manifest.chunks {
+            assert_eq!(manager.chunk_refcount(&chunk.encrypted_hash).unwrap(), 1);
+        }
+        assert_eq!(chunk_files_in(&storage).len(), 3);
+
+        // A second stored file whose manifest also references the first file's
+        // opening chunk. Uploads encrypt under their own keys and never share
+        // ciphertext by themselves, so the reference is added by hand.
+        let other_path = dir.path().join("other.bin");
+        let mut other_data = vec![0u8; 100 * 1024];
+        OsRng.fill_bytes(&mut other_data);
+        fs::write(&other_path, &other_data).unwrap();
+        let mut other = manager
+            .chunk_and_encrypt_file_canonical(&other_path)
+            .unwrap()
+            .manifest;
+        let shared = manifest.chunks[0].clone();
+        other.chunks.push(ChunkInfo {
+            index: 1,
+            ..shared.clone()
+        });
+        manager.store_manifest(&other).unwrap();
+        assert_eq!(manager.chunk_refcount(&shared.encrypted_hash).unwrap(), 2);
+        assert_eq!(chunk_files_in(&storage).len(), 4);
+
+        // Deleting the first file keeps the shared chunk for the second
+        manager.delete_file(&manifest.merkle_root).unwrap();
+        assert_eq!(manager.chunk_refcount(&shared.encrypted_hash).unwrap(), 1);
+        for chunk in &manifest.chunks[1..] {
+            assert_eq!(manager.chunk_refcount(&chunk.encrypted_hash).unwrap(), 0);
+            assert!(!storage.join(&chunk.encrypted_hash).exists());
+        }
+        assert!(storage.join(&shared.encrypted_hash).exists());
+        assert_eq!(chunk_files_in(&storage).len(), 2);
+        assert!(!manager.manifest_path(&manifest.merkle_root).exists());
+        assert!(manager.delete_file(&manifest.merkle_root).is_err());
+
+        manager.delete_file(&other.merkle_root).unwrap();
+        assert_eq!(manager.chunk_refcount(&shared.encrypted_hash).unwrap(), 0);
+        assert!(chunk_files_in(&storage).is_empty());
+    }
+
+    #[test]
+    fn test_dedup_stats_count_shared_chunks_once() {
+        let dir = tempdir().unwrap();
+        let manager = ChunkManager::new(dir.path().join("chunks"));
+
+        // Two uploads that share their opening chunk of plaintext
+        let mut shared_data = vec![0u8; DEFAULT_CHUNK_SIZE];
+        OsRng.fill_bytes(&mut shared_data);
+        let upload = |name: &str, tail: usize| {
+            let mut data = shared_data.clone();
+            let mut rest = vec![0u8; tail];
+            OsRng.fill_bytes(&mut rest);
+            data.extend_from_slice(&rest);
+            let path = dir.path().join(name);
+            fs::write(&path, &data).unwrap();
+            manager.store_file_with_manifest(&path).unwrap().manifest
+        };
+        let file_a = upload("a.bin", 300);
+        assert_eq!(manager.get_dedup_stats().unwrap().bytes_saved(), 0);
+        let file_b = upload("b.bin", 200);
+
+        // Each upload encrypts under its own key, so the same plaintext chunk
+        // is stored twice
+        assert_eq!(file_a.chunks[0].hash, file_b.chunks[0].hash);
+        assert_ne!(
+            file_a.chunks[0].encrypted_hash,
+            file_b.chunks[0].encrypted_hash
+        );
+        let stats = manager.get_dedup_stats().unwrap();
+        assert_eq!(stats.bytes_saved(), 0);
+        assert_eq!(stats.chunks_reused, 0);
+        let written = stats.bytes_written;
+
+        // A manifest referencing stored ciphertext shares it
+        let other_path = dir.path().join("other.bin");
+        fs::write(&other_path, b"tail of a file reusing a stored chunk").unwrap();
+        let mut file_c = manager
+            .chunk_and_encrypt_file_canonical(&other_path)
+            .unwrap()
+            .manifest;
+        let shared = file_a.chunks[0].clone();
+        file_c.chunks.push(ChunkInfo {
+            index: 1,
+            ..shared.clone()
+        });
+        manager.store_manifest(&file_c).unwrap();
+        let stats = manager.get_dedup_stats().unwrap();
+        assert_eq!(stats.chunks_reused, 1);
+        assert_eq!(stats.bytes_saved(), shared.encrypted_size as u64);
+        assert_eq!(
+            stats.bytes_written,
+            written + file_c.chunks[0].encrypted_size as u64
+        );
+
+        manager.delete_file(&file_a.merkle_root).unwrap();
+        let stats = manager.get_dedup_stats().unwrap();
+        assert_eq!(stats.bytes_saved(), 0);
+        assert_eq!(stats.chunks_reused, 0);
+
+        // Manifests can only be stored once their chunks are
+        let mut absent = shared.clone();
+        absent.encrypted_hash = "0".repeat(64);
+        file_c.merkle_root = "missing".to_string();
+        file_c.chunks[1] = absent;
+        assert!(matches!(
+            manager.store_manifest(&file_c),
+            Err(ChiralError::NotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_storing_same_file_twice_replaces_previous_copy() {
+        let dir = tempdir().unwrap();
+        let storage = dir.path().join("chunks");
+        let manager = ChunkManager::new(storage.clone());
+
+        let file_path = dir.path().join("input.bin");
+        let mut data = vec![0u8; 300 * 1024];
+        OsRng.fill_bytes(&mut data);
+        fs::write(&file_path, &data).unwrap();
+
+        let first = manager.store_file_with_manifest(&file_path).unwrap();
+        let second = manager.store_file_with_manifest(&file_path).unwrap();
+        assert_eq!(first.manifest.merkle_root, second.manifest.merkle_root);
+
+        // Each copy is encrypted under its own key, so the first copy's chunks
+        // are released when the second manifest replaces it.
+        for chunk in &first.manifest.chunks {
+            assert_eq!(manager.chunk_refcount(&chunk.encrypted_hash).unwrap(), 0);
+            assert!(!storage.join(&chunk.encrypted_hash).exists());
+        }
+        for chunk in &second.manifest.chunks {
+            assert_eq!(manager.chunk_refcount(&chunk.encrypted_hash).unwrap(), 1);
+            assert!(storage.join(&chunk.encrypted_hash).exists());
+        }
+
+        manager.delete_file(&second.manifest.merkle_root).unwrap();
+        assert!(chunk_files_in(&storage).is_empty());
+        assert!(manager.load_refcounts().unwrap().counts.is_empty());
+    }
+
+    #[test]
+    fn test_chunking_file_hash_matches_separate_hash_pass() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("multi-chunk.bin");
+        // Not a multiple of the chunk size, so the last chunk is short
+        let mut data = vec![0u8; 5 * 64 * 1024 + 1234];
+        OsRng.fill_bytes(&mut data);
+        fs::write(&file_path, &data).unwrap();
+
+        for algorithm in [HashAlgorithm::Sha256, HashAlgorithm::Blake3] {
+            let manager = ChunkManager::new(dir.path().join("chunks"))
+                .with_chunk_size(64 * 1024)
+                .unwrap()
+                .with_hash_algorithm(algorithm);
+            let result = manager
+                .chunk_and_encrypt_file_canonical(&file_path)
+                .unwrap();
+            assert_eq!(result.manifest.chunks.len(), 6);
+            assert_eq!(result.file_hash, manager.hash_file(&file_path).unwrap());
+        }
+
+        // The default manager's hash is the SHA-256 uploads have always keyed files by
+        let result = ChunkManager::new(dir.path().join("chunks"))
+            .chunk_and_encrypt_file_canonical(&file_path)
+            .unwrap();
+        assert_eq!(
+            result.file_hash,
+            format!("{:x}", sha2::Sha256::digest(&data))
+        );
+    }
+
+    #[test]
+    fn test_mmap_and_streaming_hashes_match() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("large.bin");
+        let mut data = vec![0u8; 16 * 1024 * 1024];
+        OsRng.fill_bytes(&mut data);
+        fs::write(&file_path, &data).unwrap();
+
+        for algorithm in [HashAlgorithm::Sha256, HashAlgorithm::Blake3] {
+            let manager =
+                ChunkManager::new(dir.path().join("chunks")).with_hash_algorithm(algorithm);
+            assert!(map_large_file(&File::open(&file_path).unwrap()).is_some());
+
+            let mapped = manager.hash_file(&file_path).unwrap();
+            let streamed = manager
+                .hash_file_streaming(File::open(&file_path).unwrap())
+                .unwrap();
+            assert_eq!(mapped, streamed);
+            assert_eq!(mapped, hex::encode(algorithm.hash(&data)));
+        }
+    }
+
+    #[test]
+    fn test_detect_mime_type_from_magic_bytes() {
+        let png = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR";
+        let pdf = b"%PDF-1.7\n%\xe2\xe3\xcf\xd3\n";
+        let zip = b"PK\x03\x04\x14\0\0\0\x08\0";
+
+        // Content wins over a misleading or missing extension
+        assert_eq!(
+            ChunkManager::detect_mime_type(png, "photo.txt"),
+            "image/png"
+        );
+        assert_eq!(
+            ChunkManager::detect_mime_type(pdf, "report"),
+            "application/pdf"
+        );
+        assert_eq!(
+            ChunkManager::detect_mime_type(zip, "bundle.bin"),
+            "application/zip"
+        );
+    }
+
+    #[test]
+    fn test_detect_mime_type_falls_back() {
+        let unknown = [0x13u8, 0x37, 0x00, 0xfe, 0x42, 0x42, 0x42, 0x42];
+        assert_eq!(
+            ChunkManager::detect_mime_type(&unknown, "blob.bin"),
+            "application/octet-stream"
+        );
+        assert_eq!(
+            ChunkManager::detect_mime_type(&unknown, "blob"),
+            "application/octet-stream"
+        );
+        // No recognizable magic bytes, but a known extension
+        assert_eq!(
+            ChunkManager::detect_mime_type(b"body {}", "style.css"),
+            "text/css"
+        );
+    }
+
+    #[test]
+    fn test_manifest_records_detected_mime_type() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("upload");
+        let mut data = b"%PDF-1.4\n".to_vec();
+        data.extend(vec![0u8; 4096]);
+        fs::write(&file_path, &data).unwrap();
+
+        let manager = ChunkManager::new(dir.path().join("chunks"));
+        let result = manager
+            .chunk_and_encrypt_file_canonical(&file_path)
+            .unwrap();
+        assert_eq!(
+            result.manifest.mime_type.as_deref(),
+            Some("application/pdf")
+        );
+    }
+
+    #[test]
+    fn test_manifest_cid_matches_ipfs() {
+        let dir = tempdir().unwrap();
+        let file_path = dir.path().join("hello.txt");
+        fs::write(&file_path, b"hello world").unwrap();
+        let manager = ChunkManager::new(dir.path().join("chunks"));
+        let mut manifest = manager
+            .chunk_and_encrypt_file_canonical(&file_path)
+            .unwrap()
+            .manifest;
+
+        // sha256("hello world"); `ipfs add --raw-leaves --cid-version 1` gives the same CID
+        let digest = "b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9";
+        assert_eq!(manifest.file_hash.as_deref(), Some(digest));
+        let raw = manifest.cid().unwrap();
+        assert_eq!(
+            raw,
+            "bafkreifzjut3te2nhyekklss27nh3k72ysco7y32koao5eei66wof36n5e"
+        );
+        assert_eq!(cid_to_hash(&raw).unwrap(), digest);
+
+        // Manifests from before file_hash was stored have no CID
+        manifest.file_hash = None;
+        assert!(manifest.cid().is_err());
+    }
+
+    #[test]
+    fn test_cid_to_hash_rejects_bad_input() {
+        assert!(cid_to_hash("not-a-cid").is_err());
+        // identity multihash (0x00) isn't a content hash we can look up
+        let identity = cid::multihash::Multihash::<64>::wrap(0x00, b"inline").unwrap();
+        let cid = cid::Cid::new_v1(CID_CODEC_RAW, identity).to_string();
+        assert!(cid_to_hash(&cid).unwrap_err().contains("Unsupported"));
+    }
 }
@@ -21,6 +21,9 @@ pub struct PeerMetrics {
     pub encryption_support: bool, // Supports encrypted transfers
     pub malicious_reports: u64,   // Number of malicious behavior reports
     pub protocols: Vec<String>,   // Protocols supported by the peer
+    /// Region the peer reported for itself (e.g. "EU"), if any
+    #[serde(default)]
+    pub region: Option<String>,
 }
 
 impl PeerMetrics {
@@ -45,6 +48,7 @@ impl PeerMetrics {
             encryption_support: false,
             malicious_reports: 0,
             protocols: Vec::new(),
+            region: None,
         }
     }
 
@@ -219,8 +223,14 @@ pub enum SelectionStrategy {
     EncryptionPreferred,
     /// Load balancing across multiple good peers
     LoadBalanced,
+    /// Balanced, but peers in the given region rank ahead of the rest; peers
+    /// with no known region are ranked as if out of region
+    RegionPreferred(String),
 }
 
+/// Score bonus that puts same-region peers ahead of any out-of-region peer
+const SAME_REGION_BONUS: f64 = 1000.0;
+
 /// Peer selection service for smart routing decisions
 #[derive(Default)]
 pub struct PeerSelectionService {
@@ -295,6 +305,17 @@ impl PeerSelectionService {
         }
     }
 
+    /// Record the region a peer reported for itself
+    pub fn set_peer_region(&mut self, peer_id: &str, region: Option<String>) {
+        if let Some(metrics) = self.metrics.get_mut(peer_id) {
+            metrics.region = region;
+        } else {
+            let mut new_metrics = PeerMetrics::new(peer_id.to_string(), "unknown".to_string());
+            new_metrics.region = region;
+            self.metrics.insert(peer_id.to_string(), new_metrics);
+        }
+    }
+
     /// Report malicious behavior for a peer
     pub fn report_malicious_peer(&mut self, peer_id: &str, severity: &str) {
         if let Some(metrics) = self.metrics.get_mut(peer_id) {
@@ -341,7 +362,7 @@ impl PeerSelectionService {
                         }
 
                         // Calculate selection score based on strategy
-                        let score = match strategy {
+                        let score = match &strategy {
                             SelectionStrategy::FastestFirst => metrics
                                 .latency_ms
                                 .map(|lat| 1000.0 - lat.min(1000) as f64)
@@ -371,6 +392,18 @@ impl PeerSelectionService {
                                     if time_since_selected < 60 { 50.0 } else { 0.0 };
                                 base_score - recency_penalty
                             }
+                            SelectionStrategy::RegionPreferred(region) => {
+                                let base = metrics.get_quality_score(false) * 1000.0;
+                                let same_region = metrics
+                                    .region
+                                    .as_deref()
+                                    .is_some_and(|r| r.eq_ignore_ascii_case(region));
+                                if same_region {
+                                    base + SAME_REGION_BONUS
+                                } else {
+                                    base
+                                }
+                            }
                         };
 
                         Some((peer_id.clone(), score))
@@ -521,4 +554,48 @@ mod tests {
         assert_eq!(selected.len(), 1);
         assert_eq!(selected[0], "peer1"); // Only peer with encryption support
     }
+
+    #[test]
+    fn test_region_preferred_selection() {
+        let mut service = PeerSelectionService::new();
+
+        // The Asian peers are better on every other metric
+        let peers = [("asia1", 0.95), ("asia2", 0.9), ("eu1", 0.6), ("eu2", 0.5)];
+        for (peer_id, reliability) in peers {
+            let mut peer = PeerMetrics::new(peer_id.to_string(), "127.0.0.1:8080".to_string());
+            peer.reliability_score = reliability;
+            peer.success_rate = reliability;
+            service.update_peer_metrics(peer);
+        }
+        service.update_peer_metrics(PeerMetrics::new(
+            "unknown".to_string(),
+            "127.0.0.1:8081".to_string(),
+        ));
+        service.set_peer_region("asia1", Some("ASIA".to_string()));
+        service.set_peer_region("asia2", Some("ASIA".to_string()));
+        service.set_peer_region("eu1", Some("EU".to_string()));
+        service.set_peer_region("eu2", Some("EU".to_string()));
+
+        let available: Vec<String> = ["asia1", "asia2", "eu1", "eu2", "unknown"]
+            .iter()
+            .map(|p| p.to_string())
+            .collect();
+        let eu = SelectionStrategy::RegionPreferred("eu".to_string());
+
+        let selected = service.select_peers(&available, 2, eu.clone(), false);
+        assert_eq!(selected, vec!["eu1", "eu2"]);
+
+        // Out-of-region peers still fill the count when the region runs out
+        let selected = service.select_peers(&available, 3, eu, false);
+        assert_eq!(selected, vec!["eu1", "eu2", "asia1"]);
+
+        // With no peers in the caller's region this is plain quality order
+        let selected = service.select_peers(
+            &available,
+            1,
+            SelectionStrategy::RegionPreferred("US".to_string()),
+            false,
+        );
+        assert_eq!(selected, vec!["asia1"]);
+    }
 }
@@ -235,7 +235,7 @@ impl Ed2kProtocolHandler {
     async fn generate_file_manifest(
         file_path: &PathBuf,
     ) -> Result<crate::manager::FileManifest, ProtocolError> {
-        const APP_CHUNK_SIZE: usize = 256 * 1024; // 256KB chunks for app-level verification
+        const APP_CHUNK_SIZE: usize = crate::manager::DEFAULT_CHUNK_SIZE; // app-level verification
         const MAX_FILE_SIZE: u64 = 100 * 1024 * 1024 * 1024; // 100GB safety limit
         const MAX_CHUNKS: usize = 400_000; // ~100GB / 256KB, prevents memory exhaustion
         
@@ -296,6 +296,9 @@ impl Ed2kProtocolHandler {
                 size: bytes_read,
                 encrypted_hash: String::new(), // No encryption for ED2K
                 encrypted_size: bytes_read,
+                compression_type: crate::manager::COMPRESSION_NONE,
+                compressed_size: bytes_read,
+                cipher_suite: crate::manager::CipherSuite::Aes256Gcm,
             });
 
             chunk_index += 1;
@@ -324,6 +327,12 @@ impl Ed2kProtocolHandler {
             merkle_root,
             chunks: chunk_infos,
             encrypted_key_bundle: None, // ED2K doesn't use encryption
+            hash_algorithm: crate::manager::HashAlgorithm::Sha256,
+            encryption_info: None,
+            mime_type: None,
+            chunk_size: APP_CHUNK_SIZE,
+            signature: None,
+            file_hash: None,
         })
     }
 
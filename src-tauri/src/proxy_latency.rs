@@ -1,5 +1,19 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tauri::Emitter;
+use tokio::net::TcpStream;
+use tokio::sync::Mutex;
+use tokio::time::timeout;
+use tracing::{debug, info};
+
+/// Weight given to the newest RTT sample in the moving average
+pub const RTT_EWMA_ALPHA: f64 = 0.3;
+/// How often the monitor pings every endpoint
+pub const PROXY_PING_INTERVAL_SECS: u64 = 30;
+/// A ping that takes longer than this counts as a failure
+pub const PROXY_PING_TIMEOUT_SECS: u64 = 5;
 
 /// Proxy latency information for optimization
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -8,6 +22,9 @@ pub struct ProxyLatencyInfo {
     pub latency_ms: Option<u64>,
     pub last_updated: u64, // timestamp
     pub status: ProxyStatus,
+    /// Exponentially weighted moving average of measured RTTs
+    #[serde(default)]
+    pub ewma_rtt_ms: Option<f64>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -37,18 +54,49 @@ impl ProxyLatencyService {
         latency_ms: Option<u64>,
         status: ProxyStatus,
     ) {
+        let ewma_rtt_ms = self
+            .proxy_latencies
+            .get(&proxy_id)
+            .and_then(|info| info.ewma_rtt_ms);
         let info = ProxyLatencyInfo {
             proxy_id: proxy_id.clone(),
             latency_ms,
-            last_updated: std::time::SystemTime::now()
-                .duration_since(std::time::UNIX_EPOCH)
-                .unwrap_or_default()
-                .as_secs(),
+            last_updated: now_secs(),
             status,
+            ewma_rtt_ms,
         };
         self.proxy_latencies.insert(proxy_id, info);
     }
 
+    /// Fold a ping result into the proxy's moving average. `None` marks a
+    /// failed ping, which takes the proxy offline but keeps its history.
+    pub fn record_rtt_sample(&mut self, proxy_id: &str, rtt_ms: Option<f64>) {
+        let info = self
+            .proxy_latencies
+            .entry(proxy_id.to_string())
+            .or_insert_with(|| ProxyLatencyInfo {
+                proxy_id: proxy_id.to_string(),
+                latency_ms: None,
+                last_updated: 0,
+                status: ProxyStatus::Connecting,
+                ewma_rtt_ms: None,
+            });
+        info.last_updated = now_secs();
+
+        match rtt_ms {
+            Some(rtt) => {
+                let ewma = match info.ewma_rtt_ms {
+                    Some(prev) => RTT_EWMA_ALPHA * rtt + (1.0 - RTT_EWMA_ALPHA) * prev,
+                    None => rtt,
+                };
+                info.ewma_rtt_ms = Some(ewma);
+                info.latency_ms = Some(ewma.round() as u64);
+                info.status = ProxyStatus::Online;
+            }
+            None => info.status = ProxyStatus::Offline,
+        }
+    }
+
     /// Get the best proxy based on latency
     pub fn get_best_proxy(&self) -> Option<&ProxyLatencyInfo> {
         self.proxy_latencies
@@ -99,3 +147,156 @@ impl Default for ProxyLatencyService {
         Self::new()
     }
 }
+
+fn now_secs() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// Periodically pings a fixed set of proxy/relay endpoints (`host:port`) and
+/// feeds the RTTs into a shared `ProxyLatencyService`.
+pub struct ProxyLatencyMonitor {
+    endpoints: Vec<String>,
+    interval: Duration,
+    service: Arc<Mutex<ProxyLatencyService>>,
+}
+
+impl ProxyLatencyMonitor {
+    pub fn new(endpoints: Vec<String>, service: Arc<Mutex<ProxyLatencyService>>) -> Self {
+        Self {
+            endpoints,
+            interval: Duration::from_secs(PROXY_PING_INTERVAL_SECS),
+            service,
+        }
+    }
+
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Endpoints from the comma-separated `CHIRAL_PROXY_ENDPOINTS` environment variable
+    pub fn endpoints_from_env() -> Vec<String> {
+        std::env::var("CHIRAL_PROXY_ENDPOINTS")
+            .map(|value| {
+                value
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|endpoint| !endpoint.is_empty())
+                    .map(str::to_string)
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Fastest online endpoint by average RTT
+    pub async fn best_proxy(&self) -> Option<ProxyLatencyInfo> {
+        self.service.lock().await.get_best_proxy().cloned()
+    }
+
+    /// Online endpoints, fastest first
+    pub async fn ranked_proxies(&self) -> Vec<ProxyLatencyInfo> {
+        self.service
+            .lock()
+            .await
+            .get_proxies_by_latency()
+            .into_iter()
+            .cloned()
+            .collect()
+    }
+
+    /// Ping every endpoint once and record the results
+    pub async fn measure_all(&self) {
+        for endpoint in &self.endpoints {
+            let rtt_ms = ping_endpoint(endpoint).await;
+            debug!("Proxy {} RTT: {:?} ms", endpoint, rtt_ms);
+            self.service
+                .lock()
+                .await
+                .record_rtt_sample(endpoint, rtt_ms);
+        }
+    }
+
+    /// Measure on every interval tick and emit `proxy-latency-updated` with
+    /// the current ranking.
+    pub async fn run(self, app_handle: tauri::AppHandle) {
+        info!(
+            "Monitoring latency of {} proxy endpoints every {:?}",
+            self.endpoints.len(),
+            self.interval
+        );
+        let mut ticker = tokio::time::interval(self.interval);
+        loop {
+            ticker.tick().await;
+            self.measure_all().await;
+            let _ = app_handle.emit("proxy-latency-updated", self.ranked_proxies().await);
+        }
+    }
+}
+
+/// RTT of a TCP handshake with the endpoint, or `None` if it is unreachable
+async fn ping_endpoint(endpoint: &str) -> Option<f64> {
+    let started = Instant::now();
+    match timeout(
+        Duration::from_secs(PROXY_PING_TIMEOUT_SECS),
+        TcpStream::connect(endpoint),
+    )
+    .await
+    {
+        Ok(Ok(_)) => Some(started.elapsed().as_secs_f64() * 1000.0),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rtt_samples_update_ewma_and_ranking() {
+        let mut service = ProxyLatencyService::new();
+        service.record_rtt_sample("a:1", Some(100.0));
+        service.record_rtt_sample("b:1", Some(200.0));
+
+        assert_eq!(service.get_best_proxy().unwrap().proxy_id, "a:1");
+
+        // 0.3 * 400 + 0.7 * 100 = 190
+        service.record_rtt_sample("a:1", Some(400.0));
+        let a = &service.proxy_latencies["a:1"];
+        assert!((a.ewma_rtt_ms.unwrap() - 190.0).abs() < 1e-9);
+        assert_eq!(a.latency_ms, Some(190));
+        assert_eq!(service.get_best_proxy().unwrap().proxy_id, "a:1");
+
+        // 0.3 * 500 + 0.7 * 190 = 283, now slower than b
+        service.record_rtt_sample("a:1", Some(500.0));
+        let ranked: Vec<_> = service
+            .get_proxies_by_latency()
+            .iter()
+            .map(|info| info.proxy_id.clone())
+            .collect();
+        assert_eq!(ranked, vec!["b:1", "a:1"]);
+
+        // A failed ping drops b from the ranking but keeps its average
+        service.record_rtt_sample("b:1", None);
+        assert_eq!(service.get_best_proxy().unwrap().proxy_id, "a:1");
+        assert_eq!(service.proxy_latencies["b:1"].ewma_rtt_ms, Some(200.0));
+    }
+
+    #[tokio::test]
+    async fn test_monitor_measures_reachable_endpoint() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let reachable = listener.local_addr().unwrap().to_string();
+        let service = Arc::new(Mutex::new(ProxyLatencyService::new()));
+        let monitor =
+            ProxyLatencyMonitor::new(vec![reachable.clone(), "127.0.0.1:1".to_string()], service);
+
+        monitor.measure_all().await;
+
+        let ranked = monitor.ranked_proxies().await;
+        assert_eq!(ranked.len(), 1);
+        assert_eq!(ranked[0].proxy_id, reachable);
+        assert_eq!(monitor.best_proxy().await.unwrap().proxy_id, reachable);
+    }
+}
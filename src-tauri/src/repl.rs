@@ -2,6 +2,7 @@
 use crate::dht::{models::FileMetadata, DhtService};
 use crate::ethereum::GethProcess;
 use crate::file_transfer::{AttemptStatus, FileTransferService};
+use chiral_network::manager::ChunkManager;
 use colored::Colorize;
 use rustyline::completion::{Completer, Pair};
 use rustyline::error::ReadlineError;
@@ -17,6 +18,7 @@ use strsim::levenshtein;
 pub struct ReplContext {
     pub dht_service: Arc<DhtService>,
     pub file_transfer_service: Option<Arc<FileTransferService>>,
+    pub chunk_manager: Option<Arc<ChunkManager>>,
     pub geth_process: Option<GethProcess>,
     pub peer_id: String,
     pub miner_address: Option<String>,
@@ -631,8 +633,51 @@ async fn cmd_list(args: &[&str], context: &ReplContext) -> Result<(), String> {
 
     match *what {
         "files" | "seeding" => {
-            println!("\n📤 Seeding Files:");
-            println!("  (This feature requires integration with file storage service)");
+            use crate::storage_manager::StorageUsage;
+
+            let chunk_manager = match &context.chunk_manager {
+                Some(chunk_manager) => chunk_manager.clone(),
+                None => {
+                    println!("\n📤 Chunk storage not available");
+                    println!();
+                    return Ok(());
+                }
+            };
+            let mut files = tokio::task::spawn_blocking(move || chunk_manager.list_manifests())
+                .await
+                .map_err(|e| format!("Listing stored files failed: {}", e))??;
+            let metadata = context.dht_service.get_all_file_metadata().await?;
+            for file in &mut files {
+                file.file_name = metadata
+                    .iter()
+                    .find(|m| m.merkle_root == file.file_hash)
+                    .map(|m| m.file_name.clone());
+            }
+
+            if files.is_empty() {
+                println!("\n📤 No stored files");
+                println!();
+                return Ok(());
+            }
+
+            println!("\n📤 Stored Files:");
+            println!("  ┌──────────────────────────────────────────────────────────────┐");
+            for file in &files {
+                let hash_short = if file.file_hash.len() > 16 {
+                    format!("{}...{}", &file.file_hash[..8], &file.file_hash[file.file_hash.len()-4..])
+                } else {
+                    file.file_hash.clone()
+                };
+                let status = if file.chunks_available { "✓".green() } else { "✗ missing chunks".red() };
+
+                println!("  │ {} {:<24} {:>10} {:>5} chunks {}",
+                    hash_short,
+                    file.file_name.as_deref().unwrap_or("-"),
+                    StorageUsage::format_bytes(file.file_size),
+                    file.chunk_count,
+                    status);
+            }
+            println!("  └──────────────────────────────────────────────────────────────┘");
             println!();
         }
         "downloads" | "dl" => {
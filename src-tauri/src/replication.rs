@@ -0,0 +1,197 @@
+// Replication health for published files: how many reachable providers hold
+// each chunk, compared against a target replica count.
+use serde::Serialize;
+use std::collections::HashSet;
+use std::time::Duration;
+use tracing::debug;
+
+use crate::dht::models::FileMetadata;
+use crate::dht::DhtService;
+use crate::manager::{FileManifest, DEFAULT_CHUNK_SIZE};
+
+/// Replicas per chunk a file should have to count as fully healthy.
+pub const DEFAULT_REPLICATION_TARGET: usize = 3;
+
+/// How long a provider that isn't already connected gets to answer a ping.
+const PROVIDER_PING_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// What one provider of a file holds.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProviderAvailability {
+    pub peer_id: String,
+    pub online: bool,
+    /// Chunk indices the provider holds; `None` means the whole file.
+    pub chunks: Option<Vec<u32>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReplicationHealth {
+    pub file_hash: String,
+    pub target: usize,
+    pub total_providers: usize,
+    pub online_providers: usize,
+    /// Online replicas of each chunk, by chunk index
+    pub chunk_replicas: Vec<usize>,
+    pub min_replicas: usize,
+    /// `min_replicas / target`, capped at 1.0
+    pub health_score: f64,
+    pub under_replicated_chunks: Vec<u32>,
+}
+
+/// Count the online replicas of each of `total_chunks` chunks. Only online
+/// providers count; chunk indices past the end of the file are ignored.
+pub fn compute_replication_health(
+    file_hash: &str,
+    total_chunks: usize,
+    providers: &[ProviderAvailability],
+    target: usize,
+) -> ReplicationHealth {
+    let target = target.max(1);
+    let mut chunk_replicas = vec![0usize; total_chunks];
+
+    for provider in providers.iter().filter(|p| p.online) {
+        match &provider.chunks {
+            None => chunk_replicas.iter_mut().for_each(|count| *count += 1),
+            Some(indices) => {
+                let unique: HashSet<u32> = indices.iter().copied().collect();
+                for index in unique {
+                    if let Some(count) = chunk_replicas.get_mut(index as usize) {
+                        *count += 1;
+                    }
+                }
+            }
+        }
+    }
+
+    let min_replicas = chunk_replicas.iter().copied().min().unwrap_or(0);
+    let under_replicated_chunks = chunk_replicas
+        .iter()
+        .enumerate()
+        .filter(|(_, count)| **count < target)
+        .map(|(index, _)| index as u32)
+        .collect();
+
+    ReplicationHealth {
+        file_hash: file_hash.to_string(),
+        target,
+        total_providers: providers.len(),
+        online_providers: providers.iter().filter(|p| p.online).count(),
+        chunk_replicas,
+        min_replicas,
+        health_score: (min_replicas as f64 / target as f64).min(1.0),
+        under_replicated_chunks,
+    }
+}
+
+/// Number of chunks in a published file, from its manifest when present.
+pub fn chunk_count(metadata: &FileMetadata) -> usize {
+    metadata
+        .manifest
+        .as_deref()
+        .and_then(|json| serde_json::from_str::<FileManifest>(json).ok())
+        .map(|manifest| manifest.chunks.len())
+        .unwrap_or_else(|| metadata.file_size.div_ceil(DEFAULT_CHUNK_SIZE as u64) as usize)
+}
+
+/// Look up the file's providers in the DHT, ping the ones we aren't connected
+/// to, and report replication across its chunks. Seeders serve whole files, so
+/// every reachable provider counts as a replica of every chunk.
+pub async fn check_replication_health(
+    dht: &DhtService,
+    metadata: &FileMetadata,
+    target: usize,
+) -> ReplicationHealth {
+    let mut peers = dht.get_seeders_for_file(&metadata.merkle_root).await;
+    for seeder in &metadata.seeders {
+        if !peers.contains(seeder) {
+            peers.push(seeder.clone());
+        }
+    }
+
+    let connected: HashSet<String> = dht.get_connected_peers().await.into_iter().collect();
+    // Ping every provider at once so one slow peer doesn't delay the rest
+    let providers = futures::future::join_all(peers.into_iter().map(|peer_id| {
+        let connected = &connected;
+        async move {
+            let online = connected.contains(&peer_id)
+                || matches!(
+                    tokio::time::timeout(
+                        PROVIDER_PING_TIMEOUT,
+                        dht.echo(peer_id.clone(), b"ping".to_vec())
+                    )
+                    .await,
+                    Ok(Ok(_))
+                );
+            debug!("Provider {} online: {}", peer_id, online);
+            ProviderAvailability {
+                peer_id,
+                online,
+                chunks: None,
+            }
+        }
+    }))
+    .await;
+
+    compute_replication_health(
+        &metadata.merkle_root,
+        chunk_count(metadata),
+        &providers,
+        target,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn provider(peer_id: &str, online: bool, chunks: Option<Vec<u32>>) -> ProviderAvailability {
+        ProviderAvailability {
+            peer_id: peer_id.to_string(),
+            online,
+            chunks,
+        }
+    }
+
+    #[test]
+    fn partially_replicated_file_reports_weakest_chunks() {
+        let providers = vec![
+            provider("full", true, None),
+            provider("partial-a", true, Some(vec![0, 1, 2, 2])),
+            provider("offline", false, None),
+            provider("partial-b", true, Some(vec![0, 1, 9])),
+        ];
+
+        let health = compute_replication_health("file", 4, &providers, 3);
+
+        assert_eq!(health.chunk_replicas, vec![3, 3, 2, 1]);
+        assert_eq!(health.min_replicas, 1);
+        assert_eq!(health.under_replicated_chunks, vec![2, 3]);
+        assert_eq!(health.total_providers, 4);
+        assert_eq!(health.online_providers, 3);
+        assert!((health.health_score - 1.0 / 3.0).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn health_score_is_capped_and_empty_when_no_providers() {
+        let providers: Vec<_> = (0..5)
+            .map(|i| provider(&format!("peer-{}", i), true, None))
+            .collect();
+        let health = compute_replication_health("file", 2, &providers, 3);
+        assert_eq!(health.health_score, 1.0);
+        assert!(health.under_replicated_chunks.is_empty());
+
+        let health = compute_replication_health("file", 2, &[], 3);
+        assert_eq!(health.health_score, 0.0);
+        assert_eq!(health.under_replicated_chunks, vec![0, 1]);
+    }
+
+    #[test]
+    fn chunk_count_falls_back_to_file_size() {
+        let metadata = FileMetadata {
+            file_size: DEFAULT_CHUNK_SIZE as u64 * 2 + 1,
+            ..Default::default()
+        };
+        assert_eq!(chunk_count(&metadata), 3);
+    }
+}
@@ -6,6 +6,7 @@ use rs_merkle::{Hasher, MerkleTree};
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 use std::time::{SystemTime, UNIX_EPOCH};
 
@@ -43,6 +44,20 @@ pub const MIN_BALANCE_MULTIPLIER: f64 = 1.2;
 /// Cryptographic signature scheme for signed transaction messages
 pub const SIGNATURE_ALGORITHM: &str = "ed25519";
 
+/// Furthest in the future a payment promise's deadline may be (seconds).
+/// Accepted promises are kept until their deadline, so this bounds how long
+/// each one stays in the ledger.
+pub const MAX_PAYMENT_DEADLINE: u64 = 2 * PAYMENT_DEADLINE_DEFAULT;
+
+/// Most unexpired payment promises a seeder keeps at once
+pub const MAX_ACCEPTED_PROMISES: usize = 10_000;
+
+/// Largest serialized verdict accepted from the DHT (bytes)
+pub const MAX_VERDICT_SIZE: usize = 16 * 1024;
+
+/// Verdicts dropped by `accept_dht_verdict` since startup
+static REJECTED_VERDICTS: AtomicU64 = AtomicU64::new(0);
+
 // ============================================================================
 // REPUTATION TYPES
 // ============================================================================
@@ -83,6 +98,19 @@ pub struct SignedTransactionMessage {
     pub downloader_signature: String, // hex-encoded ed25519 signature
 }
 
+/// Address a downloader signing promises with `key` must put in `from`:
+/// the last 20 bytes of the key's keccak256 hash, as for Ethereum addresses.
+pub fn payer_address(key: &VerifyingKey) -> String {
+    let hash = ethers::utils::keccak256(key.as_bytes());
+    format!("0x{}", hex::encode(&hash[12..]))
+}
+
+/// Convert a file price in Chiral to the smallest unit used by
+/// `SignedTransactionMessage::amount`, saturating at `u64::MAX`
+pub fn price_to_amount(price: f64) -> u64 {
+    (price * 1e18) as u64
+}
+
 impl SignedTransactionMessage {
     /// Create and sign a new transaction message
     pub fn new(
@@ -188,6 +216,121 @@ impl SignedTransactionMessage {
     }
 }
 
+/// Seeder-side record of the payment promises downloaders attach to transfer
+/// requests. Accepted promises are kept by nonce so one can't pay twice.
+#[derive(Debug, Default)]
+pub struct PaymentPromiseLedger {
+    require_payment: bool,
+    accepted: HashMap<String, SignedTransactionMessage>,
+}
+
+impl PaymentPromiseLedger {
+    pub fn new(require_payment: bool) -> Self {
+        Self {
+            require_payment,
+            accepted: HashMap::new(),
+        }
+    }
+
+    pub fn require_payment(&self) -> bool {
+        self.require_payment
+    }
+
+    /// Check the promise attached to a request for `file_hash` (priced at
+    /// `price`) from a downloader whose key is `downloader_key`, and record it
+    /// if valid. The promise's `from` must be `payer_address(downloader_key)`.
+    /// One promise covers the full price of the file, so presenting an
+    /// already accepted promise again is allowed; a different promise reusing
+    /// its nonce is not.
+    /// Returns `Ok(false)` when no promise was attached and none is required.
+    pub fn accept(
+        &mut self,
+        promise: Option<&SignedTransactionMessage>,
+        file_hash: &str,
+        price: u64,
+        seeder_address: &str,
+        downloader_key: &VerifyingKey,
+    ) -> Result<bool, String> {
+        let Some(promise) = promise else {
+            return if self.require_payment {
+                Err("payment promise required".into())
+            } else {
+                Ok(false)
+            };
+        };
+
+        self.prune_expired();
+        promise.validate()?;
+        if promise.file_hash != file_hash {
+            return Err(format!(
+                "payment promise is for file {}, not {}",
+                promise.file_hash, file_hash
+            ));
+        }
+        if !promise.to.eq_ignore_ascii_case(seeder_address) {
+            return Err(format!("payment promise is addressed to {}", promise.to));
+        }
+        if promise.amount < price {
+            return Err(format!(
+                "payment promise of {} is below the price of {}",
+                promise.amount, price
+            ));
+        }
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        if promise.deadline > now.saturating_add(MAX_PAYMENT_DEADLINE) {
+            return Err(format!(
+                "payment promise deadline is more than {}s away",
+                MAX_PAYMENT_DEADLINE
+            ));
+        }
+        let expected_from = payer_address(downloader_key);
+        if !promise.from.eq_ignore_ascii_case(&expected_from) {
+            return Err(format!(
+                "payment promise is from {}, but the key belongs to {}",
+                promise.from, expected_from
+            ));
+        }
+        if !promise.verify_signature(downloader_key)? {
+            return Err("payment promise signature is invalid".into());
+        }
+        if let Some(accepted) = self.accepted.get(&promise.nonce) {
+            // The same signed promise pays for the whole file, so a download
+            // split into several range requests can present it again until
+            // its deadline. Anything else reusing the nonce is a replay.
+            if accepted.downloader_signature == promise.downloader_signature {
+                return Ok(true);
+            }
+            return Err(format!(
+                "payment promise nonce {} was already used",
+                promise.nonce
+            ));
+        }
+        if self.accepted.len() >= MAX_ACCEPTED_PROMISES {
+            return Err("too many outstanding payment promises".into());
+        }
+
+        self.accepted.insert(promise.nonce.clone(), promise.clone());
+        Ok(true)
+    }
+
+    /// Drop accepted promises whose deadline has passed. Their nonces can't be
+    /// replayed afterwards since `validate` already rejects expired promises.
+    pub fn prune_expired(&mut self) {
+        self.accepted.retain(|_, promise| !promise.is_expired());
+    }
+
+    /// Accepted promises for one file
+    pub fn promises_for_file(&self, file_hash: &str) -> Vec<&SignedTransactionMessage> {
+        self.accepted
+            .values()
+            .filter(|promise| promise.file_hash == file_hash)
+            .collect()
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TransactionVerdict {
     pub target_id: String,
@@ -316,6 +459,72 @@ impl TransactionVerdict {
 
         Ok(verifying_key.verify(&serialized, &signature).is_ok())
     }
+
+    /// Validate the verdict and check `issuer_sig` against the ed25519 key
+    /// embedded in `issuer_id`.
+    pub fn verify_issuer_signature(&self) -> Result<(), String> {
+        self.validate()?;
+        let verifying_key = verifying_key_for_peer(&self.issuer_id)?;
+        if !self.verify_signature(&verifying_key)? {
+            return Err("signature does not match issuer".into());
+        }
+        Ok(())
+    }
+}
+
+/// A negative verdict recorded locally after a failed transfer. Drafts are
+/// not signed or published until the user confirms them.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct VerdictDraft {
+    pub target_id: String,
+    pub outcome: VerdictOutcome,
+    /// Most recent failure reason
+    pub details: String,
+    pub failures: u32,
+    pub last_failure_at: u64,
+}
+
+/// Recover the ed25519 verifying key from a libp2p peer ID. Ed25519 peer IDs
+/// inline the public key (identity multihash), so no lookup is needed.
+pub fn verifying_key_for_peer(peer_id: &str) -> Result<VerifyingKey, String> {
+    let peer_id: libp2p::PeerId = peer_id
+        .parse()
+        .map_err(|e| format!("invalid peer id: {}", e))?;
+    let multihash = peer_id.as_ref();
+    if multihash.code() != 0 {
+        return Err("peer id does not embed its public key".into());
+    }
+    let public_key = libp2p::identity::PublicKey::try_decode_protobuf(multihash.digest())
+        .map_err(|e| format!("invalid public key in peer id: {}", e))?
+        .try_into_ed25519()
+        .map_err(|_| "peer id key is not ed25519".to_string())?;
+    VerifyingKey::from_bytes(&public_key.to_bytes()).map_err(|e| e.to_string())
+}
+
+/// Parse a verdict fetched from the DHT, dropping it if it is oversized, names
+/// its issuer as its target, or isn't signed by the issuer it claims.
+pub fn accept_dht_verdict(bytes: &[u8]) -> Result<TransactionVerdict, String> {
+    let result = if bytes.len() > MAX_VERDICT_SIZE {
+        Err(format!(
+            "verdict is {} bytes, limit is {}",
+            bytes.len(),
+            MAX_VERDICT_SIZE
+        ))
+    } else {
+        serde_json::from_slice::<TransactionVerdict>(bytes)
+            .map_err(|e| format!("malformed verdict: {}", e))
+            .and_then(|verdict| verdict.verify_issuer_signature().map(|_| verdict))
+    };
+    if result.is_err() {
+        REJECTED_VERDICTS.fetch_add(1, Ordering::Relaxed);
+    }
+    result
+}
+
+/// Number of DHT verdicts rejected by `accept_dht_verdict` since startup.
+pub fn rejected_verdict_count() -> u64 {
+    REJECTED_VERDICTS.load(Ordering::Relaxed)
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -879,8 +1088,8 @@ impl ReputationDhtService {
                     "✅ Found verdict data, size={} bytes",
                     verdict_bytes.len()
                 );
-                // Try to deserialize the verdict data as a TransactionVerdict
-                match serde_json::from_slice::<TransactionVerdict>(&verdict_bytes) {
+                // Only accept verdicts actually signed by their claimed issuer
+                match accept_dht_verdict(&verdict_bytes) {
                     Ok(verdict) => {
                         println!(
                             "✅ Deserialized verdict: issuer={}, outcome={:?}",
@@ -894,8 +1103,8 @@ impl ReputationDhtService {
                         Ok(vec![verdict])
                     }
                     Err(e) => {
-                        println!("❌ Failed to deserialize verdict: {}", e);
-                        tracing::warn!("❌ Failed to deserialize verdict: {}", e);
+                        println!("❌ Rejected verdict: {}", e);
+                        tracing::warn!("❌ Rejected verdict: {}", e);
                         Ok(vec![])
                     }
                 }
@@ -1847,6 +2056,144 @@ mod tests {
         assert!(is_valid);
     }
 
+    fn payment_promise(
+        file_hash: &str,
+        deadline: u64,
+        key: &SigningKey,
+    ) -> SignedTransactionMessage {
+        SignedTransactionMessage::new(
+            payer_address(&key.verifying_key()),
+            "0xSeeder".to_string(),
+            1_000,
+            file_hash.to_string(),
+            deadline,
+            key,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_payment_promise_ledger() {
+        let downloader = SigningKey::generate(&mut OsRng);
+        let downloader_key = downloader.verifying_key();
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        let mut ledger = PaymentPromiseLedger::new(true);
+
+        // Accepted, and recorded once even when presented again for the
+        // rest of the same file
+        let promise = payment_promise("file-a", now + 600, &downloader);
+        assert_eq!(
+            ledger.accept(Some(&promise), "file-a", 1_000, "0xseeder", &downloader_key),
+            Ok(true)
+        );
+        assert_eq!(
+            ledger.accept(Some(&promise), "file-a", 1_000, "0xseeder", &downloader_key),
+            Ok(true)
+        );
+        assert_eq!(ledger.promises_for_file("file-a").len(), 1);
+
+        // A different promise can't reuse the nonce
+        let mut replayed = payment_promise("file-a", now + 900, &downloader);
+        replayed.nonce = promise.nonce.clone();
+        replayed.sign(&downloader).unwrap();
+        assert!(ledger
+            .accept(Some(&replayed), "file-a", 1_000, "0xseeder", &downloader_key)
+            .unwrap_err()
+            .contains("already used"));
+
+        // Expired
+        let expired = payment_promise("file-a", now - 10, &downloader);
+        assert!(ledger
+            .accept(Some(&expired), "file-a", 1_000, "0xseeder", &downloader_key)
+            .unwrap_err()
+            .contains("deadline"));
+
+        // Signed for a different file
+        let other_file = payment_promise("file-b", now + 600, &downloader);
+        assert!(ledger
+            .accept(Some(&other_file), "file-a", 1_000, "0xseeder", &downloader_key)
+            .unwrap_err()
+            .contains("file-b"));
+
+        // Signed by someone other than the downloader
+        let mut forged = payment_promise("file-a", now + 600, &SigningKey::generate(&mut OsRng));
+        forged.from = payer_address(&downloader_key);
+        assert_eq!(
+            ledger.accept(Some(&forged), "file-a", 1_000, "0xseeder", &downloader_key),
+            Err("payment promise signature is invalid".to_string())
+        );
+
+        // Signed by the downloader, but claiming to come from another address
+        let mut impersonated = payment_promise("file-a", now + 600, &downloader);
+        impersonated.from = "0xsomeoneelse".to_string();
+        impersonated.sign(&downloader).unwrap();
+        assert!(ledger
+            .accept(Some(&impersonated), "file-a", 1_000, "0xseeder", &downloader_key)
+            .unwrap_err()
+            .contains("0xsomeoneelse"));
+
+        // Less than the file's price
+        let underpaid = payment_promise("file-a", now + 600, &downloader);
+        assert!(ledger
+            .accept(Some(&underpaid), "file-a", 1_001, "0xseeder", &downloader_key)
+            .unwrap_err()
+            .contains("below the price"));
+
+        // Deadline too far out to keep around
+        let far = payment_promise("file-a", u64::MAX, &downloader);
+        assert!(ledger
+            .accept(Some(&far), "file-a", 1_000, "0xseeder", &downloader_key)
+            .unwrap_err()
+            .contains("deadline"));
+
+        // Missing only passes when payment isn't required
+        assert!(ledger
+            .accept(None, "file-a", 1_000, "0xseeder", &downloader_key)
+            .is_err());
+        assert_eq!(
+            PaymentPromiseLedger::new(false).accept(
+                None,
+                "file-a",
+                1_000,
+                "0xseeder",
+                &downloader_key
+            ),
+            Ok(false)
+        );
+        assert_eq!(ledger.promises_for_file("file-a").len(), 1);
+
+        // Promises past their deadline are pruned on the next accept
+        let mut stale = payment_promise("file-a", now + 600, &downloader);
+        stale.deadline = now - 10;
+        ledger.accepted.insert(stale.nonce.clone(), stale);
+        assert_eq!(ledger.promises_for_file("file-a").len(), 2);
+        let fresh = payment_promise("file-a", now + 600, &downloader);
+        ledger
+            .accept(Some(&fresh), "file-a", 1_000, "0xseeder", &downloader_key)
+            .unwrap();
+        assert_eq!(ledger.promises_for_file("file-a").len(), 2);
+        assert!(ledger
+            .promises_for_file("file-a")
+            .iter()
+            .all(|promise| !promise.is_expired()));
+
+        // A full ledger turns new promises away rather than growing
+        let mut full = PaymentPromiseLedger::new(true);
+        for i in 0..MAX_ACCEPTED_PROMISES {
+            let mut filler = fresh.clone();
+            filler.nonce = i.to_string();
+            full.accepted.insert(filler.nonce.clone(), filler);
+        }
+        let one_more = payment_promise("file-a", now + 600, &downloader);
+        assert!(full
+            .accept(Some(&one_more), "file-a", 1_000, "0xseeder", &downloader_key)
+            .unwrap_err()
+            .contains("too many"));
+    }
+
     #[test]
     fn test_public_key_cache() {
         let mut cache = PublicKeyCache::new();
@@ -2107,4 +2454,64 @@ mod tests {
         assert_eq!(results.total_duration_ms, 60);
         assert_eq!(results.events_per_second, 1666);
     }
+
+    fn signed_verdict(seed: [u8; 32], target_id: &str) -> TransactionVerdict {
+        let keypair = libp2p::identity::Keypair::ed25519_from_bytes(seed).unwrap();
+        let issuer_id = libp2p::PeerId::from(keypair.public()).to_string();
+        let mut verdict = TransactionVerdict {
+            target_id: target_id.to_string(),
+            tx_hash: None,
+            outcome: VerdictOutcome::Good,
+            details: Some("File transfer: 1024 bytes in successful outcome".to_string()),
+            metric: Some("transfer_bytes:1024".to_string()),
+            issued_at: 1_700_000_000,
+            issuer_id: String::new(),
+            issuer_seq_no: 0,
+            issuer_sig: String::new(),
+            tx_receipt: None,
+            evidence_blobs: None,
+        };
+        verdict
+            .sign_with(&SigningKey::from_bytes(&seed), &issuer_id, 0)
+            .unwrap();
+        verdict
+    }
+
+    #[test]
+    fn test_dht_verdict_signed_by_issuer_is_accepted() {
+        let verdict = signed_verdict([7u8; 32], "target-peer");
+        let bytes = serde_json::to_vec(&verdict).unwrap();
+
+        let accepted = accept_dht_verdict(&bytes).unwrap();
+        assert_eq!(accepted.issuer_id, verdict.issuer_id);
+        assert_eq!(accepted.outcome, VerdictOutcome::Good);
+    }
+
+    #[test]
+    fn test_forged_or_oversized_dht_verdicts_are_rejected() {
+        let before = rejected_verdict_count();
+
+        // Flipping the outcome after signing invalidates the signature
+        let mut mutated = signed_verdict([7u8; 32], "target-peer");
+        mutated.outcome = VerdictOutcome::Bad;
+        let err = accept_dht_verdict(&serde_json::to_vec(&mutated).unwrap()).unwrap_err();
+        assert!(err.contains("signature"));
+
+        // A valid signature from a different key than issuer_id claims
+        let mut impersonated = signed_verdict([7u8; 32], "target-peer");
+        impersonated.issuer_id = signed_verdict([9u8; 32], "target-peer").issuer_id;
+        assert!(accept_dht_verdict(&serde_json::to_vec(&impersonated).unwrap()).is_err());
+
+        // Issuers can't vouch for themselves
+        let issuer_id = signed_verdict([7u8; 32], "x").issuer_id;
+        let self_verdict = signed_verdict([7u8; 32], &issuer_id);
+        assert!(accept_dht_verdict(&serde_json::to_vec(&self_verdict).unwrap()).is_err());
+
+        let mut oversized = signed_verdict([7u8; 32], "target-peer");
+        oversized.evidence_blobs = Some(vec!["a".repeat(MAX_VERDICT_SIZE)]);
+        let err = accept_dht_verdict(&serde_json::to_vec(&oversized).unwrap()).unwrap_err();
+        assert!(err.contains("limit"));
+
+        assert!(rejected_verdict_count() >= before + 4);
+    }
 }
@@ -0,0 +1,191 @@
+// Shareable chiral:// links, so users can pass a file around with its name
+// and size instead of a bare hash:
+//
+//   chiral://<file_hash>?name=<name>&size=<bytes>&mime=<type>
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+use crate::dht::models::FileMetadata;
+
+pub const CHIRAL_URI_SCHEME: &str = "chiral://";
+
+#[derive(Debug, thiserror::Error, PartialEq, Eq)]
+pub enum ShareUriError {
+    #[error("URI must start with chiral://")]
+    MissingScheme,
+    #[error("Invalid file hash '{0}': expected 64 hex characters")]
+    InvalidHash(String),
+    #[error("Invalid size '{0}'")]
+    InvalidSize(String),
+    #[error("Malformed query parameter '{0}'")]
+    MalformedParameter(String),
+    #[error("Duplicate query parameter '{0}'")]
+    DuplicateParameter(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChiralUri {
+    pub file_hash: String,
+    pub name: Option<String>,
+    pub size: Option<u64>,
+    pub mime: Option<String>,
+}
+
+impl ChiralUri {
+    pub fn new(file_hash: impl Into<String>) -> Self {
+        ChiralUri {
+            file_hash: file_hash.into(),
+            name: None,
+            size: None,
+            mime: None,
+        }
+    }
+
+    pub fn from_metadata(metadata: &FileMetadata) -> Self {
+        ChiralUri {
+            file_hash: metadata.merkle_root.clone(),
+            name: Some(metadata.file_name.clone()).filter(|n| !n.is_empty()),
+            size: Some(metadata.file_size).filter(|&s| s > 0),
+            mime: metadata.mime_type.clone(),
+        }
+    }
+
+    /// Parse a `chiral://` URI. Unknown query parameters are ignored so newer
+    /// links still open in older clients.
+    pub fn parse(uri: &str) -> Result<Self, ShareUriError> {
+        let rest = uri
+            .trim()
+            .strip_prefix(CHIRAL_URI_SCHEME)
+            .ok_or(ShareUriError::MissingScheme)?;
+        let (hash, query) = match rest.split_once('?') {
+            Some((hash, query)) => (hash, Some(query)),
+            None => (rest, None),
+        };
+        let hash = hash.trim_end_matches('/');
+        if hash.len() != 64 || !hash.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(ShareUriError::InvalidHash(hash.to_string()));
+        }
+
+        let mut parsed = ChiralUri::new(hash.to_ascii_lowercase());
+        for param in query.into_iter().flat_map(|q| q.split('&')) {
+            if param.is_empty() {
+                continue;
+            }
+            let (key, raw_value) = param
+                .split_once('=')
+                .ok_or_else(|| ShareUriError::MalformedParameter(param.to_string()))?;
+            let value = urlencoding::decode(raw_value)
+                .map_err(|_| ShareUriError::MalformedParameter(param.to_string()))?
+                .into_owned();
+
+            let slot_taken = match key {
+                "name" => parsed.name.replace(value).is_some(),
+                "mime" => parsed.mime.replace(value).is_some(),
+                "size" => {
+                    let size = value
+                        .parse::<u64>()
+                        .map_err(|_| ShareUriError::InvalidSize(value.clone()))?;
+                    parsed.size.replace(size).is_some()
+                }
+                _ => false,
+            };
+            if slot_taken {
+                return Err(ShareUriError::DuplicateParameter(key.to_string()));
+            }
+        }
+
+        Ok(parsed)
+    }
+}
+
+impl fmt::Display for ChiralUri {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", CHIRAL_URI_SCHEME, self.file_hash)?;
+        let mut separator = '?';
+        if let Some(name) = &self.name {
+            write!(f, "{}name={}", separator, urlencoding::encode(name))?;
+            separator = '&';
+        }
+        if let Some(size) = self.size {
+            write!(f, "{}size={}", separator, size)?;
+            separator = '&';
+        }
+        if let Some(mime) = &self.mime {
+            write!(f, "{}mime={}", separator, urlencoding::encode(mime))?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for ChiralUri {
+    type Err = ShareUriError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HASH: &str = "9f86d081884c7d659a2feaa0c55ad015a3bf4f1b2b0b822cd15d6c15b0f00a08";
+
+    #[test]
+    fn test_round_trip_with_all_fields() {
+        let uri = ChiralUri {
+            file_hash: HASH.to_string(),
+            name: Some("holiday photos & notes (2024).tar.gz".to_string()),
+            size: Some(1_048_576),
+            mime: Some("application/gzip".to_string()),
+        };
+
+        let formatted = uri.to_string();
+        assert!(formatted.starts_with("chiral://9f86d081"));
+        assert!(formatted.contains("name=holiday%20photos%20%26%20notes%20%282024%29.tar.gz"));
+        assert!(formatted.contains("size=1048576"));
+        assert!(formatted.contains("mime=application%2Fgzip"));
+
+        assert_eq!(ChiralUri::parse(&formatted).unwrap(), uri);
+    }
+
+    #[test]
+    fn test_round_trip_hash_only() {
+        let uri = ChiralUri::new(HASH);
+        assert_eq!(uri.to_string(), format!("chiral://{}", HASH));
+        assert_eq!(uri.to_string().parse::<ChiralUri>().unwrap(), uri);
+    }
+
+    #[test]
+    fn test_parse_ignores_unknown_parameters() {
+        let uri = ChiralUri::parse(&format!("chiral://{}?size=42&tracker=x", HASH)).unwrap();
+        assert_eq!(uri.size, Some(42));
+        assert_eq!(uri.name, None);
+    }
+
+    #[test]
+    fn test_malformed_uris_are_rejected() {
+        assert_eq!(
+            ChiralUri::parse(&format!("magnet:?xt={}", HASH)),
+            Err(ShareUriError::MissingScheme)
+        );
+        assert!(matches!(
+            ChiralUri::parse("chiral://abc123?name=x"),
+            Err(ShareUriError::InvalidHash(_))
+        ));
+        assert!(matches!(
+            ChiralUri::parse(&format!("chiral://{}?size=big", HASH)),
+            Err(ShareUriError::InvalidSize(_))
+        ));
+        assert!(matches!(
+            ChiralUri::parse(&format!("chiral://{}?name", HASH)),
+            Err(ShareUriError::MalformedParameter(_))
+        ));
+        assert!(matches!(
+            ChiralUri::parse(&format!("chiral://{}?name=a&name=b", HASH)),
+            Err(ShareUriError::DuplicateParameter(_))
+        ));
+    }
+}
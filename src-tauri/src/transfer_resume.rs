@@ -0,0 +1,513 @@
+//! Resume tokens for peer-to-peer file transfers
+//!
+//! When a P2P transfer drops, the receiver tells the sender which chunks it
+//! already holds so the sender can skip ahead instead of restarting:
+//! - The receiver writes every verified chunk through to a `.part` file at its
+//!   offset and records the chunk index in a `.resume.json` token next to it.
+//! - On reconnect the token is attached to the file request; the sender only
+//!   sends the chunks missing from it.
+//! - Both files live under a per-node transfer directory in the app data
+//!   directory, so the token survives an application restart.
+//! - File hashes and chunk indices come from the remote peer: only SHA-256
+//!   hex hashes are accepted as file names, and chunks are only written inside
+//!   the part file's expected size.
+//! - The SHA-256 of every written chunk is kept in a `.hashes.json` sidecar; on
+//!   reopen the part file is re-hashed and chunks whose bytes no longer match
+//!   are dropped from the token and fetched again.
+
+use crate::multi_source_download::normalized_sha256_hex;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::{self, File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use tracing::{debug, info, warn};
+
+/// Chunks the receiver already holds for a file
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ResumeToken {
+    pub file_hash: String,
+    pub total_chunks: u32,
+    /// Indices of chunks that were verified and written to disk
+    pub received_chunks: BTreeSet<u32>,
+}
+
+impl ResumeToken {
+    pub fn new(file_hash: String, total_chunks: u32) -> Self {
+        Self {
+            file_hash,
+            total_chunks,
+            received_chunks: BTreeSet::new(),
+        }
+    }
+
+    pub fn has_chunk(&self, chunk_index: u32) -> bool {
+        self.received_chunks.contains(&chunk_index)
+    }
+
+    pub fn is_complete(&self) -> bool {
+        (0..self.total_chunks).all(|i| self.received_chunks.contains(&i))
+    }
+
+    /// Chunk indices still missing, in ascending order
+    pub fn missing_chunks(&self) -> Vec<u32> {
+        (0..self.total_chunks)
+            .filter(|i| !self.received_chunks.contains(i))
+            .collect()
+    }
+}
+
+/// Chunk indices a sender should transmit for `file_hash`.
+///
+/// A token for a different file or a different chunk layout is ignored and the
+/// whole file is sent, since its indices would not line up with ours.
+pub fn chunks_to_send(file_hash: &str, total_chunks: u32, token: Option<&ResumeToken>) -> Vec<u32> {
+    match token {
+        Some(token) if token.file_hash == file_hash && token.total_chunks == total_chunks => {
+            token.missing_chunks()
+        }
+        Some(token) => {
+            warn!(
+                "Ignoring resume token for {} ({} chunks); expected {} ({} chunks)",
+                token.file_hash, token.total_chunks, file_hash, total_chunks
+            );
+            (0..total_chunks).collect()
+        }
+        None => (0..total_chunks).collect(),
+    }
+}
+
+/// Default directory for partial transfers, under the app data directory so
+/// it survives reboots (temp directories are often cleared)
+pub fn default_resume_dir() -> PathBuf {
+    directories::ProjectDirs::from("com", "chiral-network", "chiral-network")
+        .map(|dirs| dirs.data_dir().join("transfers"))
+        .unwrap_or_else(|| std::env::temp_dir().join("chiral_transfers"))
+}
+
+/// The normalized form of a peer-supplied file hash, which names the resume
+/// files; anything but 64 hex characters could escape the transfer directory.
+fn validated_file_hash(file_hash: &str) -> Result<String, String> {
+    normalized_sha256_hex(file_hash)
+        .ok_or_else(|| format!("Invalid file hash for resumable transfer: {:?}", file_hash))
+}
+
+fn part_path(dir: &Path, file_hash: &str) -> PathBuf {
+    dir.join(format!("{}.part", file_hash))
+}
+
+fn token_path(dir: &Path, file_hash: &str) -> PathBuf {
+    dir.join(format!("{}.resume.json", file_hash))
+}
+
+fn hashes_path(dir: &Path, file_hash: &str) -> PathBuf {
+    dir.join(format!("{}.hashes.json", file_hash))
+}
+
+/// SHA-256 of each chunk written to the part file, by chunk index
+fn load_chunk_hashes(dir: &Path, file_hash: &str) -> BTreeMap<u32, String> {
+    fs::read(hashes_path(dir, file_hash))
+        .ok()
+        .and_then(|contents| serde_json::from_slice(&contents).ok())
+        .unwrap_or_default()
+}
+
+/// Load the persisted token for `file_hash`, if one exists and is readable
+pub fn load_token(dir: &Path, file_hash: &str) -> Option<ResumeToken> {
+    let file_hash = &validated_file_hash(file_hash).ok()?;
+    let contents = fs::read_to_string(token_path(dir, file_hash)).ok()?;
+    match serde_json::from_str::<ResumeToken>(&contents) {
+        Ok(token) if token.file_hash == file_hash => Some(token),
+        Ok(_) => None,
+        Err(e) => {
+            warn!(
+                "Discarding unreadable resume token for {}: {}",
+                file_hash, e
+            );
+            None
+        }
+    }
+}
+
+/// Remove the part file and token for `file_hash`
+pub fn discard(dir: &Path, file_hash: &str) {
+    let Ok(file_hash) = validated_file_hash(file_hash) else {
+        return;
+    };
+    let file_hash = file_hash.as_str();
+    let _ = fs::remove_file(part_path(dir, file_hash));
+    let _ = fs::remove_file(token_path(dir, file_hash));
+    let _ = fs::remove_file(hashes_path(dir, file_hash));
+}
+
+/// Receiver-side state of a transfer that can be resumed
+#[derive(Debug)]
+pub struct PartialDownload {
+    dir: PathBuf,
+    chunk_size: usize,
+    token: ResumeToken,
+    chunk_hashes: BTreeMap<u32, String>,
+}
+
+impl PartialDownload {
+    /// Open the partial transfer for `file_hash`, picking up a previous token if
+    /// it matches the chunk layout; otherwise start from scratch. Chunks from a
+    /// previous token are only kept if their bytes in the part file still hash
+    /// to what was written.
+    pub fn open(
+        dir: &Path,
+        file_hash: &str,
+        total_chunks: u32,
+        chunk_size: usize,
+    ) -> Result<Self, String> {
+        let file_hash = &validated_file_hash(file_hash)?;
+        if total_chunks == 0 || chunk_size == 0 {
+            return Err(format!(
+                "Invalid chunk layout for {}: {} chunks of {} bytes",
+                file_hash, total_chunks, chunk_size
+            ));
+        }
+        fs::create_dir_all(dir)
+            .map_err(|e| format!("Failed to create transfer directory: {}", e))?;
+
+        let token = match load_token(dir, file_hash) {
+            Some(token) if token.total_chunks == total_chunks => {
+                info!(
+                    "Resuming transfer of {} with {}/{} chunks on disk",
+                    file_hash,
+                    token.received_chunks.len(),
+                    total_chunks
+                );
+                token
+            }
+            Some(_) => {
+                discard(dir, file_hash);
+                ResumeToken::new(file_hash.to_string(), total_chunks)
+            }
+            None => ResumeToken::new(file_hash.to_string(), total_chunks),
+        };
+
+        let mut partial = Self {
+            dir: dir.to_path_buf(),
+            chunk_size,
+            chunk_hashes: load_chunk_hashes(dir, file_hash),
+            token,
+        };
+        partial.drop_unverified_chunks()?;
+        Ok(partial)
+    }
+
+    /// Re-hash every chunk the token claims and forget those whose bytes are
+    /// missing or changed (e.g. a truncated part file after a crash)
+    fn drop_unverified_chunks(&mut self) -> Result<(), String> {
+        if self.token.received_chunks.is_empty() {
+            return Ok(());
+        }
+        let mut part = File::open(part_path(&self.dir, &self.token.file_hash)).ok();
+        let mut dropped = Vec::new();
+        for &index in &self.token.received_chunks {
+            let verified = match (&mut part, self.chunk_hashes.get(&index)) {
+                (Some(file), Some(expected)) => {
+                    self.read_part_chunk(file, index).map_or(false, |data| {
+                        hex::encode(Sha256::digest(&data)) == *expected
+                    })
+                }
+                _ => false,
+            };
+            if !verified {
+                dropped.push(index);
+            }
+        }
+        if dropped.is_empty() {
+            return Ok(());
+        }
+
+        warn!(
+            "Dropping {} unverified chunk(s) of {} from resume state",
+            dropped.len(),
+            self.token.file_hash
+        );
+        for index in dropped {
+            self.token.received_chunks.remove(&index);
+            self.chunk_hashes.remove(&index);
+        }
+        self.save_token()
+    }
+
+    /// Read chunk `index` back from the part file. Only the last chunk may be short.
+    fn read_part_chunk(&self, file: &mut File, index: u32) -> Option<Vec<u8>> {
+        file.seek(SeekFrom::Start(index as u64 * self.chunk_size as u64))
+            .ok()?;
+        let mut data = Vec::with_capacity(self.chunk_size);
+        file.take(self.chunk_size as u64)
+            .read_to_end(&mut data)
+            .ok()?;
+        let is_last = index + 1 == self.token.total_chunks;
+        if data.is_empty() || (!is_last && data.len() < self.chunk_size) {
+            return None;
+        }
+        Some(data)
+    }
+
+    pub fn token(&self) -> &ResumeToken {
+        &self.token
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.token.is_complete()
+    }
+
+    /// Write a verified chunk to the part file and persist the updated token
+    pub fn write_chunk(&mut self, chunk_index: u32, data: &[u8]) -> Result<(), String> {
+        if chunk_index >= self.token.total_chunks {
+            return Err(format!(
+                "Chunk {} out of range ({} chunks)",
+                chunk_index, self.token.total_chunks
+            ));
+        }
+        let offset = chunk_index as u64 * self.chunk_size as u64;
+        let expected_size = self.token.total_chunks as u64 * self.chunk_size as u64;
+        if data.len() > self.chunk_size || offset + data.len() as u64 > expected_size {
+            return Err(format!(
+                "Chunk {} ({} bytes) doesn't fit the {}-byte part file",
+                chunk_index,
+                data.len(),
+                expected_size
+            ));
+        }
+        if self.token.has_chunk(chunk_index) {
+            return Ok(());
+        }
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .open(part_path(&self.dir, &self.token.file_hash))
+            .map_err(|e| format!("Failed to open part file: {}", e))?;
+        file.seek(SeekFrom::Start(offset))
+            .map_err(|e| format!("Failed to seek in part file: {}", e))?;
+        file.write_all(data)
+            .map_err(|e| format!("Failed to write chunk {}: {}", chunk_index, e))?;
+        file.sync_data()
+            .map_err(|e| format!("Failed to sync part file: {}", e))?;
+
+        self.token.received_chunks.insert(chunk_index);
+        self.chunk_hashes
+            .insert(chunk_index, hex::encode(Sha256::digest(data)));
+        self.save_token()
+    }
+
+    /// Write the chunk hashes, then the token, each atomically (temp file +
+    /// rename) so a crash can't leave them half-written
+    fn save_token(&self) -> Result<(), String> {
+        let hashes = serde_json::to_vec(&self.chunk_hashes)
+            .map_err(|e| format!("Failed to serialize chunk hashes: {}", e))?;
+        write_atomically(&hashes_path(&self.dir, &self.token.file_hash), &hashes)
+            .map_err(|e| format!("Failed to persist chunk hashes: {}", e))?;
+
+        let contents = serde_json::to_vec(&self.token)
+            .map_err(|e| format!("Failed to serialize resume token: {}", e))?;
+        write_atomically(&token_path(&self.dir, &self.token.file_hash), &contents)
+            .map_err(|e| format!("Failed to persist resume token: {}", e))
+    }
+
+    /// Move the completed part file to `destination` and drop the token
+    pub fn finalize(self, destination: &Path) -> Result<u64, String> {
+        if !self.is_complete() {
+            return Err(format!(
+                "Transfer of {} is incomplete: missing {} chunks",
+                self.token.file_hash,
+                self.token.missing_chunks().len()
+            ));
+        }
+
+        let part = part_path(&self.dir, &self.token.file_hash);
+        if fs::rename(&part, destination).is_err() {
+            // Rename fails across filesystems; fall back to copy + remove
+            fs::copy(&part, destination)
+                .map_err(|e| format!("Failed to move part file to {:?}: {}", destination, e))?;
+            let _ = fs::remove_file(&part);
+        }
+        let _ = fs::remove_file(token_path(&self.dir, &self.token.file_hash));
+        let _ = fs::remove_file(hashes_path(&self.dir, &self.token.file_hash));
+
+        let size = fs::metadata(destination)
+            .map(|m| m.len())
+            .map_err(|e| format!("Failed to stat {:?}: {}", destination, e))?;
+        debug!(
+            "Finalized resumable transfer into {:?} ({} bytes)",
+            destination, size
+        );
+        Ok(size)
+    }
+}
+
+fn write_atomically(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    let tmp = path.with_extension("json.tmp");
+    fs::write(&tmp, contents)?;
+    fs::rename(&tmp, path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    const CHUNK: usize = 4;
+
+    fn split(data: &[u8]) -> Vec<Vec<u8>> {
+        data.chunks(CHUNK).map(|c| c.to_vec()).collect()
+    }
+
+    fn hash_of(data: &[u8]) -> String {
+        hex::encode(Sha256::digest(data))
+    }
+
+    #[test]
+    fn test_resume_after_drop_sends_only_remaining_chunks() {
+        let dir = tempdir().unwrap();
+        let data: Vec<u8> = (0..38u8).collect();
+        let file_hash = &hash_of(&data);
+        let chunks = split(&data);
+        let total = chunks.len() as u32;
+
+        // First connection: the sender starts from scratch, the link drops halfway
+        let first_pass = chunks_to_send(file_hash, total, None);
+        assert_eq!(first_pass.len(), chunks.len());
+        {
+            let mut partial = PartialDownload::open(dir.path(), file_hash, total, CHUNK).unwrap();
+            for &index in first_pass.iter().take(chunks.len() / 2) {
+                partial.write_chunk(index, &chunks[index as usize]).unwrap();
+            }
+            assert!(!partial.is_complete());
+        }
+
+        // Reconnect (after a restart): the receiver reloads its token from disk
+        let token = load_token(dir.path(), file_hash).expect("token should be persisted");
+        let second_pass = chunks_to_send(file_hash, total, Some(&token));
+        let expected: Vec<u32> = (total / 2..total).collect();
+        assert_eq!(second_pass, expected);
+
+        let mut partial = PartialDownload::open(dir.path(), file_hash, total, CHUNK).unwrap();
+        assert_eq!(partial.token(), &token);
+        for &index in &second_pass {
+            partial.write_chunk(index, &chunks[index as usize]).unwrap();
+        }
+        assert!(partial.is_complete());
+
+        let output = dir.path().join("output.bin");
+        let size = partial.finalize(&output).unwrap();
+        assert_eq!(size, data.len() as u64);
+        assert_eq!(fs::read(&output).unwrap(), data);
+        assert!(load_token(dir.path(), file_hash).is_none());
+    }
+
+    #[test]
+    fn test_resume_refetches_chunks_whose_bytes_changed() {
+        let dir = tempdir().unwrap();
+        let data: Vec<u8> = (0..41u8).collect();
+        let file_hash = &hash_of(&data);
+        let chunks = split(&data);
+        let total = chunks.len() as u32;
+
+        // Write the first half, then abort
+        {
+            let mut partial = PartialDownload::open(dir.path(), file_hash, total, CHUNK).unwrap();
+            for index in 0..total / 2 {
+                partial.write_chunk(index, &chunks[index as usize]).unwrap();
+            }
+        }
+
+        // Chunk 1 is damaged on disk while the app is closed
+        let mut part = OpenOptions::new()
+            .write(true)
+            .open(part_path(dir.path(), file_hash))
+            .unwrap();
+        part.seek(SeekFrom::Start(CHUNK as u64)).unwrap();
+        part.write_all(&[0xff]).unwrap();
+        drop(part);
+
+        // Re-run: only the damaged chunk and the missing half are fetched
+        let mut partial = PartialDownload::open(dir.path(), file_hash, total, CHUNK).unwrap();
+        let token = load_token(dir.path(), file_hash).unwrap();
+        assert_eq!(partial.token(), &token);
+        let mut expected = vec![1];
+        expected.extend(total / 2..total);
+        let refetch = chunks_to_send(file_hash, total, Some(&token));
+        assert_eq!(refetch, expected);
+        for &index in &refetch {
+            partial.write_chunk(index, &chunks[index as usize]).unwrap();
+        }
+
+        let output = dir.path().join("verified.bin");
+        partial.finalize(&output).unwrap();
+        assert_eq!(
+            Sha256::digest(fs::read(&output).unwrap()),
+            Sha256::digest(&data)
+        );
+        assert!(!hashes_path(dir.path(), file_hash).exists());
+    }
+
+    #[test]
+    fn test_truncated_part_file_restarts_from_scratch() {
+        let dir = tempdir().unwrap();
+        let file_hash = &hash_of(b"truncated");
+        let chunks = split(&[7u8; 16]);
+        {
+            let mut partial = PartialDownload::open(dir.path(), file_hash, 4, CHUNK).unwrap();
+            for (index, chunk) in chunks.iter().enumerate().take(3) {
+                partial.write_chunk(index as u32, chunk).unwrap();
+            }
+        }
+        fs::remove_file(part_path(dir.path(), file_hash)).unwrap();
+
+        let partial = PartialDownload::open(dir.path(), file_hash, 4, CHUNK).unwrap();
+        assert!(partial.token().received_chunks.is_empty());
+    }
+
+    #[test]
+    fn test_mismatched_token_sends_whole_file() {
+        let mut token = ResumeToken::new("other-file".to_string(), 4);
+        token.received_chunks.extend([0, 1]);
+        assert_eq!(chunks_to_send("file", 4, Some(&token)), vec![0, 1, 2, 3]);
+
+        let mut token = ResumeToken::new("file".to_string(), 8);
+        token.received_chunks.extend([0, 1]);
+        assert_eq!(chunks_to_send("file", 4, Some(&token)), vec![0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn test_hostile_transfer_cannot_write_outside_its_part_file() {
+        let root = tempdir().unwrap();
+        let dir = root.path().join("transfers");
+        let data: Vec<u8> = (0..16u8).collect();
+        let chunks = split(&data);
+
+        // The sender names the file with a path instead of a hash
+        for hostile in ["../escaped", "../../etc/passwd", "/tmp/abs"] {
+            assert!(PartialDownload::open(&dir, hostile, 4, CHUNK).is_err());
+            assert!(load_token(&dir, hostile).is_none());
+            discard(&dir, hostile);
+        }
+        assert!(!root.path().join("escaped.part").exists());
+
+        // A well-formed transfer where the sender lies about chunk positions
+        let file_hash = hash_of(&data);
+        let mut partial = PartialDownload::open(&dir, &file_hash, 4, CHUNK).unwrap();
+        for &index in &chunks_to_send(&file_hash, 4, None) {
+            partial.write_chunk(index, &chunks[index as usize]).unwrap();
+        }
+        assert!(partial.write_chunk(4, &chunks[0]).is_err());
+        assert!(partial.write_chunk(u32::MAX, &chunks[0]).is_err());
+        assert!(partial.write_chunk(3, &[0u8; CHUNK + 1]).is_err());
+        assert!(PartialDownload::open(&dir, &file_hash, 0, CHUNK).is_err());
+
+        let output = root.path().join("output.bin");
+        assert_eq!(partial.finalize(&output).unwrap(), data.len() as u64);
+        assert_eq!(fs::read(&output).unwrap(), data);
+        let leftovers: Vec<_> = fs::read_dir(&dir).unwrap().collect();
+        assert!(leftovers.is_empty());
+    }
+}
@@ -3,9 +3,10 @@ use crate::encryption::{decrypt_aes_key, encrypt_aes_key, EncryptedAesKeyBundle,
 use crate::file_transfer::FileTransferService;
 use crate::keystore::Keystore;
 use crate::bandwidth::BandwidthController;
-use crate::manager::{ChunkInfo, FileManifest};
+use crate::manager::{ChunkInfo, CipherSuite, FileManifest, HashAlgorithm, COMPRESSION_NONE};
 use crate::multi_source_download::MultiSourceDownloadService;
 use crate::payment_checkpoint::PaymentCheckpointService;
+use crate::transfer_resume::{self, chunks_to_send, PartialDownload, ResumeToken};
 use aes_gcm::aead::Aead;
 use aes_gcm::{AeadCore, KeyInit};
 use serde::{Deserialize, Serialize};
@@ -39,6 +40,9 @@ lazy_static::lazy_static! {
     /// Global map of progress bars for active uploads, keyed by (peer_id, file_hash)
     static ref UPLOAD_PROGRESS_BARS: Mutex<HashMap<String, ProgressBar>> = Mutex::new(HashMap::new());
 
+    /// Resumable on-disk state of incoming transfers, keyed by file hash
+    static ref RESUMABLE_DOWNLOADS: Mutex<HashMap<String, PartialDownload>> = Mutex::new(HashMap::new());
+
     /// Requested output paths for WebRTC downloads (file_hash -> output_path).
     /// This lets the download initiator (GUI/E2E API) control the final save location,
     /// while the assembler lives in this library crate (no access to binary AppState).
@@ -63,6 +67,12 @@ async fn take_requested_download_output_path(file_hash: &str) -> Option<String>
 
 const CHUNK_SIZE: usize = 32768; // 32KB chunks - configured data channel for larger messages (8x improvement over original 4KB)
 
+/// Length of chunk `chunk_index` in a file of `file_len` bytes
+fn chunk_len(chunk_index: u32, file_len: usize) -> usize {
+    let start = (chunk_index as usize) * CHUNK_SIZE;
+    (start + CHUNK_SIZE).min(file_len).saturating_sub(start)
+}
+
 // --- WebRTC binary framing for file chunks ---
 // We send file chunks as *binary* messages instead of JSON text to avoid massive JSON overhead
 // (Vec<u8> becomes a large numeric array in JSON, easily exceeding DataChannel max message size).
@@ -259,36 +269,158 @@ const INITIAL_RETRY_DELAY_MS: u64 = 1000;
 /// Maximum delay between connection retries (milliseconds)
 const MAX_RETRY_DELAY_MS: u64 = 15000;
 
-/// Creates a WebRTC configuration with STUN and TURN servers for NAT traversal.
+/// A STUN or TURN server used for ICE. TURN servers need a username and credential.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct IceServer {
+    pub urls: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub username: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub credential: Option<String>,
+}
+
+/// ICE servers used for every peer connection.
 /// Without ICE servers, WebRTC connections will fail for users behind NAT (majority of users).
-/// 
+///
 /// TURN servers are required for symmetric NAT (common in universities/corporate networks).
-fn create_rtc_configuration() -> RTCConfiguration {
-    RTCConfiguration {
-        ice_servers: vec![
-            // Google STUN servers (reliable, no auth needed)
-            RTCIceServer {
-                urls: vec![
-                    "stun:stun.l.google.com:19302".to_string(),
-                    "stun:stun1.l.google.com:19302".to_string(),
-                    "stun:stun2.l.google.com:19302".to_string(),
-                    "stun:stun3.l.google.com:19302".to_string(),
-                ],
-                ..Default::default()
-            },
-            // Evan Brass experimental TURN server (free, public)
-            RTCIceServer {
-                urls: vec![
-                    "turn:stun.evan-brass.net".to_string(),
-                    "turn:stun.evan-brass.net?transport=tcp".to_string(),
-                    "stun:stun.evan-brass.net".to_string(),
-                ],
-                username: "guest".to_string(),
-                credential: "password".to_string(),
-                credential_type: RTCIceCredentialType::Password,
-            },
-        ],
-        ..Default::default()
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct WebRtcConfig {
+    pub ice_servers: Vec<IceServer>,
+}
+
+impl Default for WebRtcConfig {
+    fn default() -> Self {
+        Self {
+            ice_servers: vec![
+                // Google STUN servers (reliable, no auth needed)
+                IceServer {
+                    urls: vec![
+                        "stun:stun.l.google.com:19302".to_string(),
+                        "stun:stun1.l.google.com:19302".to_string(),
+                        "stun:stun2.l.google.com:19302".to_string(),
+                        "stun:stun3.l.google.com:19302".to_string(),
+                    ],
+                    username: None,
+                    credential: None,
+                },
+                // Evan Brass experimental TURN server (free, public)
+                IceServer {
+                    urls: vec![
+                        "turn:stun.evan-brass.net".to_string(),
+                        "turn:stun.evan-brass.net?transport=tcp".to_string(),
+                        "stun:stun.evan-brass.net".to_string(),
+                    ],
+                    username: Some("guest".to_string()),
+                    credential: Some("password".to_string()),
+                },
+            ],
+        }
+    }
+}
+
+impl WebRtcConfig {
+    /// ICE servers from the `CHIRAL_ICE_SERVERS` environment variable (a JSON
+    /// array of `IceServer`), falling back to the defaults if it is unset or invalid.
+    pub fn from_env() -> Self {
+        let Ok(json) = std::env::var("CHIRAL_ICE_SERVERS") else {
+            return Self::default();
+        };
+        let config = serde_json::from_str(&json)
+            .map_err(|e| e.to_string())
+            .map(|ice_servers| Self { ice_servers })
+            .and_then(|config| config.validate().map(|_| config));
+        match config {
+            Ok(config) => config,
+            Err(e) => {
+                warn!(
+                    "Ignoring invalid CHIRAL_ICE_SERVERS ({}), using default ICE servers",
+                    e
+                );
+                Self::default()
+            }
+        }
+    }
+
+    /// Check every URL is a `stun:`/`stuns:`/`turn:`/`turns:` URI with a host,
+    /// and that TURN servers carry credentials.
+    pub fn validate(&self) -> Result<(), String> {
+        for server in &self.ice_servers {
+            if server.urls.is_empty() {
+                return Err("ICE server has no URLs".to_string());
+            }
+            for url in &server.urls {
+                let (scheme, rest) = url
+                    .split_once(':')
+                    .ok_or_else(|| format!("Invalid ICE server URL: {}", url))?;
+                let host = rest.split(['?', ':']).next().unwrap_or_default();
+                match scheme {
+                    "stun" | "stuns" => {}
+                    "turn" | "turns" => {
+                        if server.username.is_none() || server.credential.is_none() {
+                            return Err(format!(
+                                "TURN server {} needs a username and credential",
+                                url
+                            ));
+                        }
+                    }
+                    _ => return Err(format!("Unsupported ICE server scheme in {}", url)),
+                }
+                if host.is_empty() {
+                    return Err(format!("ICE server URL has no host: {}", url));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn to_rtc_configuration(&self) -> RTCConfiguration {
+        RTCConfiguration {
+            ice_servers: self
+                .ice_servers
+                .iter()
+                .map(|server| match (&server.username, &server.credential) {
+                    (Some(username), Some(credential)) => RTCIceServer {
+                        urls: server.urls.clone(),
+                        username: username.clone(),
+                        credential: credential.clone(),
+                        credential_type: RTCIceCredentialType::Password,
+                    },
+                    _ => RTCIceServer {
+                        urls: server.urls.clone(),
+                        ..Default::default()
+                    },
+                })
+                .collect(),
+            ..Default::default()
+        }
+    }
+}
+
+/// Status label for the UI: "connecting", "connected", "failed", ...
+fn ice_state_label(state: RTCIceConnectionState) -> &'static str {
+    match state {
+        RTCIceConnectionState::New | RTCIceConnectionState::Checking => "connecting",
+        RTCIceConnectionState::Connected | RTCIceConnectionState::Completed => "connected",
+        RTCIceConnectionState::Failed => "failed",
+        RTCIceConnectionState::Disconnected => "disconnected",
+        RTCIceConnectionState::Closed => "closed",
+        _ => "unknown",
+    }
+}
+
+/// Emit a `webrtc_connection_state` event so the UI can show the ICE status
+fn emit_ice_state(
+    app_handle: Option<&tauri::AppHandle>,
+    peer_id: &str,
+    state: RTCIceConnectionState,
+) {
+    if let Some(app_handle) = app_handle {
+        let _ = app_handle.emit(
+            "webrtc_connection_state",
+            serde_json::json!({ "peerId": peer_id, "state": ice_state_label(state) }),
+        );
     }
 }
 
@@ -300,6 +432,9 @@ pub struct WebRTCFileRequest {
     pub file_size: u64,
     pub requester_peer_id: String,
     pub recipient_public_key: Option<String>, // For encrypted transfers
+    /// Chunks the requester already holds from an interrupted transfer
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resume_token: Option<ResumeToken>,
 }
 
 /// Sent by a downloader to request the full file manifest.
@@ -508,6 +643,8 @@ pub struct WebRTCService {
     multi_source_service: Option<Arc<MultiSourceDownloadService>>,
     /// Payment checkpoint service for incremental payments during file transfers
     payment_checkpoint: Option<Arc<PaymentCheckpointService>>,
+    /// ICE servers for new peer connections
+    ice_config: WebRtcConfig,
 }
 
 impl WebRTCService {
@@ -516,6 +653,7 @@ impl WebRTCService {
         file_transfer_service: Arc<FileTransferService>,
         keystore: Arc<Mutex<Keystore>>,
         bandwidth: Arc<BandwidthController>,
+        config: WebRtcConfig,
     ) -> Result<Self, String> {
         Self::new_with_multi_source_opt(
             Some(app_handle),
@@ -524,6 +662,7 @@ impl WebRTCService {
             bandwidth,
             None,
             None,
+            config,
         )
         .await
     }
@@ -537,6 +676,7 @@ impl WebRTCService {
         bandwidth: Arc<BandwidthController>,
         multi_source_service: Option<Arc<MultiSourceDownloadService>>,
         payment_checkpoint: Option<Arc<PaymentCheckpointService>>,
+        config: WebRtcConfig,
     ) -> Result<Self, String> {
         Self::new_with_multi_source_opt(
             Some(app_handle),
@@ -545,6 +685,7 @@ impl WebRTCService {
             bandwidth,
             multi_source_service,
             payment_checkpoint,
+            config,
         )
         .await
     }
@@ -555,6 +696,7 @@ impl WebRTCService {
         keystore: Arc<Mutex<Keystore>>,
         bandwidth: Arc<BandwidthController>,
         multi_source_service: Option<Arc<MultiSourceDownloadService>>,
+        config: WebRtcConfig,
     ) -> Result<Self, String> {
         Self::new_with_multi_source_opt(
             None,
@@ -563,6 +705,7 @@ impl WebRTCService {
             bandwidth,
             multi_source_service,
             None,
+            config,
         )
         .await
     }
@@ -574,7 +717,10 @@ impl WebRTCService {
         bandwidth: Arc<BandwidthController>,
         multi_source_service: Option<Arc<MultiSourceDownloadService>>,
         payment_checkpoint: Option<Arc<PaymentCheckpointService>>,
+        config: WebRtcConfig,
     ) -> Result<Self, String> {
+        config.validate()?;
+
         let (cmd_tx, cmd_rx) = mpsc::channel(100);
         let (event_tx, event_rx) = mpsc::channel(1000); // Increased capacity for high-throughput transfers
         let connections = Arc::new(Mutex::new(HashMap::new()));
@@ -599,6 +745,7 @@ impl WebRTCService {
             connection_manager_clone,
             multi_source_service_clone,
             payment_checkpoint_clone,
+            config.clone(),
         ));
 
         Ok(WebRTCService {
@@ -614,6 +761,7 @@ impl WebRTCService {
             connection_manager,
             multi_source_service,
             payment_checkpoint,
+            ice_config: config,
         })
     }
 
@@ -658,6 +806,7 @@ impl WebRTCService {
         connection_manager: Arc<ConnectionManager>,
         multi_source_service: Option<Arc<MultiSourceDownloadService>>,
         payment_checkpoint: Option<Arc<PaymentCheckpointService>>,
+        ice_config: WebRtcConfig,
     ) {
         while let Some(cmd) = cmd_rx.recv().await {
             match cmd {
@@ -676,6 +825,7 @@ impl WebRTCService {
                         &keystore,
                         &active_private_key,
                         &bandwidth,
+                        &ice_config,
                         &connection_manager,
                         multi_source_service.as_ref(),
                         &payment_checkpoint,
@@ -730,6 +880,7 @@ impl WebRTCService {
                         &keystore,
                         &active_private_key,
                         &bandwidth,
+                        &ice_config,
                         &connection_manager,
                         multi_source_service.as_ref(),
                         &payment_checkpoint,
@@ -751,6 +902,7 @@ impl WebRTCService {
         keystore: &Arc<Mutex<Keystore>>,
         active_private_key: &Arc<Mutex<Option<String>>>,
         bandwidth: &Arc<BandwidthController>,
+        ice_config: &WebRtcConfig,
         connection_manager: &Arc<ConnectionManager>,
         multi_source_service: Option<&Arc<MultiSourceDownloadService>>,
         payment_checkpoint: &Option<Arc<PaymentCheckpointService>>,
@@ -770,6 +922,7 @@ impl WebRTCService {
             keystore,
             active_private_key,
             bandwidth,
+            ice_config,
             multi_source_service,
             payment_checkpoint,
         )
@@ -824,6 +977,7 @@ impl WebRTCService {
         keystore: &Arc<Mutex<Keystore>>,
         active_private_key: &Arc<Mutex<Option<String>>>,
         bandwidth: &Arc<BandwidthController>,
+        ice_config: &WebRtcConfig,
         connection_manager: &Arc<ConnectionManager>,
         multi_source_service: Option<&Arc<MultiSourceDownloadService>>,
         payment_checkpoint: &Option<Arc<PaymentCheckpointService>>,
@@ -877,6 +1031,7 @@ impl WebRTCService {
             keystore,
             active_private_key,
             bandwidth,
+            ice_config,
             connection_manager,
             multi_source_service,
             payment_checkpoint,
@@ -895,6 +1050,7 @@ impl WebRTCService {
         keystore: &Arc<Mutex<Keystore>>,
         active_private_key: &Arc<Mutex<Option<String>>>,
         bandwidth: &Arc<BandwidthController>,
+        ice_config: &WebRtcConfig,
         multi_source_service: Option<&Arc<MultiSourceDownloadService>>,
         payment_checkpoint: &Option<Arc<PaymentCheckpointService>>,
     ) -> Result<(), String> {
@@ -909,6 +1065,7 @@ impl WebRTCService {
             keystore,
             active_private_key,
             bandwidth,
+            ice_config,
             multi_source_service,
             payment_checkpoint,
         )
@@ -937,6 +1094,7 @@ impl WebRTCService {
         keystore: &Arc<Mutex<Keystore>>,
         active_private_key: &Arc<Mutex<Option<String>>>,
         bandwidth: &Arc<BandwidthController>,
+        ice_config: &WebRtcConfig,
         multi_source_service: Option<&Arc<MultiSourceDownloadService>>,
         payment_checkpoint: &Option<Arc<PaymentCheckpointService>>,
     ) {
@@ -946,7 +1104,7 @@ impl WebRTCService {
         let api = APIBuilder::new().build();
 
         // Create peer connection with ICE servers for NAT traversal
-        let config = create_rtc_configuration();
+        let config = ice_config.to_rtc_configuration();
         let peer_connection = match api.new_peer_connection(config).await {
             Ok(pc) => Arc::new(pc),
             Err(e) => {
@@ -1089,10 +1247,13 @@ impl WebRTCService {
 
         // Add ICE connection state handler for debugging NAT traversal issues
         let peer_id_for_ice_state = peer_id.to_string();
+        let app_handle_for_ice_state = Some(app_handle.clone());
         peer_connection.on_ice_connection_state_change(Box::new(
             move |state: RTCIceConnectionState| {
                 let peer_id = peer_id_for_ice_state.clone();
+                let app_handle = app_handle_for_ice_state.clone();
                 Box::pin(async move {
+                    emit_ice_state(app_handle.as_ref(), &peer_id, state);
                     match state {
                         RTCIceConnectionState::Checking => {
                             info!("ICE: Checking connectivity for peer: {}", peer_id);
@@ -1794,12 +1955,21 @@ impl WebRTCService {
                                         size: (end - start),
                                         encrypted_hash: chunk_hash,
                                         encrypted_size: (end - start),
+                                        compression_type: COMPRESSION_NONE,
+                                        compressed_size: (end - start),
+                                        cipher_suite: CipherSuite::Aes256Gcm,
                                     });
                                 }
                                 let manifest = FileManifest {
                                     merkle_root: request.file_hash.clone(),
                                     chunks,
                                     encrypted_key_bundle,
+                                    hash_algorithm: HashAlgorithm::Sha256,
+                                    encryption_info: None,
+                                    mime_type: None,
+                                    chunk_size: CHUNK_SIZE,
+                                    signature: None,
+                                    file_hash: None,
                                 };
 
                                 let manifest_json = serde_json::to_string(&manifest).unwrap();
@@ -1954,6 +2124,22 @@ impl WebRTCService {
         // Calculate total chunks
         let total_chunks = ((file_data.len() as f64) / CHUNK_SIZE as f64).ceil() as u32;
 
+        // Skip chunks the receiver already holds from an interrupted transfer
+        let chunk_indices =
+            chunks_to_send(&request.file_hash, total_chunks, request.resume_token.as_ref());
+        let chunks_already_received = total_chunks - chunk_indices.len() as u32;
+        let bytes_already_received = file_data.len() as u64
+            - chunk_indices
+                .iter()
+                .map(|&i| chunk_len(i, file_data.len()) as u64)
+                .sum::<u64>();
+        if chunks_already_received > 0 {
+            info!(
+                "Resuming transfer of {} to peer {}: receiver already has {}/{} chunks",
+                request.file_hash, peer_id, chunks_already_received, total_chunks
+            );
+        }
+
         info!(
             "Starting real file transfer of {} ({} bytes, {} chunks) to peer {}",
             request.file_name,
@@ -1997,8 +2183,8 @@ impl WebRTCService {
                     file_name: request.file_name.clone(),
                     file_size: file_data.len() as u64,
                     total_chunks,
-                    chunks_sent: 0,
-                    bytes_sent: 0,
+                    chunks_sent: chunks_already_received,
+                    bytes_sent: bytes_already_received,
                     start_time: Instant::now(),
                 };
                 connection
@@ -2026,7 +2212,7 @@ impl WebRTCService {
             }
         }
 
-        info!("📦 Starting chunk loop for {} chunks to peer {}", total_chunks, peer_id);
+        info!("📦 Starting chunk loop for {} of {} chunks to peer {}", chunk_indices.len(), total_chunks, peer_id);
 
         // Debug: log data channel state before starting loop
         {
@@ -2043,7 +2229,7 @@ impl WebRTCService {
         }
 
         // Send file chunks over WebRTC data channel with flow control
-        for chunk_index in 0..total_chunks {
+        for chunk_index in chunk_indices.iter().copied() {
             // Log EVERY chunk for first 100 to debug stall
             if chunk_index < 100 {
                 info!("🔁 LOOP: Starting chunk {} for peer {}", chunk_index, peer_id);
@@ -2212,6 +2398,7 @@ impl WebRTCService {
                         .unwrap()
                         .progress_chars("=>-"));
                     pb.set_message(format!("Uploading {}", &request.file_hash[..8]));
+                    pb.set_position(chunks_already_received as u64);
                     pb
                 });
                 pb.inc(1); // Increment by 1 so indicatif can calculate speed
@@ -2418,6 +2605,9 @@ impl WebRTCService {
 
         bandwidth.acquire_download(chunk_len).await;
 
+        // Write the verified chunk through to disk so an interrupted transfer can resume
+        let resumed_chunks = Self::record_resumable_chunk(chunk, &final_chunk_data).await;
+
         // Get data channel reference before locking connections
         let dc_for_ack = {
             let conns = connections.lock().await;
@@ -2436,15 +2626,17 @@ impl WebRTCService {
 
             // Emit progress to frontend
             if let Some(total_chunks) = chunks.values().next().map(|c| c.total_chunks) {
-                let progress_percentage = (chunks.len() as f32 / total_chunks as f32) * 100.0;
-                let bytes_received = chunks.len() as u64 * CHUNK_SIZE as u64;
+                // A resumed transfer holds earlier chunks on disk rather than in memory
+                let chunks_received = resumed_chunks.unwrap_or(0).max(chunks.len());
+                let progress_percentage = (chunks_received as f32 / total_chunks as f32) * 100.0;
+                let bytes_received = chunks_received as u64 * CHUNK_SIZE as u64;
                 let estimated_total_size = total_chunks as u64 * CHUNK_SIZE as u64;
 
                 if let Some(app_handle) = app_handle {
                     if let Err(e) = app_handle.emit("webrtc_download_progress", serde_json::json!({
                         "fileHash": chunk.file_hash,
                         "progress": progress_percentage,
-                        "chunksReceived": chunks.len(),
+                        "chunksReceived": chunks_received,
                         "totalChunks": total_chunks,
                         "bytesReceived": bytes_received,
                         "totalBytes": estimated_total_size,
@@ -2453,22 +2645,44 @@ impl WebRTCService {
                     }
                 }
 
-                if chunks.len() == total_chunks as usize {
+                if chunks_received == total_chunks as usize {
                     // Finish and remove progress bar
                     if let Some(pb) = DOWNLOAD_PROGRESS_BARS.lock().await.remove(&chunk.file_hash) {
                         pb.finish_with_message(format!("✓ Downloaded {}", &chunk.file_hash[..8]));
                     }
 
-                    // Assemble file
-                    Self::assemble_file_from_chunks(
-                        &chunk.file_hash,
-                        chunks,
-                        file_transfer_service,
-                        event_tx,
-                        peer_id,
-                        app_handle,
-                    )
-                    .await;
+                    let partial = RESUMABLE_DOWNLOADS.lock().await.remove(&chunk.file_hash);
+                    match partial {
+                        Some(partial) if partial.is_complete() => {
+                            // Every chunk is on disk, possibly from before a reconnect
+                            connection.received_chunks.remove(&chunk.file_hash);
+                            Self::finalize_resumed_download(
+                                partial,
+                                &chunk.file_hash,
+                                &chunk.file_name,
+                                event_tx,
+                                peer_id,
+                                app_handle,
+                            )
+                            .await;
+                        }
+                        _ => {
+                            // Assemble file
+                            Self::assemble_file_from_chunks(
+                                &chunk.file_hash,
+                                chunks,
+                                file_transfer_service,
+                                event_tx,
+                                peer_id,
+                                app_handle,
+                            )
+                            .await;
+                            transfer_resume::discard(
+                                &transfer_resume::default_resume_dir(),
+                                &chunk.file_hash,
+                            );
+                        }
+                    }
                 }
             }
         }
@@ -2509,66 +2723,15 @@ impl WebRTCService {
         .map(|c| c.file_name.clone()) // Use file_name instead of file_hash
         .unwrap_or_else(|| format!("downloaded_{}", file_hash));
 
-    // Ensure we only use a safe basename (avoid path traversal / separators).
-    let file_name = std::path::Path::new(&raw_file_name)
-        .file_name()
-        .and_then(|s| s.to_str())
-        .map(|s| s.to_string())
-        .unwrap_or_else(|| format!("downloaded_{}", file_hash));
-
     // Compute final size without concatenating into a giant Vec<u8>.
     let file_size: usize = sorted_chunks.iter().map(|c| c.data.len()).sum();
 
-    // Choose output path:
-    // - If the caller (GUI / E2E API) requested a specific output_path, honor it.
-    // - Otherwise, fall back to the configured download directory from settings.
-    let requested_output_path: Option<std::path::PathBuf> =
-        take_requested_download_output_path(file_hash)
-            .await
-            .map(std::path::PathBuf::from);
-
-    let output_path: std::path::PathBuf = if let Some(p) = requested_output_path {
-        // If the requested path is an existing directory, write the file inside it.
-        if p.exists() && p.is_dir() {
-            p.join(&file_name)
-        } else {
-            p
-        }
-    } else {
-        // Resolve download directory (same single source of truth as the frontend command).
-        let storage_path = match crate::download_paths::get_download_directory_opt(app_handle) {
-            Ok(p) => p,
-            Err(e) => {
-                error!("Failed to resolve download directory: {}", e);
-                return;
-            }
-        };
-
-        // Ensure directory exists
-        if let Err(e) = crate::download_paths::ensure_directory_exists(&storage_path).await {
-            error!("Failed to ensure download directory exists ({}): {}", storage_path, e);
-            return;
-        }
-
-        std::path::Path::new(&storage_path).join(&file_name)
+    let Some((file_name, output_path)) =
+        Self::resolve_download_output_path(file_hash, &raw_file_name, app_handle).await
+    else {
+        return;
     };
 
-    // High-signal diagnostic: shows whether we honored a requested output path or fell back.
-    if output_path.to_string_lossy().contains("chiral-e2e-downloads") {
-        info!("📦 WebRTC assembling into E2E output path: {:?}", output_path);
-    } else {
-        info!("📦 WebRTC assembling into default output path: {:?}", output_path);
-    }
-
-    // Ensure output parent directory exists (covers requested output paths too).
-    if let Some(parent) = output_path.parent() {
-        let parent_str = parent.to_string_lossy().to_string();
-        if let Err(e) = crate::download_paths::ensure_directory_exists(&parent_str).await {
-            error!("Failed to ensure output directory exists ({}): {}", parent_str, e);
-            return;
-        }
-    }
-
     // Stream chunks to disk in order (avoid IPC + JSON serialization of raw bytes).
     use tokio::io::AsyncWriteExt;
     let file = match tokio::fs::File::create(&output_path).await {
@@ -2617,6 +2780,147 @@ impl WebRTCService {
         .await;
 }
 
+    /// Pick where a completed download is written: the path requested by the
+    /// download initiator if any, otherwise the configured download directory.
+    /// Returns the sanitized file name and the output path.
+    async fn resolve_download_output_path(
+        file_hash: &str,
+        raw_file_name: &str,
+        app_handle: Option<&tauri::AppHandle>,
+    ) -> Option<(String, std::path::PathBuf)> {
+        // Ensure we only use a safe basename (avoid path traversal / separators).
+        let file_name = std::path::Path::new(raw_file_name)
+            .file_name()
+            .and_then(|s| s.to_str())
+            .map(|s| s.to_string())
+            .unwrap_or_else(|| format!("downloaded_{}", file_hash));
+
+        // Choose output path:
+        // - If the caller (GUI / E2E API) requested a specific output_path, honor it.
+        // - Otherwise, fall back to the configured download directory from settings.
+        let requested_output_path: Option<std::path::PathBuf> =
+            take_requested_download_output_path(file_hash)
+                .await
+                .map(std::path::PathBuf::from);
+
+        let output_path: std::path::PathBuf = if let Some(p) = requested_output_path {
+            // If the requested path is an existing directory, write the file inside it.
+            if p.exists() && p.is_dir() {
+                p.join(&file_name)
+            } else {
+                p
+            }
+        } else {
+            // Resolve download directory (same single source of truth as the frontend command).
+            let storage_path = match crate::download_paths::get_download_directory_opt(app_handle) {
+                Ok(p) => p,
+                Err(e) => {
+                    error!("Failed to resolve download directory: {}", e);
+                    return None;
+                }
+            };
+
+            // Ensure directory exists
+            if let Err(e) = crate::download_paths::ensure_directory_exists(&storage_path).await {
+                error!("Failed to ensure download directory exists ({}): {}", storage_path, e);
+                return None;
+            }
+
+            std::path::Path::new(&storage_path).join(&file_name)
+        };
+
+        // High-signal diagnostic: shows whether we honored a requested output path or fell back.
+        if output_path.to_string_lossy().contains("chiral-e2e-downloads") {
+            info!("📦 WebRTC assembling into E2E output path: {:?}", output_path);
+        } else {
+            info!("📦 WebRTC assembling into default output path: {:?}", output_path);
+        }
+
+        // Ensure output parent directory exists (covers requested output paths too).
+        if let Some(parent) = output_path.parent() {
+            let parent_str = parent.to_string_lossy().to_string();
+            if let Err(e) = crate::download_paths::ensure_directory_exists(&parent_str).await {
+                error!("Failed to ensure output directory exists ({}): {}", parent_str, e);
+                return None;
+            }
+        }
+
+        Some((file_name, output_path))
+    }
+
+    /// Persist a verified chunk to the resumable part file. Returns the number of
+    /// chunks on disk, or `None` if the write-through failed, in which case the
+    /// in-memory copy is still used for assembly.
+    async fn record_resumable_chunk(chunk: &FileChunk, data: &[u8]) -> Option<usize> {
+        let mut downloads = RESUMABLE_DOWNLOADS.lock().await;
+        if !downloads.contains_key(&chunk.file_hash) {
+            match PartialDownload::open(
+                &transfer_resume::default_resume_dir(),
+                &chunk.file_hash,
+                chunk.total_chunks,
+                CHUNK_SIZE,
+            ) {
+                Ok(partial) => {
+                    downloads.insert(chunk.file_hash.clone(), partial);
+                }
+                Err(e) => {
+                    warn!("Resume state unavailable for {}: {}", chunk.file_hash, e);
+                    return None;
+                }
+            }
+        }
+
+        let partial = downloads.get_mut(&chunk.file_hash)?;
+        if let Err(e) = partial.write_chunk(chunk.chunk_index, data) {
+            warn!("Failed to persist chunk {} of {}: {}", chunk.chunk_index, chunk.file_hash, e);
+            return None;
+        }
+        Some(partial.token().received_chunks.len())
+    }
+
+    /// Complete a download whose chunks are all in the resumable part file,
+    /// including those received before a reconnect or restart.
+    async fn finalize_resumed_download(
+        partial: PartialDownload,
+        file_hash: &str,
+        raw_file_name: &str,
+        event_tx: &mpsc::Sender<WebRTCEvent>,
+        peer_id: &str,
+        app_handle: Option<&tauri::AppHandle>,
+    ) {
+        let Some((file_name, output_path)) =
+            Self::resolve_download_output_path(file_hash, raw_file_name, app_handle).await
+        else {
+            return;
+        };
+
+        let file_size = match partial.finalize(&output_path) {
+            Ok(size) => size,
+            Err(e) => {
+                error!("Failed to finalize resumed download {}: {}", file_hash, e);
+                return;
+            }
+        };
+
+        if let Some(app_handle) = app_handle {
+            if let Err(e) = app_handle.emit("webrtc_download_complete", serde_json::json!({
+                "fileHash": file_hash,
+                "fileName": file_name,
+                "fileSize": file_size,
+                "outputPath": output_path.to_string_lossy().to_string(),
+            })) {
+                error!("Failed to emit webrtc_download_complete event: {}", e);
+            }
+        }
+
+        let _ = event_tx
+            .send(WebRTCEvent::TransferCompleted {
+                peer_id: peer_id.to_string(),
+                file_hash: file_hash.to_string(),
+            })
+            .await;
+    }
+
     fn calculate_chunk_checksum(data: &[u8]) -> String {
         let mut hasher = Sha256::default();
         hasher.update(data);
@@ -2646,7 +2950,7 @@ impl WebRTCService {
         let api = APIBuilder::new().build();
 
         // Create peer connection with ICE servers for NAT traversal
-        let config = create_rtc_configuration();
+        let config = self.ice_config.to_rtc_configuration();
         let peer_connection: Arc<RTCPeerConnection> = match api.new_peer_connection(config).await {
             Ok(pc) => Arc::new(pc),
             Err(e) => {
@@ -2778,10 +3082,13 @@ impl WebRTCService {
 
         // Add ICE connection state handler for debugging NAT traversal issues
         let peer_id_for_ice_state = peer_id.to_string();
+        let app_handle_for_ice_state = self.app_handle.clone();
         peer_connection.on_ice_connection_state_change(Box::new(
             move |state: RTCIceConnectionState| {
                 let peer_id = peer_id_for_ice_state.clone();
+                let app_handle = app_handle_for_ice_state.clone();
                 Box::pin(async move {
+                    emit_ice_state(app_handle.as_ref(), &peer_id, state);
                     match state {
                         RTCIceConnectionState::Checking => {
                             info!("ICE: Checking connectivity for peer: {}", peer_id);
@@ -2913,7 +3220,7 @@ impl WebRTCService {
         let api = APIBuilder::new().build();
 
         // Create peer connection with ICE servers for NAT traversal
-        let config = create_rtc_configuration();
+        let config = self.ice_config.to_rtc_configuration();
         let peer_connection: Arc<RTCPeerConnection> = match api.new_peer_connection(config).await {
             Ok(pc) => Arc::new(pc),
             Err(e) => {
@@ -3067,10 +3374,13 @@ impl WebRTCService {
 
         // Add ICE connection state handler for debugging NAT traversal issues
         let peer_id_for_ice_state = peer_id.to_string();
+        let app_handle_for_ice_state = self.app_handle.clone();
         peer_connection.on_ice_connection_state_change(Box::new(
             move |state: RTCIceConnectionState| {
                 let peer_id = peer_id_for_ice_state.clone();
+                let app_handle = app_handle_for_ice_state.clone();
                 Box::pin(async move {
+                    emit_ice_state(app_handle.as_ref(), &peer_id, state);
                     match state {
                         RTCIceConnectionState::Checking => {
                             info!("ICE: Checking connectivity for peer: {}", peer_id);
@@ -3180,8 +3490,15 @@ impl WebRTCService {
     pub async fn send_file_request(
         &self,
         peer_id: String,
-        request: WebRTCFileRequest,
+        mut request: WebRTCFileRequest,
     ) -> Result<(), String> {
+        // Reconnecting after an interruption: tell the seeder which chunks we already have
+        if request.resume_token.is_none() {
+            request.resume_token = transfer_resume::load_token(
+                &transfer_resume::default_resume_dir(),
+                &request.file_hash,
+            );
+        }
         self.cmd_tx
             .send(WebRTCCommand::SendFileRequest { peer_id, request })
             .await
@@ -3381,8 +3698,14 @@ pub async fn init_webrtc_service(
 ) -> Result<(), String> {
     let mut service = WEBRTC_SERVICE.lock().await;
     if service.is_none() {
-        let webrtc_service =
-            WebRTCService::new(app_handle, file_transfer_service, keystore, bandwidth).await?;
+        let webrtc_service = WebRTCService::new(
+            app_handle,
+            file_transfer_service,
+            keystore,
+            bandwidth,
+            WebRtcConfig::from_env(),
+        )
+        .await?;
         *service = Some(Arc::new(webrtc_service));
     }
     Ok(())
@@ -3412,6 +3735,7 @@ impl FileTransferService {
                 file_size: 0,                             // Will be updated
                 requester_peer_id: "local_peer".to_string(), // Should be actual local peer ID
                 recipient_public_key: None,               // No encryption for basic downloads
+                resume_token: None,
             };
 
             webrtc_service.send_file_request(peer_id, request).await?;
@@ -3421,4 +3745,71 @@ impl FileTransferService {
 
         Ok(())
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn turn_config() -> WebRtcConfig {
+        WebRtcConfig {
+            ice_servers: vec![
+                IceServer {
+                    urls: vec!["stun:stun.example.org:3478".to_string()],
+                    username: None,
+                    credential: None,
+                },
+                IceServer {
+                    urls: vec!["turn:turn.example.org:3478?transport=udp".to_string()],
+                    username: Some("alice".to_string()),
+                    credential: Some("secret".to_string()),
+                },
+            ],
+        }
+    }
+
+    #[tokio::test]
+    async fn test_ice_config_applied_to_peer_connection() {
+        let config = turn_config();
+        assert!(config.validate().is_ok());
+
+        let api = APIBuilder::new().build();
+        let peer_connection = api
+            .new_peer_connection(config.to_rtc_configuration())
+            .await
+            .unwrap();
+        let applied = peer_connection.get_configuration().await;
+
+        assert_eq!(applied.ice_servers.len(), 2);
+        assert_eq!(applied.ice_servers[0].urls, vec!["stun:stun.example.org:3478"]);
+        assert!(applied.ice_servers[0].username.is_empty());
+        assert_eq!(
+            applied.ice_servers[1].urls,
+            vec!["turn:turn.example.org:3478?transport=udp"]
+        );
+        assert_eq!(applied.ice_servers[1].username, "alice");
+        assert_eq!(applied.ice_servers[1].credential, "secret");
+        assert_eq!(
+            applied.ice_servers[1].credential_type,
+            RTCIceCredentialType::Password
+        );
+
+        peer_connection.close().await.unwrap();
+    }
+
+    #[test]
+    fn test_ice_config_validation() {
+        assert!(WebRtcConfig::default().validate().is_ok());
+
+        let mut bad_scheme = turn_config();
+        bad_scheme.ice_servers[0].urls = vec!["http://stun.example.org".to_string()];
+        assert!(bad_scheme.validate().is_err());
+
+        let mut missing_host = turn_config();
+        missing_host.ice_servers[0].urls = vec!["stun:".to_string()];
+        assert!(missing_host.validate().is_err());
+
+        let mut turn_without_credentials = turn_config();
+        turn_without_credentials.ice_servers[1].credential = None;
+        assert!(turn_without_credentials.validate().is_err());
+    }
+}
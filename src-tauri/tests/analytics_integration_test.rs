@@ -456,4 +456,54 @@ mod analytics_integration_tests {
             metrics.avg_download_speed_kbps
         );
     }
+
+    #[tokio::test]
+    async fn test_transfer_records_summary() {
+        use chiral_network::analytics::{TransferDirection, TransferRecord};
+
+        let analytics = AnalyticsService::new();
+        let day = 1_700_006_400; // a UTC midnight
+        let next_day = day + 86_400;
+        let transfers = [
+            (TransferDirection::Upload, 4_000, 2_000, true, day + 10),
+            (TransferDirection::Download, 6_000, 1_000, true, day + 20),
+            (TransferDirection::Download, 1_000, 500, false, day + 30),
+            (TransferDirection::Download, 2_000, 1_000, true, next_day),
+        ];
+        for (i, (direction, bytes, duration_ms, success, timestamp)) in
+            transfers.into_iter().enumerate()
+        {
+            analytics
+                .record_transfer_outcome(TransferRecord {
+                    transfer_id: format!("transfer-{}", i),
+                    direction,
+                    bytes,
+                    duration_ms,
+                    source: "peer-a".to_string(),
+                    success,
+                    timestamp,
+                })
+                .await;
+        }
+
+        let summary = analytics.get_analytics_summary().await;
+        assert_eq!(summary.total_transfers, 4);
+        assert_eq!(summary.successful_transfers, 3);
+        assert_eq!(summary.failed_transfers, 1);
+        assert_eq!(summary.total_uploaded_bytes, 4_000);
+        assert_eq!(summary.total_downloaded_bytes, 8_000);
+        assert!((summary.success_rate - 0.75).abs() < 1e-9);
+        // 12,000 bytes over 4 seconds of successful transfers
+        assert!((summary.average_throughput_bps - 3_000.0).abs() < 1e-9);
+
+        assert_eq!(summary.daily_totals.len(), 2);
+        assert_eq!(summary.daily_totals[0].day_start, day);
+        assert_eq!(summary.daily_totals[0].uploaded_bytes, 4_000);
+        assert_eq!(summary.daily_totals[0].downloaded_bytes, 6_000);
+        assert_eq!(summary.daily_totals[0].failed_transfers, 1);
+        assert_eq!(summary.daily_totals[1].downloaded_bytes, 2_000);
+
+        assert_eq!(summary.recent_transfers.len(), 4);
+        assert_eq!(summary.recent_transfers[0].transfer_id, "transfer-3");
+    }
 }
@@ -7,7 +7,9 @@
 //! 4. Chunk hash extraction for download verification
 
 use chiral_network::dht::models::FileMetadata;
-use chiral_network::manager::{ChunkManager, FileManifest, ChunkInfo};
+use chiral_network::manager::{
+    ChunkInfo, ChunkManager, CipherSuite, FileManifest, HashAlgorithm, DEFAULT_CHUNK_SIZE,
+};
 use std::path::Path;
 use tempfile::TempDir;
 use tokio;
@@ -64,6 +66,9 @@ fn test_file_metadata_with_manifest_serialization() {
                 size: 1024,
                 encrypted_hash: String::new(),
                 encrypted_size: 1024,
+                compression_type: 0,
+                compressed_size: 1024,
+                cipher_suite: CipherSuite::Aes256Gcm,
             },
             ChunkInfo {
                 index: 1,
@@ -71,9 +76,18 @@ fn test_file_metadata_with_manifest_serialization() {
                 size: 1024,
                 encrypted_hash: String::new(),
                 encrypted_size: 1024,
+                compression_type: 0,
+                compressed_size: 1024,
+                cipher_suite: CipherSuite::Aes256Gcm,
             },
         ],
         encrypted_key_bundle: None,
+        hash_algorithm: HashAlgorithm::Sha256,
+        encryption_info: None,
+        mime_type: None,
+        chunk_size: DEFAULT_CHUNK_SIZE,
+        signature: None,
+        file_hash: None,
     };
 
     let manifest_json = serde_json::to_string(&manifest).unwrap();
@@ -135,6 +149,9 @@ fn test_chunk_hash_extraction_workflow() {
                 size: 256 * 1024,
                 encrypted_hash: String::new(),
                 encrypted_size: 256 * 1024,
+                compression_type: 0,
+                compressed_size: 256 * 1024,
+                cipher_suite: CipherSuite::Aes256Gcm,
             },
             ChunkInfo {
                 index: 1,
@@ -142,6 +159,9 @@ fn test_chunk_hash_extraction_workflow() {
                 size: 256 * 1024,
                 encrypted_hash: String::new(),
                 encrypted_size: 256 * 1024,
+                compression_type: 0,
+                compressed_size: 256 * 1024,
+                cipher_suite: CipherSuite::Aes256Gcm,
             },
             ChunkInfo {
                 index: 2,
@@ -149,9 +169,18 @@ fn test_chunk_hash_extraction_workflow() {
                 size: 128 * 1024,
                 encrypted_hash: String::new(),
                 encrypted_size: 128 * 1024,
+                compression_type: 0,
+                compressed_size: 128 * 1024,
+                cipher_suite: CipherSuite::Aes256Gcm,
             },
         ],
         encrypted_key_bundle: None,
+        hash_algorithm: HashAlgorithm::Sha256,
+        encryption_info: None,
+        mime_type: None,
+        chunk_size: DEFAULT_CHUNK_SIZE,
+        signature: None,
+        file_hash: None,
     };
 
     // Store in metadata (upload to DHT)
@@ -268,6 +297,9 @@ fn test_manifest_with_variable_chunk_sizes() {
                 size: 256 * 1024, // Full chunk
                 encrypted_hash: String::new(),
                 encrypted_size: 256 * 1024,
+                compression_type: 0,
+                compressed_size: 256 * 1024,
+                cipher_suite: CipherSuite::Aes256Gcm,
             },
             ChunkInfo {
                 index: 1,
@@ -275,6 +307,9 @@ fn test_manifest_with_variable_chunk_sizes() {
                 size: 256 * 1024, // Full chunk
                 encrypted_hash: String::new(),
                 encrypted_size: 256 * 1024,
+                compression_type: 0,
+                compressed_size: 256 * 1024,
+                cipher_suite: CipherSuite::Aes256Gcm,
             },
             ChunkInfo {
                 index: 2,
@@ -282,9 +317,18 @@ fn test_manifest_with_variable_chunk_sizes() {
                 size: 50 * 1024, // Partial last chunk
                 encrypted_hash: String::new(),
                 encrypted_size: 50 * 1024,
+                compression_type: 0,
+                compressed_size: 50 * 1024,
+                cipher_suite: CipherSuite::Aes256Gcm,
             },
         ],
         encrypted_key_bundle: None,
+        hash_algorithm: HashAlgorithm::Sha256,
+        encryption_info: None,
+        mime_type: None,
+        chunk_size: DEFAULT_CHUNK_SIZE,
+        signature: None,
+        file_hash: None,
     };
 
     let manifest_json = serde_json::to_string(&manifest).unwrap();
@@ -326,6 +370,9 @@ fn test_manifest_json_round_trip_integrity() {
             size: data.len(),
             encrypted_hash: String::new(),
             encrypted_size: data.len(),
+            compression_type: 0,
+            compressed_size: data.len(),
+            cipher_suite: CipherSuite::Aes256Gcm,
         });
     }
 
@@ -333,6 +380,12 @@ fn test_manifest_json_round_trip_integrity() {
         merkle_root: "integrity_test_root".to_string(),
         chunks,
         encrypted_key_bundle: None,
+        hash_algorithm: HashAlgorithm::Sha256,
+        encryption_info: None,
+        mime_type: None,
+        chunk_size: DEFAULT_CHUNK_SIZE,
+        signature: None,
+        file_hash: None,
     };
 
     // JSON round-trip
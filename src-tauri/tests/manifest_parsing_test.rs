@@ -4,7 +4,9 @@
 //! from FileManifest JSON stored in FileMetadata.
 
 use chiral_network::dht::models::FileMetadata;
-use chiral_network::manager::{FileManifest, ChunkInfo};
+use chiral_network::manager::{
+    ChunkInfo, CipherSuite, FileManifest, HashAlgorithm, DEFAULT_CHUNK_SIZE,
+};
 use sha2::{Digest, Sha256};
 use hex;
 
@@ -27,6 +29,9 @@ fn create_test_manifest() -> FileManifest {
             size: data.len(),
             encrypted_hash: String::new(),
             encrypted_size: data.len(),
+            compression_type: 0,
+            compressed_size: data.len(),
+            cipher_suite: CipherSuite::Aes256Gcm,
         });
     }
 
@@ -34,6 +39,12 @@ fn create_test_manifest() -> FileManifest {
         merkle_root: "test_merkle_root".to_string(),
         chunks,
         encrypted_key_bundle: None,
+        hash_algorithm: HashAlgorithm::Sha256,
+        encryption_info: None,
+        mime_type: None,
+        chunk_size: DEFAULT_CHUNK_SIZE,
+        signature: None,
+        file_hash: None,
     }
 }
 
@@ -99,6 +110,9 @@ fn test_manifest_parsing_with_missing_chunks() {
                 size: 100,
                 encrypted_hash: String::new(),
                 encrypted_size: 100,
+                compression_type: 0,
+                compressed_size: 100,
+                cipher_suite: CipherSuite::Aes256Gcm,
             },
             ChunkInfo {
                 index: 2, // Missing index 1
@@ -106,9 +120,18 @@ fn test_manifest_parsing_with_missing_chunks() {
                 size: 100,
                 encrypted_hash: String::new(),
                 encrypted_size: 100,
+                compression_type: 0,
+                compressed_size: 100,
+                cipher_suite: CipherSuite::Aes256Gcm,
             },
         ],
         encrypted_key_bundle: None,
+        hash_algorithm: HashAlgorithm::Sha256,
+        encryption_info: None,
+        mime_type: None,
+        chunk_size: DEFAULT_CHUNK_SIZE,
+        signature: None,
+        file_hash: None,
     };
 
     let manifest_json = serde_json::to_string(&manifest).unwrap();
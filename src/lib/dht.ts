@@ -127,6 +127,8 @@ export interface DhtHealth {
   dcutrHolePunchFailures: number;
   lastDcutrSuccess: number | null;
   lastDcutrFailure: number | null;
+  lastConnectionEvent: number | null;
+  connectionsRefused: number;
 }
 
 export class DhtService {
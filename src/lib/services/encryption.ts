@@ -18,19 +18,30 @@ export const encryptionService = {
    * Invokes the backend to chunk and encrypt a file.
    * @param filePath The absolute path to the file.
    * @param recipientPublicKey Optional recipient's X25519 public key (hex-encoded). If not provided, encrypts for self.
+   * @param uploadId Optional id for self uploads, so they can be stopped with `cancelUpload`.
    * @returns A promise that resolves to the file manifest.
    */
-  async encryptFile(filePath: string, recipientPublicKey?: string): Promise<FileManifestForJs> {
+  async encryptFile(filePath: string, recipientPublicKey?: string, uploadId?: string): Promise<FileManifestForJs> {
     if (recipientPublicKey) {
       return await invoke('encrypt_file_for_recipient', { 
         filePath, 
         recipientPublicKey 
       });
     } else {
-      return await invoke('encrypt_file_for_self_upload', { filePath });
+      return await invoke('encrypt_file_for_self_upload', { filePath, uploadId });
     }
   },
 
+  /**
+   * Stops an in-progress self upload while it is being chunked. Chunks already
+   * written are removed.
+   * @param uploadId The id passed to `encryptFile`.
+   * @returns Whether an upload with that id was in progress.
+   */
+  async cancelUpload(uploadId: string): Promise<boolean> {
+    return await invoke('cancel_upload', { uploadId });
+  },
+
   /**
    * Invokes the backend to reassemble and decrypt a file from its chunks.
    * @param manifest The file manifest containing chunk info and the encrypted key.
@@ -21,6 +21,7 @@ export interface PeerMetrics {
   total_bytes_transferred: number;
   protocols: string[];
   encryption_support: boolean;
+  region?: string | null;
 }
 
 /**
@@ -32,7 +33,9 @@ export type PeerSelectionStrategy =
   | "bandwidth"
   | "balanced"
   | "encryption"
-  | "load_balanced";
+  | "load_balanced"
+  // Prefer peers that reported this region, e.g. "region:EU"
+  | `region:${string}`;
 
 /**
  * Smart peer selection service for optimal file transfers
@@ -184,6 +187,23 @@ export class PeerSelectionService {
     }
   }
 
+  /**
+   * Record the region a peer reported for itself
+   */
+  static async setPeerRegion(
+    peerId: string,
+    region: string | null
+  ): Promise<void> {
+    try {
+      await invoke("set_peer_region", {
+        peerId,
+        region,
+      });
+    } catch (error) {
+      console.error("Failed to set peer region:", error);
+    }
+  }
+
   /**
    * Clean up inactive peer metrics
    */
@@ -698,6 +698,17 @@ export interface AppSettings {
   selectedProtocol: "WebRTC" | "BitTorrent" | "ED2K" | "FTP"; // Protocol selected for file uploads
 }
 
+// Chunk sizes the backend accepts, in KB. The chunk manager needs a power of two.
+export const CHUNK_SIZE_OPTIONS_KB = [64, 128, 256, 512, 1024];
+
+// Round a stored chunk size to the nearest supported value (ties round up).
+export function nearestChunkSizeKb(kb: number): number {
+  const value = Number.isFinite(kb) ? kb : 256;
+  return CHUNK_SIZE_OPTIONS_KB.reduce((best, option) =>
+    Math.abs(option - value) <= Math.abs(best - value) ? option : best
+  );
+}
+
 // Export the settings store
 // We initialize with a safe default structure. Settings.svelte will load/persist the actual state.
 export const settings = writable<AppSettings>({
@@ -35,6 +35,13 @@ export interface AccountCreationResult {
   blacklist?: unknown[];
 }
 
+export interface KeystoreAccountSummary {
+  address: string;
+  label: string | null;
+  created_at: number;
+  two_fa_enabled: boolean;
+}
+
 export interface TotpSetupInfo {
   secret: string;
   otpauthUrl: string;
@@ -1254,21 +1261,29 @@ export class WalletService {
     });
   }
 
-  async listKeystoreAccounts(): Promise<string[]> {
+  async listKeystoreAccounts(): Promise<KeystoreAccountSummary[]> {
     if (!this.isTauri) {
       return [];
     }
     try {
-      return (await invoke("list_keystore_accounts")) as string[];
+      return (await invoke("list_keystore_accounts")) as KeystoreAccountSummary[];
     } catch (error) {
       console.error("Failed to list keystore accounts:", error);
       return [];
     }
   }
 
+  async setKeystoreAccountLabel(address: string, label: string | null): Promise<void> {
+    if (!this.isTauri) {
+      return;
+    }
+    await invoke("set_keystore_account_label", { address, label });
+  }
+
   async loadFromKeystore(
     address: string,
-    password: string
+    password: string,
+    totpCode?: string
   ): Promise<AccountCreationResult> {
     if (!this.isTauri) {
       throw new Error("Keystore access is only available in the desktop app");
@@ -1277,6 +1292,7 @@ export class WalletService {
     const account = (await invoke("load_account_from_keystore", {
       address,
       password,
+      totpCode: totpCode ?? null,
     })) as AccountCreationResult;
     this.setActiveAccount(account);
     await this.syncFromBackend();
@@ -1343,7 +1359,8 @@ export class WalletService {
   async verifyAndEnableTwoFactor(
     secret: string,
     code: string,
-    password: string
+    password: string,
+    currentCode?: string
   ): Promise<boolean> {
     if (!this.isTauri) {
       throw new Error("2FA is only available in the desktop app");
@@ -1352,6 +1369,7 @@ export class WalletService {
       secret,
       code,
       password,
+      currentCode: currentCode ?? null,
     })) as boolean;
   }
 
@@ -1365,11 +1383,11 @@ export class WalletService {
     })) as boolean;
   }
 
-  async disableTwoFactor(password: string): Promise<void> {
+  async disableTwoFactor(password: string, code: string): Promise<void> {
     if (!this.isTauri) {
       throw new Error("2FA is only available in the desktop app");
     }
-    await invoke("disable_2fa", { password });
+    await invoke("disable_2fa", { password, code });
   }
 
   async isTwoFactorEnabled(): Promise<boolean> {